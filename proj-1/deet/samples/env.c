@@ -0,0 +1,11 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <unistd.h>
+
+int main() {
+    const char *greeting = getenv("GREETING");
+    char cwd[4096];
+    printf("GREETING=%s\n", greeting ? greeting : "(unset)");
+    printf("cwd=%s\n", getcwd(cwd, sizeof(cwd)) ? cwd : "(unknown)");
+    return 0;
+}
@@ -0,0 +1,17 @@
+#include <stdio.h>
+#include <unistd.h>
+
+void after_exec() {
+    printf("Running again\n");
+}
+
+int main(int argc, char *argv[]) {
+    if (argc > 1) {
+        after_exec();
+        return 0;
+    }
+    printf("Executing again\n");
+    fflush(stdout);
+    execl("/proc/self/exe", argv[0], "again", (char *)NULL);
+    return 1;
+}
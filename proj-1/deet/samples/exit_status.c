@@ -0,0 +1,8 @@
+#include <stdio.h>
+
+int status = 3;
+
+int main() {
+    printf("Exiting with status %d\n", status);
+    return status;
+}
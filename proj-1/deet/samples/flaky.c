@@ -0,0 +1,16 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+int main(int argc, char *argv[]) {
+    int seed = 0;
+    if (argc > 1 && strncmp(argv[1], "--seed=", 7) == 0) {
+        seed = atoi(argv[1] + 7);
+    }
+    // Fails once in three runs
+    if (seed % 3 == 0 && seed != 0) {
+        printf("Failed with seed %d\n", seed);
+        return 1;
+    }
+    return 0;
+}
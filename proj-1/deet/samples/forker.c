@@ -0,0 +1,22 @@
+#include <stdio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+void in_child() {
+    printf("In the child\n");
+}
+
+void in_parent() {
+    printf("In the parent\n");
+}
+
+int main() {
+    pid_t pid = fork();
+    if (pid == 0) {
+        in_child();
+        return 0;
+    }
+    waitpid(pid, NULL, 0);
+    in_parent();
+    return 0;
+}
@@ -0,0 +1,12 @@
+#include <stdio.h>
+
+static inline __attribute__((always_inline)) int twice(int x) {
+    return x * 2;
+}
+
+int main() {
+    int a = twice(3);
+    int b = twice(a);
+    printf("%d %d\n", a, b);
+    return 0;
+}
@@ -0,0 +1,10 @@
+#include <stdio.h>
+
+int main() {
+    int total = 0;
+    for (int i = 0; i < 5; i++) {
+        total += i;
+    }
+    printf("total = %d\n", total);
+    return 0;
+}
@@ -0,0 +1,16 @@
+#include <stdio.h>
+
+typedef int counter_t;
+
+int primes[5] = {2, 3, 5, 7, 11};
+long big[20];
+
+int main() {
+    counter_t count = -7;
+    short negative = -2;
+    char letter = 'q';
+    int *first = &primes[0];
+    const int *last = &primes[4];
+    printf("%d %d %c %d %d\n", count, negative, letter, *first, *last);
+    return 0;
+}
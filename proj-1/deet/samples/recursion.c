@@ -0,0 +1,19 @@
+#include <stdio.h>
+#include <stdlib.h>
+
+int descend(int depth, int corrupt) {
+    if (depth == 0) {
+        if (corrupt) {
+            // Clobber the saved frame pointer, as a stack overflow bug might
+            *(long *)__builtin_frame_address(0) = 0x10;
+        }
+        int *null = NULL;
+        return *null;
+    }
+    return descend(depth - 1, corrupt) + 1;
+}
+
+int main(int argc, char *argv[]) {
+    printf("%d\n", descend(1000, argc > 1));
+    return 0;
+}
@@ -0,0 +1,6 @@
+fn main() {
+    let greeting: &str = "hello, world";
+    let owned = String::from("owned \"string\"");
+    let empty = String::new();
+    println!("{} {} {}", greeting, owned, empty.len());
+}
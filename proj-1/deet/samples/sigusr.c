@@ -0,0 +1,15 @@
+#include <signal.h>
+#include <stdio.h>
+
+static volatile sig_atomic_t handled = 0;
+
+void handler(int sig) {
+    handled++;
+}
+
+int main() {
+    signal(SIGUSR1, handler);
+    raise(SIGUSR1);
+    printf("handler ran %d times\n", handled);
+    return 0;
+}
@@ -0,0 +1,15 @@
+#include <stdio.h>
+#include <string.h>
+
+char long_text[1000];
+
+void show(const char *text) {
+    printf("%zu\n", strlen(text));
+}
+
+int main() {
+    show("tab\there \"quoted\"\n");
+    memset(long_text, 'a', sizeof(long_text) - 1);
+    show(long_text);
+    return 0;
+}
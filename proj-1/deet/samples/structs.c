@@ -0,0 +1,25 @@
+#include <stdio.h>
+
+struct point {
+    int x;
+    int y;
+};
+
+struct node {
+    int value;
+    struct node *next;
+};
+
+int main() {
+    struct point origin = {3, -4};
+    struct node last = {30, NULL};
+    struct node first = {10, &last};
+    struct node *head = &first;
+    int values[4] = {1, 2, 3, 4};
+    int sum = 0;
+    for (int i = 0; i < 4; i++) {
+        sum += values[i];
+    }
+    printf("%d %d %d\n", origin.x + origin.y, head->next->value, sum);
+    return 0;
+}
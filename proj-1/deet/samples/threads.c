@@ -0,0 +1,27 @@
+#include <pthread.h>
+#include <stdio.h>
+
+int counter = 0;
+pthread_mutex_t lock = PTHREAD_MUTEX_INITIALIZER;
+
+void *worker(void *arg) {
+    int id = *(int *)arg;
+    pthread_mutex_lock(&lock);
+    counter += id;
+    pthread_mutex_unlock(&lock);
+    printf("worker %d done\n", id);
+    return NULL;
+}
+
+int main() {
+    pthread_t threads[2];
+    int ids[2] = {1, 2};
+    for (int i = 0; i < 2; i++) {
+        pthread_create(&threads[i], NULL, worker, &ids[i]);
+    }
+    for (int i = 0; i < 2; i++) {
+        pthread_join(threads[i], NULL);
+    }
+    printf("counter = %d\n", counter);
+    return 0;
+}
@@ -1,375 +1,3201 @@
-use crate::debugger_command::DebuggerCommand;
-use crate::inferior::Inferior;
+use crate::debugger_command::{
+    help_text, parse_signal, CatchEvent, DebuggerCommand, ExamineFormat, ExamineSpec, FollowForkMode,
+};
+use crate::inferior::{self, Inferior, LaunchSettings};
 use crate::inferior::Status;
-use crate::dwarf_data::{DwarfData, Error as DwarfError};
+use crate::inferior::{register_value, set_register_value, REGISTER_NAMES};
+use iced_x86::{Decoder, DecoderOptions, Formatter, GasFormatter, Instruction, Mnemonic};
+use crate::error::DeetError;
+use crate::expression::{self, MemoryReader, Place};
+use crate::output;
+use crate::dwarf_data::{format_bytes, format_string, DwarfData, Error as DwarfError, Location, Type, TypeKind, ValueFormat, Variable, MAX_ARRAY_ELEMENTS};
+use nix::sys::signal::Signal;
+use nix::unistd::Pid;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
-use std::collections::HashMap;
-use std::fs::File;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use std::io::{BufReader, BufRead};
 
+/// Longest string `x/s` will print before giving up on finding the NUL terminator, and most bytes
+/// of a Rust string `print` shows
+const MAX_STRING_LEN: usize = 4096;
+
+/// Most units `x` examines at once
+const MAX_EXAMINE_COUNT: usize = 4096;
+
+/// Longest possible x86-64 instruction, in bytes
+const MAX_INSTRUCTION_LEN: usize = 15;
+
+/// Number of source lines `list` prints at a time
+const LIST_SIZE: usize = 10;
+
+/// Number of bytes `disassemble` decodes when it can't tell where the function ends
+const DISASSEMBLE_LEN: usize = 32;
+
+/// Most functions `info functions` lists before asking for a narrower filter
+const MAX_FUNCTIONS_LISTED: usize = 100;
+
+/// Most breakpoint command lists run one after another (because each one resumes the inferior
+/// into another breakpoint with commands) before deet returns to the prompt
+const MAX_BP_COMMAND_DEPTH: usize = 100;
+
+/// Printed instead of running a command that needs the inferior stopped while it's running in
+/// the background
+const RUNNING_MESSAGE: &str = "The program is running. Use `interrupt` to stop it first.";
+
 #[derive(Clone, Debug)]
 pub struct Breakpoint {
     pub addr: usize,
-    pub orig_byte: u8
+    pub orig_byte: u8,
+    /// Number shown to the user
+    pub number: usize,
+    /// Which of the breakpoint's locations this is, counting from 1, if it has more than one
+    pub location_number: Option<usize>,
+    /// Temporary breakpoints are deleted the first time they're hit
+    pub temporary: bool,
+    /// Set by deet for itself, as run_until does, rather than by the user. Internal breakpoints
+    /// are never listed or reported, and their number doesn't mean anything.
+    pub internal: bool,
+    /// Command lines run each time the breakpoint is hit
+    pub commands: Vec<String>,
+    /// Location as the user gave it, so it can be resolved again if the target is rebuilt
+    pub location: String,
+    /// Set with --force, so it's installed even outside executable memory
+    pub force: bool,
+    /// Source line the address was at when the location was resolved, to notice if it moves
+    pub line: Option<String>,
+    /// Expression that must be true (nonzero) for the breakpoint to stop the inferior
+    pub condition: Option<String>,
+}
+
+impl Breakpoint {
+    /// Returns how the user refers to this location of the breakpoint: `N`, or `N.M` for location
+    /// M of a breakpoint with several.
+    pub fn label(&self) -> String {
+        match self.location_number {
+            Some(location_number) => format!("{}.{}", self.number, location_number),
+            None => self.number.to_string(),
+        }
+    }
+}
+
+/// Stops the inferior at an event rather than an address
+struct Catchpoint {
+    number: usize,
+    event: CatchEvent,
+}
+
+/// An expression printed every time the inferior stops
+struct Display {
+    number: usize,
+    format: ValueFormat,
+    expr: String,
+}
+
+impl Display {
+    /// The expression with its /FMT in front, if it has one, like `/x total`.
+    fn describe(&self) -> String {
+        match self.format.suffix() {
+            "" => self.expr.clone(),
+            suffix => format!("{} {}", suffix, self.expr),
+        }
+    }
+}
+
+/// Counts of what happened over the whole deet session, shown by `info stats`
+#[derive(Default)]
+struct SessionStats {
+    runs: usize,
+    breakpoint_hits: usize,
+    /// Signals other than SIGTRAP that stopped the inferior
+    signals: usize,
+    /// Time inferiors spent alive, not counting the current one
+    runtime: Duration,
+}
+
+/// Formats a duration as seconds, to the millisecond: "1.327s".
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+/// A stack frame selected with up/down/frame
+struct SelectedFrame {
+    number: usize,
+    rip: usize,
+    rbp: usize,
 }
 
 pub struct Debugger {
     target: String,
-    history_path: String,
+    /// Arguments for the inferior, remembered across runs
+    args: Vec<String>,
+    /// Environment and working directory for the inferior, remembered across runs
+    launch_settings: LaunchSettings,
+    /// History file of the current target, which the command history is saved to on exit
+    history_path: PathBuf,
     readline: Editor<()>,
     inferior: Option<Inferior>,
     debug_data: DwarfData,
+    /// Modification time and size of the target when debug_data was read from it
+    target_stamp: Option<(SystemTime, u64)>,
     breakpoints: HashMap<usize, Breakpoint>,
-    inferior_stopped_by_bp: bool,
+    /// Breakpoints whose locations don't resolve to any code yet (so their addr means nothing).
+    /// They're tried again at every run and whenever the symbols are re-read.
+    pending_bps: Vec<Breakpoint>,
+    next_bp_number: usize,
+    /// Lines of each source file read so far, or None if the file couldn't be read
+    source_cache: HashMap<String, Option<Vec<String>>>,
+    /// File and line number where a bare `list` continues from
+    list_position: Option<(String, usize)>,
+    /// Frame that commands inspecting the stack operate on, or None for the innermost one
+    selected_frame: Option<SelectedFrame>,
+    /// Signal to deliver to the inferior when it's next resumed
+    pending_signal: Option<Signal>,
+    /// Signals that stop the inferior but aren't passed on to it
+    nopass_signals: HashSet<Signal>,
+    /// Commands of the last breakpoint hit, waiting to be run once the current command is done
+    hit_bp_commands: Option<Vec<String>>,
+    /// The temporary breakpoint the inferior last stopped at, deleted by then but still to be
+    /// reported
+    hit_temporary_bp: Option<Breakpoint>,
+    /// Exit status of the last run of the inferior, which deet exits with too
+    exit_code: Option<i32>,
+    /// Extra argument for each run of `run --until-failure`, with {} standing for the run's number
+    rerun_arg: Option<String>,
+    catchpoints: Vec<Catchpoint>,
+    next_catch_number: usize,
+    follow_fork_mode: FollowForkMode,
+    /// Processes of this run that were let go after a fork, and why
+    detached_processes: Vec<(Pid, &'static str)>,
+    displays: Vec<Display>,
+    next_display_number: usize,
+    stats: SessionStats,
+    /// When the current inferior was started or attached to
+    run_started: Option<Instant>,
 }
 
 impl Debugger {
     /// Initializes the debugger.
     pub fn new(target: &str) -> Debugger {
         // Initialize the DwarfData
-        let debug_data = match DwarfData::from_file(target) {
+        let debug_data = match Debugger::load_debug_data(target) {
             Ok(val) => val,
-            Err(DwarfError::ErrorOpeningFile) => {
-                println!("Could not open file {}", target);
-                std::process::exit(1);
-            }
-            Err(DwarfError::DwarfFormatError(err)) => {
-                println!("Could not debugging symbols from {}: {:?}", target, err);
+            Err(e) => {
+                println!("{}", e);
                 std::process::exit(1);
             }
         };
-        
-        let history_path = format!("{}/.deet_history", std::env::var("HOME").unwrap());
+
+        let history_path = Debugger::history_path(target);
         let mut readline = Editor::<()>::new();
-        // Attempt to load history from ~/.deet_history if it exists
-        let _ = readline.load_history(&history_path);
+        Debugger::load_history(&mut readline, &history_path);
 
         Debugger {
             target: target.to_string(),
+            args: Vec::new(),
+            launch_settings: LaunchSettings::default(),
             history_path,
             readline,
             inferior: None,
             debug_data,
+            target_stamp: Debugger::file_stamp(target),
             breakpoints: HashMap::new(),
-            inferior_stopped_by_bp: false
+            pending_bps: Vec::new(),
+            next_bp_number: 0,
+            source_cache: HashMap::new(),
+            list_position: None,
+            selected_frame: None,
+            pending_signal: None,
+            // Interrupting the inferior with ctrl+c shouldn't also kill it
+            nopass_signals: vec![Signal::SIGINT].into_iter().collect(),
+            hit_bp_commands: None,
+            hit_temporary_bp: None,
+            exit_code: None,
+            rerun_arg: None,
+            catchpoints: Vec::new(),
+            next_catch_number: 0,
+            follow_fork_mode: FollowForkMode::default(),
+            detached_processes: Vec::new(),
+            displays: Vec::new(),
+            next_display_number: 0,
+            stats: SessionStats::default(),
+            run_started: None,
         }
     }
 
-    fn cont(&mut self) {
-        if self.inferior.is_none() || 
-            !self.inferior.as_mut().unwrap().running().unwrap() {
-                println!("No running subprocess");
+    /// Reads the debugging symbols of `target`. If they can't be read, the target can still be
+    /// run and debugged at the machine level, so that's only a warning.
+    fn load_debug_data(target: &str) -> Result<DwarfData, String> {
+        let debug_data = match DwarfData::from_file(target) {
+            Ok(debug_data) => debug_data,
+            Err(DwarfError::ErrorOpeningFile) => {
+                return Err(format!("Could not open file {}", target));
+            }
+            Err(DwarfError::DwarfFormatError(err)) => {
+                println!("warning: could not read debugging symbols from {}: {:?}", target, err);
+                DwarfData::without_symbols(target)
+                    .map_err(|_| format!("{} is not an executable deet can debug", target))?
+            }
+        };
+        if !debug_data.has_symbols() {
+            println!("warning: no debugging symbols found in {}", target);
+        }
+        Ok(debug_data)
+    }
+
+    /// Returns the history file for `target`: ~/.deet_history.d/ followed by a hash of the
+    /// target's absolute path, so that each program gets its own history.
+    fn history_path(target: &str) -> PathBuf {
+        let target = fs::canonicalize(target).unwrap_or_else(|_| PathBuf::from(target));
+        // FNV-1a, which unlike std's hasher is guaranteed to give the same hash in every build
+        let hash = target.to_string_lossy().bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        Path::new(&std::env::var("HOME").unwrap())
+            .join(".deet_history.d")
+            .join(format!("{:016x}", hash))
+    }
+
+    /// Loads the history in `path`, if there is any. A target without a history file of its own
+    /// yet starts out with the history from ~/.deet_history, where older versions of deet kept
+    /// the history of every target.
+    fn load_history(readline: &mut Editor<()>, path: &Path) {
+        if readline.load_history(path).is_err() {
+            let old_path = format!("{}/.deet_history", std::env::var("HOME").unwrap());
+            let _ = readline.load_history(&old_path);
+        }
+    }
+
+    /// Writes the command history to the current target's history file.
+    fn save_history(&mut self) {
+        if let Some(dir) = self.history_path.parent() {
+            // save_history reports the error if this didn't work
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Err(err) = self.readline.save_history(&self.history_path) {
+            println!("Warning: failed to save history file at {}: {}", self.history_path.display(), err);
+        }
+    }
+
+    /// Prints the last `count` lines of the command history, numbered for `!N`.
+    fn show_history(&self, count: usize) {
+        let history = self.readline.history();
+        for index in history.len().saturating_sub(count)..history.len() {
+            println!("{:>5}  {}", index + 1, history.get(index).unwrap());
+        }
+    }
+
+    /// Returns the modification time and size of a file, which change when it's rebuilt.
+    fn file_stamp(path: &str) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    /// Switches to debugging `target`, killing the current inferior first. Breakpoints are moved
+    /// to wherever their locations are in the new target.
+    fn load_target(&mut self, target: &str) {
+        let debug_data = match Debugger::load_debug_data(target) {
+            Ok(debug_data) => debug_data,
+            Err(e) => {
+                println!("{}", e);
                 return;
+            }
+        };
+        self.discard_inferior();
+        // Carry on with the new target's history
+        self.save_history();
+        self.history_path = Debugger::history_path(target);
+        self.readline.clear_history();
+        Debugger::load_history(&mut self.readline, &self.history_path);
+        self.target = target.to_string();
+        self.target_stamp = Debugger::file_stamp(target);
+        self.debug_data = debug_data;
+        println!("Reading symbols from {}", target);
+        self.rebind_breakpoints();
+    }
+
+    /// Re-reads the target's debugging symbols if it has changed on disk since they were read.
+    fn reload_if_changed(&mut self) {
+        let stamp = Debugger::file_stamp(&self.target);
+        if stamp.is_none() || stamp == self.target_stamp {
+            return;
         }
+        println!("`{}' has changed; re-reading symbols.", self.target);
+        let target = self.target.clone();
+        self.load_target(&target);
+    }
 
-        // At first, self.inferior_stopped_by_bp flag is false. When the inferior is stopped at a breakpoint,
-        // this flag shoule be set to true. Then after the next `continue` command, self.inferior_stopped_by_bp
-        // is true, and the byte in the memory at breakpoint address should be set to the original value, and %rip -= 1.
-        // So the inferior can re-execute the next instruction, as if the breakpoint doesn't exist.\
-        // 
-        // Finally restore this breakpoint, which set the byte at breakpoint address to 0xcc, and clear flag.
-        if self.inferior_stopped_by_bp {
-            match self.inferior.as_mut().unwrap().step() {
-                Ok(status) => {
-                    match status {
-                        Status::Exited(code) => {
-                            println!("Child exited (status {})", code);
-                            self.inferior = None;
-                            return;
-                        },
-                        Status::Signaled(signal) => {
-                            println!("Child signaled (signal {})", signal);
-                            self.inferior = None;
-                            return;
-                        },
-                        Status::Stopped(signal, rip) => {
-                            if signal == nix::sys::signal::Signal::SIGTRAP {
-                                self.reset_bp(rip);
-                                self.inferior_stopped_by_bp = false;
-                            }
-                        }    
+    /// Resolves every breakpoint's location again after the debugging symbols changed. Breakpoints
+    /// set at an address are kept as they are, with a warning, and ones whose location no longer
+    /// exists become pending. Must be called while there's no inferior.
+    fn rebind_breakpoints(&mut self) {
+        self.source_cache.clear();
+        self.list_position = None;
+        let mut breakpoints: Vec<Breakpoint> = self.breakpoints.drain().map(|(_, bp)| bp).collect();
+        breakpoints.sort_by_key(|breakpoint| breakpoint.number);
+        // The locations of a breakpoint all come from the same user location, which may resolve
+        // to a different number of them now
+        breakpoints.dedup_by_key(|breakpoint| breakpoint.number);
+        for breakpoint in breakpoints {
+            let addrs = if breakpoint.location.starts_with('*') {
+                println!(
+                    "Warning: breakpoint {} is at a fixed address ({}), which may be wrong now",
+                    breakpoint.number, breakpoint.location
+                );
+                vec![breakpoint.addr]
+            } else {
+                match self.resolve_locations(&breakpoint.location) {
+                    Ok(addrs) => addrs,
+                    Err(e) => {
+                        println!("Breakpoint {} ({}) pending: {}", breakpoint.number, breakpoint.location, e);
+                        self.pending_bps.push(Breakpoint { location_number: None, ..breakpoint });
+                        continue;
                     }
-                },
-                Err(e) => {
-                    println!("Error stepping inferior ({:?})", e);
-                    self.inferior = None;
-                    self.inferior_stopped_by_bp = false;
-                    return;
                 }
+            };
+            let (taken, free): (Vec<usize>, Vec<usize>) =
+                addrs.into_iter().partition(|addr| self.breakpoints.contains_key(addr));
+            if free.is_empty() {
+                println!(
+                    "Deleting breakpoint {}: breakpoint {} is at the same address",
+                    breakpoint.number, self.breakpoints[&taken[0]].number
+                );
+                continue;
+            }
+            let multiple = free.len() > 1;
+            for (index, addr) in free.into_iter().enumerate() {
+                let location_number = if multiple { Some(index + 1) } else { None };
+                let line = if breakpoint.location.starts_with('*') {
+                    // Left as it was, so launching can tell if the address moved to another line
+                    breakpoint.line.clone()
+                } else {
+                    self.source_line(addr)
+                };
+                self.breakpoints.insert(addr, Breakpoint { addr, location_number, line, ..breakpoint.clone() });
             }
-            
         }
+        self.resolve_pending_bps();
+    }
 
-        // Continue 
-        match self.inferior.as_mut().unwrap().cont() {
-            Ok(status) => {
-                match status {
-                    Status::Exited(code) => {
-                        println!("Child exited (status {})", code);
-                        self.inferior_stopped_by_bp = false;
-                        self.inferior = None;
-                    },
-                    Status::Signaled(signal) => {
-                        println!("Child signaled (signal {})", signal);
-                        self.inferior_stopped_by_bp = false;
-                        self.inferior = None;
-                    },
-                    Status::Stopped(signal, rip) => {
-                        println!("Child stopped (signal {})", signal);
-
-                        if let Some(line) = self.debug_data.get_line_from_addr(rip) {
-                            println!("Stopped at {}", line);
-                            self.print_code(rip);
-                        }
-
-                        // Check breakpoint
-                        if signal == nix::sys::signal::Signal::SIGTRAP {
-                            self.restore_bp(rip);
-                            self.inferior_stopped_by_bp = true;
+    /// Tries again to resolve the locations of pending breakpoints, and sets the ones that now
+    /// resolve (in the inferior too, if there is one). Locations that already have a breakpoint
+    /// are left to it.
+    fn resolve_pending_bps(&mut self) {
+        for breakpoint in std::mem::take(&mut self.pending_bps) {
+            let addrs: Vec<usize> = match self.resolve_locations(&breakpoint.location) {
+                Ok(addrs) => addrs.into_iter().filter(|addr| !self.breakpoints.contains_key(addr)).collect(),
+                Err(_) => {
+                    self.pending_bps.push(breakpoint);
+                    continue;
+                }
+            };
+            if addrs.is_empty() {
+                println!("Deleting breakpoint {}: its locations all have breakpoints already", breakpoint.number);
+                continue;
+            }
+            let multiple = addrs.len() > 1;
+            for (index, &addr) in addrs.iter().enumerate() {
+                let location_number = if multiple { Some(index + 1) } else { None };
+                let line = self.source_line(addr);
+                let mut resolved = Breakpoint { addr, location_number, line, ..breakpoint.clone() };
+                if let Some(inferior) = self.inferior.as_mut().filter(|inferior| inferior.running()) {
+                    match inferior.write_byte(addr, 0xcc) {
+                        Ok(orig_byte) => resolved.orig_byte = orig_byte,
+                        Err(e) => {
+                            println!("Error setting breakpoint {} at {:#x}: {}", resolved.label(), addr, e);
+                            continue;
                         }
                     }
                 }
-            },
-            Err(_) => {
-                println!("Error continuing subprocess");
+                self.breakpoints.insert(addr, resolved);
+            }
+            if multiple {
+                println!("Pending breakpoint {} ({}) resolved to {} locations", breakpoint.number, breakpoint.location, addrs.len());
+            } else {
+                println!("Pending breakpoint {} ({}) resolved to {:#x}", breakpoint.number, breakpoint.location, addrs[0]);
             }
         }
     }
 
-    fn reset_bp(&mut self, rip: usize) {
-        // Set the breakpoint
-        if let Some(breakpoint) = self.breakpoints.get_mut(&(rip - 1)) {
-            breakpoint.orig_byte = self.inferior.as_mut().unwrap()
-                .write_byte(breakpoint.addr, 0xcc)
-                .expect(&format!("Reset breakpoint at {} failed", breakpoint.addr));
+    /// Returns the source line `addr` is at, as `file:line`, if the debugging symbols know.
+    fn source_line(&self, addr: usize) -> Option<String> {
+        self.debug_data.get_line_from_addr(addr).map(|line| line.to_string())
+    }
+
+    /// Checks that every breakpoint's address is still at the source line it was resolved to,
+    /// which it may not be if the target was rebuilt without deet noticing. Breakpoints that have
+    /// moved are reported, and ones set by name or line are resolved again (becoming pending if
+    /// that fails). Must be called while there's no inferior.
+    fn check_breakpoint_lines(&mut self) {
+        let mut moved: Vec<Breakpoint> = self
+            .breakpoints
+            .values()
+            .filter(|breakpoint| !breakpoint.internal && self.source_line(breakpoint.addr) != breakpoint.line)
+            .cloned()
+            .collect();
+        moved.sort_by_key(|breakpoint| (breakpoint.number, breakpoint.location_number));
+        for breakpoint in &moved {
+            println!(
+                "Warning: breakpoint {} was set at {}, but {:#x} is now {}",
+                breakpoint.label(),
+                breakpoint.line.as_deref().unwrap_or("an address with no line"),
+                breakpoint.addr,
+                self.source_line(breakpoint.addr).unwrap_or_else(|| "at no line".to_string())
+            );
+        }
+        // Breakpoints at an address stay there, now at the line they've moved to
+        for breakpoint in moved.iter().filter(|breakpoint| breakpoint.location.starts_with('*')) {
+            let line = self.source_line(breakpoint.addr);
+            self.breakpoints.get_mut(&breakpoint.addr).unwrap().line = line;
         }
+        moved.retain(|breakpoint| !breakpoint.location.starts_with('*'));
+        moved.dedup_by_key(|breakpoint| breakpoint.number);
+        for breakpoint in moved {
+            self.breakpoints.retain(|_, other| other.number != breakpoint.number);
+            println!("Resolving breakpoint {} ({}) again", breakpoint.number, breakpoint.location);
+            self.pending_bps.push(Breakpoint { location_number: None, ..breakpoint });
+        }
+        self.resolve_pending_bps();
     }
 
-    fn restore_bp(&mut self, rip: usize) -> Option<()> {
-        // Now rip == breakpoint_addr + 1;
-        if let Some(breakpoint) = self.breakpoints.get(&(rip - 1)) {
-            // Restore the breakpoint
-            let inferior = self.inferior.as_mut().unwrap();
-            inferior.write_byte(breakpoint.addr, breakpoint.orig_byte)
-                    .expect(&format!("Restore breakpoint at {} failed", breakpoint.addr));
-            inferior.step_back_rip().unwrap();
-            return Some(())
+    /// Returns true if there is an inferior and it hasn't terminated yet.
+    fn inferior_alive(&self) -> bool {
+        match self.inferior.as_ref() {
+            Some(inferior) => inferior.running(),
+            None => false,
         }
-        None
     }
 
-    fn parse_address(addr: &str) -> Option<usize> {
-        let addr_without_0x = if addr.to_lowercase().starts_with("0x") {
-            &addr[2..]
+    /// Returns true if the inferior was left running because waiting for it timed out.
+    fn inferior_still_running(&self) -> bool {
+        self.inferior.as_ref().is_some_and(|inferior| inferior.still_running())
+    }
+
+    /// Notices if the inferior was killed from outside deet since it last stopped, in which case
+    /// the way it terminated is reported and it's let go. An inferior running in the background
+    /// that has stopped (or terminated) since the last command has that reported instead.
+    fn check_inferior(&mut self) {
+        if !self.inferior_alive() {
+            return;
+        }
+        let inferior = self.inferior.as_mut().unwrap();
+        let checked = if inferior.still_running() {
+            inferior.poll_running().map(|stopped| stopped.then_some(None))
         } else {
-            &addr
+            inferior.poll_exit().map(|exited| exited.map(Some))
         };
-        usize::from_str_radix(addr_without_0x, 16).ok()
+        match checked {
+            Ok(None) => {}
+            // Stopped in the background; finish the resume to report where
+            Ok(Some(None)) => {
+                self.cont();
+            }
+            Ok(Some(Some(status))) => self.report_exit(status),
+            Err(e) => {
+                println!("Error checking on the inferior: {}", e);
+                self.end_run();
+                self.inferior = None;
+            }
+        }
     }
 
-    fn print_code(&self, rip: usize) {
-        if let Some(line) = self.debug_data.get_line_from_addr(rip) {
-            let source_file = if let Ok(file) = File::open(line.file) { file } else { return } ;
-            if let Some(code) = BufReader::new(source_file).lines().nth(line.number - 1) {
-                println!("{}\t{}", line.number, code.unwrap());
+    /// Reports that an operation on the inferior failed. If that's because the inferior is gone,
+    /// finds out how it terminated.
+    fn inferior_error(&mut self, what: &str, err: DeetError) {
+        println!("{}: {}", what, err);
+        if err.process_gone() {
+            self.check_inferior();
+        }
+    }
+
+    /// Gets rid of the current inferior, if it's still alive: a process we spawned is killed,
+    /// while a process we attached to is detached from and left running.
+    fn discard_inferior(&mut self) {
+        if !self.inferior_alive() {
+            self.inferior = None;
+            return;
+        }
+        if self.inferior.as_ref().unwrap().is_attached() {
+            // Only a stopped process can be detached from
+            if self.inferior_still_running() {
+                self.interrupt();
             }
+            if self.inferior_alive() {
+                self.detach();
+            }
+        } else {
+            self.kill();
         }
+        self.inferior = None;
+        self.selected_frame = None;
+        self.pending_signal = None;
     }
 
-    pub fn run(&mut self) {
-        loop {
-            match self.get_next_command() {
-                DebuggerCommand::Run(args) => {
-                    // If the inferior exists and is running, kill it.
-                    if self.inferior.is_some() && 
-                        self.inferior.as_mut().unwrap().running().unwrap() {
-                        self.inferior.as_mut().unwrap()
-                                     .kill().unwrap();
-                    }
-                    if let Some(inferior) = Inferior::new(&self.target, &args, &mut self.breakpoints) {
-                        // Create the inferior
-                        self.inferior = Some(inferior);
-                        // Wake up the inferior
-                        self.cont();
-                    } else {
-                        println!("Error starting subprocess");
-                    }
-                }
-                DebuggerCommand::Quit => {
-                    if self.inferior.is_some() && 
-                        self.inferior.as_mut().unwrap().running().unwrap() {
-                        self.inferior.as_mut().unwrap().kill().unwrap();
-                    }
-                    return;
-                },
-                DebuggerCommand::Continue => {
-                    self.cont();
-                },
-                DebuggerCommand::Backtrace => {
-                    if let Some(inferior) = &self.inferior {
-                        match inferior.print_backtrace(&self.debug_data) {
-                            Err(e) => {
-                                println!("Error printing backtrace: {:?}", e);
-                            },
-                            _ => { }
-                        }
-                    }
-                },
-                DebuggerCommand::Breakpoint(token) => {
-                    self.set_bp(token);
-                },
-                DebuggerCommand::Next => {
-                    
-                    if let Some(inferior) = &self.inferior {
-                        let line_number = self.debug_data.get_line_from_addr(
-                            inferior.get_rip().unwrap()
-                        );
-                        if line_number.is_none() {
-                            println!("Error get current line number");
-                            break;
-                        }
-                        let old_line_number = line_number.unwrap();
-                        
-                        loop {
-                            match self.inferior.as_mut().unwrap().step() {
-                                Err(e) => {
-                                    println!("Error next command: {:?}", e);
-                                },
-                                Ok(status) => {
-                                    match status {
-                                        Status::Exited(code) => {
-                                            println!("Child exited (status {})", code);
-                                            self.inferior = None;
-                                            break;
-                                        },
-                                        Status::Signaled(signal) => {
-                                            println!("Child signaled (signal {})", signal);
-                                            self.inferior = None;
-                                            break;
-                                        },
-                                        Status::Stopped(signal, rip) => {
-                                            
-                                            if self.inferior_stopped_by_bp {
-                                                self.reset_bp(rip);
-                                                self.inferior_stopped_by_bp = false;
-                                            }
-                                            
-                                            if signal != nix::sys::signal::Signal::SIGTRAP {
-                                                println!("Child stopped (signal {})", signal);
-                                                self.print_code(rip);
-                                                break;
-                                            }
-
-                                            // println!("rip: {:#x}", rip);
-                                            if self.restore_bp(rip).is_some() {
-                                                // Stopped at a breakpoint
-                                                // println!("stopped at a breakpoint");
-                                                self.print_code(rip);
-                                                self.inferior_stopped_by_bp = true;
-                                                break;
-                                            } else {
-                                                // Just a step, get the line number
-                                                if let Some(line_number) = self.debug_data.get_line_from_addr(rip) {
-                                                    //println!("line_number: {}, old: {}", line_number.number, old_line_number.number);
-                                                    if line_number.number != old_line_number.number {
-                                                        // Reach the next line, stop.
-                                                        self.print_code(rip);
-                                                        break;
-                                                    }
-                                                }
-                                                // Continue to execute the next instruction.
-                                            }
-                                        }   
-                                    }
-                                }
-                            }
-                        }
-                    } else {
-                        println!("No running subprocess!");
-                    }
+    /// Kills the inferior, whether we spawned it or attached to it.
+    fn kill(&mut self) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        if let Err(e) = self.inferior.as_mut().unwrap().kill() {
+            println!("Error killing inferior: {}", e);
+        }
+        self.end_run();
+        self.inferior = None;
+    }
+
+    /// Removes all breakpoints from the inferior and lets it keep running untraced.
+    fn detach(&mut self) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        let inferior = self.inferior.as_mut().unwrap();
+        let pid = inferior.pid();
+        if let Err(e) = inferior.detach(&self.breakpoints) {
+            println!("Error detaching from process {}: {}", pid, e);
+            return;
+        }
+        if !inferior.is_attached() {
+            println!("Warning: process {} was started by deet and will keep running", pid);
+        }
+        println!("Detached from process {}", pid);
+        self.end_run();
+        self.inferior = None;
+    }
+
+    /// Attaches to the already running process with the given pid.
+    pub fn attach(&mut self, pid: &str) {
+        let pid = match pid.parse::<i32>() {
+            Ok(pid) if pid > 0 => Pid::from_raw(pid),
+            _ => {
+                println!("Invalid process id {}", pid);
+                return;
+            }
+        };
+        self.discard_inferior();
+        match Inferior::attach(pid).and_then(|inferior| self.adopt_inferior(inferior)) {
+            Ok(()) => {
+                println!("Attached to process {}", pid);
+                self.run_started = Some(Instant::now());
+                if let Ok(rip) = self.inferior.as_ref().unwrap().get_rip() {
+                    self.report_location(rip);
                 }
             }
+            Err(e) => println!("Error attaching to process {}: {}", pid, e),
         }
     }
 
-    /// This function prompts the user to enter a command, and continues re-prompting until the user
-    /// enters a valid command. It uses DebuggerCommand::from_tokens to do the command parsing.
-    ///
-    /// You don't need to read, understand, or modify this function.
-    fn get_next_command(&mut self) -> DebuggerCommand {
+    /// Takes charge of a newly started or attached inferior: moves the debugging symbols and
+    /// breakpoints to wherever the executable was loaded (which varies from run to run for a
+    /// position-independent executable), then installs the breakpoints. If that fails, the
+    /// inferior is let go.
+    fn adopt_inferior(&mut self, mut inferior: Inferior) -> Result<(), DeetError> {
+        if let Some(load_address) = inferior.load_address() {
+            let load_bias = load_address.wrapping_sub(self.debug_data.image_base());
+            let delta = load_bias.wrapping_sub(self.debug_data.load_bias());
+            if delta != 0 {
+                self.debug_data.relocate(load_bias);
+                self.breakpoints = self
+                    .breakpoints
+                    .drain()
+                    .map(|(addr, mut breakpoint)| {
+                        breakpoint.addr = addr.wrapping_add(delta);
+                        (breakpoint.addr, breakpoint)
+                    })
+                    .collect();
+                self.list_position = None;
+            }
+        }
+        if let Err(e) = inferior.install_breakpoints(&mut self.breakpoints) {
+            if inferior.is_attached() {
+                let _ = inferior.detach(&self.breakpoints);
+            } else {
+                let _ = inferior.kill();
+            }
+            return Err(e);
+        }
+        self.inferior = Some(inferior);
+        self.pending_signal = None;
+        Ok(())
+    }
+
+    /// Executes a single instruction. Returns the signal and %rip after the step, or None if the
+    /// inferior terminated, in which case self.inferior is cleared, or is still running after
+    /// the wait timeout.
+    fn step(&mut self) -> Option<(Signal, usize)> {
+        self.selected_frame = None;
+        let signal = self.pending_signal.take();
+        match self.inferior.as_mut().unwrap().step_resume(&self.breakpoints, signal) {
+            Ok(Status::Running) => {
+                self.report_still_running();
+                None
+            }
+            Ok(Status::Stopped(signal, rip)) => {
+                self.record_stop_signal(signal);
+                Some((signal, rip))
+            }
+            Ok(status @ Status::Exiting(_, _))
+            | Ok(status @ Status::Exec(_))
+            | Ok(status @ Status::Forked(_, _, _))
+            | Ok(status @ Status::Cloned(_)) => {
+                let (rip, _) = self.handle_event(status)?;
+                Some((Signal::SIGTRAP, rip))
+            }
+            Ok(status) => {
+                self.report_exit(status);
+                None
+            }
+            Err(e) => {
+                self.inferior_error("Error stepping inferior", e);
+                None
+            }
+        }
+    }
+
+    /// Wakes up the inferior and waits until it stops or terminates. Returns the signal that
+    /// stopped the inferior and its %rip, or None if it terminated (or couldn't be resumed), in
+    /// which case self.inferior is cleared, or if it's still running after the wait timeout.
+    /// When the inferior stops at a breakpoint, the returned %rip is the breakpoint address.
+    /// Breakpoints whose conditions are false don't stop it, except at `stop_at`.
+    fn resume(&mut self, stop_at: Option<usize>) -> Option<(Signal, usize)> {
+        self.selected_frame = None;
+        self.hit_temporary_bp = None;
+        let (_, thread_before) = self.inferior.as_ref().unwrap().current_thread();
         loop {
-            // Print prompt and get next line of user input
-            match self.readline.readline("(deet) ") {
-                Err(ReadlineError::Interrupted) => {
-                    // User pressed ctrl+c. We're going to ignore it
-                    println!("Type \"quit\" to exit");
+            let signal = self.pending_signal.take();
+            let status = self.inferior.as_mut().unwrap().continue_resume(&self.breakpoints, signal);
+            if let Ok(Status::Stopped(_, _)) | Ok(Status::Breakpoint(_)) = status {
+                let (number, tid) = self.inferior.as_ref().unwrap().current_thread();
+                if tid != thread_before {
+                    println!("[Switching to thread {} ({})]", number, tid);
                 }
-                Err(ReadlineError::Eof) => {
-                    // User pressed ctrl+d, which is the equivalent of "quit" for our purposes
-                    return DebuggerCommand::Quit;
+            }
+            match status {
+                Ok(Status::Running) => {
+                    self.report_still_running();
+                    return None;
                 }
-                Err(err) => {
-                    panic!("Unexpected I/O error: {:?}", err);
+                // A breakpoint whose condition is false is passed over, unless it's where the
+                // caller is headed anyway
+                Ok(Status::Breakpoint(addr)) if stop_at != Some(addr) && !self.condition_holds(addr) => {}
+                Ok(Status::Breakpoint(addr)) => {
+                    self.breakpoint_hit(addr);
+                    return Some((Signal::SIGTRAP, addr));
                 }
-                Ok(line) => {
-                    if line.trim().len() == 0 {
-                        continue;
-                    }
-                    self.readline.add_history_entry(line.as_str());
-                    if let Err(err) = self.readline.save_history(&self.history_path) {
-                        println!(
-                            "Warning: failed to save history file at {}: {}",
-                            self.history_path, err
-                        );
+                Ok(Status::Stopped(signal, rip)) => {
+                    self.record_stop_signal(signal);
+                    return Some((signal, rip));
+                }
+                Ok(status @ Status::Exiting(_, _))
+                | Ok(status @ Status::Exec(_))
+                | Ok(status @ Status::Forked(_, _, _))
+                | Ok(status @ Status::Cloned(_)) => {
+                    // Only a catchpoint stops there; otherwise carry on
+                    if let (rip, true) = self.handle_event(status)? {
+                        return Some((Signal::SIGTRAP, rip));
                     }
-                    let tokens: Vec<&str> = line.split_whitespace().collect();
-                    if let Some(cmd) = DebuggerCommand::from_tokens(&tokens) {
-                        return cmd;
-                    } else {
-                        println!("Unrecognized command.");
+                }
+                Ok(status) => {
+                    self.report_exit(status);
+                    return None;
+                }
+                Err(e) => {
+                    self.inferior_error("Error continuing subprocess", e);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Tells the user that the inferior is still running and how to stop it, after waiting for
+    /// it timed out.
+    fn report_still_running(&self) {
+        let waited = inferior::wait_timeout().unwrap_or_default();
+        println!("Child is still running after {}; use `interrupt` to stop it", format_duration(waited));
+    }
+
+    /// Stops the inferior running in the background with SIGINT and reports where it stopped.
+    fn interrupt(&mut self) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        if !self.inferior_still_running() {
+            println!("The program is not running.");
+            return;
+        }
+        if let Err(e) = self.inferior.as_ref().unwrap().interrupt() {
+            self.inferior_error("Error interrupting the inferior", e);
+            return;
+        }
+        self.cont();
+    }
+
+    /// Takes out the breakpoints run_until sets for itself, which are left behind if the
+    /// inferior was still running when it gave up waiting.
+    fn remove_internal_bps(&mut self) {
+        let internal: Vec<usize> =
+            self.breakpoints.values().filter(|bp| bp.internal).map(|bp| bp.addr).collect();
+        for addr in internal {
+            match self.inferior.as_mut() {
+                Some(inferior) if inferior.running() => {
+                    if inferior.remove_breakpoint(&mut self.breakpoints, addr).is_err() {
+                        println!("Error removing breakpoint at {:#x}", addr);
                     }
                 }
+                _ => {
+                    self.breakpoints.remove(&addr);
+                }
             }
         }
     }
 
-    fn set_bp(&mut self, token: String) {
+    /// Deals with the inferior stopping at a ptrace event: reports the catchpoint for it if
+    /// there is one, after exec, moves breakpoints and symbols over to the new program, and
+    /// after a fork, switches to the new process if following the child.
+    /// Returns the inferior's %rip and whether a catchpoint stopped it, or None if the inferior
+    /// was lost along the way, in which case self.inferior is cleared.
+    fn handle_event(&mut self, status: Status) -> Option<(usize, bool)> {
+        let (event, rip) = match status {
+            Status::Exiting(_, rip) => (CatchEvent::Exit, rip),
+            Status::Exec(rip) => {
+                self.follow_exec()?;
+                (CatchEvent::Exec, rip)
+            }
+            Status::Forked(child, shares_memory, rip) => {
+                self.follow_fork(child, shares_memory);
+                return Some((rip, false));
+            }
+            Status::Cloned(rip) => return Some((rip, false)),
+            _ => return None,
+        };
+        let catchpoint = match self.catchpoints.iter().find(|catchpoint| catchpoint.event == event) {
+            Some(catchpoint) => catchpoint,
+            None => return Some((rip, false)),
+        };
+        match status {
+            Status::Exiting(pending, _) => match *pending {
+                Status::Signaled(signal) => println!(
+                    "Catchpoint {} (exit), process about to be killed by signal {}",
+                    catchpoint.number, signal
+                ),
+                Status::Exited(code) => println!(
+                    "Catchpoint {} (exit), process about to exit with status {}",
+                    catchpoint.number, code
+                ),
+                _ => {}
+            },
+            _ => println!("Catchpoint {} (exec)", catchpoint.number),
+        }
+        Some((rip, true))
+    }
 
-        let bp_addr: Option<usize>;
-        if token.starts_with("*") {
-            // address
-            bp_addr = Debugger::parse_address(&token[1..]);
-        } else if let Some(line_number) = token.parse::<usize>().ok() {
-            // line number
-            bp_addr = self.debug_data.get_addr_for_line(None, line_number);
-        } else {
-            // function name
-            bp_addr = self.debug_data.get_addr_for_function(None, &token);
+    /// Called when the inferior has forked `child`, which is stopped. Depending on
+    /// follow-fork-mode, either the child is detached from, or the parent is and the child
+    /// becomes the inferior. Either way, the process let go gets its breakpoints taken out,
+    /// unless it shares memory with the other one (after vfork).
+    fn follow_fork(&mut self, child: Pid, shares_memory: bool) {
+        let no_breakpoints = HashMap::new();
+        let breakpoints = if shares_memory { &no_breakpoints } else { &self.breakpoints };
+        let parent = self.inferior.as_ref().unwrap().pid();
+        let mut detached = match self.follow_fork_mode {
+            FollowForkMode::Parent => {
+                println!("Detaching after fork from child process {}", child);
+                self.inferior.as_ref().unwrap().forked(child)
+            }
+            FollowForkMode::Child => {
+                println!("Attaching after process {} fork to child process {}", parent, child);
+                let followed = self.inferior.as_ref().unwrap().forked(child);
+                self.inferior.replace(followed).unwrap()
+            }
+        };
+        if let Err(e) = detached.detach(breakpoints) {
+            println!("Error detaching from process {}: {}", detached.pid(), e);
         }
+        self.detached_processes.push((detached.pid(), "detached after fork"));
+    }
 
-        if bp_addr.is_none() {
-            println!("Invalid breakpoint!");
-            return;
+    /// Called when the inferior has called exec. The breakpoints in its memory went with the old
+    /// program, so they're put into the new one, after reading its symbols if it's a different
+    /// program. Returns None if the inferior couldn't be kept.
+    fn follow_exec(&mut self) -> Option<()> {
+        let inferior = self.inferior.take().unwrap();
+        let program = fs::read_link(format!("/proc/{}/exe", inferior.pid()))
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| self.target.clone());
+        println!("process {} is executing new program: {}", inferior.pid(), program);
+        let same_program = fs::canonicalize(&self.target)
+            .is_ok_and(|target| target.to_string_lossy() == program);
+        if !same_program {
+            match Debugger::load_debug_data(&program) {
+                Ok(debug_data) => {
+                    self.target = program.clone();
+                    self.target_stamp = Debugger::file_stamp(&program);
+                    self.debug_data = debug_data;
+                    self.rebind_breakpoints();
+                }
+                Err(e) => {
+                    println!("{}; deleting all breakpoints", e);
+                    self.breakpoints.clear();
+                }
+            }
         }
-        
-        let addr = bp_addr.unwrap();
-        let mut breakpoint = Breakpoint { addr: addr, orig_byte: 0};
-                
-        if self.inferior.is_some() {
+        if let Err(e) = self.adopt_inferior(inferior) {
+            println!("Error inserting breakpoints into the new program: {}", e);
+            self.end_run();
+            return None;
+        }
+        Some(())
+    }
+
+    /// Resumes the inferior until it reaches `addr` in a frame whose %rsp is at least `frame_sp`
+    /// (so that deeper recursive calls passing through `addr` don't count). Returns true once it
+    /// gets there; if the inferior stops anywhere else first, the stop is reported and false is
+    /// returned.
+    fn run_until(&mut self, addr: usize, frame_sp: usize) -> bool {
+        // Only set a temporary breakpoint if the user doesn't have one at the address already;
+        // theirs must survive this.
+        let temporary = !self.breakpoints.contains_key(&addr);
+        if temporary {
             match self.inferior.as_mut().unwrap().write_byte(addr, 0xcc) {
-                Ok(orig_byte) => { breakpoint.orig_byte = orig_byte },
+                Ok(orig_byte) => {
+                    let breakpoint = Breakpoint {
+                        addr,
+                        orig_byte,
+                        number: 0,
+                        location_number: None,
+                        temporary: false,
+                        internal: true,
+                        commands: Vec::new(),
+                        location: format!("*{:#x}", addr),
+                        force: false,
+                        line: None,
+                        condition: None,
+                    };
+                    self.breakpoints.insert(addr, breakpoint);
+                }
                 Err(_) => {
-                    println!("Error setting breakpoint at {}", addr);
-                    return;
+                    println!("Error setting breakpoint at {:#x}", addr);
+                    return false;
+                }
+            }
+        }
+
+        let reached = loop {
+            let (signal, rip) = match self.resume(Some(addr)) {
+                Some(stop) => stop,
+                None => break false,
+            };
+            if signal == Signal::SIGTRAP && rip == addr {
+                // A deeper recursive call passing through the same address has a lower %rsp
+                let rsp = self.inferior.as_ref().unwrap().get_rsp().unwrap_or(0);
+                if rsp >= frame_sp {
+                    break true;
+                }
+                if temporary {
+                    continue;
+                }
+            }
+            self.report_stop(signal, rip);
+            break false;
+        };
+
+        if temporary {
+            match self.inferior.as_mut() {
+                // Taken out by remove_internal_bps once the inferior has stopped
+                Some(inferior) if inferior.still_running() => {}
+                Some(inferior) => {
+                    if inferior.remove_breakpoint(&mut self.breakpoints, addr).is_err() {
+                        println!("Error removing breakpoint at {:#x}", addr);
+                    }
+                }
+                None => {
+                    self.breakpoints.remove(&addr);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Returns the value %rsp will have once the function containing `rip` has returned, which
+    /// identifies the function's stack frame.
+    fn frame_sp(&self, rip: usize) -> Option<usize> {
+        let func = self.debug_data.get_function_containing(rip)?;
+        let (_, frame_sp) = self.inferior.as_ref()?.get_return_address(func.address).ok()?;
+        Some(frame_sp)
+    }
+
+    /// Prints the environment the inferior will be launched with, or just the variable `name`.
+    fn show_env(&self, name: Option<&str>) {
+        let mut env: BTreeMap<String, String> = std::env::vars().collect();
+        for (var, value) in &self.launch_settings.env {
+            match value {
+                Some(value) => env.insert(var.clone(), value.clone()),
+                None => env.remove(var),
+            };
+        }
+        match name {
+            Some(name) => match env.get(name) {
+                Some(value) => println!("{} = {}", name, value),
+                None => println!("Environment variable \"{}\" not defined.", name),
+            },
+            None => {
+                for (var, value) in &env {
+                    println!("{}={}", var, value);
                 }
             }
         }
-        
-        println!("Set breakpoint {} at {:#x}", self.breakpoints.len(), addr);
-        
-        breakpoint.addr = addr;
-        self.breakpoints.insert(addr, breakpoint);
-        return;
+    }
+
+    /// Sets the working directory the inferior will be launched in. A relative `dir` is relative
+    /// to the previous one.
+    fn cd(&mut self, dir: &str) {
+        let base = match &self.launch_settings.cwd {
+            Some(cwd) => cwd.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        match std::fs::canonicalize(base.join(dir)) {
+            Ok(path) if path.is_dir() => {
+                println!("Working directory {}.", path.display());
+                self.launch_settings.cwd = Some(path);
+            }
+            Ok(path) => println!("{}: Not a directory.", path.display()),
+            Err(e) => println!("{}: {}.", dir, e),
+        }
+    }
+
+    /// Remembers the signal that stopped the inferior so it's delivered when the inferior is
+    /// resumed, as it would have been without the debugger. SIGTRAPs are ours (breakpoints and
+    /// single steps), so they never are.
+    fn record_stop_signal(&mut self, signal: Signal) {
+        if signal != Signal::SIGTRAP {
+            self.stats.signals += 1;
+        }
+        self.pending_signal = if signal == Signal::SIGTRAP || self.nopass_signals.contains(&signal) {
+            None
+        } else {
+            Some(signal)
+        };
+    }
+
+    /// Sets the signal delivered when the inferior is next resumed. `signal 0` cancels it.
+    fn set_pending_signal(&mut self, name: &str) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        if name == "0" {
+            self.pending_signal = None;
+            println!("No signal will be delivered on the next resume");
+            return;
+        }
+        match parse_signal(name) {
+            Some(signal) => {
+                self.pending_signal = Some(signal);
+                println!("Signal {} will be delivered on the next resume", signal);
+            }
+            None => println!("Unknown signal \"{}\"", name),
+        }
+    }
+
+    /// Sets whether `name` is passed on to the inferior when it stops it, or shows the setting.
+    fn handle_signal(&mut self, name: &str, action: Option<&str>) {
+        let signal = match parse_signal(name) {
+            Some(signal) => signal,
+            None => {
+                println!("Unknown signal \"{}\"", name);
+                return;
+            }
+        };
+        match action {
+            Some("pass") => {
+                self.nopass_signals.remove(&signal);
+            }
+            Some("nopass") => {
+                self.nopass_signals.insert(signal);
+            }
+            Some(other) => {
+                println!("Unrecognized action \"{}\" (expected pass or nopass)", other);
+                return;
+            }
+            None => {}
+        }
+        let pass = if self.nopass_signals.contains(&signal) { "No" } else { "Yes" };
+        println!("Signal\tPass to program\n{}\t{}", signal, pass);
+    }
+
+    /// Prints how the inferior terminated and how long it ran for, and forgets about it.
+    fn report_exit(&mut self, status: Status) {
+        let how = match status {
+            Status::Exited(code) => {
+                self.exit_code = Some(code);
+                format!("exited (status {})", code)
+            }
+            Status::Signaled(signal) => format!("signaled (signal {})", output::signal(signal.as_str())),
+            // Still alive
+            _ => return,
+        };
+        println!("Child {} after {}", how, format_duration(self.end_run()));
+        self.inferior = None;
+        self.pending_signal = None;
+    }
+
+    /// Adds the time since the current inferior started to the session's runtime, and returns
+    /// that time. The inferior is about to be let go of.
+    fn end_run(&mut self) -> Duration {
+        let elapsed = self.run_started.take().map_or(Duration::ZERO, |started| started.elapsed());
+        self.stats.runtime += elapsed;
+        elapsed
+    }
+
+    /// Prints the counts kept over this session, and how long inferiors have run for in total.
+    fn info_stats(&self) {
+        let current = self.run_started.map_or(Duration::ZERO, |started| started.elapsed());
+        println!("Runs: {}", self.stats.runs);
+        println!("Breakpoint hits: {}", self.stats.breakpoint_hits);
+        println!("Signals: {}", self.stats.signals);
+        println!("Total runtime: {}", format_duration(self.stats.runtime + current));
+    }
+
+    /// Reports that the inferior was stopped by `signal` at `rip`: hitting a breakpoint is
+    /// reported as such, any other signal by name. Then shows where it stopped.
+    fn report_stop(&mut self, signal: Signal, rip: usize) {
+        if signal != Signal::SIGTRAP || self.stopped_breakpoint(rip).is_none() {
+            println!("Child stopped (signal {})", output::signal(signal.as_str()));
+        }
+        self.report_location(rip);
+    }
+
+    /// Prints where the stopped inferior is, as `Stopped at <function> (<file>:<line>)` (prefixed
+    /// with the breakpoint if there's one at `rip`) and the source line, then shows the display
+    /// expressions.
+    fn report_location(&mut self, rip: usize) {
+        let function = self.debug_data.get_function_from_addr(rip);
+        let line = self.debug_data.get_line_from_addr(rip);
+        let place = match &line {
+            Some(line) => output::location(&line.to_string()),
+            None => output::location(&format!("{:#x}", rip)),
+        };
+        let location = match (function, &line) {
+            (Some(function), _) => format!("{} ({})", output::function(&function), place),
+            (None, Some(_)) => format!("{:#x} ({})", rip, place),
+            // Without symbols, the address is all there is to go on
+            (None, None) => place,
+        };
+        match self.stopped_breakpoint(rip).filter(|breakpoint| !breakpoint.internal) {
+            Some(breakpoint) => {
+                println!("Breakpoint {}, Stopped at {}", output::breakpoint(&breakpoint.label()), location)
+            }
+            None => println!("Stopped at {}", location),
+        }
+        if line.is_some() {
+            self.print_code(rip);
+        }
+        self.show_displays();
+    }
+
+    /// Returns the breakpoint at `rip`, where the inferior is stopped. That includes a temporary
+    /// one that was deleted when it was hit.
+    fn stopped_breakpoint(&self, rip: usize) -> Option<&Breakpoint> {
+        let temporary = self.hit_temporary_bp.as_ref().filter(|breakpoint| breakpoint.addr == rip);
+        self.breakpoints.get(&rip).or(temporary)
+    }
+
+    /// Resumes the inferior and reports where it stops. Returns true if it stopped at a
+    /// breakpoint, as opposed to terminating or receiving some other signal.
+    fn cont(&mut self) -> bool {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return false;
+        }
+
+        match self.resume(None) {
+            Some((signal, rip)) => {
+                self.report_stop(signal, rip);
+                signal == Signal::SIGTRAP
+            }
+            None => false,
+        }
+    }
+
+    /// Runs `command` (`continue`, `next`, `stepi` or `nexti`) up to `count` times. Each run returns whether the
+    /// inferior stopped the usual way for that command; anything else (the inferior exiting, a
+    /// signal, or stepping hitting a breakpoint) ends the repetition early.
+    fn repeat(&mut self, name: &str, count: usize, command: fn(&mut Debugger) -> bool) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        for done in 1..=count {
+            if !command(self) {
+                if done < count {
+                    println!("{} stopped after {} of {} repetitions", name, done, count);
+                }
+                return;
+            }
+        }
+    }
+
+    /// Runs until the current function returns, then prints where it returned to and the
+    /// returned value.
+    fn finish(&mut self) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+
+        let inferior = self.inferior.as_ref().unwrap();
+        let rip = match inferior.get_rip() {
+            Ok(rip) => rip,
+            Err(e) => {
+                self.inferior_error("Error reading registers", e);
+                return;
+            }
+        };
+        let func = match self.debug_data.get_function_containing(rip) {
+            Some(func) => func.clone(),
+            None => {
+                println!("Cannot find the function containing {:#x}", rip);
+                return;
+            }
+        };
+        if func.name == "main" {
+            println!("\"finish\" not meaningful in the outermost frame.");
+            return;
+        }
+        let (ret_addr, frame_sp) = match inferior.get_return_address(func.address) {
+            Ok(val) => val,
+            Err(e) => {
+                println!("Error reading return address: {}", e);
+                return;
+            }
+        };
+
+        println!("Run till exit from {}", func.name);
+        let returned = self.run_until(ret_addr, frame_sp);
+
+        if returned {
+            let caller = self
+                .debug_data
+                .get_function_from_addr(ret_addr)
+                .unwrap_or_else(|| "??".to_string());
+            match self.debug_data.get_line_from_addr(ret_addr) {
+                Some(line) => println!("Returned to {} ({})", caller, line),
+                None => println!("Returned to {} ({:#x})", caller, ret_addr),
+            }
+            self.print_code(ret_addr);
+            if let Some(return_type) = &func.return_type {
+                if return_type.name == "float" || return_type.name == "double" {
+                    println!("Value returned is in %xmm0, which deet can't read");
+                } else if let Ok(rax) = self.inferior.as_ref().unwrap().get_rax() {
+                    println!("Value returned: {}", return_type.format_value(rax));
+                }
+            }
+            self.show_displays();
+        }
+    }
+
+    /// Moves the stopped inferior's %rip, as if it had stopped there: a breakpoint at the new
+    /// address is stepped over rather than hit when the inferior resumes.
+    fn move_rip(&mut self, rip: usize) -> Result<(), DeetError> {
+        self.selected_frame = None;
+        self.inferior.as_mut().unwrap().jump(&self.breakpoints, rip)
+    }
+
+    /// Moves the inferior to `location` without running anything in between. Jumping out of the
+    /// current function leaves the stack meant for it, so that needs confirming.
+    fn jump(&mut self, location: &str) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        let addr = match self.resolve_location(location) {
+            Ok(addr) => addr,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let rip = match self.inferior.as_ref().unwrap().get_rip() {
+            Ok(rip) => rip,
+            Err(e) => {
+                self.inferior_error("Error reading registers", e);
+                return;
+            }
+        };
+        let current = self.debug_data.get_function_containing(rip).map(|func| func.address);
+        let destination = self.debug_data.get_function_containing(addr);
+        if current.is_none() || destination.map(|func| func.address) != current {
+            let question = format!(
+                "{} is not in the current function (it's in {}). Jump anyway? (y/n) ",
+                location,
+                destination.map_or("??", |func| func.name.as_str())
+            );
+            if !self.confirm(&question, false) {
+                println!("Not confirmed.");
+                return;
+            }
+        }
+        if let Err(e) = self.move_rip(addr) {
+            println!("Error setting %rip: {}", e);
+            return;
+        }
+        self.report_location(addr);
+    }
+
+    /// Pops the innermost frame, so that its function returns to its caller right away, with
+    /// `value` in %rax if given. Asks first.
+    fn force_return(&mut self, value: Option<&str>) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        let inferior = self.inferior.as_ref().unwrap();
+        let mut regs = match inferior.get_regs() {
+            Ok(regs) => regs,
+            Err(e) => {
+                self.inferior_error("Error reading registers", e);
+                return;
+            }
+        };
+        let rip = regs.rip as usize;
+        let func = match self.debug_data.get_function_containing(rip) {
+            Some(func) => func.clone(),
+            None => {
+                println!("Cannot find the function containing {:#x}", rip);
+                return;
+            }
+        };
+        if func.name == "main" {
+            println!("\"return\" not meaningful in the outermost frame.");
+            return;
+        }
+        let raw_value = match value {
+            Some(value) => {
+                let value_type = func.return_type.clone().unwrap_or_else(|| Type::new("long".to_string(), 8));
+                if value_type.name == "float" || value_type.name == "double" {
+                    println!("Returning {} values isn't supported; they go in %xmm0", value_type.name);
+                    return;
+                }
+                match value_type.parse_value(value) {
+                    Ok(raw) => Some(raw),
+                    Err(e) => {
+                        println!("{}", e);
+                        return;
+                    }
+                }
+            }
+            None => None,
+        };
+        let (ret_addr, frame_sp) = match inferior.get_return_address(func.address) {
+            Ok(val) => val,
+            Err(e) => {
+                println!("Error reading return address: {}", e);
+                return;
+            }
+        };
+        // Once the prologue has run, the caller's %rbp is saved at the bottom of the frame
+        if rip != func.address && rip != func.address + 1 {
+            regs.rbp = match inferior.read_word(regs.rbp as usize) {
+                Ok(rbp) => rbp as u64,
+                Err(e) => {
+                    println!("Error reading the saved %rbp: {}", e);
+                    return;
+                }
+            };
+        }
+        regs.rsp = frame_sp as u64;
+        if let Some(raw_value) = raw_value {
+            regs.rax = raw_value;
+        }
+
+        if !self.confirm(&format!("Make {} return now? (y/n) ", func.name), false) {
+            println!("Not confirmed.");
+            return;
+        }
+        let popped = self.inferior.as_ref().unwrap().set_regs(regs);
+        if let Err(e) = popped.and_then(|_| self.move_rip(ret_addr)) {
+            println!("Error popping the frame: {}", e);
+            return;
+        }
+        println!("{}", self.describe_frame(0, ret_addr, regs.rbp as usize));
+        self.print_code(ret_addr);
+    }
+
+    /// Continues until the inferior reaches `location`, or stops for some other reason first.
+    fn advance(&mut self, location: &str) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        let addr = match self.resolve_location(location) {
+            Ok(addr) => addr,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        // Any frame will do
+        if self.run_until(addr, 0) {
+            self.report_location(addr);
+        }
+    }
+
+    /// Steps until execution reaches a different source line in the current function or the
+    /// function returns, stepping over any function calls along the way. With `forward_only`
+    /// (bare `until`), lines before the starting one don't count, so jumping back to the top
+    /// of a loop keeps going until the loop is done. Returns true if it stopped at a new line,
+    /// rather than at a breakpoint, a signal, or the inferior's exit.
+    fn next(&mut self, forward_only: bool) -> bool {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return false;
+        }
+        let rip = match self.inferior.as_ref().unwrap().get_rip() {
+            Ok(rip) => rip,
+            Err(e) => {
+                self.inferior_error("Error reading registers", e);
+                return false;
+            }
+        };
+        let line = match self.debug_data.get_line_from_addr(rip) {
+            Some(line) => line,
+            None => {
+                println!("Cannot find line information for {:#x}", rip);
+                return false;
+            }
+        };
+        let frame_sp = match self.frame_sp(rip) {
+            Some(frame_sp) => frame_sp,
+            None => {
+                println!("Cannot find the function containing {:#x}", rip);
+                return false;
+            }
+        };
+
+        loop {
+            let prev_regs = match self.inferior.as_ref().unwrap().get_regs() {
+                Ok(regs) => regs,
+                Err(e) => {
+                    self.inferior_error("Error reading registers", e);
+                    return false;
+                }
+            };
+            let (signal, mut rip) = match self.step() {
+                Some(stop) => stop,
+                None => return false,
+            };
+            if signal != Signal::SIGTRAP {
+                self.report_stop(signal, rip);
+                return false;
+            }
+
+            // A call pushes the address of the instruction after it. Run until the callee
+            // returns there rather than stepping through it; that may already be the next line.
+            // A breakpoint on the callee's first instruction stops us there instead.
+            let inferior = self.inferior.as_ref().unwrap();
+            let rsp = inferior.get_rsp().unwrap_or(0);
+            let prev_rip = prev_regs.rip as usize;
+            let ret_addr = if rsp + 8 == prev_regs.rsp as usize && !self.breakpoints.contains_key(&rip) {
+                inferior.read_word(rsp).ok()
+                    .filter(|&addr| addr > prev_rip && addr <= prev_rip + MAX_INSTRUCTION_LEN)
+            } else {
+                None
+            };
+            if let Some(ret_addr) = ret_addr {
+                if !self.run_until(ret_addr, rsp + 8) {
+                    return false;
+                }
+                rip = ret_addr;
+            } else if rsp >= frame_sp {
+                // The function returned
+                if self.debug_data.get_line_from_addr(rip).is_some() {
+                    self.report_location(rip);
+                    return true;
+                }
+                // Returned into code without debugging information (e.g. main returning
+                // into libc), so there are no more lines to stop at
+                self.cont();
+                return false;
+            }
+
+            if self.breakpoints.contains_key(&rip) {
+                // About to execute a breakpoint; stop as if it had been hit
+                self.stop_at_bp(rip);
+                self.report_location(rip);
+                return false;
+            }
+            match self.debug_data.get_line_from_addr(rip) {
+                Some(new_line) if new_line.file != line.file
+                    || new_line.number > line.number
+                    || (new_line.number < line.number && !forward_only) =>
+                {
+                    self.report_location(rip);
+                    return true;
+                }
+                // Still on the same line, or in code without line information: keep stepping
+                _ => {}
+            }
+        }
+    }
+
+    /// Executes a single instruction, then prints the new %rip and its source line. With
+    /// `over_calls` (`nexti`), a call instruction runs until the called function returns. Returns
+    /// true if it stopped after the instruction, rather than at a breakpoint, a signal, or the
+    /// inferior's exit.
+    fn step_instruction(&mut self, over_calls: bool) -> bool {
+        let inferior = self.inferior.as_ref().unwrap();
+        let (rip, rsp) = match (inferior.get_rip(), inferior.get_rsp()) {
+            (Ok(rip), Ok(rsp)) => (rip, rsp),
+            (Err(e), _) | (_, Err(e)) => {
+                self.inferior_error("Error reading registers", e);
+                return false;
+            }
+        };
+
+        let call_len = if over_calls {
+            let bytes = self.read_code(rip, MAX_INSTRUCTION_LEN);
+            let instruction = Decoder::with_ip(64, &bytes, rip as u64, DecoderOptions::NONE).decode();
+            if instruction.mnemonic() == Mnemonic::Call {
+                Some(instruction.len())
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+        let rip = match call_len {
+            Some(len) => {
+                // The call returns to the next instruction with %rsp back where it is now
+                if !self.run_until(rip + len, rsp) {
+                    return false;
+                }
+                rip + len
+            }
+            None => {
+                let (signal, rip) = match self.step() {
+                    Some(stop) => stop,
+                    None => return false,
+                };
+                if signal != Signal::SIGTRAP {
+                    self.report_stop(signal, rip);
+                    return false;
+                }
+                rip
+            }
+        };
+
+        let at_bp = self.breakpoints.contains_key(&rip);
+        if at_bp {
+            // About to execute a breakpoint; stop as if it had been hit
+            self.stop_at_bp(rip);
+        }
+        match self.debug_data.get_function_containing(rip) {
+            Some(func) => println!("{:#x} <{}+{}>", rip, func.name, rip - func.address),
+            None => println!("{:#x}", rip),
+        }
+        self.report_location(rip);
+        !at_bp
+    }
+
+    /// Prints the given registers of the stopped inferior, or all of them if `names` is empty.
+    fn info_registers(&mut self, names: &[String]) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        let mut regs = match self.inferior.as_ref().unwrap().get_regs() {
+            Ok(regs) => regs,
+            Err(e) => {
+                self.inferior_error("Error reading registers", e);
+                return;
+            }
+        };
+        // In an outer frame, show the %rip and %rbp that frame will have once it's resumed
+        if let Some(frame) = &self.selected_frame {
+            regs.rip = frame.rip as u64;
+            regs.rbp = frame.rbp as u64;
+        }
+        let names: Vec<&str> = if names.is_empty() {
+            REGISTER_NAMES.to_vec()
+        } else {
+            names.iter().map(|name| name.trim_start_matches('$')).collect()
+        };
+        for name in names {
+            let value = match register_value(&regs, name) {
+                Some(value) => value,
+                None => {
+                    println!("Invalid register `{}'", name);
+                    continue;
+                }
+            };
+            let natural = match name {
+                "eflags" => Debugger::format_eflags(value),
+                "rip" => match self.debug_data.get_function_containing(value as usize) {
+                    Some(func) => format!("<{}+{}>", func.name, value as usize - func.address),
+                    None => String::new(),
+                },
+                "rsp" | "rbp" => String::new(),
+                _ => format!("{}", value as i64),
+            };
+            // The name and value take 28 columns; the rest is cut to fit the terminal
+            let natural = output::truncate(&natural, output::terminal_width().saturating_sub(28));
+            println!("{}", format!("{:<8}{:#018x}  {}", name, value, natural).trim_end());
+        }
+    }
+
+    /// Returns the canonical frame address of frame `number` (0 being the innermost): the value
+    /// %rsp had before the call that created the frame. gcc describes local variable locations
+    /// relative to it.
+    fn frame_base(&self, number: usize, rip: usize, rbp: usize) -> Option<usize> {
+        if number == 0 {
+            self.frame_sp(rip)
+        } else {
+            // Outer frames are past their prologue, so the saved %rbp and the return address
+            // sit right below the frame base
+            Some(rbp + 16)
+        }
+    }
+
+    /// Returns the address of a variable of the frame with the given frame base, or None if it
+    /// isn't in memory.
+    fn variable_address(var: &Variable, frame_base: Option<usize>) -> Option<usize> {
+        match var.location {
+            Location::Address(addr) => Some(addr),
+            Location::FramePointerOffset(offset) => Some((frame_base? as isize + offset) as usize),
+            Location::Unavailable => None,
+        }
+    }
+
+    /// Reads a variable of the frame with the given frame base, and formats it according to its
+    /// type. Returns None if the value can't be found or its type isn't supported.
+    fn read_variable(&self, var: &Variable, frame_base: Option<usize>) -> Option<String> {
+        let addr = Debugger::variable_address(var, frame_base)?;
+        self.read_value(addr, &var.entity_type, ValueFormat::Natural)
+    }
+
+    /// Reads a value of the given type at `addr` and formats it. Returns None if the memory can't
+    /// be read or the type isn't supported.
+    fn read_value(&self, addr: usize, value_type: &Type, format: ValueFormat) -> Option<String> {
+        // Only the elements of an array that will be shown need to be read
+        let len = match &value_type.kind {
+            TypeKind::Struct(members) => {
+                // Members that can't be shown are marked, but the struct itself must be there
+                self.inferior.as_ref()?.read_memory(addr, value_type.size)?;
+                let members: Vec<String> = members
+                    .iter()
+                    .map(|member| {
+                        let value = self
+                            .read_value(addr + member.offset, &member.member_type, format)
+                            .unwrap_or_else(|| "<unavailable>".to_string());
+                        format!("{} = {}", member.name, value)
+                    })
+                    .collect();
+                return Some(format!("{{{}}}", members.join(", ")));
+            }
+            // Elements that need more than their own bytes to format are read one by one
+            TypeKind::Array(element, count) if matches!(element.kind, TypeKind::Struct(_) | TypeKind::Str { .. }) => {
+                let shown = (*count).min(MAX_ARRAY_ELEMENTS);
+                let mut elements = (0..shown)
+                    .map(|index| self.read_value(addr + index * element.size, element, format))
+                    .collect::<Option<Vec<String>>>()?;
+                if *count > shown {
+                    elements.push("...".to_string());
+                }
+                return Some(format!("[{}]", elements.join(", ")));
+            }
+            TypeKind::Array(element, count) => element.size * (*count).min(MAX_ARRAY_ELEMENTS),
+            TypeKind::Str { ptr_offset, len_offset } => return self.read_str(addr + ptr_offset, addr + len_offset).ok(),
+            _ if value_type.size > 8 => return None,
+            _ => value_type.size,
+        };
+        if value_type.size == 0 {
+            return None;
+        }
+        let bytes = self.inferior.as_ref()?.read_memory(addr, len)?;
+        Some(format_bytes(&bytes, value_type, format))
+    }
+
+    /// Reads a Rust string whose data pointer and length are at `ptr_addr` and `len_addr`, and
+    /// formats its contents (up to MAX_STRING_LEN bytes of them) along with its length.
+    fn read_str(&self, ptr_addr: usize, len_addr: usize) -> Result<String, DeetError> {
+        let inferior = self.inferior.as_ref().ok_or(DeetError::NoInferior)?;
+        let ptr = inferior.read_word(ptr_addr)?;
+        let len = inferior.read_word(len_addr)?;
+        let bytes = inferior.read_memory(ptr, len.min(MAX_STRING_LEN)).ok_or(DeetError::BadAddress(ptr))?;
+        Ok(format_string(&bytes, len))
+    }
+
+    /// Prints the value of an expression, as seen from the selected frame.
+    fn print(&mut self, format: ValueFormat, expr: &str) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        match self.evaluate(format, expr) {
+            Ok(value) => println!("{} = {}", expr, value),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    /// Evaluates an expression with the variables seen from the selected frame, and formats its
+    /// value. The inferior must be alive.
+    fn evaluate(&self, format: ValueFormat, expr: &str) -> Result<String, String> {
+        let lookup = |name: &str| self.locate_variable(name);
+        let value = expression::evaluate(expr, &lookup, self.inferior.as_ref().unwrap())?;
+        let (addr, value_type) = match value.place {
+            Place::Memory(addr) => (addr, value.value_type),
+            Place::Immediate(raw) => return Ok(format_bytes(&raw.to_le_bytes(), &value.value_type, format)),
+        };
+        if let TypeKind::Str { ptr_offset, len_offset } = value_type.kind {
+            // Say which pointer couldn't be followed, rather than that the string isn't supported
+            return self.read_str(addr + ptr_offset, addr + len_offset).map_err(|e| e.to_string());
+        }
+        match self.read_value(addr, &value_type, format) {
+            Some(value) => Ok(value),
+            None if value_type.size > 8 && matches!(value_type.kind, TypeKind::Base) => {
+                Err(format!("Printing values of type {} isn't supported", value_type.name))
+            }
+            None => Err(DeetError::BadAddress(addr).to_string()),
+        }
+    }
+
+    /// Prints a display expression, gdb-style: "1: /x total = 0xa".
+    fn show_display(&self, display: &Display) {
+        let value = self
+            .evaluate(display.format, &display.expr)
+            .unwrap_or_else(|_| "<not available in this frame>".to_string());
+        println!("{}: {} = {}", display.number, display.describe(), value);
+    }
+
+    /// Prints every display expression, if the inferior is alive to read them from.
+    fn show_displays(&self) {
+        if !self.inferior_alive() {
+            return;
+        }
+        for display in &self.displays {
+            self.show_display(display);
+        }
+    }
+
+    /// Adds a display expression, and shows it right away if the inferior is alive.
+    fn add_display(&mut self, format: ValueFormat, expr: String) {
+        let display = Display { number: self.next_display_number, format, expr };
+        self.next_display_number += 1;
+        if self.inferior_alive() {
+            self.show_display(&display);
+        }
+        self.displays.push(display);
+    }
+
+    /// Removes display `number`, or every display expression if None.
+    fn undisplay(&mut self, number: Option<usize>) {
+        match number {
+            None => self.displays.clear(),
+            Some(number) => match self.displays.iter().position(|display| display.number == number) {
+                Some(index) => {
+                    self.displays.remove(index);
+                }
+                None => println!("No display number {}.", number),
+            },
+        }
+    }
+
+    fn list_displays(&self) {
+        if self.displays.is_empty() {
+            println!("There are no auto-display expressions now.");
+            return;
+        }
+        println!("Auto-display expressions now in effect:\nNum\tExpression");
+        for display in &self.displays {
+            println!("{}\t{}", display.number, display.describe());
+        }
+    }
+
+    /// Returns the frame base of the selected frame.
+    fn selected_frame_base(&self) -> Option<usize> {
+        match &self.selected_frame {
+            Some(frame) => self.frame_base(frame.number, frame.rip, frame.rbp),
+            None => self.frame_base(0, self.current_rip()?, 0),
+        }
+    }
+
+    /// Finds the variable `name` as seen from the selected frame: a local or parameter of its
+    /// function, or else a global. Returns the variable's address and type.
+    fn locate_variable(&self, name: &str) -> Result<(usize, Type), String> {
+        if !self.debug_data.has_symbols() {
+            return Err(DeetError::NoDebugInfo.to_string());
+        }
+        let local = self.current_rip()
+            .and_then(|rip| self.debug_data.get_function_containing(rip))
+            .and_then(|func| func.variables.iter().find(|var| var.name == name));
+        let var = match local.or_else(|| self.debug_data.get_global_variable(name)) {
+            Some(var) => var,
+            None => return Err(format!("No symbol \"{}\" in current context.", name)),
+        };
+        match Debugger::variable_address(var, self.selected_frame_base()) {
+            Some(addr) => Ok((addr, var.entity_type.clone())),
+            None => Err(format!("\"{}\" is not available here", name)),
+        }
+    }
+
+    /// Assigns `value` to the variable `name` in the selected frame, or to a register if `name`
+    /// starts with `$`.
+    fn set_variable(&mut self, name: &str, value: &str) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        if let Some(register) = name.strip_prefix('$') {
+            let value = match Type::new("unsigned long".to_string(), 8).parse_value(value)
+                .or_else(|_| Type::new("long".to_string(), 8).parse_value(value))
+            {
+                Ok(value) => value,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            let inferior = self.inferior.as_ref().unwrap();
+            let result = inferior.get_regs().and_then(|mut regs| {
+                match set_register_value(&mut regs, register, value) {
+                    Some(()) => inferior.set_regs(regs).map(|_| true),
+                    None => Ok(false),
+                }
+            });
+            match result {
+                Ok(true) => {}
+                Ok(false) => println!("Invalid register `{}'", register),
+                Err(e) => println!("Error setting register: {}", e),
+            }
+            return;
+        }
+
+        let (addr, entity_type) = match self.locate_variable(name) {
+            Ok(found) => found,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let raw = match entity_type.parse_value(value) {
+            Ok(raw) => raw,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let bytes = raw.to_le_bytes();
+        if let Err(e) = self.inferior.as_mut().unwrap().write_bytes(addr, &bytes[..entity_type.size]) {
+            println!("Error writing to {:#x}: {}", addr, e);
+        }
+    }
+
+    /// Lists the functions with debugging information whose names contain `filter`, grouped by
+    /// source file.
+    fn info_functions(&self, filter: Option<&str>) {
+        let matching: Vec<_> = self.debug_data
+            .functions()
+            // Declarations of library functions show up with no address; skip them.
+            .filter(|(_, func)| func.address != 0)
+            .filter(|(_, func)| filter.is_none_or(|filter| func.name.contains(filter)))
+            .collect();
+        if matching.is_empty() {
+            println!("No functions found.");
+            return;
+        }
+        let mut current_file = None;
+        for (file, func) in matching.iter().take(MAX_FUNCTIONS_LISTED) {
+            if current_file != Some(&file.name) {
+                println!("File {}:", file.name);
+                current_file = Some(&file.name);
+            }
+            println!("  {:#x}\t{} (line {})", func.address, func.name, func.line_number);
+        }
+        if matching.len() > MAX_FUNCTIONS_LISTED {
+            println!(
+                "...and {} more. Use `info functions TEXT` to narrow the list.",
+                matching.len() - MAX_FUNCTIONS_LISTED
+            );
+        }
+    }
+
+    /// Describes the addresses of the source line `break location` would stop at.
+    fn info_line(&self, location: &str) {
+        let addr = match self.resolve_location(location) {
+            Ok(addr) => addr,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let describe = |addr: usize| match self.debug_data.get_function_containing(addr) {
+            Some(func) => format!("{:#x} <{}+{}>", addr, func.name, addr - func.address),
+            None => format!("{:#x}", addr),
+        };
+        match (self.debug_data.get_line_from_addr(addr), self.debug_data.get_line_range(addr)) {
+            (Some(line), Some((start, end))) => println!(
+                "Line {} of \"{}\" starts at address {} and ends at {}.",
+                line.number, line.file, describe(start), describe(end)
+            ),
+            _ => println!("No line number information available for address {}", describe(addr)),
+        }
+    }
+
+    /// Prints the parameters and local variables of the function the selected frame is in.
+    fn info_locals(&mut self) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        let rip = match self.current_rip() {
+            Some(rip) => rip,
+            None => {
+                println!("Error reading registers");
+                return;
+            }
+        };
+        let func = match self.debug_data.get_function_containing(rip) {
+            Some(func) => func,
+            None => {
+                println!("No symbol table info available.");
+                return;
+            }
+        };
+        if func.variables.is_empty() {
+            println!("No locals.");
+            return;
+        }
+        let frame_base = self.selected_frame_base();
+        for var in &func.variables {
+            let value = self.read_variable(var, frame_base).unwrap_or_else(|| "<unavailable>".to_string());
+            println!("{}: {} = {}", var.name, var.entity_type.name, value);
+        }
+    }
+
+    /// Reads up to `len` bytes of the inferior's code starting at `start`, stopping early at
+    /// unreadable memory. The instructions under breakpoints are returned rather than int3s.
+    fn read_code(&self, start: usize, len: usize) -> Vec<u8> {
+        let mut bytes = self.inferior.as_ref().unwrap().read_bytes(start, len);
+        for breakpoint in self.breakpoints.values() {
+            if breakpoint.addr >= start && breakpoint.addr < start + bytes.len() {
+                bytes[breakpoint.addr - start] = breakpoint.orig_byte;
+            }
+        }
+        bytes
+    }
+
+    /// Prints the instructions of the given function (or at the given address), or of the
+    /// function the selected frame is in, marking the instruction at its %rip with "=>".
+    fn disassemble(&mut self, location: Option<String>) {
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        let rip = self.current_rip();
+        let start = match &location {
+            Some(location) => {
+                let addr = self.debug_data.get_addr_for_function(None, location).or_else(|| {
+                    Debugger::parse_address(location.strip_prefix('*').unwrap_or(location))
+                });
+                match addr {
+                    Some(addr) => addr,
+                    None => {
+                        println!("No function or address \"{}\"", location);
+                        return;
+                    }
+                }
+            }
+            None => match rip {
+                Some(rip) => rip,
+                None => {
+                    println!("Error reading registers");
+                    return;
+                }
+            },
+        };
+        // Disassemble the whole function if there's one here, otherwise a fixed amount
+        let (start, end) = match self.debug_data.get_function_containing(start) {
+            Some(func) => (func.address, func.address + func.text_length),
+            None => (start, start + DISASSEMBLE_LEN),
+        };
+
+        let bytes = self.read_code(start, end - start);
+        if bytes.is_empty() {
+            println!("{}", DeetError::BadAddress(start));
+            return;
+        }
+
+        let mut decoder = Decoder::with_ip(64, &bytes, start as u64, DecoderOptions::NONE);
+        let mut formatter = GasFormatter::new();
+        formatter.options_mut().set_uppercase_hex(false);
+        formatter.options_mut().set_branch_leading_zeros(false);
+        let mut instruction = Instruction::default();
+        let mut text = String::new();
+        while decoder.can_decode() {
+            decoder.decode_out(&mut instruction);
+            let addr = instruction.ip() as usize;
+            text.clear();
+            if instruction.is_invalid() {
+                text.push_str("(bad)");
+            } else {
+                formatter.format(&instruction, &mut text);
+            }
+            let offset = addr - start;
+            let hex_bytes: Vec<String> = bytes[offset..offset + instruction.len()]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            let symbol = match self.debug_data.get_function_containing(addr) {
+                Some(func) => format!(" <{}+{}>", func.name, addr - func.address),
+                None => String::new(),
+            };
+            let marker = if Some(addr) == rip { "=>" } else { "  " };
+            println!("{} {:#x}{}:\t{:<24}{}", marker, addr, symbol, hex_bytes.join(" "), text);
+        }
+    }
+
+    /// Prints memory of the stopped inferior, gdb `x` style. `addr` is either a hex address or a
+    /// register like `$rsp`.
+    fn examine(&mut self, spec: &str, addr: Option<String>) {
+        let spec = match ExamineSpec::parse(spec) {
+            Ok(spec) => spec,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let addr = match addr {
+            Some(addr) => addr,
+            None => {
+                println!("Argument required (starting display address).");
+                return;
+            }
+        };
+        if spec.count > MAX_EXAMINE_COUNT {
+            println!("Can't examine more than {} units at once.", MAX_EXAMINE_COUNT);
+            return;
+        }
+        if !self.inferior_alive() {
+            println!("{}", DeetError::NoInferior);
+            return;
+        }
+        let inferior = self.inferior.as_ref().unwrap();
+        let addr = match addr.strip_prefix('$') {
+            Some(reg) => {
+                let regs = match inferior.get_regs() {
+                    Ok(regs) => regs,
+                    Err(e) => {
+                        self.inferior_error("Error reading registers", e);
+                        return;
+                    }
+                };
+                match register_value(&regs, reg) {
+                    Some(value) => value as usize,
+                    None => {
+                        println!("Invalid register `{}'", reg);
+                        return;
+                    }
+                }
+            }
+            None => match Debugger::parse_address(&addr) {
+                Some(addr) => addr,
+                None => {
+                    println!("Invalid address {}", addr);
+                    return;
+                }
+            },
+        };
+
+        if spec.format == ExamineFormat::Str {
+            let mut addr = addr;
+            for _ in 0..spec.count {
+                match inferior.read_string(addr, MAX_STRING_LEN) {
+                    Some(string) => {
+                        let truncated = string.len() == MAX_STRING_LEN;
+                        println!(
+                            "{:#x}:\t{:?}{}",
+                            addr,
+                            String::from_utf8_lossy(&string),
+                            if truncated { "..." } else { "" }
+                        );
+                        // The next string starts after the NUL, if there's any more address space
+                        match addr.checked_add(string.len() + if truncated { 0 } else { 1 }) {
+                            Some(next) => addr = next,
+                            None => return,
+                        }
+                    }
+                    None => {
+                        println!("{:#x}:\t<error>", addr);
+                        return;
+                    }
+                }
+            }
+            return;
+        }
+
+        let bytes = match spec.count.checked_mul(spec.size) {
+            Some(len) => inferior.read_bytes(addr, len),
+            None => Vec::new(),
+        };
+        let per_line = if spec.size >= 4 { 16 / spec.size } else { 8 };
+        for i in 0..spec.count {
+            // Stop past the end of the address space
+            let cell_addr = match i.checked_mul(spec.size).and_then(|offset| addr.checked_add(offset)) {
+                Some(cell_addr) => cell_addr,
+                None => break,
+            };
+            if i % per_line == 0 {
+                if i != 0 {
+                    println!();
+                }
+                print!("{:#x}:", cell_addr);
+            }
+            // The read stops at the first unreadable word, and so do we
+            let cell = match bytes.get(i * spec.size..(i + 1) * spec.size) {
+                Some(cell) => cell,
+                None => {
+                    print!("\t<error>");
+                    break;
+                }
+            };
+            // Memory is little-endian
+            let value = cell.iter().rev().fold(0u64, |value, &byte| (value << 8) | byte as u64);
+            match spec.format {
+                ExamineFormat::Decimal => {
+                    let shift = 64 - 8 * spec.size;
+                    print!("\t{}", ((value << shift) as i64) >> shift);
+                }
+                _ => print!("\t{:#0width$x}", value, width = 2 + 2 * spec.size),
+            }
+        }
+        println!();
+    }
+
+    /// Decodes the commonly used status and control bits of %eflags, gdb-style: "[ ZF PF IF ]".
+    fn format_eflags(eflags: u64) -> String {
+        const FLAGS: [(u32, &str); 9] = [
+            (0, "CF"),
+            (2, "PF"),
+            (4, "AF"),
+            (6, "ZF"),
+            (7, "SF"),
+            (8, "TF"),
+            (9, "IF"),
+            (10, "DF"),
+            (11, "OF"),
+        ];
+        let set: Vec<&str> = FLAGS
+            .iter()
+            .filter(|(bit, _)| eflags & (1 << bit) != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        format!("[ {} ]", set.join(" "))
+    }
+
+    /// Makes the inferior, which is about to execute the breakpoint at `addr`, stop there as if
+    /// the breakpoint had been hit.
+    fn stop_at_bp(&mut self, addr: usize) {
+        if let Err(e) = self.inferior.as_mut().unwrap().stop_at_breakpoint(&self.breakpoints, addr) {
+            println!("Error removing breakpoint at {:#x}: {}", addr, e);
+            return;
+        }
+        self.breakpoint_hit(addr);
+    }
+
+    /// Called when the inferior stops at the breakpoint at `addr`: queues its commands to run,
+    /// and deletes it if it's temporary.
+    fn breakpoint_hit(&mut self, addr: usize) {
+        let breakpoint = &self.breakpoints[&addr];
+        // The ones run_until sets for itself don't count
+        if !breakpoint.internal {
+            self.stats.breakpoint_hits += 1;
+        }
+        if !breakpoint.commands.is_empty() {
+            self.hit_bp_commands = Some(breakpoint.commands.clone());
+        }
+        self.hit_temporary_bp = Some(breakpoint.clone()).filter(|breakpoint| breakpoint.temporary);
+        if breakpoint.temporary {
+            let number = breakpoint.number;
+            println!("Temporary breakpoint {} hit and removed", number);
+            self.delete_bp(number);
+        }
+    }
+
+    /// Deletes every location of breakpoint `number`. Returns false if there's no such
+    /// breakpoint.
+    fn delete_bp(&mut self, number: usize) -> bool {
+        let pending = self.pending_bps.len();
+        self.pending_bps.retain(|breakpoint| breakpoint.number != number);
+        if self.pending_bps.len() < pending {
+            return true;
+        }
+        let addrs: Vec<usize> = self
+            .breakpoints
+            .values()
+            .filter(|breakpoint| !breakpoint.internal && breakpoint.number == number)
+            .map(|breakpoint| breakpoint.addr)
+            .collect();
+        for &addr in &addrs {
+            if !self.inferior_alive() {
+                self.breakpoints.remove(&addr);
+            } else if let Err(e) = self.inferior.as_mut().unwrap().remove_breakpoint(&mut self.breakpoints, addr) {
+                println!("Error removing breakpoint at {:#x}: {}", addr, e);
+            }
+        }
+        !addrs.is_empty()
+    }
+
+    fn parse_address(addr: &str) -> Option<usize> {
+        let addr_without_0x = if addr.to_lowercase().starts_with("0x") {
+            &addr[2..]
+        } else {
+            addr
+        };
+        usize::from_str_radix(addr_without_0x, 16).ok()
+    }
+
+    /// Returns the lines of the given source file, reading it from disk the first time.
+    fn source_lines(&mut self, file: &str) -> Option<&Vec<String>> {
+        self.source_cache
+            .entry(file.to_string())
+            .or_insert_with(|| {
+                let source_file = File::open(file).ok()?;
+                BufReader::new(source_file).lines().collect::<Result<_, _>>().ok()
+            })
+            .as_ref()
+    }
+
+    /// Returns the %rip of the selected frame.
+    fn current_rip(&self) -> Option<usize> {
+        match &self.selected_frame {
+            Some(frame) => Some(frame.rip),
+            None => self.inferior.as_ref()?.get_rip().ok(),
+        }
+    }
+
+    fn selected_frame_number(&self) -> usize {
+        self.selected_frame.as_ref().map_or(0, |frame| frame.number)
+    }
+
+    /// Describes a stack frame the way backtrace lists it, with the function's arguments.
+    fn describe_frame(&self, number: usize, rip: usize, rbp: usize) -> String {
+        let func_name = self.debug_data.get_function_from_addr(rip).unwrap_or_else(|| "??".to_string());
+        let args = match self.debug_data.get_function_containing(rip) {
+            Some(func) => {
+                let frame_base = self.frame_base(number, rip, rbp);
+                func.variables
+                    .iter()
+                    .filter(|var| var.is_parameter)
+                    .map(|var| {
+                        let value = self.read_variable(var, frame_base).unwrap_or_else(|| "?".to_string());
+                        format!("{}={}", var.name, value)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+            None => String::new(),
+        };
+        let place = match self.debug_data.get_line_from_addr(rip) {
+            Some(line) => {
+                // Fit the whole frame on one line (along with the marker backtrace puts in front)
+                // by shortening the path if need be
+                let rest = format!("=> #{} {} ({}) (:{})", number, func_name, args, line.number);
+                let room = output::terminal_width().saturating_sub(rest.chars().count());
+                format!("{}:{}", output::shorten_path(&line.file, room), line.number)
+            }
+            None => format!("{:#x}", rip),
+        };
+        format!("#{} {} ({}) ({})", number, output::function(&func_name), args, output::location(&place))
+    }
+
+    /// Prints the innermost `depth` frames of the stack, numbering each frame and marking the
+    /// selected one with "=>".
+    fn backtrace(&mut self, depth: usize) {
+        if !self.inferior_alive() {
+            println!("No stack");
+            return;
+        }
+        // One extra frame tells whether there are more than are shown
+        let mut frames = match self.inferior.as_ref().unwrap().frames(&self.debug_data, depth + 1) {
+            Ok(frames) => frames,
+            Err(e) => {
+                println!("Error printing backtrace: {}", e);
+                return;
+            }
+        };
+        let truncated = frames.len() > depth;
+        frames.truncate(depth);
+        let selected = self.selected_frame_number();
+        for (number, &(rip, rbp)) in frames.iter().enumerate() {
+            let marker = if number == selected { "=>" } else { "  " };
+            println!("{} {}", marker, self.describe_frame(number, rip, rbp));
+        }
+        if truncated {
+            println!("(More stack frames follow...)");
+        }
+    }
+
+    /// Makes frame `number` (0 being the innermost) the one that stack-inspecting commands
+    /// operate on, and shows where it is.
+    fn select_frame(&mut self, number: usize) {
+        if !self.inferior_alive() {
+            println!("No stack");
+            return;
+        }
+        let frames = match self.inferior.as_ref().unwrap().frames(&self.debug_data, number + 1) {
+            Ok(frames) => frames,
+            Err(e) => {
+                println!("Error walking the stack: {}", e);
+                return;
+            }
+        };
+        let (rip, rbp) = match frames.get(number) {
+            Some(&frame) => frame,
+            None => {
+                println!("No frame at level {}; the stack has {} frames", number, frames.len());
+                return;
+            }
+        };
+        self.selected_frame = Some(SelectedFrame { number, rip, rbp });
+        println!("{}", self.describe_frame(number, rip, rbp));
+        self.print_code(rip);
+    }
+
+    /// Prints the line of source code containing `rip`, and centers the next `list` on it.
+    fn print_code(&mut self, rip: usize) {
+        if let Some(line) = self.debug_data.get_line_from_addr(rip) {
+            let first = line.number.saturating_sub(LIST_SIZE / 2).max(1);
+            self.list_position = Some((line.file.clone(), first));
+            if let Some(code) = self.source_lines(&line.file).and_then(|lines| lines.get(line.number - 1)) {
+                println!("{}\t{}", line.number, code);
+            }
+        }
+    }
+
+    /// Prints LIST_SIZE lines of source code centered on `center`, or following the previous
+    /// listing if None. The line the selected frame is stopped at is marked with "=>".
+    fn list(&mut self, center: Option<usize>) {
+        let current_line = self.current_rip().and_then(|rip| self.debug_data.get_line_from_addr(rip));
+        let debug_data = &self.debug_data;
+        let position = self.list_position.take().or_else(|| {
+            // Before anything has been listed, list around main, like gdb
+            let line = debug_data.get_line_from_addr(debug_data.get_addr_for_function(None, "main")?)?;
+            Some((line.file, line.number.saturating_sub(LIST_SIZE / 2).max(1)))
+        });
+        let (file, first) = match position {
+            Some((file, next)) => {
+                (file, center.map_or(next, |center| center.saturating_sub(LIST_SIZE / 2).max(1)))
+            }
+            None => {
+                println!("No source file to list");
+                return;
+            }
+        };
+
+        let lines = match self.source_lines(&file) {
+            Some(lines) => lines,
+            None => {
+                // Without the source, the best we can do is say where we are
+                println!("{}:{}", file, first);
+                return;
+            }
+        };
+        if first > lines.len() {
+            println!("Line number {} out of range; \"{}\" has {} lines.", first, file, lines.len());
+            self.list_position = Some((file, first));
+            return;
+        }
+        let last = (first + LIST_SIZE - 1).min(lines.len());
+        for number in first..=last {
+            let is_current = match &current_line {
+                Some(line) => line.number == number && line.file == file,
+                None => false,
+            };
+            let marker = if is_current { "=>" } else { "  " };
+            println!("{} {}\t{}", marker, number, lines[number - 1]);
+        }
+        self.list_position = Some((file, last + 1));
+    }
+
+    pub fn run(&mut self) {
+        loop {
+            let command = self.get_next_command();
+            if !self.execute(command) {
+                return;
+            }
+        }
+    }
+
+    /// Runs the commands in the script at `path`, one per line, echoing each one first. Blank
+    /// lines and lines starting with # are skipped, and the script is abandoned at the first line
+    /// that isn't a valid command. Returns false if the script quit the debugger.
+    pub fn run_script(&mut self, path: &str) -> bool {
+        let script = match fs::read_to_string(path) {
+            Ok(script) => script,
+            Err(e) => {
+                println!("Could not read {}: {}", path, e);
+                return true;
+            }
+        };
+        for (index, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            println!("(deet) {}", line);
+            match DebuggerCommand::parse_line(line) {
+                Ok(command) => {
+                    if !self.execute(command) {
+                        return false;
+                    }
+                }
+                Err(e) => {
+                    println!("{}:{}: {}", path, index + 1, e);
+                    println!("Stopped running {}", path);
+                    return true;
+                }
+            }
+        }
+        true
+    }
+
+    /// Runs a command, then the commands attached to the breakpoint it stopped at (if any), and
+    /// so on if those stop at another breakpoint with commands. Returns false once the debugger
+    /// should exit.
+    pub fn execute(&mut self, command: DebuggerCommand) -> bool {
+        self.check_inferior();
+        if !self.inferior_still_running() {
+            self.remove_internal_bps();
+        }
+        self.hit_bp_commands = None;
+        if self.inferior_still_running() && !command.works_while_running() {
+            println!("{}", RUNNING_MESSAGE);
+            return true;
+        }
+        if !self.dispatch(command) {
+            return false;
+        }
+        let mut depth = 0;
+        while let Some(lines) = self.hit_bp_commands.take() {
+            if depth == MAX_BP_COMMAND_DEPTH {
+                println!("Breakpoint commands nested more than {} deep; stopping", MAX_BP_COMMAND_DEPTH);
+                break;
+            }
+            depth += 1;
+            for line in lines {
+                let command = match DebuggerCommand::parse_line(&line) {
+                    Ok(command) => command,
+                    Err(e) => {
+                        println!("{}", e);
+                        break;
+                    }
+                };
+                if self.inferior_still_running() && !command.works_while_running() {
+                    println!("{}", RUNNING_MESSAGE);
+                    break;
+                }
+                if !self.dispatch(command) {
+                    return false;
+                }
+                if self.hit_bp_commands.is_some() {
+                    // The inferior was resumed into another breakpoint with commands, which
+                    // take over from the rest of these
+                    break;
+                }
+            }
+        }
+        true
+    }
+
+    /// Starts a new inferior with `args`, or the arguments of the previous run if there are
+    /// none, killing the running one first. The new inferior is stopped before running any of
+    /// the program's code. Returns false if it couldn't be started.
+    fn launch(&mut self, args: Vec<String>) -> bool {
+        if !args.is_empty() {
+            self.args = args;
+        }
+        // If the inferior exists and is running, kill it.
+        self.discard_inferior();
+        self.exit_code = None;
+        self.detached_processes.clear();
+        self.reload_if_changed();
+        self.resolve_pending_bps();
+        self.check_breakpoint_lines();
+        let started = Inferior::new(&self.target, &self.args, &self.launch_settings)
+            .map(|inferior| self.adopt_inferior(inferior));
+        match started {
+            Some(Ok(())) => {
+                self.stats.runs += 1;
+                self.run_started = Some(Instant::now());
+                true
+            }
+            _ => {
+                println!("Error starting subprocess");
+                false
+            }
+        }
+    }
+
+    /// Starts a new inferior like `run`, but stops it at the start of main, or at the entry point
+    /// if the target has no symbol for main.
+    fn start(&mut self, args: Vec<String>) {
+        if !self.launch(args) {
+            return;
+        }
+        let addr = match self.debug_data.get_addr_for_function(None, "main") {
+            Some(addr) => addr,
+            None => {
+                let entry = self.debug_data.entry_point();
+                println!("No symbol \"main\"; stopping at the entry point {:#x}", entry);
+                entry
+            }
+        };
+        // A statically linked program starts at its entry point, so it's already there
+        let rip = self.inferior.as_ref().unwrap().get_rip().ok();
+        if rip == Some(addr) || self.run_until(addr, 0) {
+            self.report_location(addr);
+        }
+    }
+
+    /// Runs the program to completion over and over, with the arguments of the previous run plus
+    /// the rerun argument (if set) for each, until a run exits with a nonzero status or is killed
+    /// by a signal, or `max` runs have passed. A run that stops (at a breakpoint or by a signal)
+    /// also ends the loop, and is left for inspection. A failed run is repeated with the same
+    /// rerun argument, so it can be debugged.
+    fn run_until_failure(&mut self, max: Option<usize>) {
+        let args = self.args.clone();
+        let mut number = 0;
+        let rerun_arg = loop {
+            number += 1;
+            if max.is_some_and(|max| number > max) {
+                println!("No failure in {} runs", number - 1);
+                return;
+            }
+            let rerun_arg = self.rerun_arg.as_ref().map(|arg| arg.replace("{}", &number.to_string()));
+            if !self.launch(args.iter().cloned().chain(rerun_arg.clone()).collect()) {
+                return;
+            }
+            self.args = args.clone();
+            self.cont();
+            if self.inferior_alive() {
+                println!("Run {} stopped", number);
+                return;
+            }
+            match self.exit_code {
+                Some(0) => {}
+                Some(code) => {
+                    println!("Run {} failed with status {}", number, code);
+                    break rerun_arg;
+                }
+                None => {
+                    println!("Run {} was killed by a signal", number);
+                    break rerun_arg;
+                }
+            }
+        };
+        if let Some(rerun_arg) = rerun_arg {
+            println!("Running again with {}", rerun_arg);
+            if self.launch(args.iter().cloned().chain(Some(rerun_arg)).collect()) {
+                self.args = args;
+                self.cont();
+            }
+        }
+    }
+
+    /// Runs a single command. Returns false if it's `quit`.
+    fn dispatch(&mut self, command: DebuggerCommand) -> bool {
+        match command {
+            DebuggerCommand::Run(args) => {
+                if self.launch(args) {
+                    // Wake up the inferior
+                    self.cont();
+                }
+            }
+            DebuggerCommand::Start(args) => {
+                self.start(args);
+            }
+            DebuggerCommand::RunUntilFailure(max) => {
+                self.run_until_failure(max);
+            }
+            DebuggerCommand::SetRerunArg(arg) => {
+                self.rerun_arg = arg;
+            }
+            DebuggerCommand::ShowRerunArg => match &self.rerun_arg {
+                Some(arg) => println!("Each run of `run --until-failure` gets the argument {}.", arg),
+                None => println!("Runs of `run --until-failure` get no extra argument."),
+            },
+            DebuggerCommand::Quit(force) => {
+                if !force && !self.confirm_quit() {
+                    return true;
+                }
+                self.discard_inferior();
+                self.save_history();
+                return false;
+            },
+            DebuggerCommand::Continue(count) => {
+                self.repeat("continue", count, Debugger::cont);
+            },
+            DebuggerCommand::Backtrace(depth) => {
+                self.backtrace(depth);
+            },
+            DebuggerCommand::Breakpoint(token, force) => {
+                self.set_bp(token, false, force);
+            },
+            DebuggerCommand::TemporaryBreakpoint(token, force) => {
+                self.set_bp(token, true, force);
+            },
+            DebuggerCommand::BreakpointCommands(number, lines) => {
+                self.set_bp_commands(number, lines);
+            }
+            DebuggerCommand::Condition(number, condition) => {
+                self.set_bp_condition(number, condition);
+            }
+            DebuggerCommand::File(target) => {
+                self.load_target(&target);
+            }
+            DebuggerCommand::Catch(event) => {
+                self.add_catchpoint(event);
+            }
+            DebuggerCommand::ListCatchpoints => {
+                self.list_catchpoints();
+            }
+            DebuggerCommand::DeleteBreakpoint(number) => {
+                if !self.delete_bp(number) {
+                    println!("No breakpoint number {}.", number);
+                }
+            }
+            DebuggerCommand::DeleteCatchpoints(number) => {
+                self.delete_catchpoints(number);
+            }
+            DebuggerCommand::SetFollowForkMode(mode) => {
+                self.follow_fork_mode = mode;
+            }
+            DebuggerCommand::ShowFollowForkMode => {
+                println!(
+                    "Debugger response to a program call of fork or vfork is \"{}\".",
+                    self.follow_fork_mode.name()
+                );
+            }
+            DebuggerCommand::InfoInferiors => {
+                self.info_inferiors();
+            }
+            DebuggerCommand::InfoThreads => {
+                self.info_threads();
+            }
+            DebuggerCommand::InfoStats => {
+                self.info_stats();
+            }
+            DebuggerCommand::Thread(number) => {
+                self.select_thread(number);
+            }
+            DebuggerCommand::ListBreakpoints => {
+                self.list_bps();
+            },
+            DebuggerCommand::Finish => {
+                self.finish();
+            },
+            DebuggerCommand::Jump(location) => {
+                self.jump(&location);
+            }
+            DebuggerCommand::Return(value) => {
+                self.force_return(value.as_deref());
+            }
+            DebuggerCommand::Help(topic) => match help_text(topic.as_deref()) {
+                Ok(text) | Err(text) => println!("{}", text),
+            },
+            DebuggerCommand::History(count) => {
+                self.show_history(count);
+            }
+            DebuggerCommand::Signal(name) => {
+                self.set_pending_signal(&name);
+            }
+            DebuggerCommand::Handle(name, action) => {
+                self.handle_signal(&name, action.as_deref());
+            }
+            DebuggerCommand::Disassemble(location) => {
+                self.disassemble(location);
+            }
+            DebuggerCommand::InfoFunctions(filter) => {
+                self.info_functions(filter.as_deref());
+            }
+            DebuggerCommand::InfoLine(location) => {
+                self.info_line(&location);
+            }
+            DebuggerCommand::Print(format, expr) => {
+                self.print(format, &expr);
+            }
+            DebuggerCommand::Display(format, expr) => {
+                self.add_display(format, expr);
+            }
+            DebuggerCommand::ShowDisplays => {
+                self.show_displays();
+            }
+            DebuggerCommand::ListDisplays => {
+                self.list_displays();
+            }
+            DebuggerCommand::Undisplay(number) => {
+                self.undisplay(number);
+            }
+            DebuggerCommand::InfoLocals => {
+                self.info_locals();
+            }
+            DebuggerCommand::InfoRegisters(names) => {
+                self.info_registers(&names);
+            },
+            DebuggerCommand::Examine(spec, addr) => {
+                self.examine(&spec, addr);
+            },
+            DebuggerCommand::SetArgs(args) => {
+                self.args = args;
+            },
+            DebuggerCommand::SetEnv(name, value) => {
+                self.launch_settings.env.insert(name, Some(value));
+            }
+            DebuggerCommand::UnsetEnv(name) => {
+                self.launch_settings.env.insert(name, None);
+            }
+            DebuggerCommand::ShowEnv(name) => {
+                self.show_env(name.as_deref());
+            }
+            DebuggerCommand::Cd(dir) => {
+                self.cd(&dir);
+            }
+            DebuggerCommand::SetVariable(name, value) => {
+                self.set_variable(&name, &value);
+            }
+            DebuggerCommand::ShowArgs => {
+                if self.args.is_empty() {
+                    println!("The program being debugged has no arguments.");
+                    return true;
+                }
+                let args: Vec<String> = self
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        if arg.is_empty() || arg.contains(char::is_whitespace) {
+                            format!("{:?}", arg)
+                        } else {
+                            arg.clone()
+                        }
+                    })
+                    .collect();
+                println!("Arguments for the program being debugged: {}", args.join(" "));
+            },
+            DebuggerCommand::Kill => {
+                self.kill();
+            },
+            DebuggerCommand::Interrupt => {
+                self.interrupt();
+            }
+            DebuggerCommand::SetWaitTimeout(seconds) => {
+                inferior::set_wait_timeout(seconds.map(Duration::from_secs));
+            }
+            DebuggerCommand::ShowWaitTimeout => match inferior::wait_timeout() {
+                Some(timeout) => println!(
+                    "Resuming the program waits up to {} for it to stop.",
+                    format_duration(timeout)
+                ),
+                None => println!("Resuming the program waits until it stops."),
+            },
+            DebuggerCommand::Detach => {
+                self.detach();
+            },
+            DebuggerCommand::Attach(pid) => match pid {
+                Some(pid) => self.attach(&pid),
+                None => println!("Argument required (process-id to attach)."),
+            },
+            DebuggerCommand::Next(count) => {
+                self.repeat("next", count, |debugger| debugger.next(false));
+            }
+            DebuggerCommand::StepInstruction(count) => {
+                self.repeat("stepi", count, |debugger| debugger.step_instruction(false));
+            }
+            DebuggerCommand::NextInstruction(count) => {
+                self.repeat("nexti", count, |debugger| debugger.step_instruction(true));
+            }
+            DebuggerCommand::Until(None) => {
+                self.next(true);
+            }
+            DebuggerCommand::Until(Some(location)) => {
+                self.advance(&location);
+            }
+            DebuggerCommand::List(center) => {
+                self.list(center);
+            }
+            DebuggerCommand::Up(levels) => {
+                let number = self.selected_frame_number() + levels;
+                self.select_frame(number);
+            }
+            DebuggerCommand::Down(levels) => match self.selected_frame_number().checked_sub(levels) {
+                Some(number) => self.select_frame(number),
+                None => println!("Bottom (innermost) frame selected; you cannot go down."),
+            },
+            DebuggerCommand::Frame(number) => {
+                let number = number.unwrap_or_else(|| self.selected_frame_number());
+                self.select_frame(number);
+            }
+        }
+        true
+    }
+
+    /// Asks whether to quit while the inferior is still alive; there's nothing to ask otherwise.
+    /// End of input counts as yes, so ctrl+d twice still gets out.
+    fn confirm_quit(&mut self) -> bool {
+        if !self.inferior_alive() {
+            return true;
+        }
+        let action = if self.inferior.as_ref().unwrap().is_attached() { "Detach from" } else { "Kill" };
+        let question =
+            format!("A debugging session is active. {} the inferior and quit? (y/n) ", action);
+        self.confirm(&question, true)
+    }
+
+    /// Asks a yes or no question until it's answered. End of input gives `default`, and ctrl+c
+    /// counts as no.
+    fn confirm(&mut self, question: &str, default: bool) -> bool {
+        loop {
+            match self.readline.readline(question) {
+                Ok(answer) => match answer.trim() {
+                    "y" | "Y" | "yes" => return true,
+                    "n" | "N" | "no" => return false,
+                    _ => println!("Please answer y or n."),
+                },
+                Err(ReadlineError::Eof) => return default,
+                Err(ReadlineError::Interrupted) => return false,
+                Err(err) => panic!("Unexpected I/O error: {:?}", err),
+            }
+        }
+    }
+
+    /// Returns what deet should exit with: the exit status of the last run of the inferior, or 0
+    /// if it didn't exit on its own.
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code.unwrap_or(0)
+    }
+
+    /// This function prompts the user to enter a command, and continues re-prompting until the user
+    /// enters a valid command. It uses DebuggerCommand::from_tokens to do the command parsing.
+    ///
+    /// You don't need to read, understand, or modify this function.
+    fn get_next_command(&mut self) -> DebuggerCommand {
+        loop {
+            // Print prompt and get next line of user input
+            match self.readline.readline("(deet) ") {
+                Err(ReadlineError::Interrupted) => {
+                    // User pressed ctrl+c. We're going to ignore it
+                    println!("Type \"quit\" to exit");
+                }
+                Err(ReadlineError::Eof) => {
+                    // User pressed ctrl+d, which is the equivalent of "quit" for our purposes
+                    return DebuggerCommand::Quit(false);
+                }
+                Err(err) => {
+                    panic!("Unexpected I/O error: {:?}", err);
+                }
+                Ok(line) => {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    // Lines read from a pipe rather than a terminal keep their newline
+                    let line = line.trim_end().to_string();
+                    // !N runs history line N again
+                    let event = line.trim().strip_prefix('!').and_then(|n| n.parse::<usize>().ok());
+                    let line = match event {
+                        Some(number) => {
+                            let history = self.readline.history();
+                            match number.checked_sub(1).and_then(|index| history.get(index)) {
+                                Some(line) => line.clone(),
+                                None => {
+                                    println!("!{}: event not found", number);
+                                    continue;
+                                }
+                            }
+                        }
+                        None => line,
+                    };
+                    if event.is_some() {
+                        println!("{}", line);
+                    }
+                    // The history is only saved on exit, since rewriting the file after every
+                    // command gets slow once it's long
+                    self.readline.add_history_entry(line.as_str());
+                    match DebuggerCommand::parse_line(&line) {
+                        Ok(cmd) => return cmd,
+                        Err(e) => println!("{}", e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolves a location (`*0x401136`, `func`, `42`, `file.c:42` or `file.c:func`) to an
+    /// address, or explains why it couldn't be resolved. If the location has code in several
+    /// places, this is the lowest address.
+    fn resolve_location(&self, location: &str) -> Result<usize, String> {
+        self.resolve_locations(location).map(|addrs| addrs[0])
+    }
+
+    /// Resolves a location to every address it has code at: a line can have code in several
+    /// functions, and a name can belong to several functions. Never returns an empty list.
+    fn resolve_locations(&self, location: &str) -> Result<Vec<usize>, String> {
+        if let Some(addr) = location.strip_prefix('*') {
+            // address
+            return Debugger::parse_address(addr)
+                .map(|addr| vec![addr])
+                .ok_or(format!("Invalid address {}", addr));
+        }
+        if !self.debug_data.has_symbols() {
+            return Err(DeetError::NoDebugInfo.to_string());
+        }
+
+        // An optional file component, but don't split up Rust paths like `mod::func`
+        let (file, spec) = match location.rsplit_once(':') {
+            Some((file, spec)) if !file.is_empty() && !file.ends_with(':') => (Some(file), spec),
+            _ => (None, location),
+        };
+        if let Some(file) = file {
+            if self.debug_data.get_target_file(file).is_none() {
+                return Err(format!("No source file matching \"{}\"", file));
+            }
+        }
+
+        if let Ok(line_number) = spec.parse::<usize>() {
+            // line number
+            let addrs = self.debug_data.get_addrs_for_line(file, line_number);
+            if addrs.is_empty() {
+                return Err(match file {
+                    Some(file) => format!("No code at or after line {} in {}", line_number, file),
+                    None => format!("No code at or after line {}", line_number),
+                });
+            }
+            Ok(addrs)
+        } else {
+            // function name
+            let addrs = self.debug_data.get_addrs_for_function(file, spec);
+            if addrs.is_empty() {
+                return Err(format!("No function named \"{}\"", spec));
+            }
+            Ok(addrs)
+        }
+    }
+
+    /// Sets a breakpoint at every address `token` resolves to, all under one number. Addresses
+    /// that already have a breakpoint keep it. Unless `force` is true, addresses outside the
+    /// inferior's executable memory are refused.
+    fn set_bp(&mut self, token: String, temporary: bool, force: bool) {
+        let addrs = match self.resolve_locations(&token) {
+            Ok(addrs) => addrs,
+            // An address is what it is, but a name or line may turn up once the program is rebuilt
+            Err(e) if token.starts_with('*') => {
+                println!("{}", e);
+                return;
+            }
+            Err(e) => {
+                println!("{}", e);
+                let number = self.next_bp_number;
+                self.next_bp_number += 1;
+                println!("Breakpoint {} ({}) pending.", number, token);
+                self.pending_bps.push(Breakpoint {
+                    addr: 0,
+                    orig_byte: 0,
+                    number,
+                    location_number: None,
+                    temporary,
+                    internal: false,
+                    commands: Vec::new(),
+                    location: token,
+                    force,
+                    line: None,
+                    condition: None,
+                });
+                return;
+            }
+        };
+        let (taken, addrs): (Vec<usize>, Vec<usize>) =
+            addrs.into_iter().partition(|addr| self.breakpoints.contains_key(addr));
+        for addr in &taken {
+            println!("Breakpoint {} is already set at {:#x}", self.breakpoints[addr].number, addr);
+        }
+        if addrs.is_empty() {
+            return;
+        }
+        if let Some(inferior) = self.inferior.as_ref().filter(|_| !force) {
+            if let Some(addr) = addrs.iter().find(|&&addr| !inferior.is_executable(addr)) {
+                println!("Address {:#x} is not in executable memory (add --force to set it anyway)", addr);
+                return;
+            }
+        }
+
+        let number = self.next_bp_number;
+        let multiple = addrs.len() > 1;
+        let mut breakpoints: Vec<Breakpoint> = Vec::new();
+        for (index, &addr) in addrs.iter().enumerate() {
+            let mut breakpoint = Breakpoint {
+                addr,
+                orig_byte: 0,
+                number,
+                location_number: if multiple { Some(index + 1) } else { None },
+                temporary,
+                internal: false,
+                commands: Vec::new(),
+                location: token.clone(),
+                force,
+                line: self.source_line(addr),
+                condition: None,
+            };
+            if let Some(inferior) = self.inferior.as_mut() {
+                match inferior.write_byte(addr, 0xcc) {
+                    Ok(orig_byte) => breakpoint.orig_byte = orig_byte,
+                    Err(e) => {
+                        println!("Error setting breakpoint at {:#x}: {}", addr, e);
+                        // Take back the locations already set
+                        for breakpoint in &breakpoints {
+                            let _ = inferior.write_byte(breakpoint.addr, breakpoint.orig_byte);
+                        }
+                        return;
+                    }
+                }
+            }
+            breakpoints.push(breakpoint);
+        }
+
+        let kind = if temporary { "temporary breakpoint" } else { "breakpoint" };
+        if multiple {
+            println!("Set {} {} with {} locations", kind, number, addrs.len());
+        } else {
+            println!("Set {} {} at {:#x}", kind, number, addrs[0]);
+        }
+        for breakpoint in breakpoints {
+            self.breakpoints.insert(breakpoint.addr, breakpoint);
+        }
+        self.next_bp_number += 1;
+    }
+
+    /// Attaches command lines to the breakpoint with the given number, replacing any it had.
+    fn set_bp_commands(&mut self, number: usize, lines: Vec<String>) {
+        let mut found = false;
+        let breakpoints = self.breakpoints.values_mut().chain(self.pending_bps.iter_mut());
+        for breakpoint in breakpoints.filter(|breakpoint| !breakpoint.internal && breakpoint.number == number) {
+            breakpoint.commands = lines.clone();
+            found = true;
+        }
+        if !found {
+            println!("No breakpoint number {}.", number);
+        }
+    }
+
+    /// Makes the breakpoint with the given number stop only when `condition` is true, or every
+    /// time if None. The condition is only checked for syntax here; it's evaluated at each hit.
+    fn set_bp_condition(&mut self, number: usize, condition: Option<String>) {
+        if let Some(Err(e)) = condition.as_deref().map(expression::check) {
+            println!("{}", e);
+            return;
+        }
+        let mut found = false;
+        let breakpoints = self.breakpoints.values_mut().chain(self.pending_bps.iter_mut());
+        for breakpoint in breakpoints.filter(|breakpoint| !breakpoint.internal && breakpoint.number == number) {
+            breakpoint.condition = condition.clone();
+            found = true;
+        }
+        if !found {
+            println!("No breakpoint number {}.", number);
+        } else if condition.is_none() {
+            println!("Breakpoint {} now unconditional.", number);
+        }
+    }
+
+    /// Returns whether the breakpoint at `addr`, which the inferior just hit, should stop it:
+    /// whether it has no condition or its condition is true. A condition that can't be evaluated
+    /// stops the inferior, saying why.
+    fn condition_holds(&self, addr: usize) -> bool {
+        let breakpoint = &self.breakpoints[&addr];
+        let condition = match &breakpoint.condition {
+            Some(condition) => condition,
+            None => return true,
+        };
+        let lookup = |name: &str| self.locate_variable(name);
+        match expression::evaluate_condition(condition, &lookup, self.inferior.as_ref().unwrap()) {
+            Ok(holds) => holds,
+            Err(e) => {
+                println!("Error in testing condition for breakpoint {}: {}", breakpoint.label(), e);
+                true
+            }
+        }
+    }
+
+    /// Lists the threads of the inferior with the function each one is in, marking the selected
+    /// one with "*".
+    fn info_threads(&self) {
+        if !self.inferior_alive() {
+            println!("No threads.");
+            return;
+        }
+        let inferior = self.inferior.as_ref().unwrap();
+        let (_, current) = inferior.current_thread();
+        for (number, tid, rip) in inferior.threads() {
+            let marker = if tid == current { "*" } else { " " };
+            let location = match rip {
+                Some(rip) => {
+                    let func_name = self.debug_data.get_function_from_addr(rip).unwrap_or_else(|| "??".to_string());
+                    format!("{:#x} in {}", rip, output::function(&func_name))
+                }
+                None => "<unknown>".to_string(),
+            };
+            println!("{} {}\tThread {}\t{}", marker, number, tid, location);
+        }
+    }
+
+    /// Makes thread `number` the one that commands operate on, and shows where it is.
+    fn select_thread(&mut self, number: Option<usize>) {
+        if !self.inferior_alive() {
+            println!("No threads.");
+            return;
+        }
+        let (current, _) = self.inferior.as_ref().unwrap().current_thread();
+        let number = number.unwrap_or(current);
+        if number != current {
+            match self.inferior.as_mut().unwrap().select_thread(number, &self.breakpoints) {
+                Ok(true) => {}
+                Ok(false) => {
+                    println!("Invalid thread ID: {}", number);
+                    return;
+                }
+                Err(e) => {
+                    println!("Error switching threads: {}", e);
+                    return;
+                }
+            }
+            self.selected_frame = None;
+        }
+        let inferior = self.inferior.as_ref().unwrap();
+        let (number, tid) = inferior.current_thread();
+        println!("[Switching to thread {} ({})]", number, tid);
+        match inferior.get_regs() {
+            Ok(regs) => {
+                println!("{}", self.describe_frame(0, regs.rip as usize, regs.rbp as usize));
+                self.print_code(regs.rip as usize);
+            }
+            Err(e) => self.inferior_error("Error reading registers", e),
+        }
+    }
+
+    /// Lists the inferior (marked with "*") and the processes of this run that were detached from
+    /// after forks.
+    fn info_inferiors(&self) {
+        let current = self.inferior.as_ref().filter(|inferior| inferior.running());
+        if current.is_none() && self.detached_processes.is_empty() {
+            println!("No inferiors.");
+            return;
+        }
+        if let Some(inferior) = current {
+            println!("* process {}", inferior.pid());
+        }
+        for (pid, reason) in &self.detached_processes {
+            println!("  process {} ({})", pid, reason);
+        }
+    }
+
+    fn add_catchpoint(&mut self, event: CatchEvent) {
+        let number = self.next_catch_number;
+        self.next_catch_number += 1;
+        self.catchpoints.push(Catchpoint { number, event });
+        println!("Catchpoint {} ({})", number, event.name());
+    }
+
+    fn list_catchpoints(&self) {
+        if self.catchpoints.is_empty() {
+            println!("No catchpoints.");
+            return;
+        }
+        println!("Num\tWhat");
+        for catchpoint in &self.catchpoints {
+            println!("{}\t{}", catchpoint.number, catchpoint.event.name());
+        }
+    }
+
+    /// Deletes catchpoint `number`, or every catchpoint if None.
+    fn delete_catchpoints(&mut self, number: Option<usize>) {
+        match number {
+            None => self.catchpoints.clear(),
+            Some(number) => match self.catchpoints.iter().position(|catchpoint| catchpoint.number == number) {
+                Some(index) => {
+                    self.catchpoints.remove(index);
+                }
+                None => println!("No catchpoint number {}.", number),
+            },
+        }
+    }
+
+    fn list_bps(&self) {
+        let mut breakpoints: Vec<(&Breakpoint, bool)> = self
+            .breakpoints
+            .values()
+            .filter(|breakpoint| !breakpoint.internal)
+            .map(|breakpoint| (breakpoint, false))
+            .chain(self.pending_bps.iter().map(|breakpoint| (breakpoint, true)))
+            .collect();
+        if breakpoints.is_empty() {
+            println!("No breakpoints.");
+            return;
+        }
+        breakpoints.sort_by_key(|(breakpoint, _)| (breakpoint.number, breakpoint.location_number));
+        println!("Num\tDisp\tAddress\t\t\tWhat");
+        for (breakpoint, pending) in breakpoints {
+            let disposition = if breakpoint.temporary { "del" } else { "keep" };
+            let location = self.describe_bp_location(breakpoint);
+            match breakpoint.location_number {
+                None if pending => {
+                    let number = output::breakpoint(&breakpoint.number.to_string());
+                    println!("{}\t{}\t{:<18}\t{}", number, disposition, "<PENDING>", breakpoint.location);
+                }
+                None => {
+                    let number = output::breakpoint(&breakpoint.number.to_string());
+                    println!("{}\t{}\t{:#018x}\t{}", number, disposition, breakpoint.addr, location);
+                }
+                // A breakpoint with several locations gets a line of its own, then one for each
+                // location
+                Some(1) => {
+                    let count = self.breakpoints.values().filter(|bp| !bp.internal && bp.number == breakpoint.number).count();
+                    let number = output::breakpoint(&breakpoint.number.to_string());
+                    println!("{}\t{}\t{:<18}\t{} locations", number, disposition, "<MULTIPLE>", count);
+                }
+                Some(_) => {}
+            }
+            if breakpoint.location_number.unwrap_or(1) == 1 {
+                if let Some(condition) = &breakpoint.condition {
+                    println!("\tstop only if {}", condition);
+                }
+                for line in &breakpoint.commands {
+                    println!("\t{}", line);
+                }
+            }
+            if breakpoint.location_number.is_some() {
+                println!("{}\t\t{:#018x}\t{}", output::breakpoint(&breakpoint.label()), breakpoint.addr, location);
+            }
+        }
+    }
+
+    /// Describes where a breakpoint location is in the source now, for `info breakpoints`, noting
+    /// the line it was set at if that's different.
+    fn describe_bp_location(&self, breakpoint: &Breakpoint) -> String {
+        let addr = breakpoint.addr;
+        let line = self.source_line(addr);
+        let mut description = match &line {
+            Some(line) => format!(
+                "in {} at {}",
+                output::function(&self.debug_data.get_function_from_addr(addr).unwrap_or_else(|| "??".to_string())),
+                output::location(line)
+            ),
+            None => String::new(),
+        };
+        if let Some(set_at) = breakpoint.line.as_ref().filter(|&set_at| line.as_ref() != Some(set_at)) {
+            description.push_str(&format!(" (set at {})", set_at));
+        }
+        description
     }
 }
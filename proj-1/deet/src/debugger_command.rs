@@ -1,28 +1,1473 @@
+use crate::dwarf_data::ValueFormat;
+use nix::sys::signal::Signal;
+use std::convert::TryFrom;
+
 pub enum DebuggerCommand {
-    Quit,
+    /// Quit, without asking first if true
+    Quit(bool),
+    /// Run with the given arguments, or with the previous ones if empty
     Run(Vec<String>),
-    Continue,
-    Backtrace,
-    Breakpoint(String),
-    Next,
+    /// Like Run, but stop at the start of main
+    Start(Vec<String>),
+    /// Run over and over until a run fails, at most the given number of times
+    RunUntilFailure(Option<usize>),
+    /// Add this argument (with {} replaced by the run's number) to each run of
+    /// RunUntilFailure, or none if None
+    SetRerunArg(Option<String>),
+    ShowRerunArg,
+    /// Continue the given number of times
+    Continue(usize),
+    /// Show at most the given number of frames
+    Backtrace(usize),
+    /// Set a breakpoint at the given location, even outside executable memory if true
+    Breakpoint(String, bool),
+    TemporaryBreakpoint(String, bool),
+    ListBreakpoints,
+    /// Debug the given executable instead
+    File(String),
+    /// Set the command lines run when the breakpoint with the given number is hit
+    BreakpointCommands(usize, Vec<String>),
+    /// Make the breakpoint with the given number stop only if the expression is true, or always
+    /// if None
+    Condition(usize, Option<String>),
+    /// Step over the given number of lines
+    Next(usize),
+    /// Execute the given number of instructions
+    StepInstruction(usize),
+    /// Execute the given number of instructions, running called functions to completion
+    NextInstruction(usize),
+    /// Continue until the given location, or until a line after the current one if None
+    Until(Option<String>),
+    Finish,
+    /// Move %rip to the given location without running the code in between
+    Jump(String),
+    /// Make the current function return right away, with the given return value if any
+    Return(Option<String>),
+    InfoRegisters(Vec<String>),
+    InfoLocals,
+    /// Print the value of an expression
+    Print(ValueFormat, String),
+    /// Print an expression like `print` does every time the inferior stops
+    Display(ValueFormat, String),
+    /// Print every display expression now
+    ShowDisplays,
+    ListDisplays,
+    /// Stop displaying the expression with the given number, or all of them if None
+    Undisplay(Option<usize>),
+    /// List functions, optionally only those whose names contain the given text
+    InfoFunctions(Option<String>),
+    InfoLine(String),
+    /// Examine memory: the format specifier (the part after "x/", possibly empty) and the address
+    Examine(String, Option<String>),
+    Attach(Option<String>),
+    Detach,
+    Kill,
+    /// Stop the program, which was left running when waiting for it timed out
+    Interrupt,
+    /// Wait at most the given number of seconds for the program to stop after resuming it, or
+    /// for as long as it takes if None
+    SetWaitTimeout(Option<u64>),
+    ShowWaitTimeout,
+    SetArgs(Vec<String>),
+    ShowArgs,
+    SetEnv(String, String),
+    UnsetEnv(String),
+    /// Show the given environment variable, or the whole environment if None
+    ShowEnv(Option<String>),
+    Cd(String),
+    /// Assign a value to a variable or (with a leading `$`) a register
+    SetVariable(String, String),
+    /// List source code around the given line, or continue the previous listing if None
+    List(Option<usize>),
+    /// Select the frame the given number of levels further out
+    Up(usize),
+    /// Select the frame the given number of levels further in
+    Down(usize),
+    /// Select the given frame, or describe the selected one if None
+    Frame(Option<usize>),
+    /// Deliver the given signal when the inferior is next resumed
+    Signal(String),
+    /// Set whether the given signal is passed on to the inferior ("pass" or "nopass"), or show
+    /// the setting if None
+    Handle(String, Option<String>),
+    /// Show all commands, or explain the given one
+    Help(Option<String>),
+    /// Show the given number of most recent command lines
+    History(usize),
+    /// Disassemble the given function or address, or the code around the selected frame's %rip
+    Disassemble(Option<String>),
+    /// Stop when the inferior reaches the given event
+    Catch(CatchEvent),
+    ListCatchpoints,
+    /// Delete every location of the breakpoint with the given number
+    DeleteBreakpoint(usize),
+    /// Delete the catchpoint with the given number, or all of them if None
+    DeleteCatchpoints(Option<usize>),
+    SetFollowForkMode(FollowForkMode),
+    ShowFollowForkMode,
+    InfoInferiors,
+    InfoThreads,
+    /// Show the number of runs, breakpoint hits and signals so far, and the total runtime
+    InfoStats,
+    /// Select the thread with the given number, or describe the selected one if None
+    Thread(Option<usize>),
 }
 
-impl DebuggerCommand {
-    pub fn from_tokens(tokens: &Vec<&str>) -> Option<DebuggerCommand> {
-        match tokens[0] {
-            "q" | "quit" => Some(DebuggerCommand::Quit),
-            "r" | "run" => {
-                let args = tokens[1..].to_vec();
-                Some(DebuggerCommand::Run(
-                    args.iter().map(|s| s.to_string()).collect(),
-                ))
-            }
-            "c" | "cont" | "continue" => Some(DebuggerCommand::Continue),
-            "bt" | "back" | "backtrace" => Some(DebuggerCommand::Backtrace),
-            "b" | "break" => Some(DebuggerCommand::Breakpoint(tokens[1].to_string())),
-            "n" | "next" => Some(DebuggerCommand::Next),
-            // Default case:
+/// Events a catchpoint can stop at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CatchEvent {
+    /// The inferior is about to exit
+    Exit,
+    /// The inferior called exec
+    Exec,
+}
+
+/// Which process to keep debugging when the inferior forks
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FollowForkMode {
+    #[default]
+    Parent,
+    Child,
+}
+
+impl FollowForkMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            FollowForkMode::Parent => "parent",
+            FollowForkMode::Child => "child",
+        }
+    }
+}
+
+impl CatchEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            CatchEvent::Exit => "exit",
+            CatchEvent::Exec => "exec",
+        }
+    }
+}
+
+/// Splits a command line into tokens on whitespace. Single or double quotes group words into one
+/// token (`run "two words" other` has three tokens), and a backslash escapes the next character
+/// outside single quotes.
+pub fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    // Distinguishes an empty quoted token ("") from no token at all
+    let mut in_token = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => token.push(c),
+            (_, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    token.push(escaped);
+                }
+                in_token = true;
+            }
+            (Some(_), c) => token.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_token = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut token));
+                    in_token = false;
+                }
+            }
+            (None, c) => {
+                token.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Parses a signal given by name, with or without the SIG prefix (`SIGUSR1`, `usr1`), or by
+/// number.
+pub fn parse_signal(name: &str) -> Option<Signal> {
+    if let Ok(number) = name.parse::<i32>() {
+        return Signal::try_from(number).ok();
+    }
+    let name = name.to_uppercase();
+    if name.starts_with("SIG") {
+        name.parse().ok()
+    } else {
+        format!("SIG{}", name).parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExamineFormat {
+    Hex,
+    Decimal,
+    Str,
+}
+
+/// Parsed form of the `/4xw` part of an `x` command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExamineSpec {
+    pub count: usize,
+    pub format: ExamineFormat,
+    /// Size of each unit in bytes (1, 2, 4 or 8). Ignored for strings.
+    pub size: usize,
+}
+
+impl ExamineSpec {
+    /// Parses a gdb-style format specifier: an optional repeat count followed by format (x, d, s)
+    /// and size (b, h, w, g) letters in any order. Anything left out defaults to one hex word.
+    pub fn parse(spec: &str) -> Result<ExamineSpec, String> {
+        let digits_end = spec.find(|c: char| !c.is_ascii_digit()).unwrap_or(spec.len());
+        let count = if digits_end == 0 {
+            1
+        } else {
+            spec[..digits_end]
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid count in \"{}\"", spec))?
+        };
+        if count == 0 {
+            return Err("Count must be positive".to_string());
+        }
+        let mut parsed = ExamineSpec {
+            count,
+            format: ExamineFormat::Hex,
+            size: 4,
+        };
+        for letter in spec[digits_end..].chars() {
+            match letter {
+                'x' => parsed.format = ExamineFormat::Hex,
+                'd' => parsed.format = ExamineFormat::Decimal,
+                's' => parsed.format = ExamineFormat::Str,
+                'b' => parsed.size = 1,
+                'h' => parsed.size = 2,
+                'w' => parsed.size = 4,
+                'g' => parsed.size = 8,
+                _ => return Err(format!("Invalid format letter '{}'", letter)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Frames `backtrace` shows when not told how many
+const DEFAULT_BACKTRACE_DEPTH: usize = 64;
+
+/// Parses the /FMT suffix of a `print` or `display` command name, like the x in `print/x`.
+fn value_format(name: &str) -> Option<ValueFormat> {
+    match name.find('/').map(|slash| &name[slash + 1..]) {
+        None | Some("d") => Some(ValueFormat::Natural),
+        Some("x") => Some(ValueFormat::Hex),
+        Some("b") => Some(ValueFormat::Binary),
+        Some("c") => Some(ValueFormat::Char),
+        Some(_) => None,
+    }
+}
+
+/// Parses what follows the location of `break` or `tbreak`: nothing, or `--force`.
+fn force_flag(tokens: &[&str]) -> Option<bool> {
+    match tokens.get(2..)? {
+        [] => Some(false),
+        ["--force"] => Some(true),
+        _ => None,
+    }
+}
+
+/// Collects the given tokens into owned strings.
+fn strings(tokens: &[&str]) -> Vec<String> {
+    tokens.iter().map(|s| s.to_string()).collect()
+}
+
+/// Parses an optional numeric argument, using `default` if it's missing.
+fn optional_number(token: Option<&&str>, default: usize) -> Option<usize> {
+    token.map_or(Some(default), |n| n.parse().ok())
+}
+
+/// Parses an optional repetition count like the 3 in `next 3`, which must be positive.
+fn repeat_count(token: Option<&&str>) -> Option<usize> {
+    optional_number(token, 1).filter(|&count| count > 0)
+}
+
+/// An entry in the command table, which drives both parsing and `help`.
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Fewest arguments the command accepts
+    pub min_args: usize,
+    /// Whether the command accepts a /FMT suffix, like `x/4xw`
+    pub takes_format: bool,
+    pub usage: &'static str,
+    /// One-line description for `help`
+    pub summary: &'static str,
+    /// Longer explanation for `help <command>`
+    pub details: &'static str,
+    /// Builds the command from all of its tokens (including the command name itself), or returns
+    /// None if the arguments are invalid
+    parse: fn(&[&str]) -> Option<DebuggerCommand>,
+}
+
+pub static COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "run",
+        aliases: &["r"],
+        min_args: 0,
+        takes_format: false,
+        usage: "run [ARGS...] | run --until-failure [MAX]",
+        summary: "Start the program, killing any running instance first",
+        details: "Without ARGS, the arguments from the previous run (or `set args`) are reused.\n\
+                  `run --until-failure` runs the program over and over (at most MAX times) until \
+                  a run exits with a nonzero status, is killed by a signal or stops. With \
+                  `set rerun-arg`, each run gets an extra argument, and a failing run is repeated \
+                  with the same one.",
+        parse: |tokens| match tokens.get(1) {
+            Some(&"--until-failure") => match tokens.get(2) {
+                Some(max) => Some(DebuggerCommand::RunUntilFailure(Some(max.parse().ok().filter(|&max| max > 0)?))),
+                None => Some(DebuggerCommand::RunUntilFailure(None)),
+            },
+            _ => Some(DebuggerCommand::Run(strings(&tokens[1..]))),
+        },
+    },
+    CommandSpec {
+        name: "start",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "start [ARGS...]",
+        summary: "Start the program and stop at the beginning of main",
+        details: "Takes ARGS like `run`. Without a symbol for main, stops at the program's entry \
+                  point instead. A breakpoint already set at main is reported as hit.",
+        parse: |tokens| Some(DebuggerCommand::Start(strings(&tokens[1..]))),
+    },
+    CommandSpec {
+        name: "continue",
+        aliases: &["c", "cont"],
+        min_args: 0,
+        takes_format: false,
+        usage: "continue [N]",
+        summary: "Resume the program until it stops again",
+        details: "With N, keeps resuming after each breakpoint until N stops have happened.",
+        parse: |tokens| Some(DebuggerCommand::Continue(repeat_count(tokens.get(1))?)),
+    },
+    CommandSpec {
+        name: "next",
+        aliases: &["n"],
+        min_args: 0,
+        takes_format: false,
+        usage: "next [N]",
+        summary: "Run to the next source line, stepping over calls",
+        details: "Stops early if the current function returns. With N, steps over N lines, \
+                  stopping sooner at a breakpoint or signal.",
+        parse: |tokens| Some(DebuggerCommand::Next(repeat_count(tokens.get(1))?)),
+    },
+    CommandSpec {
+        name: "stepi",
+        aliases: &["si"],
+        min_args: 0,
+        takes_format: false,
+        usage: "stepi [N]",
+        summary: "Execute one machine instruction",
+        details: "With N, executes N instructions, stopping sooner at a breakpoint or signal.",
+        parse: |tokens| Some(DebuggerCommand::StepInstruction(repeat_count(tokens.get(1))?)),
+    },
+    CommandSpec {
+        name: "nexti",
+        aliases: &["ni"],
+        min_args: 0,
+        takes_format: false,
+        usage: "nexti [N]",
+        summary: "Execute one machine instruction, stepping over calls",
+        details: "A call instruction runs until the called function returns. With N, executes \
+                  N instructions, stopping sooner at a breakpoint or signal.",
+        parse: |tokens| Some(DebuggerCommand::NextInstruction(repeat_count(tokens.get(1))?)),
+    },
+    CommandSpec {
+        name: "until",
+        aliases: &["u"],
+        min_args: 0,
+        takes_format: false,
+        usage: "until [LOCATION]",
+        summary: "Run until a line after the current one, or until LOCATION",
+        details: "Without LOCATION, jumps back to earlier lines (like the top of a loop) don't \
+                  stop the program.",
+        parse: |tokens| Some(DebuggerCommand::Until(tokens.get(1).map(|s| s.to_string()))),
+    },
+    CommandSpec {
+        name: "advance",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "advance LOCATION",
+        summary: "Run until LOCATION is reached",
+        details: "",
+        parse: |tokens| Some(DebuggerCommand::Until(Some(tokens[1].to_string()))),
+    },
+    CommandSpec {
+        name: "finish",
+        aliases: &["fin"],
+        min_args: 0,
+        takes_format: false,
+        usage: "finish",
+        summary: "Run until the current function returns, and show its return value",
+        details: "",
+        parse: |_| Some(DebuggerCommand::Finish),
+    },
+    CommandSpec {
+        name: "jump",
+        aliases: &["j"],
+        min_args: 1,
+        takes_format: false,
+        usage: "jump LOCATION",
+        summary: "Move the program to LOCATION without running the code in between",
+        details: "LOCATION is written the same way as for `break`. The program stays stopped there. \
+                  Skipping or repeating code can leave the program in an inconsistent state, so \
+                  jumping out of the current function asks first.",
+        parse: |tokens| Some(DebuggerCommand::Jump(tokens[1].to_string())),
+    },
+    CommandSpec {
+        name: "return",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "return [VALUE]",
+        summary: "Make the current function return now, with VALUE if given",
+        details: "Asks first. The rest of the function doesn't run, so anything it would have \
+                  cleaned up stays as it is, and registers it saved other than %rbp aren't \
+                  restored.",
+        parse: |tokens| match tokens.len() {
+            1 => Some(DebuggerCommand::Return(None)),
+            2 => Some(DebuggerCommand::Return(Some(tokens[1].to_string()))),
+            _ => None,
+        },
+    },
+    CommandSpec {
+        name: "break",
+        aliases: &["b"],
+        min_args: 1,
+        takes_format: false,
+        usage: "break LOCATION [--force] | break list",
+        summary: "Set a breakpoint",
+        details: "LOCATION is a function, a line number, FILE:LINE, FILE:FUNCTION or *ADDRESS.\n\
+                  An address outside the program's code is refused unless --force is given.\n\
+                  `break list` lists breakpoints, like `info breakpoints`.",
+        parse: |tokens| match tokens[1] {
+            "list" => Some(DebuggerCommand::ListBreakpoints),
+            location => Some(DebuggerCommand::Breakpoint(location.to_string(), force_flag(tokens)?)),
+        },
+    },
+    CommandSpec {
+        name: "commands",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "commands N [\"COMMAND; COMMAND...\"]",
+        summary: "Run commands whenever breakpoint N is hit",
+        details: "The commands, separated by semicolons, run in order once the breakpoint stops the \
+                  program. If one of them resumes it (like continue), the rest are skipped. \
+                  Without commands, breakpoint N's commands are removed.",
+        parse: |tokens| {
+            let number = tokens[1].parse().ok()?;
+            let lines = tokens[2..]
+                .join(" ")
+                .split(';')
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect();
+            Some(DebuggerCommand::BreakpointCommands(number, lines))
+        },
+    },
+    CommandSpec {
+        name: "condition",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "condition N [EXPRESSION]",
+        summary: "Make breakpoint N stop the program only if an expression is true",
+        details: "EXPRESSION is written the same way as for `print`, and is true if it's nonzero. It's \
+                  evaluated each time the breakpoint is hit, in the frame the breakpoint is in. \
+                  Without EXPRESSION, breakpoint N stops the program every time again.",
+        parse: |tokens| {
+            let number = tokens[1].parse().ok()?;
+            let condition = Some(tokens[2..].join(" ")).filter(|condition| !condition.is_empty());
+            Some(DebuggerCommand::Condition(number, condition))
+        },
+    },
+    CommandSpec {
+        name: "file",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "file PROGRAM",
+        summary: "Debug a different program",
+        details: "Kills the running program first. Breakpoints are moved to the same locations in \
+                  PROGRAM. `run` also re-reads the program if it has been rebuilt.",
+        parse: |tokens| Some(DebuggerCommand::File(tokens[1].to_string())),
+    },
+    CommandSpec {
+        name: "tbreak",
+        aliases: &["tb"],
+        min_args: 1,
+        takes_format: false,
+        usage: "tbreak LOCATION [--force]",
+        summary: "Set a breakpoint that is deleted when it's hit",
+        details: "LOCATION and --force work the same way as for `break`.",
+        parse: |tokens| Some(DebuggerCommand::TemporaryBreakpoint(tokens[1].to_string(), force_flag(tokens)?)),
+    },
+    CommandSpec {
+        name: "catch",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "catch exit | catch exec | catch list",
+        summary: "Stop when the program is about to exit, or calls exec",
+        details: "At `catch exit`, the program can still be inspected, and `continue` lets it exit.\n\
+                  `catch list` lists catchpoints.",
+        parse: |tokens| match tokens[1] {
+            "exit" => Some(DebuggerCommand::Catch(CatchEvent::Exit)),
+            "exec" => Some(DebuggerCommand::Catch(CatchEvent::Exec)),
+            "list" => Some(DebuggerCommand::ListCatchpoints),
+            _ => None,
+        },
+    },
+    CommandSpec {
+        name: "delete",
+        aliases: &["d"],
+        min_args: 1,
+        takes_format: false,
+        usage: "delete N | delete catch [N]",
+        summary: "Delete a breakpoint, or catchpoints",
+        details: "Deleting a breakpoint deletes all of its locations. \
+                  Without N, `delete catch` deletes every catchpoint.",
+        parse: |tokens| match tokens[1] {
+            "catch" => match tokens.get(2) {
+                Some(number) => Some(DebuggerCommand::DeleteCatchpoints(Some(number.parse().ok()?))),
+                None => Some(DebuggerCommand::DeleteCatchpoints(None)),
+            },
+            number => Some(DebuggerCommand::DeleteBreakpoint(number.parse().ok()?)).filter(|_| tokens.len() == 2),
+        },
+    },
+    CommandSpec {
+        name: "backtrace",
+        aliases: &["bt", "back", "where"],
+        min_args: 0,
+        takes_format: false,
+        usage: "backtrace [N]",
+        summary: "Show the stack, marking the selected frame",
+        details: "Shows the innermost N frames (64 by default).",
+        parse: |tokens| {
+            let depth = optional_number(tokens.get(1), DEFAULT_BACKTRACE_DEPTH)?;
+            Some(DebuggerCommand::Backtrace(depth)).filter(|_| depth > 0)
+        },
+    },
+    CommandSpec {
+        name: "up",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "up [N]",
+        summary: "Select the frame N levels (default 1) further out",
+        details: "",
+        parse: |tokens| Some(DebuggerCommand::Up(optional_number(tokens.get(1), 1)?)),
+    },
+    CommandSpec {
+        name: "down",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "down [N]",
+        summary: "Select the frame N levels (default 1) further in",
+        details: "",
+        parse: |tokens| Some(DebuggerCommand::Down(optional_number(tokens.get(1), 1)?)),
+    },
+    CommandSpec {
+        name: "frame",
+        aliases: &["f"],
+        min_args: 0,
+        takes_format: false,
+        usage: "frame [N]",
+        summary: "Select frame N, or describe the selected frame",
+        details: "",
+        parse: |tokens| match tokens.get(1) {
+            Some(number) => Some(DebuggerCommand::Frame(Some(number.parse().ok()?))),
+            None => Some(DebuggerCommand::Frame(None)),
+        },
+    },
+    CommandSpec {
+        name: "thread",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "thread [N]",
+        summary: "Select thread N, or describe the selected thread",
+        details: "Stack, register and variable commands, and stepping, apply to the selected \
+                  thread. The other threads stay stopped while the program is stopped, and all of \
+                  them run when it's resumed. Threads are numbered from 1 (the main thread), as \
+                  `info threads` lists them.",
+        parse: |tokens| match tokens.get(1) {
+            Some(number) => Some(DebuggerCommand::Thread(Some(number.parse().ok()?))),
+            None => Some(DebuggerCommand::Thread(None)),
+        },
+    },
+    CommandSpec {
+        name: "list",
+        aliases: &["l"],
+        min_args: 0,
+        takes_format: false,
+        usage: "list [LINE]",
+        summary: "Show source code around LINE, or continue the previous listing",
+        details: "",
+        parse: |tokens| match tokens.get(1) {
+            Some(line) => Some(DebuggerCommand::List(Some(line.parse().ok()?))),
+            None => Some(DebuggerCommand::List(None)),
+        },
+    },
+    CommandSpec {
+        name: "info",
+        aliases: &["i"],
+        min_args: 1,
+        takes_format: false,
+        usage: "info breakpoints | info registers [REGISTER...] | info locals | \
+                info functions [TEXT] | info line LOCATION | info inferiors | info display | \
+                info threads | info stats",
+        summary: "Show breakpoints, registers, local variables, functions, line addresses, \
+                  processes, display expressions, threads or session statistics",
+        details: "`info functions TEXT` only lists functions whose names contain TEXT.\n\
+                  `info line` shows the addresses of the line `break LOCATION` would stop at.",
+        parse: |tokens| match tokens[1] {
+            "b" | "break" | "breakpoints" => Some(DebuggerCommand::ListBreakpoints),
+            "r" | "reg" | "registers" => Some(DebuggerCommand::InfoRegisters(strings(&tokens[2..]))),
+            "locals" => Some(DebuggerCommand::InfoLocals),
+            "functions" => Some(DebuggerCommand::InfoFunctions(tokens.get(2).map(|s| s.to_string()))),
+            "line" => Some(DebuggerCommand::InfoLine(tokens.get(2)?.to_string())),
+            "inferiors" => Some(DebuggerCommand::InfoInferiors),
+            "display" => Some(DebuggerCommand::ListDisplays),
+            "threads" => Some(DebuggerCommand::InfoThreads),
+            "stats" => Some(DebuggerCommand::InfoStats),
+            _ => None,
+        },
+    },
+    CommandSpec {
+        name: "regs",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "regs [REGISTER...]",
+        summary: "Show registers, like `info registers`",
+        details: "",
+        parse: |tokens| Some(DebuggerCommand::InfoRegisters(strings(&tokens[1..]))),
+    },
+    CommandSpec {
+        name: "print",
+        aliases: &["p"],
+        min_args: 1,
+        takes_format: true,
+        usage: "print[/FMT] EXPRESSION",
+        summary: "Print the value of an expression",
+        details: "FMT is x (hex), b (binary), c (character) or d (decimal). EXPRESSION is made of \
+                  variables and integers, with the operators + - * / % == != < <= > >= && || !, \
+                  *POINTER, &VARIABLE, ARRAY[INDEX] (arrays, pointers and Rust slices), \
+                  STRUCT.MEMBER and POINTER->MEMBER. Rust &str and String values are printed as \
+                  their text and length, and have a .len member.",
+        parse: |tokens| Some(DebuggerCommand::Print(value_format(tokens[0])?, tokens[1..].join(" "))),
+    },
+    CommandSpec {
+        name: "display",
+        aliases: &["disp"],
+        min_args: 0,
+        takes_format: true,
+        usage: "display[/FMT] [EXPRESSION]",
+        summary: "Print an expression every time the program stops",
+        details: "FMT and EXPRESSION are written the same way as for `print`. Without EXPRESSION, \
+                  prints every display expression now.",
+        parse: |tokens| {
+            let format = value_format(tokens[0])?;
+            match tokens.get(1) {
+                Some(_) => Some(DebuggerCommand::Display(format, tokens[1..].join(" "))),
+                None if !tokens[0].contains('/') => Some(DebuggerCommand::ShowDisplays),
+                None => None,
+            }
+        },
+    },
+    CommandSpec {
+        name: "undisplay",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "undisplay [N]",
+        summary: "Stop displaying an expression",
+        details: "Without N, removes every display expression.",
+        parse: |tokens| match tokens.get(1) {
+            Some(number) => Some(DebuggerCommand::Undisplay(Some(number.parse().ok()?))),
+            None => Some(DebuggerCommand::Undisplay(None)),
+        },
+    },
+    CommandSpec {
+        name: "x",
+        aliases: &[],
+        min_args: 0,
+        takes_format: true,
+        usage: "x[/FMT] ADDRESS",
+        summary: "Examine memory",
+        details: "FMT is a repeat count followed by a format letter (x hex, d decimal, s string) \
+                  and a size letter (b, h, w, g for 1, 2, 4, 8 bytes). Strings are read up to \
+                  their NUL terminator, or 4096 bytes.\n\
+                  ADDRESS is a hex address or a register like $rsp.",
+        parse: |tokens| {
+            let spec = tokens[0].find('/').map_or("", |slash| &tokens[0][slash + 1..]);
+            Some(DebuggerCommand::Examine(spec.to_string(), tokens.get(1).map(|s| s.to_string())))
+        },
+    },
+    CommandSpec {
+        name: "disassemble",
+        aliases: &["disas"],
+        min_args: 0,
+        takes_format: false,
+        usage: "disassemble [FUNCTION | ADDRESS]",
+        summary: "Show machine instructions",
+        details: "Without an argument, disassembles the function the selected frame is in.",
+        parse: |tokens| Some(DebuggerCommand::Disassemble(tokens.get(1).map(|s| s.to_string()))),
+    },
+    CommandSpec {
+        name: "set",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "set args [ARGS...] | set env NAME=VALUE | set var NAME = VALUE | \
+                set follow-fork-mode parent|child | set rerun-arg [ARG] | set wait-timeout SECONDS",
+        summary: "Set program arguments, environment variables, variable values or settings",
+        details: "`set var $REGISTER = VALUE` sets a register.\n\
+                  `set follow-fork-mode` chooses which process to debug after a fork; the other \
+                  one runs on untraced.\n\
+                  `set rerun-arg` gives each run of `run --until-failure` an extra argument, with \
+                  {} replaced by the run's number (e.g. --seed={}). Without ARG, there's none.\n\
+                  `set wait-timeout` returns to the prompt when the program is still running \
+                  SECONDS after resuming it; `interrupt` stops it then. 0 means no timeout.",
+        parse: |tokens| match tokens[1] {
+            "args" => Some(DebuggerCommand::SetArgs(strings(&tokens[2..]))),
+            "env" | "environment" => {
+                // Either `set env NAME=value` or `set env NAME value`
+                let assignment = tokens[2..].join(" ");
+                let (name, value) = match tokens.get(2)?.find('=') {
+                    Some(equals) => (&tokens[2][..equals], assignment[equals + 1..].to_string()),
+                    None => (tokens[2], tokens[3..].join(" ")),
+                };
+                if name.is_empty() {
+                    return None;
+                }
+                Some(DebuggerCommand::SetEnv(name.to_string(), value))
+            }
+            "var" | "variable" => {
+                // The spaces around `=` are optional
+                let assignment = tokens[2..].join(" ");
+                let mut sides = assignment.splitn(2, '=').map(|side| side.trim());
+                let (name, value) = (sides.next()?, sides.next()?);
+                if name.is_empty() || value.is_empty() {
+                    return None;
+                }
+                Some(DebuggerCommand::SetVariable(name.to_string(), value.to_string()))
+            }
+            "follow-fork-mode" => match *tokens.get(2)? {
+                "parent" => Some(DebuggerCommand::SetFollowForkMode(FollowForkMode::Parent)),
+                "child" => Some(DebuggerCommand::SetFollowForkMode(FollowForkMode::Child)),
+                _ => None,
+            },
+            "rerun-arg" => Some(DebuggerCommand::SetRerunArg(tokens.get(2).map(|s| s.to_string()))),
+            "wait-timeout" => match tokens.get(2)?.parse().ok()? {
+                0 => Some(DebuggerCommand::SetWaitTimeout(None)),
+                seconds => Some(DebuggerCommand::SetWaitTimeout(Some(seconds))),
+            },
             _ => None,
+        },
+    },
+    CommandSpec {
+        name: "show",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "show args | show env [NAME] | show follow-fork-mode | show rerun-arg | \
+                show wait-timeout",
+        summary: "Show program arguments, environment variables or settings",
+        details: "",
+        parse: |tokens| match tokens[1] {
+            "args" => Some(DebuggerCommand::ShowArgs),
+            "follow-fork-mode" => Some(DebuggerCommand::ShowFollowForkMode),
+            "rerun-arg" => Some(DebuggerCommand::ShowRerunArg),
+            "wait-timeout" => Some(DebuggerCommand::ShowWaitTimeout),
+            "env" | "environment" => Some(DebuggerCommand::ShowEnv(tokens.get(2).map(|s| s.to_string()))),
+            _ => None,
+        },
+    },
+    CommandSpec {
+        name: "unset",
+        aliases: &[],
+        min_args: 2,
+        takes_format: false,
+        usage: "unset env NAME",
+        summary: "Remove an environment variable from the program's environment",
+        details: "",
+        parse: |tokens| match tokens[1] {
+            "env" | "environment" => Some(DebuggerCommand::UnsetEnv(tokens[2].to_string())),
+            _ => None,
+        },
+    },
+    CommandSpec {
+        name: "cd",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "cd DIRECTORY",
+        summary: "Set the working directory the program runs in",
+        details: "",
+        parse: |tokens| Some(DebuggerCommand::Cd(tokens[1].to_string())),
+    },
+    CommandSpec {
+        name: "signal",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "signal SIGNAL",
+        summary: "Deliver SIGNAL to the program when it's next resumed",
+        details: "`signal 0` cancels delivering the signal that stopped the program.",
+        parse: |tokens| Some(DebuggerCommand::Signal(tokens[1].to_string())),
+    },
+    CommandSpec {
+        name: "handle",
+        aliases: &[],
+        min_args: 1,
+        takes_format: false,
+        usage: "handle SIGNAL [pass | nopass]",
+        summary: "Choose whether SIGNAL is passed to the program after stopping it",
+        details: "",
+        parse: |tokens| Some(DebuggerCommand::Handle(tokens[1].to_string(), tokens.get(2).map(|s| s.to_string()))),
+    },
+    CommandSpec {
+        name: "attach",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "attach PID",
+        summary: "Debug an already running process",
+        details: "",
+        parse: |tokens| Some(DebuggerCommand::Attach(tokens.get(1).map(|s| s.to_string()))),
+    },
+    CommandSpec {
+        name: "detach",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "detach",
+        summary: "Let the program run on without the debugger",
+        details: "",
+        parse: |_| Some(DebuggerCommand::Detach),
+    },
+    CommandSpec {
+        name: "kill",
+        aliases: &["k"],
+        min_args: 0,
+        takes_format: false,
+        usage: "kill",
+        summary: "Kill the program",
+        details: "",
+        parse: |_| Some(DebuggerCommand::Kill),
+    },
+    CommandSpec {
+        name: "interrupt",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "interrupt",
+        summary: "Stop the program when it was left running after the wait timeout",
+        details: "The program is stopped with SIGINT, as ctrl+c would while waiting for it.",
+        parse: |_| Some(DebuggerCommand::Interrupt),
+    },
+    CommandSpec {
+        name: "history",
+        aliases: &[],
+        min_args: 0,
+        takes_format: false,
+        usage: "history [N]",
+        summary: "Show the last N command lines (10 by default)",
+        details: "Each line is numbered; !N runs line N again. History is kept separately for \
+                  each program, and saved when deet exits.",
+        parse: |tokens| Some(DebuggerCommand::History(optional_number(tokens.get(1), 10)?)),
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &["h"],
+        min_args: 0,
+        takes_format: false,
+        usage: "help [COMMAND]",
+        summary: "List commands, or explain COMMAND",
+        details: "",
+        parse: |tokens| Some(DebuggerCommand::Help(tokens.get(1).map(|s| s.to_string()))),
+    },
+    CommandSpec {
+        name: "quit",
+        aliases: &["q", "quit!", "q!"],
+        min_args: 0,
+        takes_format: false,
+        usage: "quit[!] [-y]",
+        summary: "Exit, killing the program (or detaching from it if it was attached to)",
+        details: "Asks first if the program is still running, unless given as quit! or quit -y. \
+                  deet exits with the status the program last exited with.",
+        parse: |tokens| match tokens.get(1) {
+            None => Some(DebuggerCommand::Quit(tokens[0].ends_with('!'))),
+            Some(&"-y") => Some(DebuggerCommand::Quit(true)),
+            Some(_) => None,
+        },
+    },
+];
+
+/// Looks up a command by its name or one of its aliases.
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|spec| spec.name == name || spec.aliases.contains(&name))
+}
+
+/// Returns the text `help` prints: every command with a summary, or the full usage of `topic`.
+pub fn help_text(topic: Option<&str>) -> Result<String, String> {
+    match topic {
+        None => {
+            let mut text = String::from("List of commands:\n");
+            for spec in COMMANDS {
+                let mut names = vec![spec.name];
+                names.extend(spec.aliases);
+                text.push_str(&format!("  {:<28}{}\n", names.join(", "), spec.summary));
+            }
+            text.push_str("Type \"help\" followed by a command name for more information.");
+            Ok(text)
+        }
+        Some(topic) => {
+            let spec = find_command(topic).ok_or_else(|| format!("Undefined command: \"{}\". Try \"help\".", topic))?;
+            let mut text = format!("Usage: {}\n{}.", spec.usage, spec.summary);
+            if !spec.aliases.is_empty() {
+                text.push_str(&format!("\nAliases: {}", spec.aliases.join(", ")));
+            }
+            if !spec.details.is_empty() {
+                text.push_str(&format!("\n{}", spec.details));
+            }
+            Ok(text)
+        }
+    }
+}
+
+impl DebuggerCommand {
+    /// Parses a tokenized command line. The error explains what was wrong, naming the command.
+    /// Tokenizes a whole command line and parses it like from_tokens.
+    pub fn parse_line(line: &str) -> Result<DebuggerCommand, String> {
+        let tokens = tokenize(line);
+        if tokens.is_empty() {
+            return Err("Empty command".to_string());
+        }
+        let tokens: Vec<&str> = tokens.iter().map(|s| s.as_str()).collect();
+        DebuggerCommand::from_tokens(&tokens)
+    }
+
+    pub fn from_tokens(tokens: &[&str]) -> Result<DebuggerCommand, String> {
+        let mut name_and_format = tokens[0].splitn(2, '/');
+        let name = name_and_format.next().unwrap();
+        let has_format = name_and_format.next().is_some();
+        let spec = match find_command(name) {
+            Some(spec) if spec.takes_format || !has_format => spec,
+            _ => return Err(format!("Undefined command: \"{}\". Try \"help\".", tokens[0])),
+        };
+        let num_args = tokens.len() - 1;
+        if num_args < spec.min_args {
+            return Err(format!(
+                "{} requires at least {} argument{}. Usage: {}",
+                spec.name,
+                spec.min_args,
+                if spec.min_args == 1 { "" } else { "s" },
+                spec.usage
+            ));
+        }
+        (spec.parse)(tokens).ok_or_else(|| format!("Invalid arguments to {}. Usage: {}", spec.name, spec.usage))
+    }
+
+    /// Returns whether this command can be used while the program is left running in the
+    /// background, which is only possible for ones that don't need it stopped. `continue` goes
+    /// back to waiting for it.
+    pub fn works_while_running(&self) -> bool {
+        matches!(
+            self,
+            DebuggerCommand::Interrupt
+                | DebuggerCommand::Continue(_)
+                | DebuggerCommand::Kill
+                | DebuggerCommand::Quit(_)
+                | DebuggerCommand::Run(_)
+                | DebuggerCommand::Start(_)
+                | DebuggerCommand::Help(_)
+                | DebuggerCommand::History(_)
+                | DebuggerCommand::InfoStats
+                | DebuggerCommand::SetWaitTimeout(_)
+                | DebuggerCommand::ShowWaitTimeout
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("run a  b"), vec!["run", "a", "b"]);
+        assert_eq!(tokenize("run \"two words\" other"), vec!["run", "two words", "other"]);
+        assert_eq!(tokenize("run 'it''s' \"\""), vec!["run", "its", ""]);
+        assert_eq!(tokenize("run \"say \\\"hi\\\"\" a\\ b"), vec!["run", "say \"hi\"", "a b"]);
+        assert!(tokenize("   ").is_empty());
+    }
+
+    #[test]
+    fn test_examine_spec_defaults() {
+        let spec = ExamineSpec::parse("").unwrap();
+        assert_eq!(spec.count, 1);
+        assert_eq!(spec.format, ExamineFormat::Hex);
+        assert_eq!(spec.size, 4);
+    }
+
+    #[test]
+    fn test_examine_spec_full() {
+        assert_eq!(
+            ExamineSpec::parse("4xw").unwrap(),
+            ExamineSpec { count: 4, format: ExamineFormat::Hex, size: 4 }
+        );
+        assert_eq!(
+            ExamineSpec::parse("16db").unwrap(),
+            ExamineSpec { count: 16, format: ExamineFormat::Decimal, size: 1 }
+        );
+        // Size and format may come in either order
+        assert_eq!(
+            ExamineSpec::parse("2gx").unwrap(),
+            ExamineSpec { count: 2, format: ExamineFormat::Hex, size: 8 }
+        );
+    }
+
+    #[test]
+    fn test_examine_spec_partial() {
+        assert_eq!(ExamineSpec::parse("3").unwrap().count, 3);
+        assert_eq!(ExamineSpec::parse("h").unwrap().size, 2);
+        assert_eq!(ExamineSpec::parse("s").unwrap().format, ExamineFormat::Str);
+    }
+
+    #[test]
+    fn test_examine_spec_invalid() {
+        assert!(ExamineSpec::parse("0x").is_err());
+        assert!(ExamineSpec::parse("4q").is_err());
+        assert!(ExamineSpec::parse("x4").is_err());
+        assert!(ExamineSpec::parse("99999999999999999999999x").is_err());
+    }
+
+    #[test]
+    fn test_catch_commands() {
+        match DebuggerCommand::from_tokens(&["catch", "exec"]) {
+            Ok(DebuggerCommand::Catch(event)) => assert_eq!(event, CatchEvent::Exec),
+            _ => panic!("catch exec should parse as a catchpoint"),
+        }
+        match DebuggerCommand::from_tokens(&["delete", "catch", "2"]) {
+            Ok(DebuggerCommand::DeleteCatchpoints(number)) => assert_eq!(number, Some(2)),
+            _ => panic!("delete catch 2 should parse as deleting a catchpoint"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["catch", "fork"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["delete", "catch", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_delete_breakpoint() {
+        match DebuggerCommand::from_tokens(&["d", "3"]) {
+            Ok(DebuggerCommand::DeleteBreakpoint(number)) => assert_eq!(number, 3),
+            _ => panic!("d 3 should delete breakpoint 3"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["delete", "main"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["delete", "1", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_follow_fork_mode() {
+        match DebuggerCommand::from_tokens(&["set", "follow-fork-mode", "child"]) {
+            Ok(DebuggerCommand::SetFollowForkMode(mode)) => assert_eq!(mode, FollowForkMode::Child),
+            _ => panic!("set follow-fork-mode child should parse"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["set", "follow-fork-mode"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["set", "follow-fork-mode", "both"]).is_err());
+    }
+
+    #[test]
+    fn test_quit_forms() {
+        for (tokens, forced) in &[
+            (&["quit"][..], false),
+            (&["q"][..], false),
+            (&["quit!"][..], true),
+            (&["q!"][..], true),
+            (&["quit", "-y"][..], true),
+        ] {
+            match DebuggerCommand::from_tokens(tokens) {
+                Ok(DebuggerCommand::Quit(force)) => assert_eq!(force, *forced),
+                _ => panic!("{:?} should parse as quit", tokens),
+            }
+        }
+        assert!(DebuggerCommand::from_tokens(&["quit", "now"]).is_err());
+    }
+
+    #[test]
+    fn test_examine_command() {
+        match DebuggerCommand::from_tokens(&["x/8xb", "$rsp"]) {
+            Ok(DebuggerCommand::Examine(spec, addr)) => {
+                assert_eq!(spec, "8xb");
+                assert_eq!(addr, Some("$rsp".to_string()));
+            }
+            _ => panic!("x/8xb should parse as an examine command"),
+        }
+    }
+
+    #[test]
+    fn test_list_command() {
+        match DebuggerCommand::from_tokens(&["list"]) {
+            Ok(DebuggerCommand::List(None)) => {}
+            _ => panic!("list should parse as a list command continuing the previous listing"),
+        }
+        match DebuggerCommand::from_tokens(&["l", "12"]) {
+            Ok(DebuggerCommand::List(Some(12))) => {}
+            _ => panic!("l 12 should parse as a list command centered on line 12"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["list", "main"]).is_err());
+    }
+
+    #[test]
+    fn test_frame_commands() {
+        match DebuggerCommand::from_tokens(&["up"]) {
+            Ok(DebuggerCommand::Up(1)) => {}
+            _ => panic!("up should default to one level"),
+        }
+        match DebuggerCommand::from_tokens(&["down", "3"]) {
+            Ok(DebuggerCommand::Down(3)) => {}
+            _ => panic!("down 3 should parse as three levels"),
+        }
+        match DebuggerCommand::from_tokens(&["frame", "2"]) {
+            Ok(DebuggerCommand::Frame(Some(2))) => {}
+            _ => panic!("frame 2 should select frame 2"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["up", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_set_variable_command() {
+        for tokens in &[vec!["set", "var", "x", "=", "5"], vec!["set", "variable", "x=5"]] {
+            match DebuggerCommand::from_tokens(tokens) {
+                Ok(DebuggerCommand::SetVariable(name, value)) => {
+                    assert_eq!(name, "x");
+                    assert_eq!(value, "5");
+                }
+                _ => panic!("{:?} should parse as a set variable command", tokens),
+            }
+        }
+        assert!(DebuggerCommand::from_tokens(&["set", "var", "x"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["set", "var", "x", "="]).is_err());
+    }
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("SIGUSR1"), Some(Signal::SIGUSR1));
+        assert_eq!(parse_signal("usr1"), Some(Signal::SIGUSR1));
+        assert_eq!(parse_signal("11"), Some(Signal::SIGSEGV));
+        assert_eq!(parse_signal("SIGNOPE"), None);
+        assert_eq!(parse_signal("0"), None);
+    }
+
+    #[test]
+    fn test_set_env_command() {
+        for tokens in &[
+            vec!["set", "env", "NAME=a b"],
+            vec!["set", "env", "NAME=a", "b"],
+            vec!["set", "env", "NAME", "a", "b"],
+        ] {
+            match DebuggerCommand::from_tokens(tokens) {
+                Ok(DebuggerCommand::SetEnv(name, value)) => {
+                    assert_eq!(name, "NAME");
+                    assert_eq!(value, "a b");
+                }
+                _ => panic!("{:?} should parse as a set env command", tokens),
+            }
+        }
+        match DebuggerCommand::from_tokens(&["set", "env", "EMPTY="]) {
+            Ok(DebuggerCommand::SetEnv(name, value)) => {
+                assert_eq!(name, "EMPTY");
+                assert_eq!(value, "");
+            }
+            _ => panic!("set env EMPTY= should set EMPTY to the empty string"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["set", "env", "=x"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["unset", "env"]).is_err());
+    }
+
+    #[test]
+    fn test_aliases() {
+        for spec in COMMANDS {
+            for alias in spec.aliases {
+                assert_eq!(find_command(alias).unwrap().name, spec.name);
+            }
+        }
+        for (alias, name) in &[
+            ("b", "break"),
+            ("c", "continue"),
+            ("cont", "continue"),
+            ("r", "run"),
+            ("bt", "backtrace"),
+            ("where", "backtrace"),
+            ("n", "next"),
+            ("q", "quit"),
+        ] {
+            assert_eq!(find_command(alias).unwrap().name, *name);
+        }
+        // Aliases must be unambiguous
+        let mut names: Vec<&str> = COMMANDS.iter().flat_map(|spec| spec.aliases.iter().copied().chain(Some(spec.name))).collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+
+    #[test]
+    fn test_alias_parsing() {
+        match DebuggerCommand::from_tokens(&["where"]) {
+            Ok(DebuggerCommand::Backtrace(depth)) => assert_eq!(depth, DEFAULT_BACKTRACE_DEPTH),
+            _ => panic!("where should parse as backtrace"),
+        }
+        match DebuggerCommand::from_tokens(&["b", "main"]) {
+            Ok(DebuggerCommand::Breakpoint(location, false)) => assert_eq!(location, "main"),
+            _ => panic!("b main should parse as a breakpoint"),
+        }
+        match DebuggerCommand::from_tokens(&["r", "a", "b"]) {
+            Ok(DebuggerCommand::Run(args)) => assert_eq!(args, vec!["a", "b"]),
+            _ => panic!("r a b should parse as run with two arguments"),
+        }
+        match DebuggerCommand::from_tokens(&["run", "--until-failure", "50"]) {
+            Ok(DebuggerCommand::RunUntilFailure(Some(50))) => {}
+            _ => panic!("run --until-failure 50 should run at most 50 times"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["run", "--until-failure", "0"]).is_err());
+        match DebuggerCommand::from_tokens(&["set", "rerun-arg", "--seed={}"]) {
+            Ok(DebuggerCommand::SetRerunArg(Some(arg))) => assert_eq!(arg, "--seed={}"),
+            _ => panic!("set rerun-arg should take the argument"),
+        }
+        match DebuggerCommand::from_tokens(&["set", "wait-timeout", "5"]) {
+            Ok(DebuggerCommand::SetWaitTimeout(Some(5))) => {}
+            _ => panic!("set wait-timeout 5 should wait 5 seconds"),
+        }
+        match DebuggerCommand::from_tokens(&["set", "wait-timeout", "0"]) {
+            Ok(DebuggerCommand::SetWaitTimeout(None)) => {}
+            _ => panic!("set wait-timeout 0 should turn the timeout off"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["set", "wait-timeout", "soon"]).is_err());
+        match DebuggerCommand::from_tokens(&["start", "a"]) {
+            Ok(DebuggerCommand::Start(args)) => assert_eq!(args, vec!["a"]),
+            _ => panic!("start a should parse as start with one argument"),
+        }
+    }
+
+    #[test]
+    fn test_wrong_arity() {
+        for (tokens, name) in &[
+            (vec!["break"], "break"),
+            (vec!["tb"], "tbreak"),
+            (vec!["advance"], "advance"),
+            (vec!["unset", "env"], "unset"),
+        ] {
+            match DebuggerCommand::from_tokens(tokens) {
+                Err(e) => assert!(e.starts_with(&format!("{} requires at least", name)), "{}", e),
+                Ok(_) => panic!("{:?} should be missing an argument", tokens),
+            }
+        }
+        match DebuggerCommand::from_tokens(&["info", "nonsense"]) {
+            Err(e) => assert!(e.contains("Invalid arguments to info"), "{}", e),
+            Ok(_) => panic!("info nonsense should be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_commands() {
+        match DebuggerCommand::from_tokens(&["frobnicate"]) {
+            Err(e) => assert!(e.contains("\"frobnicate\""), "{}", e),
+            Ok(_) => panic!("frobnicate isn't a command"),
+        }
+        // Only some commands take a /FMT suffix
+        assert!(DebuggerCommand::from_tokens(&["break/x", "main"]).is_err());
+    }
+
+    #[test]
+    fn test_help_text() {
+        let text = help_text(None).unwrap();
+        for spec in COMMANDS {
+            assert!(text.contains(spec.summary));
+        }
+        let text = help_text(Some("bt")).unwrap();
+        assert!(text.starts_with("Usage: backtrace"));
+        assert!(text.contains("where"));
+        assert!(help_text(Some("frobnicate")).is_err());
+    }
+
+    #[test]
+    fn test_info_commands() {
+        match DebuggerCommand::from_tokens(&["info", "functions", "sq"]) {
+            Ok(DebuggerCommand::InfoFunctions(Some(filter))) => assert_eq!(filter, "sq"),
+            _ => panic!("info functions sq should list functions matching sq"),
+        }
+        match DebuggerCommand::from_tokens(&["i", "line", "main"]) {
+            Ok(DebuggerCommand::InfoLine(location)) => assert_eq!(location, "main"),
+            _ => panic!("i line main should parse as info line"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["info", "line"]).is_err());
+    }
+
+    #[test]
+    fn test_repeat_counts() {
+        match DebuggerCommand::from_tokens(&["next", "10"]) {
+            Ok(DebuggerCommand::Next(count)) => assert_eq!(count, 10),
+            _ => panic!("next 10 should step 10 lines"),
+        }
+        match DebuggerCommand::from_tokens(&["c"]) {
+            Ok(DebuggerCommand::Continue(count)) => assert_eq!(count, 1),
+            _ => panic!("c should continue once"),
+        }
+        match DebuggerCommand::from_tokens(&["si", "5"]) {
+            Ok(DebuggerCommand::StepInstruction(count)) => assert_eq!(count, 5),
+            _ => panic!("si 5 should step 5 instructions"),
+        }
+        match DebuggerCommand::from_tokens(&["ni"]) {
+            Ok(DebuggerCommand::NextInstruction(count)) => assert_eq!(count, 1),
+            _ => panic!("ni should step 1 instruction"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["next", "0"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["continue", "-2"]).is_err());
+    }
+
+    #[test]
+    fn test_print_command() {
+        match DebuggerCommand::from_tokens(&["p/x", "*ptr"]) {
+            Ok(DebuggerCommand::Print(ValueFormat::Hex, expr)) => assert_eq!(expr, "*ptr"),
+            _ => panic!("p/x *ptr should print *ptr in hex"),
+        }
+        match DebuggerCommand::from_tokens(&["print", "total"]) {
+            Ok(DebuggerCommand::Print(ValueFormat::Natural, expr)) => assert_eq!(expr, "total"),
+            _ => panic!("print total should print total"),
+        }
+        match DebuggerCommand::from_tokens(&["print", "arr[i]", "+", "1"]) {
+            Ok(DebuggerCommand::Print(ValueFormat::Natural, expr)) => assert_eq!(expr, "arr[i] + 1"),
+            _ => panic!("print arr[i] + 1 should print the whole expression"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["print/z", "total"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["print"]).is_err());
+    }
+
+    #[test]
+    fn test_display_commands() {
+        match DebuggerCommand::from_tokens(&["display/x", "total"]) {
+            Ok(DebuggerCommand::Display(ValueFormat::Hex, expr)) => assert_eq!(expr, "total"),
+            _ => panic!("display/x total should display total in hex"),
+        }
+        match DebuggerCommand::from_tokens(&["display"]) {
+            Ok(DebuggerCommand::ShowDisplays) => {}
+            _ => panic!("display should show every display expression"),
+        }
+        match DebuggerCommand::from_tokens(&["undisplay", "2"]) {
+            Ok(DebuggerCommand::Undisplay(Some(2))) => {}
+            _ => panic!("undisplay 2 should remove display 2"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["display/x"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["undisplay", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_condition_command() {
+        match DebuggerCommand::from_tokens(&["condition", "1", "i", "==", "3"]) {
+            Ok(DebuggerCommand::Condition(1, Some(condition))) => assert_eq!(condition, "i == 3"),
+            _ => panic!("condition 1 i == 3 should set a condition"),
+        }
+        match DebuggerCommand::from_tokens(&["condition", "1"]) {
+            Ok(DebuggerCommand::Condition(1, None)) => {}
+            _ => panic!("condition 1 should remove the condition"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["condition", "i", "==", "3"]).is_err());
+    }
+
+    #[test]
+    fn test_jump_and_return() {
+        match DebuggerCommand::from_tokens(&["j", "*0x401136"]) {
+            Ok(DebuggerCommand::Jump(location)) => assert_eq!(location, "*0x401136"),
+            _ => panic!("j *0x401136 should parse as a jump"),
+        }
+        match DebuggerCommand::from_tokens(&["return", "-1"]) {
+            Ok(DebuggerCommand::Return(Some(value))) => assert_eq!(value, "-1"),
+            _ => panic!("return -1 should return -1"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["jump"]).is_err());
+        assert!(DebuggerCommand::from_tokens(&["return", "1", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_history_command() {
+        match DebuggerCommand::from_tokens(&["history"]) {
+            Ok(DebuggerCommand::History(10)) => {}
+            _ => panic!("history should show the last 10 lines"),
+        }
+        match DebuggerCommand::from_tokens(&["history", "3"]) {
+            Ok(DebuggerCommand::History(3)) => {}
+            _ => panic!("history 3 should show the last 3 lines"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["history", "all"]).is_err());
+    }
+
+    #[test]
+    fn test_thread_commands() {
+        match DebuggerCommand::from_tokens(&["thread", "2"]) {
+            Ok(DebuggerCommand::Thread(Some(2))) => {}
+            _ => panic!("thread 2 should select thread 2"),
+        }
+        match DebuggerCommand::from_tokens(&["info", "threads"]) {
+            Ok(DebuggerCommand::InfoThreads) => {}
+            _ => panic!("info threads should list threads"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["thread", "main"]).is_err());
+    }
+
+    #[test]
+    fn test_info_stats() {
+        match DebuggerCommand::from_tokens(&["info", "stats"]) {
+            Ok(DebuggerCommand::InfoStats) => {}
+            _ => panic!("info stats should show session statistics"),
+        }
+    }
+
+    #[test]
+    fn test_break_force() {
+        match DebuggerCommand::from_tokens(&["break", "*0x404040", "--force"]) {
+            Ok(DebuggerCommand::Breakpoint(location, true)) => assert_eq!(location, "*0x404040"),
+            _ => panic!("break *0x404040 --force should force the breakpoint"),
+        }
+        match DebuggerCommand::from_tokens(&["tbreak", "main", "--force"]) {
+            Ok(DebuggerCommand::TemporaryBreakpoint(location, true)) => assert_eq!(location, "main"),
+            _ => panic!("tbreak main --force should force the breakpoint"),
+        }
+        assert!(DebuggerCommand::from_tokens(&["break", "main", "--forcefully"]).is_err());
+    }
+
+    #[test]
+    fn test_breakpoint_commands() {
+        match DebuggerCommand::parse_line("commands 1 \"bt; print i;continue\"") {
+            Ok(DebuggerCommand::BreakpointCommands(1, lines)) => {
+                assert_eq!(lines, vec!["bt", "print i", "continue"]);
+            }
+            _ => panic!("commands should split its argument at semicolons"),
+        }
+        match DebuggerCommand::from_tokens(&["commands", "2"]) {
+            Ok(DebuggerCommand::BreakpointCommands(2, lines)) => assert!(lines.is_empty()),
+            _ => panic!("commands 2 should clear breakpoint 2's commands"),
         }
+        assert!(DebuggerCommand::from_tokens(&["commands", "x", "bt"]).is_err());
     }
 }
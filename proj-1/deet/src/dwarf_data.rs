@@ -1,6 +1,6 @@
 use crate::gimli_wrapper;
 use addr2line::Context;
-use object::Object;
+use object::{Object, ObjectSegment};
 use std::convert::TryInto;
 use std::{fmt, fs};
 
@@ -12,7 +12,14 @@ pub enum Error {
 
 pub struct DwarfData {
     files: Vec<File>,
-    addr2line: Context<addr2line::gimli::EndianRcSlice<addr2line::gimli::RunTimeEndian>>,
+    /// None if the debugging information couldn't be read at all
+    addr2line: Option<Context<addr2line::gimli::EndianRcSlice<addr2line::gimli::RunTimeEndian>>>,
+    /// Lowest address the executable asks to be loaded at
+    image_base: usize,
+    /// Address execution starts at, from the ELF header
+    entry: usize,
+    /// How far the addresses in `files` have been moved from the ones in the executable
+    load_bias: usize,
 }
 
 impl fmt::Debug for DwarfData {
@@ -31,8 +38,8 @@ impl DwarfData {
     pub fn from_file(path: &str) -> Result<DwarfData, Error> {
         let file = fs::File::open(path).or(Err(Error::ErrorOpeningFile))?;
         let mmap = unsafe { memmap::Mmap::map(&file).or(Err(Error::ErrorOpeningFile))? };
-        let object = object::File::parse(&*mmap)
-            .or_else(|e| Err(gimli_wrapper::Error::ObjectError(e.to_string())))?;
+        let object = object::File::parse(&mmap)
+            .map_err(|e| gimli_wrapper::Error::ObjectError(e.to_string()))?;
         let endian = if object.is_little_endian() {
             gimli::RunTimeEndian::Little
         } else {
@@ -40,58 +47,187 @@ impl DwarfData {
         };
         Ok(DwarfData {
             files: gimli_wrapper::load_file(&object, endian)?,
-            addr2line: Context::new(&object).or_else(|e| Err(gimli_wrapper::Error::from(e)))?,
+            addr2line: Some(Context::new(&object).map_err(gimli_wrapper::Error::from)?),
+            image_base: DwarfData::lowest_segment(&object),
+            entry: object.entry() as usize,
+            load_bias: 0,
         })
     }
 
-    #[allow(dead_code)]
-    fn get_target_file(&self, file: &str) -> Option<&File> {
+    /// Reads only what's needed to run an executable whose debugging information is unusable,
+    /// so every query about functions, lines and variables comes back empty.
+    pub fn without_symbols(path: &str) -> Result<DwarfData, Error> {
+        let file = fs::File::open(path).or(Err(Error::ErrorOpeningFile))?;
+        let mmap = unsafe { memmap::Mmap::map(&file).or(Err(Error::ErrorOpeningFile))? };
+        let object = object::File::parse(&mmap)
+            .map_err(|e| gimli_wrapper::Error::ObjectError(e.to_string()))?;
+        Ok(DwarfData {
+            files: Vec::new(),
+            addr2line: None,
+            image_base: DwarfData::lowest_segment(&object),
+            entry: object.entry() as usize,
+            load_bias: 0,
+        })
+    }
+
+    fn lowest_segment(object: &object::File) -> usize {
+        object.segments().map(|segment| segment.address()).min().unwrap_or(0) as usize
+    }
+
+    /// Returns whether there's any debugging information (a stripped executable has none).
+    pub fn has_symbols(&self) -> bool {
+        !self.files.is_empty()
+    }
+
+    /// Returns the lowest address the executable asks to be loaded at. A position-independent
+    /// executable is loaded somewhere else, and the difference is its load bias.
+    pub fn image_base(&self) -> usize {
+        self.image_base
+    }
+
+    /// Returns the address the program starts executing at (in a process, so after relocation).
+    pub fn entry_point(&self) -> usize {
+        self.entry.wrapping_add(self.load_bias)
+    }
+
+    pub fn load_bias(&self) -> usize {
+        self.load_bias
+    }
+
+    /// Moves every address to where it is in a process that loaded the executable `load_bias`
+    /// bytes above its image base.
+    pub fn relocate(&mut self, load_bias: usize) {
+        let delta = load_bias.wrapping_sub(self.load_bias);
+        self.load_bias = load_bias;
+        for file in &mut self.files {
+            for func in &mut file.functions {
+                // Declarations without code have no address to move
+                if func.address != 0 {
+                    func.address = func.address.wrapping_add(delta);
+                }
+            }
+            for line in &mut file.lines {
+                line.address = line.address.wrapping_add(delta);
+            }
+            for var in &mut file.global_variables {
+                if let Location::Address(addr) = var.location {
+                    var.location = Location::Address(addr.wrapping_add(delta));
+                }
+            }
+        }
+    }
+
+    /// Finds the source file with the given name. A relative name like `foo.c` or `src/foo.c`
+    /// matches any file whose path ends with it.
+    pub fn get_target_file(&self, file: &str) -> Option<&File> {
         self.files.iter().find(|f| {
-            f.name == file || (!file.contains("/") && f.name.ends_with(&format!("/{}", file)))
+            f.name == file || (!file.starts_with('/') && f.name.ends_with(&format!("/{}", file)))
         })
     }
 
     #[allow(dead_code)]
     pub fn get_addr_for_line(&self, file: Option<&str>, line_number: usize) -> Option<usize> {
+        self.get_addrs_for_line(file, line_number).first().copied()
+    }
+
+    /// Returns every address the code for a line starts at, lowest first. Like get_addr_for_line,
+    /// this uses the closest line at or after the requested one that has code. That line can have
+    /// code in several functions, or in several places in one function where an inline function
+    /// was inlined, and each of those gets an address.
+    pub fn get_addrs_for_line(&self, file: Option<&str>, line_number: usize) -> Vec<usize> {
         let target_file = match file {
-            Some(filename) => self.get_target_file(filename)?,
-            None => self.files.get(0)?,
+            Some(filename) => self.get_target_file(filename),
+            None => self.files.first(),
         };
-        Some(
-            target_file
-                .lines
-                .iter()
-                .find(|line| line.number >= line_number)?
-                .address,
-        )
+        let target_file = match target_file {
+            Some(target_file) => target_file,
+            None => return Vec::new(),
+        };
+        // Lines are ordered by address, not line number: find the closest line at or after the
+        // requested one that has code, then the first address for it in each function.
+        let closest = target_file
+            .lines
+            .iter()
+            .map(|line| line.number)
+            .filter(|number| *number >= line_number)
+            .min();
+        let mut places = Vec::new();
+        let mut addrs = Vec::new();
+        for line in target_file.lines.iter().filter(|line| Some(line.number) == closest) {
+            let function = self.get_function_containing(line.address).map(|func| func.address);
+            let place = (function, self.inline_call_sites(line.address));
+            if !places.contains(&place) {
+                places.push(place);
+                addrs.push(line.address);
+            }
+        }
+        addrs.sort_unstable();
+        addrs
     }
 
-    #[allow(dead_code)]
     pub fn get_addr_for_function(&self, file: Option<&str>, func_name: &str) -> Option<usize> {
-        match file {
-            Some(filename) => Some(
-                self.get_target_file(filename)?
-                    .functions
-                    .iter()
-                    .find(|func| func.name == func_name)?
-                    .address,
-            ),
-            None => {
-                for file in &self.files {
-                    if let Some(func) = file.functions.iter().find(|func| func.name == func_name) {
-                        return Some(func.address);
-                    }
-                }
-                None
-            }
-        }
+        self.get_addrs_for_function(file, func_name).first().copied()
+    }
+
+    /// Returns the address of every function named `func_name`, lowest first. There can be
+    /// several: a static function in more than one file, or the instantiations of a generic
+    /// function, which are named like `func_name<T>`.
+    pub fn get_addrs_for_function(&self, file: Option<&str>, func_name: &str) -> Vec<usize> {
+        let files: Vec<&File> = match file {
+            Some(filename) => self.get_target_file(filename).into_iter().collect(),
+            None => self.files.iter().collect(),
+        };
+        let mut addrs: Vec<usize> = files
+            .iter()
+            .flat_map(|file| file.functions.iter())
+            .filter(|func| {
+                func.name == func_name
+                    || func.name.strip_prefix(func_name).is_some_and(|rest| rest.starts_with('<'))
+            })
+            .map(|func| func.address)
+            .collect();
+        addrs.sort_unstable();
+        addrs.dedup();
+        addrs
+    }
+
+    /// Returns every function along with the file it's in.
+    pub fn functions(&self) -> impl Iterator<Item = (&File, &Function)> {
+        self.files
+            .iter()
+            .flat_map(|file| file.functions.iter().map(move |func| (file, func)))
+    }
+
+    /// Returns the range of addresses [start, end) generated for the line containing `addr`,
+    /// according to the line table.
+    pub fn get_line_range(&self, addr: usize) -> Option<(usize, usize)> {
+        let rows = self.files.iter().flat_map(|file| file.lines.iter());
+        let start = rows.clone().map(|line| line.address).filter(|&a| a <= addr).max()?;
+        let end = rows.map(|line| line.address).filter(|&a| a > addr).min()?;
+        Some((start, end))
+    }
+
+    pub fn get_function_containing(&self, addr: usize) -> Option<&Function> {
+        self.files
+            .iter()
+            .flat_map(|file| file.functions.iter())
+            .find(|func| func.address <= addr && addr < func.address + func.text_length)
+    }
+
+    /// Finds the global variable with the given name, in any source file.
+    pub fn get_global_variable(&self, name: &str) -> Option<&Variable> {
+        self.files
+            .iter()
+            .flat_map(|file| file.global_variables.iter())
+            .find(|var| var.name == name)
     }
 
     #[allow(dead_code)]
     pub fn get_line_from_addr(&self, curr_addr: usize) -> Option<Line> {
         let location = self
             .addr2line
-            .find_location(curr_addr.try_into().unwrap())
+            .as_ref()?
+            .find_location(curr_addr.wrapping_sub(self.load_bias).try_into().unwrap())
             .ok()??;
         Some(Line {
             file: location.file?.to_string(),
@@ -104,13 +240,36 @@ impl DwarfData {
     pub fn get_function_from_addr(&self, curr_addr: usize) -> Option<String> {
         let frame = self
             .addr2line
-            .find_frames(curr_addr.try_into().unwrap())
+            .as_ref()?
+            .find_frames(curr_addr.wrapping_sub(self.load_bias).try_into().unwrap())
             .ok()?
             .next()
             .ok()??;
         Some(frame.function?.raw_name().ok()?.to_string())
     }
 
+    /// Returns the call sites the code at `addr` was inlined at, innermost first, as file and line
+    /// number. Empty if it wasn't inlined.
+    fn inline_call_sites(&self, addr: usize) -> Vec<(String, u32)> {
+        let mut sites = Vec::new();
+        let mut frames = match self.addr2line.as_ref() {
+            Some(context) => match context.find_frames(addr.wrapping_sub(self.load_bias).try_into().unwrap()) {
+                Ok(frames) => frames,
+                Err(_) => return sites,
+            },
+            None => return sites,
+        };
+        // The innermost frame is where `addr` itself is; each one after that is a caller, at the
+        // line it called (or rather, inlined) the one before
+        let _ = frames.next();
+        while let Ok(Some(frame)) = frames.next() {
+            if let Some(location) = frame.location {
+                sites.push((location.file.unwrap_or("").to_string(), location.line.unwrap_or(0)));
+            }
+        }
+        sites
+    }
+
     #[allow(dead_code)]
     pub fn print(&self) {
         for file in &self.files {
@@ -148,25 +307,257 @@ impl DwarfData {
     }
 }
 
+/// Most array elements `format_bytes` shows; the rest are elided with "...".
+pub const MAX_ARRAY_ELEMENTS: usize = 16;
+
 #[derive(Debug, Clone, Default)]
 pub struct Type {
     pub name: String,
     pub size: usize,
+    pub kind: TypeKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum TypeKind {
+    /// Integers, characters, booleans and floating point numbers
+    #[default]
+    Base,
+    /// Pointer to a value of the given type, or None for `void *` and types deet doesn't support
+    Pointer(Option<Box<Type>>),
+    /// Array with the given element type and number of elements
+    Array(Box<Type>, usize),
+    /// Rust `&str` or `String`: a pointer to UTF-8 bytes and their length, at these offsets
+    Str { ptr_offset: usize, len_offset: usize },
+    /// Struct with the given members, in order. Members of types deet doesn't support are left
+    /// out, and a struct nested deeply within itself (through pointers) has no members.
+    Struct(Vec<Member>),
+}
+
+/// A member of a struct
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    /// Offset within the struct in bytes
+    pub offset: usize,
+    pub member_type: Type,
+}
+
+/// How `print` shows a value: as its type suggests, or reinterpreted per its /FMT letter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueFormat {
+    Natural,
+    Hex,
+    Binary,
+    Char,
+}
+
+impl ValueFormat {
+    /// Returns the /FMT suffix that selects this format, or "" for the natural one.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            ValueFormat::Natural => "",
+            ValueFormat::Hex => "/x",
+            ValueFormat::Binary => "/b",
+            ValueFormat::Char => "/c",
+        }
+    }
 }
 
 impl Type {
     pub fn new(name: String, size: usize) -> Self {
         Type {
-            name: name,
-            size: size,
+            name,
+            size,
+            kind: TypeKind::Base,
+        }
+    }
+
+    pub fn pointer(target: Option<Type>, size: usize) -> Self {
+        let name = match &target {
+            Some(target) => format!("{} *", target.name),
+            None => "void *".to_string(),
+        };
+        Type {
+            name,
+            size,
+            kind: TypeKind::Pointer(target.map(Box::new)),
+        }
+    }
+
+    pub fn array(element: Type, count: usize) -> Self {
+        Type {
+            name: format!("{} [{}]", element.name, count),
+            size: element.size * count,
+            kind: TypeKind::Array(Box::new(element), count),
+        }
+    }
+
+    pub fn string(name: String, size: usize, ptr_offset: usize, len_offset: usize) -> Self {
+        Type {
+            name,
+            size,
+            kind: TypeKind::Str { ptr_offset, len_offset },
+        }
+    }
+
+    pub fn structure(name: String, size: usize, members: Vec<Member>) -> Self {
+        Type {
+            name,
+            size,
+            kind: TypeKind::Struct(members),
+        }
+    }
+
+    /// Returns whether values of this type are unsigned integers (pointers count as such).
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self.kind, TypeKind::Pointer(_)) || self.name.contains("unsigned") || self.name.starts_with('u')
+    }
+
+    /// Formats the raw bits of a value of this type (e.g. the contents of a register). Bits beyond
+    /// the size of the type are ignored, and signed types are sign-extended.
+    pub fn format_value(&self, raw: u64) -> String {
+        if let TypeKind::Pointer(_) = self.kind {
+            return format!("{:#x}", raw);
+        }
+        let bits = self.size * 8;
+        let value = if bits == 0 || bits >= 64 {
+            raw
+        } else {
+            raw & ((1 << bits) - 1)
+        };
+        let signed = if bits == 0 || bits >= 64 {
+            value as i64
+        } else {
+            ((value << (64 - bits)) as i64) >> (64 - bits)
+        };
+        let unsigned = self.is_unsigned();
+        match self.name.as_str() {
+            "_Bool" | "bool" => format!("{}", value != 0),
+            "float" | "f32" => format!("{}", f32::from_bits(value as u32)),
+            "double" | "f64" => format!("{}", f64::from_bits(value)),
+            "char" if self.size == 4 => match std::char::from_u32(value as u32) {
+                Some(c) => format!("{:?}", c),
+                None => format!("{:#x}", value),
+            },
+            name if name.contains("char") => {
+                let c = value as u8 as char;
+                let number = if unsigned { value as i64 } else { signed };
+                if c.is_ascii_graphic() || c == ' ' {
+                    format!("{} '{}'", number, c)
+                } else {
+                    format!("{} {:?}", number, c)
+                }
+            }
+            _ if unsigned => format!("{}", value),
+            _ => format!("{}", signed),
+        }
+    }
+
+    /// Parses `text` as a value of this type and returns its raw bits, the inverse of
+    /// format_value. Integers may be decimal or 0x-prefixed hex, and a single non-digit character
+    /// is taken as a character literal for char types. Values that don't fit are rejected.
+    pub fn parse_value(&self, text: &str) -> Result<u64, String> {
+        if self.size == 0 || self.size > 8 {
+            return Err(format!("Setting values of type {} isn't supported", self.name));
+        }
+        if let TypeKind::Array(..) = self.kind {
+            return Err(format!("Setting arrays ({}) isn't supported", self.name));
+        }
+        if let TypeKind::Struct(_) = self.kind {
+            return Err(format!("Setting structs ({}) isn't supported", self.name));
+        }
+        let bits = self.size * 8;
+        let is_pointer = matches!(self.kind, TypeKind::Pointer(_));
+        let unsigned = self.is_unsigned();
+        let invalid = || format!("Invalid {} value \"{}\"", self.name, text);
+        match self.name.as_str() {
+            "_Bool" | "bool" => match text {
+                "true" | "1" => Ok(1),
+                "false" | "0" => Ok(0),
+                _ => Err(invalid()),
+            },
+            "float" | "f32" => text.parse::<f32>().map(|f| f.to_bits() as u64).map_err(|_| invalid()),
+            "double" | "f64" => text.parse::<f64>().map(|f| f.to_bits()).map_err(|_| invalid()),
+            name if !is_pointer && name.contains("char") && text.chars().count() == 1
+                && !text.starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                let c = text.chars().next().unwrap() as u64;
+                if bits < 64 && c >> bits != 0 {
+                    return Err(format!("'{}' doesn't fit in {}", text, self.name));
+                }
+                Ok(c)
+            }
+            _ => {
+                let (negative, digits) = match text.strip_prefix('-') {
+                    Some(digits) => (true, digits),
+                    None => (false, text),
+                };
+                let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+                    Some(hex) => i128::from_str_radix(hex, 16),
+                    None => digits.parse::<i128>(),
+                }
+                .map_err(|_| invalid())?;
+                let value = if negative { -magnitude } else { magnitude };
+                let (min, max) = if unsigned {
+                    (0, (1i128 << bits) - 1)
+                } else {
+                    (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+                };
+                if value < min || value > max {
+                    return Err(format!("{} is out of range for {}", text, self.name));
+                }
+                // Two's complement, truncated to the size of the type
+                Ok((value as u64) & (u64::MAX >> (64 - bits)))
+            }
         }
     }
 }
 
+/// Formats a value of type `value_type` from its bytes in memory (little-endian) per `format`.
+/// Only the first MAX_ARRAY_ELEMENTS elements of an array need to be present in `bytes`.
+pub fn format_bytes(bytes: &[u8], value_type: &Type, format: ValueFormat) -> String {
+    if let TypeKind::Array(element, count) = &value_type.kind {
+        let mut elements: Vec<String> = bytes
+            .chunks_exact(element.size.max(1))
+            .take((*count).min(MAX_ARRAY_ELEMENTS))
+            .map(|chunk| format_bytes(chunk, element, format))
+            .collect();
+        if *count > elements.len() {
+            elements.push("...".to_string());
+        }
+        return format!("[{}]", elements.join(", "));
+    }
+
+    let size = value_type.size.min(bytes.len()).min(8);
+    let raw = bytes[..size]
+        .iter()
+        .enumerate()
+        .fold(0u64, |raw, (i, &byte)| raw | (byte as u64) << (8 * i));
+    match format {
+        ValueFormat::Natural => value_type.format_value(raw),
+        ValueFormat::Hex => format!("{:#x}", raw),
+        ValueFormat::Binary => format!("{:b}", raw),
+        ValueFormat::Char => Type::new("char".to_string(), 1).format_value(raw),
+    }
+}
+
+/// Formats the contents of a string of `len` bytes, of which `bytes` are the first (or all),
+/// gdb-style: `"hello" (len 5)`. Invalid UTF-8 is replaced rather than rejected.
+pub fn format_string(bytes: &[u8], len: usize) -> String {
+    format!(
+        "{:?}{} (len {})",
+        String::from_utf8_lossy(bytes),
+        if bytes.len() < len { "..." } else { "" },
+        len
+    )
+}
+
 #[derive(Clone)]
 pub enum Location {
     Address(usize),
     FramePointerOffset(isize),
+    /// Optimized out, or described by a location expression we don't support
+    Unavailable,
 }
 
 impl fmt::Display for Location {
@@ -174,6 +565,7 @@ impl fmt::Display for Location {
         match *self {
             Location::Address(addr) => write!(f, "Address({:#x})", addr),
             Location::FramePointerOffset(offset) => write!(f, "FramePointerOffset({})", offset),
+            Location::Unavailable => write!(f, "Unavailable"),
         }
     }
 }
@@ -191,6 +583,7 @@ pub struct Variable {
     pub entity_type: Type,
     pub location: Location,
     pub line_number: usize, // Line number in source file
+    pub is_parameter: bool, // Formal parameter rather than a local variable
 }
 
 #[derive(Debug, Default, Clone)]
@@ -200,6 +593,7 @@ pub struct Function {
     pub text_length: usize,
     pub line_number: usize, // Line number in source file
     pub variables: Vec<Variable>,
+    pub return_type: Option<Type>, // None for functions returning void
 }
 
 #[derive(Debug, Default, Clone)]
@@ -223,4 +617,145 @@ impl fmt::Display for Line {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_integer_value() {
+        let int = Type::new("int".to_string(), 4);
+        assert_eq!(int.parse_value("42"), Ok(42));
+        assert_eq!(int.parse_value("0x10"), Ok(16));
+        assert_eq!(int.parse_value("-1"), Ok(0xffff_ffff));
+        assert!(int.parse_value("2147483648").is_err());
+        assert!(int.parse_value("forty").is_err());
+        let byte = Type::new("unsigned char".to_string(), 1);
+        assert_eq!(byte.parse_value("255"), Ok(255));
+        assert!(byte.parse_value("300").is_err());
+        assert!(byte.parse_value("-1").is_err());
+    }
+
+    #[test]
+    fn test_parse_other_values() {
+        assert_eq!(Type::new("_Bool".to_string(), 1).parse_value("true"), Ok(1));
+        assert_eq!(Type::new("char".to_string(), 1).parse_value("A"), Ok(65));
+        assert_eq!(Type::new("char".to_string(), 1).parse_value("7"), Ok(7));
+        assert_eq!(Type::new("double".to_string(), 8).parse_value("1.5"), Ok(1.5f64.to_bits()));
+    }
+
+    #[test]
+    fn test_format_natural() {
+        assert_eq!(format_bytes(&[42, 0, 0, 0], &Type::new("int".to_string(), 4), ValueFormat::Natural), "42");
+        let pointer = Type::pointer(Some(Type::new("int".to_string(), 4)), 8);
+        assert_eq!(pointer.name, "int *");
+        assert_eq!(format_bytes(&[0x10, 0x40, 0, 0, 0, 0, 0, 0], &pointer, ValueFormat::Natural), "0x4010");
+    }
+
+    #[test]
+    fn test_format_negative_at_each_width() {
+        for (name, size) in &[("signed char", 1), ("short", 2), ("int", 4), ("long", 8)] {
+            let value_type = Type::new(name.to_string(), *size);
+            let minus_two = &(-2i64).to_le_bytes()[..*size];
+            let natural = format_bytes(minus_two, &value_type, ValueFormat::Natural);
+            // Chars are followed by the character itself
+            assert_eq!(natural.split(' ').next(), Some("-2"), "{} -2", name);
+            let ones = "1".repeat(size * 8 - 1);
+            assert_eq!(format_bytes(minus_two, &value_type, ValueFormat::Binary), format!("{}0", ones));
+            let hex = format!("{:#x}", u64::MAX >> (64 - size * 8) & !1);
+            assert_eq!(format_bytes(minus_two, &value_type, ValueFormat::Hex), hex);
+        }
+    }
+
+    #[test]
+    fn test_format_letters() {
+        let int = Type::new("int".to_string(), 4);
+        assert_eq!(format_bytes(&[255, 0, 0, 0], &int, ValueFormat::Hex), "0xff");
+        assert_eq!(format_bytes(&[5, 0, 0, 0], &int, ValueFormat::Binary), "101");
+        assert_eq!(format_bytes(&[65, 0, 0, 0], &int, ValueFormat::Char), "65 'A'");
+        assert_eq!(format_bytes(&[10, 0, 0, 0], &int, ValueFormat::Char), "10 '\\n'");
+    }
+
+    #[test]
+    fn test_format_arrays() {
+        let short = Type::new("short".to_string(), 2);
+        let array = Type::array(short.clone(), 3);
+        assert_eq!(array.name, "short [3]");
+        assert_eq!(array.size, 6);
+        assert_eq!(format_bytes(&[1, 0, 0xff, 0xff, 3, 0], &array, ValueFormat::Natural), "[1, -1, 3]");
+        assert_eq!(format_bytes(&[1, 0, 0xff, 0xff, 3, 0], &array, ValueFormat::Hex), "[0x1, 0xffff, 0x3]");
+
+        let long_array = Type::array(Type::new("char".to_string(), 1), 20);
+        let formatted = format_bytes(&[b'a'; MAX_ARRAY_ELEMENTS], &long_array, ValueFormat::Natural);
+        assert_eq!(formatted.matches("97 'a'").count(), MAX_ARRAY_ELEMENTS);
+        assert!(formatted.ends_with(", ...]"));
+    }
+
+    #[test]
+    fn test_parse_value_round_trip() {
+        let short = Type::new("short".to_string(), 2);
+        assert_eq!(short.format_value(short.parse_value("-300").unwrap()), "-300");
+    }
+
+    #[test]
+    fn test_format_string() {
+        assert_eq!(format_string(b"hello", 5), "\"hello\" (len 5)");
+        assert_eq!(format_string(b"", 0), "\"\" (len 0)");
+        assert_eq!(format_string(b"say \"hi\"\n", 9), "\"say \\\"hi\\\"\\n\" (len 9)");
+        assert_eq!(format_string(b"abc", 10), "\"abc\"... (len 10)");
+        assert_eq!(format_string(&[b'a', 0xff], 2), "\"a\u{fffd}\" (len 2)");
+        let str_type = Type::string("&str".to_string(), 16, 0, 8);
+        assert!(str_type.parse_value("\"x\"").is_err());
+    }
 
+    /// A file with a generic function `max` instantiated twice and an inline helper (line 3)
+    /// whose code is in both of them
+    fn multi_location_data() -> DwarfData {
+        let function = |name: &str, address| Function {
+            name: name.to_string(),
+            address,
+            text_length: 0x20,
+            ..Function::default()
+        };
+        let line = |number, address| Line { file: "lib.rs".to_string(), number, address };
+        DwarfData {
+            files: vec![File {
+                name: "/src/lib.rs".to_string(),
+                functions: vec![function("max<i32>", 0x1000), function("max<u8>", 0x1040), function("main", 0x1080)],
+                lines: vec![
+                    line(2, 0x1000),
+                    line(3, 0x1008),
+                    line(3, 0x1010),
+                    line(2, 0x1040),
+                    line(3, 0x1048),
+                    line(7, 0x1080),
+                ],
+                ..File::default()
+            }],
+            addr2line: None,
+            image_base: 0,
+            entry: 0,
+            load_bias: 0,
+        }
+    }
+
+    #[test]
+    fn test_addrs_for_function() {
+        let data = multi_location_data();
+        assert_eq!(data.get_addrs_for_function(None, "max"), vec![0x1000, 0x1040]);
+        assert_eq!(data.get_addrs_for_function(Some("lib.rs"), "max<u8>"), vec![0x1040]);
+        assert_eq!(data.get_addrs_for_function(None, "main"), vec![0x1080]);
+        assert!(data.get_addrs_for_function(None, "ma").is_empty());
+        assert_eq!(data.get_addr_for_function(None, "max"), Some(0x1000));
+    }
+
+    #[test]
+    fn test_addrs_for_line() {
+        let data = multi_location_data();
+        // One address per function the line has code in
+        assert_eq!(data.get_addrs_for_line(None, 3), vec![0x1008, 0x1048]);
+        // The closest line with code after 4 is 7
+        assert_eq!(data.get_addrs_for_line(Some("lib.rs"), 4), vec![0x1080]);
+        assert!(data.get_addrs_for_line(None, 8).is_empty());
+        assert_eq!(data.get_addr_for_line(None, 2), Some(0x1000));
+    }
+}
@@ -0,0 +1,42 @@
+use nix::errno::Errno;
+use std::fmt;
+
+/// Why an operation on the inferior failed.
+#[derive(Debug)]
+pub enum DeetError {
+    /// A ptrace, waitpid or kill call failed
+    Ptrace(nix::Error),
+    /// There's no process being debugged
+    NoInferior,
+    /// The target has no debugging symbols to answer the question with
+    NoDebugInfo,
+    /// The memory at this address can't be read or written
+    BadAddress(usize),
+    /// The inferior stopped in a way deet didn't ask to be told about
+    UnexpectedStop,
+}
+
+impl DeetError {
+    /// Returns true if the inferior no longer exists, e.g. because it was killed from outside.
+    pub fn process_gone(&self) -> bool {
+        matches!(self, DeetError::Ptrace(nix::Error::Sys(Errno::ESRCH)))
+    }
+}
+
+impl From<nix::Error> for DeetError {
+    fn from(err: nix::Error) -> DeetError {
+        DeetError::Ptrace(err)
+    }
+}
+
+impl fmt::Display for DeetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeetError::Ptrace(err) => write!(f, "{}", err),
+            DeetError::NoInferior => write!(f, "No running subprocess"),
+            DeetError::NoDebugInfo => write!(f, "No symbol table is loaded.  Use the \"file\" command."),
+            DeetError::BadAddress(addr) => write!(f, "Cannot access memory at address {:#x}", addr),
+            DeetError::UnexpectedStop => write!(f, "The inferior stopped unexpectedly"),
+        }
+    }
+}
@@ -0,0 +1,591 @@
+//! Evaluates the expressions `print`, `display` and breakpoint conditions take: integer literals,
+//! variables, the arithmetic, comparison and logical operators, unary `-`, `!`, `*` and `&`,
+//! indexing of arrays, pointers and Rust slices, and struct members with `.` and `->`.
+
+use crate::dwarf_data::{Type, TypeKind};
+use crate::error::DeetError;
+
+/// Memory values are read from. Inferior reads it with ptrace; tests use a fake.
+pub trait MemoryReader {
+    /// Reads `len` bytes at `addr`, or returns None if any of them can't be read.
+    fn read_memory(&self, addr: usize, len: usize) -> Option<Vec<u8>>;
+}
+
+/// Where a value is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Place {
+    /// In memory at this address, so it can be read in full and have its address taken
+    Memory(usize),
+    /// Computed, with these raw bits
+    Immediate(u64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Value {
+    pub value_type: Type,
+    pub place: Place,
+}
+
+/// Finds a variable by name, returning its address and type.
+pub type Lookup<'a> = dyn Fn(&str) -> Result<(usize, Type), String> + 'a;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(u64),
+    Name(String),
+    Operator(&'static str),
+}
+
+/// Operators, longest first so that `<=` isn't read as `<` then `=`
+const OPERATORS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "->", "+", "-", "*", "/", "%", "<", ">", "!", "&", "(", ")", "[", "]",
+    ".",
+];
+
+/// Binary operators from the loosest binding to the tightest
+const PRECEDENCE: &[&[&str]] = &[&["||"], &["&&"], &["==", "!="], &["<", "<=", ">", ">="], &["+", "-"], &["*", "/", "%"]];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(u64),
+    Variable(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Member(Box<Expr>, String),
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = expr.trim_start();
+    while !rest.is_empty() {
+        let word_end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+        if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            let word = &rest[..word_end];
+            let number = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => word.parse(),
+            };
+            tokens.push(Token::Number(number.map_err(|_| format!("Invalid number \"{}\".", word))?));
+            rest = &rest[word_end..];
+        } else if word_end > 0 {
+            tokens.push(Token::Name(rest[..word_end].to_string()));
+            rest = &rest[word_end..];
+        } else {
+            match OPERATORS.iter().find(|&&operator| rest.starts_with(operator)) {
+                Some(&operator) => {
+                    tokens.push(Token::Operator(operator));
+                    rest = &rest[operator.len()..];
+                }
+                None => return Err(format!("Invalid character '{}' in expression.", rest.chars().next().unwrap())),
+            }
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek_operator(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) => Some(*operator),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), String> {
+        if self.peek_operator() == Some(operator) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' in expression.", operator))
+        }
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == PRECEDENCE.len() {
+            return self.parse_unary();
+        }
+        let mut left = self.parse_binary(level + 1)?;
+        while let Some(operator) = self.peek_operator().filter(|operator| PRECEDENCE[level].contains(operator)) {
+            self.position += 1;
+            let right = self.parse_binary(level + 1)?;
+            left = Expr::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        match self.peek_operator() {
+            Some(operator @ ("-" | "!" | "*" | "&")) => {
+                self.position += 1;
+                Ok(Expr::Unary(operator, Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_postfix(),
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            match self.peek_operator() {
+                Some("[") => {
+                    self.position += 1;
+                    let index = self.parse_binary(0)?;
+                    self.expect("]")?;
+                    expr = Expr::Index(Box::new(expr), Box::new(index));
+                }
+                Some(operator @ ("." | "->")) => {
+                    self.position += 1;
+                    let name = match self.tokens.get(self.position) {
+                        Some(Token::Name(name)) => name.clone(),
+                        _ => return Err(format!("Expected a member name after '{}'.", operator)),
+                    };
+                    self.position += 1;
+                    if operator == "->" {
+                        expr = Expr::Unary("*", Box::new(expr));
+                    }
+                    expr = Expr::Member(Box::new(expr), name);
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::Name(name)) => Ok(Expr::Variable(name)),
+            Some(Token::Operator("(")) => {
+                let expr = self.parse_binary(0)?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(Token::Operator(operator)) => Err(format!("Unexpected '{}' in expression.", operator)),
+            None => Err("Incomplete expression.".to_string()),
+        }
+    }
+}
+
+fn parse(expr: &str) -> Result<Expr, String> {
+    let mut parser = Parser { tokens: tokenize(expr)?, position: 0 };
+    let parsed = parser.parse_binary(0)?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(parsed),
+        Some(_) => Err("Junk at end of expression.".to_string()),
+    }
+}
+
+/// Checks that `expr` is a well-formed expression, without evaluating it.
+pub fn check(expr: &str) -> Result<(), String> {
+    parse(expr).map(|_| ())
+}
+
+/// Evaluates `expr`, finding variables with `lookup` and reading their values from `memory`.
+pub fn evaluate(expr: &str, lookup: &Lookup, memory: &dyn MemoryReader) -> Result<Value, String> {
+    Evaluator { lookup, memory }.evaluate(&parse(expr)?)
+}
+
+/// Evaluates `expr` as a condition, which holds if its value is nonzero.
+pub fn evaluate_condition(expr: &str, lookup: &Lookup, memory: &dyn MemoryReader) -> Result<bool, String> {
+    let evaluator = Evaluator { lookup, memory };
+    let value = evaluator.evaluate(&parse(expr)?)?;
+    Ok(evaluator.integer(&value)? != 0)
+}
+
+struct Evaluator<'a> {
+    lookup: &'a Lookup<'a>,
+    memory: &'a dyn MemoryReader,
+}
+
+fn int_type() -> Type {
+    Type::new("int".to_string(), 4)
+}
+
+fn long_type() -> Type {
+    Type::new("long".to_string(), 8)
+}
+
+fn is_float(value_type: &Type) -> bool {
+    matches!(value_type.name.as_str(), "float" | "double" | "f32" | "f64")
+}
+
+/// Returns the raw bits of `value`, truncated to `size` bytes.
+fn truncate(value: i128, size: usize) -> u64 {
+    (value as u64) & (u64::MAX >> (64 - 8 * size.clamp(1, 8)))
+}
+
+impl Evaluator<'_> {
+    fn evaluate(&self, expr: &Expr) -> Result<Value, String> {
+        match expr {
+            Expr::Number(number) => {
+                let value_type = if *number <= i32::MAX as u64 { int_type() } else { long_type() };
+                Ok(Value { value_type, place: Place::Immediate(*number) })
+            }
+            Expr::Variable(name) => {
+                let (addr, value_type) = (self.lookup)(name)?;
+                Ok(Value { value_type, place: Place::Memory(addr) })
+            }
+            Expr::Unary(operator, operand) => self.unary(operator, self.evaluate(operand)?),
+            Expr::Binary(operator, left, right) => self.binary(operator, left, right),
+            Expr::Index(base, index) => {
+                let base = self.evaluate(base)?;
+                let index = self.evaluate(index)?;
+                let index = self.integer(&index)?;
+                self.index(base, index)
+            }
+            Expr::Member(base, name) => self.member(self.evaluate(base)?, name),
+        }
+    }
+
+    /// Reads `len` bytes at `addr`.
+    fn read(&self, addr: usize, len: usize) -> Result<Vec<u8>, String> {
+        self.memory.read_memory(addr, len).ok_or_else(|| DeetError::BadAddress(addr).to_string())
+    }
+
+    /// Returns the raw bits of a scalar value (up to 8 bytes).
+    fn raw(&self, value: &Value) -> Result<u64, String> {
+        let size = value.value_type.size;
+        let scalar = matches!(value.value_type.kind, TypeKind::Base | TypeKind::Pointer(_));
+        if !scalar || size == 0 || size > 8 {
+            return Err(format!("Values of type {} can't be used here.", value.value_type.name));
+        }
+        match value.place {
+            Place::Immediate(raw) => Ok(raw),
+            Place::Memory(addr) => {
+                let bytes = self.read(addr, size)?;
+                Ok(bytes.iter().enumerate().fold(0u64, |raw, (i, &byte)| raw | (byte as u64) << (8 * i)))
+            }
+        }
+    }
+
+    /// Returns the value of an integer or pointer, sign-extended if its type is signed.
+    fn integer(&self, value: &Value) -> Result<i128, String> {
+        if is_float(&value.value_type) {
+            return Err(format!("Arithmetic on type {} isn't supported.", value.value_type.name));
+        }
+        let raw = self.raw(value)?;
+        let bits = 8 * value.value_type.size;
+        if value.value_type.is_unsigned() {
+            Ok(raw as i128)
+        } else {
+            Ok((((raw << (64 - bits)) as i64) >> (64 - bits)) as i128)
+        }
+    }
+
+    /// Returns the type a pointer points to, and its address.
+    fn dereference(&self, pointer: &Value) -> Result<(Type, usize), String> {
+        let target = match &pointer.value_type.kind {
+            TypeKind::Pointer(Some(target)) => (**target).clone(),
+            TypeKind::Pointer(None) => {
+                return Err("Attempt to take contents of a pointer to an unsupported type.".to_string());
+            }
+            _ => return Err("Attempt to take contents of a non-pointer value.".to_string()),
+        };
+        Ok((target, self.raw(pointer)? as usize))
+    }
+
+    fn unary(&self, operator: &str, operand: Value) -> Result<Value, String> {
+        match operator {
+            "*" => {
+                let (value_type, addr) = self.dereference(&operand)?;
+                Ok(Value { value_type, place: Place::Memory(addr) })
+            }
+            "&" => match operand.place {
+                Place::Memory(addr) => Ok(Value {
+                    value_type: Type::pointer(Some(operand.value_type), 8),
+                    place: Place::Immediate(addr as u64),
+                }),
+                Place::Immediate(_) => Err("Attempt to take address of value not located in memory.".to_string()),
+            },
+            "!" => {
+                let value = self.integer(&operand)?;
+                Ok(Value { value_type: int_type(), place: Place::Immediate((value == 0) as u64) })
+            }
+            _ => {
+                let value = self.integer(&operand)?;
+                let value_type = if operand.value_type.size < 4 { int_type() } else { operand.value_type };
+                Ok(Value { place: Place::Immediate(truncate(-value, value_type.size)), value_type })
+            }
+        }
+    }
+
+    fn binary(&self, operator: &str, left: &Expr, right: &Expr) -> Result<Value, String> {
+        let left = self.evaluate(left)?;
+        let truth = |value: bool| Value { value_type: int_type(), place: Place::Immediate(value as u64) };
+        // Only evaluate the right side if it decides the result
+        match operator {
+            "&&" if self.integer(&left)? == 0 => return Ok(truth(false)),
+            "||" if self.integer(&left)? != 0 => return Ok(truth(true)),
+            "&&" | "||" => return Ok(truth(self.integer(&self.evaluate(right)?)? != 0)),
+            _ => {}
+        }
+        let right = self.evaluate(right)?;
+        let (a, b) = (self.integer(&left)?, self.integer(&right)?);
+        let pointee_size = |value: &Value| match &value.value_type.kind {
+            TypeKind::Pointer(Some(target)) => Some(target.size.max(1) as i128),
+            TypeKind::Pointer(None) => Some(1),
+            _ => None,
+        };
+        let (left_pointee, right_pointee) = (pointee_size(&left), pointee_size(&right));
+        let result = match operator {
+            "==" => return Ok(truth(a == b)),
+            "!=" => return Ok(truth(a != b)),
+            "<" => return Ok(truth(a < b)),
+            "<=" => return Ok(truth(a <= b)),
+            ">" => return Ok(truth(a > b)),
+            ">=" => return Ok(truth(a >= b)),
+            // Pointer arithmetic counts in elements, like C's
+            "+" | "-" if left_pointee.is_some() && right_pointee.is_none() => {
+                let offset = b.wrapping_mul(left_pointee.unwrap());
+                let addr = if operator == "+" { a.wrapping_add(offset) } else { a.wrapping_sub(offset) };
+                return Ok(Value { place: Place::Immediate(truncate(addr, 8)), value_type: left.value_type });
+            }
+            "+" if right_pointee.is_some() && left_pointee.is_none() => {
+                let addr = b.wrapping_add(a.wrapping_mul(right_pointee.unwrap()));
+                return Ok(Value { place: Place::Immediate(truncate(addr, 8)), value_type: right.value_type });
+            }
+            "-" if left_pointee.is_some() => {
+                let difference = (a - b) / left_pointee.unwrap();
+                return Ok(Value { value_type: long_type(), place: Place::Immediate(truncate(difference, 8)) });
+            }
+            _ if left_pointee.is_some() || right_pointee.is_some() => {
+                return Err(format!("Operator {} can't be used with pointers.", operator));
+            }
+            // Wrapping around like C's (the result is truncated to the operands' width anyway)
+            "+" => a.wrapping_add(b),
+            "-" => a.wrapping_sub(b),
+            "*" => a.wrapping_mul(b),
+            "/" | "%" if b == 0 => return Err("Division by zero".to_string()),
+            "/" => a / b,
+            _ => a % b,
+        };
+        // The wider of the operands' types (but at least an int), unsigned if either is
+        let value_type = [&left.value_type, &right.value_type]
+            .iter()
+            .map(|&value_type| value_type.clone())
+            .chain(std::iter::once(int_type()))
+            .max_by_key(|value_type| (value_type.size, value_type.is_unsigned()))
+            .unwrap();
+        Ok(Value { place: Place::Immediate(truncate(result, value_type.size)), value_type })
+    }
+
+    /// Returns element `index` of an array, pointer or Rust slice.
+    fn index(&self, base: Value, index: i128) -> Result<Value, String> {
+        let (element, addr, count) = match (&base.value_type.kind, base.place) {
+            (TypeKind::Array(element, count), Place::Memory(addr)) => ((**element).clone(), addr, Some(*count)),
+            (TypeKind::Pointer(_), _) => {
+                let (element, addr) = self.dereference(&base)?;
+                (element, addr, None)
+            }
+            (TypeKind::Struct(_), Place::Memory(_)) if self.slice_parts(&base).is_some() => {
+                let (data_ptr, length) = self.slice_parts(&base).unwrap();
+                let (element, addr) = self.dereference(&data_ptr)?;
+                (element, addr, Some(self.integer(&length)? as usize))
+            }
+            _ => return Err(format!("Cannot subscript a value of type {}.", base.value_type.name)),
+        };
+        if let Some(count) = count {
+            if !(0..count as i128).contains(&index) {
+                return Err(format!("Index {} is out of bounds for {} (length {}).", index, base.value_type.name, count));
+            }
+        }
+        let addr = (addr as i128).wrapping_add(index.wrapping_mul(element.size as i128)) as usize;
+        Ok(Value { value_type: element, place: Place::Memory(addr) })
+    }
+
+    /// Returns the data pointer and length of a Rust slice, or None if `value` isn't one.
+    fn slice_parts(&self, value: &Value) -> Option<(Value, Value)> {
+        let part = |name: &str| self.member(value.clone(), name).ok();
+        let data_ptr = part("data_ptr")?;
+        let length = part("length")?;
+        match data_ptr.value_type.kind {
+            TypeKind::Pointer(_) => Some((data_ptr, length)),
+            _ => None,
+        }
+    }
+
+    /// Returns member `name` of a struct. Rust strings and slices have a `len` member too.
+    fn member(&self, base: Value, name: &str) -> Result<Value, String> {
+        let addr = match base.place {
+            Place::Memory(addr) => addr,
+            Place::Immediate(_) => {
+                return Err("Attempt to extract a component of a value that is not a structure.".to_string())
+            }
+        };
+        match &base.value_type.kind {
+            TypeKind::Str { len_offset, .. } if name == "len" => Ok(Value {
+                value_type: Type::new("usize".to_string(), 8),
+                place: Place::Memory(addr + len_offset),
+            }),
+            TypeKind::Struct(members) => match members.iter().find(|member| member.name == name) {
+                Some(member) => Ok(Value {
+                    value_type: member.member_type.clone(),
+                    place: Place::Memory(addr + member.offset),
+                }),
+                None if name == "len" && members.iter().any(|member| member.name == "length") => {
+                    self.member(base.clone(), "length")
+                }
+                None => Err(format!("There is no member named {}.", name)),
+            },
+            _ => Err("Attempt to extract a component of a value that is not a structure.".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dwarf_data::Member;
+    use std::collections::HashMap;
+
+    /// Memory made of the given bytes at the given addresses
+    struct FakeMemory(HashMap<usize, u8>);
+
+    impl MemoryReader for FakeMemory {
+        fn read_memory(&self, addr: usize, len: usize) -> Option<Vec<u8>> {
+            (addr..addr + len).map(|addr| self.0.get(&addr).copied()).collect()
+        }
+    }
+
+    fn int() -> Type {
+        Type::new("int".to_string(), 4)
+    }
+
+    /// Variables: `i` (int, 5) at 0x100, `arr` (int [4], 10 20 30 40) at 0x200, `p` (int *,
+    /// pointing at arr) at 0x300, `pt` (struct with x = 1 and y = -2) at 0x400, `s` (a slice of
+    /// the first 3 elements of arr) at 0x500 and `u` (unsigned long, the largest one) at 0x600
+    fn fixture() -> (FakeMemory, HashMap<String, (usize, Type)>) {
+        let mut memory = HashMap::new();
+        let mut write = |addr: usize, bytes: &[u8]| {
+            for (offset, &byte) in bytes.iter().enumerate() {
+                memory.insert(addr + offset, byte);
+            }
+        };
+        write(0x100, &5i32.to_le_bytes());
+        for (n, value) in [10i32, 20, 30, 40].iter().enumerate() {
+            write(0x200 + 4 * n, &value.to_le_bytes());
+        }
+        write(0x300, &0x200u64.to_le_bytes());
+        write(0x400, &1i32.to_le_bytes());
+        write(0x404, &(-2i32).to_le_bytes());
+        write(0x500, &0x200u64.to_le_bytes());
+        write(0x508, &3u64.to_le_bytes());
+        write(0x600, &u64::MAX.to_le_bytes());
+
+        let member = |name: &str, offset, member_type| Member { name: name.to_string(), offset, member_type };
+        let int_pointer = Type::pointer(Some(int()), 8);
+        let mut variables = HashMap::new();
+        variables.insert("i".to_string(), (0x100, int()));
+        variables.insert("arr".to_string(), (0x200, Type::array(int(), 4)));
+        variables.insert("p".to_string(), (0x300, int_pointer.clone()));
+        let point = Type::structure("point".to_string(), 8, vec![member("x", 0, int()), member("y", 4, int())]);
+        variables.insert("pt".to_string(), (0x400, point));
+        let usize_type = Type::new("usize".to_string(), 8);
+        let slice = Type::structure(
+            "&[i32]".to_string(),
+            16,
+            vec![member("data_ptr", 0, int_pointer), member("length", 8, usize_type)],
+        );
+        variables.insert("s".to_string(), (0x500, slice));
+        variables.insert("u".to_string(), (0x600, Type::new("unsigned long".to_string(), 8)));
+        (FakeMemory(memory), variables)
+    }
+
+    /// Evaluates `expr` in the fixture and returns its value as an integer.
+    fn eval(expr: &str) -> Result<i128, String> {
+        let (memory, variables) = fixture();
+        let lookup = |name: &str| {
+            variables.get(name).cloned().ok_or_else(|| format!("No symbol \"{}\" in current context.", name))
+        };
+        let value = evaluate(expr, &lookup, &memory)?;
+        Evaluator { lookup: &lookup, memory: &memory }.integer(&value)
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval("i + 1"), Ok(6));
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9));
+        assert_eq!(eval("-i % 3"), Ok(-2));
+        assert_eq!(eval("0x10 / 4 - 5"), Ok(-1));
+        assert_eq!(eval("i / 0"), Err("Division by zero".to_string()));
+    }
+
+    #[test]
+    fn test_wrapping() {
+        assert_eq!(eval("u * u"), Ok(1));
+        assert_eq!(eval("u * 2"), Ok(u64::MAX as i128 - 1));
+        assert_eq!(eval("u + 1"), Ok(0));
+        assert_eq!(eval("0 - u"), Ok(1));
+        assert_eq!(eval("&arr[0] + u * u"), Ok(0x204));
+        assert_eq!(eval("&p[u] - p"), Ok(-1));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(eval("i == 5"), Ok(1));
+        assert_eq!(eval("i > 5 || i <= 4"), Ok(0));
+        assert_eq!(eval("i >= 5 && !(i != 5)"), Ok(1));
+        // The right side isn't evaluated when the left decides
+        assert_eq!(eval("i == 4 && nosuch"), Ok(0));
+        assert!(eval("i == 5 && nosuch").is_err());
+    }
+
+    #[test]
+    fn test_indexing() {
+        assert_eq!(eval("arr[2]"), Ok(30));
+        assert_eq!(eval("arr[i - 2] + arr[0]"), Ok(50));
+        assert_eq!(eval("arr[4]"), Err("Index 4 is out of bounds for int [4] (length 4).".to_string()));
+        assert_eq!(eval("p[3]"), Ok(40));
+        assert_eq!(eval("s[2]"), Ok(30));
+        assert!(eval("s[3]").unwrap_err().starts_with("Index 3 is out of bounds"));
+        assert_eq!(eval("s.len"), Ok(3));
+        assert_eq!(eval("i[0]"), Err("Cannot subscript a value of type int.".to_string()));
+    }
+
+    #[test]
+    fn test_pointers() {
+        assert_eq!(eval("*p"), Ok(10));
+        assert_eq!(eval("*(p + 1)"), Ok(20));
+        assert_eq!(eval("&arr[2] - p"), Ok(2));
+        assert_eq!(eval("&i"), Ok(0x100));
+        assert_eq!(eval("*&i"), Ok(5));
+        assert_eq!(eval("*i"), Err("Attempt to take contents of a non-pointer value.".to_string()));
+        assert_eq!(eval("&1"), Err("Attempt to take address of value not located in memory.".to_string()));
+    }
+
+    #[test]
+    fn test_members() {
+        assert_eq!(eval("pt.x - pt.y"), Ok(3));
+        assert_eq!(eval("(&pt)->y"), Ok(-2));
+        assert_eq!(eval("pt.z"), Err("There is no member named z.".to_string()));
+        assert!(eval("i.x").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(eval("nosuch"), Err("No symbol \"nosuch\" in current context.".to_string()));
+        assert_eq!(eval("i +"), Err("Incomplete expression.".to_string()));
+        assert_eq!(eval("i i"), Err("Junk at end of expression.".to_string()));
+        assert_eq!(eval("arr[1"), Err("Expected ']' in expression.".to_string()));
+        assert!(eval("i $ 2").is_err());
+        assert!(check("arr[i] > 2").is_ok());
+        assert!(check("(i").is_err());
+    }
+
+    #[test]
+    fn test_memory_error() {
+        let (memory, _) = fixture();
+        let lookup = |_: &str| Ok::<_, String>((0x900, int()));
+        let value = evaluate("x + 1", &lookup, &memory);
+        assert_eq!(value.unwrap_err(), "Cannot access memory at address 0x900");
+    }
+}
@@ -5,12 +5,11 @@
 //!
 //! This code is a huge mess. Please don't read it unless you're trying to do an extension :)
 
-use gimli;
 use gimli::{UnitOffset, UnitSectionOffset};
 use object::Object;
 use std::borrow;
 //use std::io::{BufWriter, Write};
-use crate::dwarf_data::{File, Function, Line, Location, Type, Variable};
+use crate::dwarf_data::{File, Function, Line, Location, Member, Type, Variable};
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fmt::Write;
@@ -34,7 +33,7 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
     let borrow_section: &dyn for<'a> Fn(
         &'a borrow::Cow<[u8]>,
     ) -> gimli::EndianSlice<'a, gimli::RunTimeEndian> =
-        &|section| gimli::EndianSlice::new(&*section, endian);
+        &|section| gimli::EndianSlice::new(section, endian);
 
     // Create `EndianSlice`s for all of the sections.
     let dwarf = dwarf_cow.borrow(&borrow_section);
@@ -48,9 +47,13 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
     let mut iter = dwarf.units();
     while let Some(header) = iter.next()? {
         let unit = dwarf.unit(header)?;
+        load_types(&unit, &dwarf, &mut offset_to_type)?;
 
         // Iterate over the Debugging Information Entries (DIEs) in the unit.
         let mut depth = 0;
+        // What to add to the offsets of the current function's variables to make them relative
+        // to the canonical frame address
+        let mut frame_base_offset = 0;
         let mut entries = unit.entries();
         while let Some((delta_depth, entry)) = entries.next_dfs()? {
             depth += delta_depth;
@@ -67,6 +70,22 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                     } else {
                         "<unknown>".to_string()
                     };
+                    // rustc adds the codegen unit to the name of the crate root, like
+                    // `src/main.rs/@/main.1a2b3c4d-cgu.0`
+                    let name = match name.find("/@/") {
+                        Some(end) => name[..end].to_string(),
+                        None => name,
+                    };
+                    // The line number program uses absolute paths, so resolve a relative name
+                    // against the compilation directory for the two to match up
+                    let name = match unit.comp_dir {
+                        Some(ref comp_dir) if path::Path::new(&name).is_relative() => {
+                            let mut path = path::PathBuf::from(comp_dir.to_string_lossy().as_ref());
+                            path.push(name);
+                            path.to_string_lossy().into_owned()
+                        }
+                        _ => name,
+                    };
                     compilation_units.push(File {
                         name,
                         global_variables: Vec::new(),
@@ -74,35 +93,9 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                         lines: Vec::new(),
                     });
                 }
-                gimli::DW_TAG_base_type => {
-                    let name = if let Ok(Some(attr)) = entry.attr(gimli::DW_AT_name) {
-                        if let Ok(DebugValue::Str(name)) = get_attr_value(&attr, &unit, &dwarf) {
-                            name
-                        } else {
-                            "<unknown>".to_string()
-                        }
-                    } else {
-                        "<unknown>".to_string()
-                    };
-                    let byte_size = if let Ok(Some(attr)) = entry.attr(gimli::DW_AT_byte_size) {
-                        if let Ok(DebugValue::Uint(byte_size)) =
-                            get_attr_value(&attr, &unit, &dwarf)
-                        {
-                            byte_size
-                        } else {
-                            // TODO: report error?
-                            0
-                        }
-                    } else {
-                        // TODO: report error?
-                        0
-                    };
-                    let type_offset = entry.offset().0;
-                    offset_to_type
-                        .insert(type_offset, Type::new(name, byte_size.try_into().unwrap()));
-                }
                 gimli::DW_TAG_subprogram => {
                     let mut func: Function = Default::default();
+                    frame_base_offset = 0;
                     let mut attrs = entry.attrs();
                     while let Some(attr) = attrs.next()? {
                         let val = get_attr_value(&attr, &unit, &dwarf);
@@ -129,6 +122,16 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                                     func.line_number = line_number.try_into().unwrap();
                                 }
                             }
+                            // rustc describes variables relative to %rbp rather than the CFA;
+                            // once the prologue has run, %rbp is 16 bytes below the CFA
+                            gimli::DW_AT_frame_base if is_rbp(&attr, &unit) => {
+                                frame_base_offset = -16;
+                            }
+                            gimli::DW_AT_type => {
+                                if let Ok(DebugValue::Size(offset)) = val {
+                                    func.return_type = offset_to_type.get(&offset).cloned();
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -151,15 +154,18 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                             }
                             gimli::DW_AT_type => {
                                 if let Ok(DebugValue::Size(offset)) = val {
-                                    if let Some(dtype) = offset_to_type.get(&offset).clone() {
+                                    if let Some(dtype) = offset_to_type.get(&offset) {
                                         entity_type = Some(dtype.clone());
                                     }
                                 }
                             }
                             gimli::DW_AT_location => {
-                                if let Some(loc) = get_location(&attr, &unit) {
-                                    location = Some(loc);
-                                }
+                                location = match get_location(&attr, &unit) {
+                                    Some(Location::FramePointerOffset(offset)) => {
+                                        Some(Location::FramePointerOffset(offset + frame_base_offset))
+                                    }
+                                    loc => loc.or(location),
+                                };
                             }
                             gimli::DW_AT_decl_line => {
                                 if let Ok(DebugValue::Uint(num)) = val {
@@ -169,12 +175,18 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                             _ => {}
                         }
                     }
-                    if entity_type.is_some() && location.is_some() {
+                    // Locals without a usable location are still worth listing; a global
+                    // without one (or an unnamed parameter in a prototype) is just a declaration
+                    if depth > 1 && location.is_none() && !name.is_empty() {
+                        location = Some(Location::Unavailable);
+                    }
+                    if let (Some(entity_type), Some(location)) = (entity_type, location) {
                         let var = Variable {
                             name,
-                            entity_type: entity_type.unwrap(),
-                            location: location.unwrap(),
+                            entity_type,
+                            location,
                             line_number: line_number.try_into().unwrap(),
+                            is_parameter: entry.tag() == gimli::DW_TAG_formal_parameter,
                         };
                         if depth == 1 {
                             compilation_units
@@ -208,6 +220,10 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                 if !row.end_sequence() {
                     // Determine the path. Real applications should cache this for performance.
                     let mut path = path::PathBuf::new();
+                    if let Some(ref comp_dir) = unit.comp_dir {
+                        // Only matters if the directory below is relative (or missing)
+                        path.push(comp_dir.to_string_lossy().as_ref());
+                    }
                     if let Some(file) = row.file(header) {
                         if let Some(dir) = file.directory(header) {
                             path.push(dwarf.attr_string(&unit, dir)?.to_string_lossy().as_ref());
@@ -243,7 +259,225 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
     Ok(compilation_units)
 }
 
+/// A type DIE as read from a unit, before the types it refers to (by section offset) are resolved.
+enum RawType {
+    Base(String, usize),
+    /// Target type (None for `void *`) and pointer size
+    Pointer(Option<usize>, usize),
+    /// Element type and number of elements (multi-dimensional arrays are flattened)
+    Array(Option<usize>, usize),
+    /// typedef, const or volatile: the same as the type it refers to, as far as deet cares
+    Alias(Option<usize>),
+    /// Name, size and members. Rust strings resolve to their own kind of type.
+    Struct(String, usize, Vec<RawMember>),
+}
+
+/// A member of a struct: its name, offset within the struct and type.
+struct RawMember {
+    name: String,
+    offset: usize,
+    member_type: Option<usize>,
+}
+
+/// Deepest chain of types (e.g. pointer to typedef to pointer...) load_types follows.
+const MAX_TYPE_DEPTH: usize = 16;
+
+/// Times a struct that refers to itself is expanded within itself, which is how many pointers
+/// deep its members can be followed (like `node->next->next->value`). Deeper down, it has no
+/// members.
+const MAX_STRUCT_NESTING: usize = 4;
+
+/// Adds the types deet supports that are declared in `unit` to `offset_to_type`, keyed by section
+/// offset. This is a separate pass because a DIE can refer to types declared after it.
+fn load_types<R: Reader>(
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    offset_to_type: &mut HashMap<usize, Type>,
+) -> Result<(), Error> {
+    let mut raw_types: HashMap<usize, RawType> = HashMap::new();
+    let mut last_array = None;
+    // Structs enclosing the current DIE, with their depths, innermost last
+    let mut structs: Vec<(isize, usize)> = Vec::new();
+    let mut depth = 0;
+    let mut entries = unit.entries();
+    while let Some((delta_depth, entry)) = entries.next_dfs()? {
+        depth += delta_depth;
+        while structs.last().is_some_and(|&(struct_depth, _)| struct_depth >= depth) {
+            structs.pop();
+        }
+        let mut name = None;
+        let mut size = None;
+        let mut target = None;
+        let mut count = None;
+        let mut member_offset = None;
+        let mut attrs = entry.attrs();
+        while let Some(attr) = attrs.next()? {
+            match attr.name() {
+                gimli::DW_AT_name => {
+                    if let Ok(DebugValue::Str(attr_name)) = get_attr_value(&attr, unit, dwarf) {
+                        name = Some(attr_name);
+                    }
+                }
+                gimli::DW_AT_type => {
+                    if let Ok(DebugValue::Size(offset)) = get_attr_value(&attr, unit, dwarf) {
+                        target = Some(offset);
+                    }
+                }
+                // Bounds are often stored in raw data forms, which get_attr_value doesn't decode
+                gimli::DW_AT_byte_size => size = attr.udata_value().map(|bytes| bytes as usize),
+                gimli::DW_AT_count => count = attr.udata_value().map(|n| n as usize),
+                gimli::DW_AT_upper_bound => count = attr.udata_value().map(|n| n as usize + 1),
+                gimli::DW_AT_data_member_location => member_offset = attr.udata_value().map(|n| n as usize),
+                _ => {}
+            }
+        }
+        let offset = match entry.offset().to_unit_section_offset(unit) {
+            UnitSectionOffset::DebugInfoOffset(goff) => goff.0,
+            UnitSectionOffset::DebugTypesOffset(goff) => goff.0,
+        };
+        let raw_type = match entry.tag() {
+            gimli::DW_TAG_base_type => {
+                RawType::Base(name.unwrap_or_else(|| "<unknown>".to_string()), size.unwrap_or(0))
+            }
+            gimli::DW_TAG_pointer_type => RawType::Pointer(target, size.unwrap_or(8)),
+            gimli::DW_TAG_array_type => {
+                last_array = Some(offset);
+                RawType::Array(target, 1)
+            }
+            gimli::DW_TAG_subrange_type => {
+                if let Some(RawType::Array(_, total)) = last_array.and_then(|a| raw_types.get_mut(&a)) {
+                    // Arrays without a bound (like `int a[]`) have no elements we can show
+                    *total *= count.unwrap_or(0);
+                }
+                continue;
+            }
+            gimli::DW_TAG_typedef | gimli::DW_TAG_const_type | gimli::DW_TAG_volatile_type => {
+                RawType::Alias(target)
+            }
+            gimli::DW_TAG_structure_type => {
+                structs.push((depth, offset));
+                RawType::Struct(name.unwrap_or_default(), size.unwrap_or(0), Vec::new())
+            }
+            gimli::DW_TAG_member => {
+                let parent = structs.last().filter(|&&(struct_depth, _)| struct_depth == depth - 1);
+                if let Some(RawType::Struct(_, _, members)) = parent.and_then(|&(_, s)| raw_types.get_mut(&s)) {
+                    members.push(RawMember {
+                        name: name.unwrap_or_default(),
+                        offset: member_offset.unwrap_or(0),
+                        member_type: target,
+                    });
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        raw_types.insert(offset, raw_type);
+    }
+
+    // `enclosing` holds the structs being resolved, so that one which refers to itself (like a
+    // linked list node) isn't expanded forever
+    fn resolve(
+        offset: usize,
+        raw_types: &HashMap<usize, RawType>,
+        depth: usize,
+        enclosing: &mut Vec<usize>,
+    ) -> Option<Type> {
+        if depth > MAX_TYPE_DEPTH {
+            return None;
+        }
+        match raw_types.get(&offset)? {
+            RawType::Base(name, size) => Some(Type::new(name.clone(), *size)),
+            RawType::Pointer(target, size) => {
+                let target = target.and_then(|target| resolve(target, raw_types, depth + 1, enclosing));
+                Some(Type::pointer(target, *size))
+            }
+            RawType::Array(element, count) => {
+                Some(Type::array(resolve((*element)?, raw_types, depth + 1, enclosing)?, *count))
+            }
+            RawType::Alias(target) => resolve((*target)?, raw_types, depth + 1, enclosing),
+            RawType::Struct(name, size, members) => {
+                if let Some((ptr_offset, len_offset)) = string_layout(name, members, raw_types) {
+                    return Some(Type::string(name.clone(), *size, ptr_offset, len_offset));
+                }
+                if enclosing.iter().filter(|&&enclosing| enclosing == offset).count() >= MAX_STRUCT_NESTING {
+                    return Some(Type::structure(name.clone(), *size, Vec::new()));
+                }
+                enclosing.push(offset);
+                let members = members
+                    .iter()
+                    .filter_map(|member| {
+                        Some(Member {
+                            name: member.name.clone(),
+                            offset: member.offset,
+                            member_type: resolve(member.member_type?, raw_types, depth + 1, enclosing)?,
+                        })
+                    })
+                    .collect();
+                enclosing.pop();
+                Some(Type::structure(name.clone(), *size, members))
+            }
+        }
+    }
+    for &offset in raw_types.keys() {
+        if let Some(resolved) = resolve(offset, &raw_types, 0, &mut Vec::new()) {
+            offset_to_type.insert(offset, resolved);
+        }
+    }
+    Ok(())
+}
+
+/// Finds the offsets of the data pointer and length within a Rust `&str` or `String`, going by
+/// the names rustc gives their members, or returns None for any other struct.
+fn string_layout(name: &str, members: &[RawMember], raw_types: &HashMap<usize, RawType>) -> Option<(usize, usize)> {
+    fn member<'a>(members: &'a [RawMember], name: &str) -> Option<&'a RawMember> {
+        members.iter().find(|m| m.name == name)
+    }
+    match name {
+        "&str" => Some((member(members, "data_ptr")?.offset, member(members, "length")?.offset)),
+        "String" => {
+            // String { vec: Vec<u8> { buf: RawVec<u8> { ..., ptr, ... }, len } }, where the raw
+            // buffer's layout differs between Rust versions
+            let vec = member(members, "vec")?;
+            let vec_members = struct_members(vec.member_type?, raw_types, 0)?;
+            let buf = member(vec_members, "buf")?;
+            let len = member(vec_members, "len")?;
+            let ptr = pointer_offset(buf.member_type?, raw_types, 0)?;
+            Some((vec.offset + buf.offset + ptr, vec.offset + len.offset))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the members of the struct at `offset`, looking through typedefs.
+fn struct_members(offset: usize, raw_types: &HashMap<usize, RawType>, depth: usize) -> Option<&[RawMember]> {
+    if depth > MAX_TYPE_DEPTH {
+        return None;
+    }
+    match raw_types.get(&offset)? {
+        RawType::Struct(_, _, members) => Some(members),
+        RawType::Alias(target) => struct_members((*target)?, raw_types, depth + 1),
+        _ => None,
+    }
+}
+
+/// Returns the offset of the first pointer within the type at `offset`, which is 0 if it is a
+/// pointer itself, searching the members of structs (and the structs within those) in order.
+fn pointer_offset(offset: usize, raw_types: &HashMap<usize, RawType>, depth: usize) -> Option<usize> {
+    if depth > MAX_TYPE_DEPTH {
+        return None;
+    }
+    match raw_types.get(&offset)? {
+        RawType::Pointer(..) => Some(0),
+        RawType::Alias(target) => pointer_offset((*target)?, raw_types, depth + 1),
+        RawType::Struct(_, _, members) => members
+            .iter()
+            .find_map(|m| Some(m.offset + pointer_offset(m.member_type?, raw_types, depth + 1)?)),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub enum DebugValue {
     Str(String),
     Uint(u64),
@@ -253,6 +487,7 @@ pub enum DebugValue {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     GimliError(gimli::Error),
     Addr2lineError(addr2line::gimli::Error),
@@ -312,6 +547,17 @@ fn get_location<R: Reader>(attr: &gimli::Attribute<R>, unit: &gimli::Unit<R>) ->
     None
 }
 
+/// Returns whether the expression in `attr` is just %rbp.
+fn is_rbp<R: Reader>(attr: &gimli::Attribute<R>, unit: &gimli::Unit<R>) -> bool {
+    if let gimli::AttributeValue::Exprloc(ref data) = attr.value() {
+        let mut pc = data.0.clone();
+        if let Ok(gimli::Operation::Register { register }) = gimli::Operation::parse(&mut pc, unit.encoding()) {
+            return register == gimli::X86_64::RBP && pc.is_empty();
+        }
+    }
+    false
+}
+
 // based on dwarf_dump.rs
 fn get_attr_value<R: Reader>(
     attr: &gimli::Attribute<R>,
@@ -344,6 +590,13 @@ fn get_attr_value<R: Reader>(
                 Ok(DebugValue::Str(format!("<.debug_str+0x{:08x}>", offset.0)))
             }
         }
+        gimli::AttributeValue::DebugLineStrRef(offset) => {
+            if let Ok(s) = dwarf.debug_line_str.get_str(offset) {
+                Ok(DebugValue::Str(format!("{}", s.to_string_lossy()?)))
+            } else {
+                Ok(DebugValue::Str(format!("<.debug_line_str+0x{:08x}>", offset.0)))
+            }
+        }
         gimli::AttributeValue::Sdata(data) => Ok(DebugValue::Int(data)),
         gimli::AttributeValue::Addr(data) => Ok(DebugValue::Uint(data)),
         gimli::AttributeValue::Udata(data) => Ok(DebugValue::Uint(data)),
@@ -1,13 +1,22 @@
+use libc::user_regs_struct;
 use nix::sys::ptrace;
 use nix::sys::signal;
 use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
-use nix::unistd::Pid;
+use nix::unistd::{getpgrp, isatty, setpgid, tcgetpgrp, tcsetpgrp, Pid};
 use std::os::unix::process::CommandExt;
-use std::process::{Child, Command};
+use std::process::Command;
 use std::mem::size_of;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use crate::debugger::Breakpoint;
 use crate::dwarf_data::{DwarfData};
+use crate::error::DeetError;
+use crate::expression::MemoryReader;
 
 pub enum Status {
     /// Indicates inferior stopped. Contains the signal that stopped the process, as well as the
@@ -20,143 +29,1188 @@ pub enum Status {
     /// Indicates the inferior exited due to a signal. Contains the signal that killed the
     /// process.
     Signaled(signal::Signal),
+
+    /// Indicates the inferior is about to terminate, but is stopped so it can still be inspected.
+    /// Contains the status it's terminating with (Exited or Signaled), as well as the current
+    /// instruction pointer.
+    Exiting(Box<Status>, usize),
+
+    /// Indicates the inferior called exec and is stopped before the new program's first
+    /// instruction. Contains the current instruction pointer.
+    Exec(usize),
+
+    /// Indicates the inferior forked a new process, which is traced and stopped too. Contains the
+    /// new process's pid, whether it shares the inferior's memory until it calls exec (it was
+    /// created with vfork), and the current instruction pointer.
+    Forked(Pid, bool, usize),
+
+    /// Indicates the inferior started a thread, which is traced and stopped too. Contains the
+    /// current instruction pointer.
+    Cloned(usize),
+
+    /// Indicates the inferior stopped at a breakpoint. Contains the breakpoint's address, which
+    /// is also the current instruction pointer: the instruction there hasn't run yet.
+    Breakpoint(usize),
+
+    /// Indicates the inferior was still running when the wait timeout (see `set_wait_timeout`)
+    /// ran out. The resume is finished by the next one, which waits for the inferior to stop.
+    Running,
+}
+
+/// What an inferior that is still running was resumed by, so the resume can be finished once it
+/// stops
+#[derive(Clone, Copy)]
+enum Resume {
+    Continue,
+    /// A single step in the current thread, which re-arms the breakpoint at the address it left
+    Step(Option<usize>),
+}
+
+/// Events the inferior stops at besides signals, reported as Status::Exiting, Status::Exec,
+/// Status::Forked and Status::Cloned
+fn trace_options() -> ptrace::Options {
+    ptrace::Options::PTRACE_O_TRACEEXIT
+        | ptrace::Options::PTRACE_O_TRACEEXEC
+        | ptrace::Options::PTRACE_O_TRACEFORK
+        | ptrace::Options::PTRACE_O_TRACEVFORK
+        | ptrace::Options::PTRACE_O_TRACECLONE
+}
+
+/// Environment and working directory to launch the inferior with, on top of deet's own.
+#[derive(Default)]
+pub struct LaunchSettings {
+    /// Variables to set (Some) or remove (None)
+    pub env: BTreeMap<String, Option<String>>,
+    pub cwd: Option<PathBuf>,
 }
 
 /// This function calls ptrace with PTRACE_TRACEME to enable debugging on a process. You should use
 /// pre_exec with Command to call this in the child process.
 fn child_traceme() -> Result<(), std::io::Error> {
-    ptrace::traceme().or(Err(std::io::Error::new(
-        std::io::ErrorKind::Other,
-        "ptrace TRACEME failed",
-    )))
+    ptrace::traceme().or(Err(std::io::Error::other("ptrace TRACEME failed")))
+}
+
+/// Moves the child into its own process group, which has the terminal while the child runs (see
+/// `lend_terminal`) and otherwise keeps ctrl+c at the deet prompt from reaching it. Also meant to
+/// be called using pre_exec.
+fn child_setpgid() -> Result<(), std::io::Error> {
+    setpgid(Pid::from_raw(0), Pid::from_raw(0))
+        .or(Err(std::io::Error::other("setpgid failed")))
+}
+
+/// Makes `pgrp` the foreground process group of the terminal on stdin. SIGTTOU is blocked
+/// meanwhile, since the kernel sends it to a background process (us, taking the terminal back)
+/// that does this.
+fn set_foreground(pgrp: Pid) -> Result<(), nix::Error> {
+    let mut ttou = signal::SigSet::empty();
+    ttou.add(signal::Signal::SIGTTOU);
+    let mut old_mask = signal::SigSet::empty();
+    signal::pthread_sigmask(signal::SigmaskHow::SIG_BLOCK, Some(&ttou), Some(&mut old_mask))?;
+    let result = tcsetpgrp(libc::STDIN_FILENO, pgrp);
+    signal::pthread_sigmask(signal::SigmaskHow::SIG_SETMASK, Some(&old_mask), None)?;
+    result
+}
+
+/// Hands the terminal to process group `pgrp`, so that ctrl+c and reads from the terminal go to it
+/// rather than to deet. Returns our own process group, to give the terminal back to, or None if
+/// stdin isn't a terminal we're in the foreground of.
+fn lend_terminal(pgrp: Pid) -> Option<Pid> {
+    let ours = getpgrp();
+    if !isatty(libc::STDIN_FILENO).unwrap_or(false) || tcgetpgrp(libc::STDIN_FILENO).ok()? != ours {
+        return None;
+    }
+    set_foreground(pgrp).ok()?;
+    Some(ours)
+}
+
+/// Pid of the inferior while we're waiting on it (i.e. while it's running), 0 otherwise.
+static WAITING_PID: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn forward_sigint(_: libc::c_int) {
+    let pid = WAITING_PID.load(Ordering::SeqCst);
+    if pid > 0 {
+        // kill is async-signal-safe. The inferior stops with SIGINT, which waitpid reports.
+        unsafe { libc::kill(pid, libc::SIGINT) };
+    }
+}
+
+/// Milliseconds to wait for the inferior to stop before giving control back to the user, or 0 to
+/// wait for as long as it takes
+static WAIT_TIMEOUT_MS: AtomicU64 = AtomicU64::new(0);
+
+/// How long the inferior has to run before we say that it's running
+const RUNNING_NOTICE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest sleep between polls of a running inferior. The sleeps start out much shorter, since
+/// most waits (like those for single steps) are over almost at once.
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Sets how long resuming the inferior waits for it to stop before returning Status::Running,
+/// or None to wait for as long as it takes.
+pub fn set_wait_timeout(timeout: Option<Duration>) {
+    WAIT_TIMEOUT_MS.store(timeout.map_or(0, |timeout| timeout.as_millis() as u64), Ordering::Relaxed);
+}
+
+pub fn wait_timeout() -> Option<Duration> {
+    match WAIT_TIMEOUT_MS.load(Ordering::Relaxed) {
+        0 => None,
+        millis => Some(Duration::from_millis(millis)),
+    }
+}
+
+/// Installs a SIGINT handler that interrupts the inferior when deet gets SIGINT while it is
+/// running: from ctrl+c, if the inferior doesn't have the terminal (see `lend_terminal`), or from
+/// kill. At the deet prompt, rustyline reads ctrl+c as a key press instead.
+pub fn install_sigint_handler() -> Result<(), nix::Error> {
+    let action = signal::SigAction::new(
+        signal::SigHandler::Handler(forward_sigint),
+        // Restart interrupted system calls instead of failing them with EINTR
+        signal::SaFlags::SA_RESTART,
+        signal::SigSet::empty(),
+    );
+    unsafe { signal::sigaction(signal::Signal::SIGINT, &action) }.map(drop)
+}
+
+/// Names of the registers shown by `info registers`, in display order.
+pub const REGISTER_NAMES: [&str; 18] = [
+    "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10", "r11", "r12",
+    "r13", "r14", "r15", "rip", "eflags",
+];
+
+/// Looks up a register in `regs` by name (e.g. "rsp"). Returns None for unknown names.
+pub fn register_value(regs: &user_regs_struct, name: &str) -> Option<u64> {
+    Some(match name {
+        "rax" => regs.rax,
+        "rbx" => regs.rbx,
+        "rcx" => regs.rcx,
+        "rdx" => regs.rdx,
+        "rsi" => regs.rsi,
+        "rdi" => regs.rdi,
+        "rbp" => regs.rbp,
+        "rsp" => regs.rsp,
+        "r8" => regs.r8,
+        "r9" => regs.r9,
+        "r10" => regs.r10,
+        "r11" => regs.r11,
+        "r12" => regs.r12,
+        "r13" => regs.r13,
+        "r14" => regs.r14,
+        "r15" => regs.r15,
+        "rip" => regs.rip,
+        "eflags" => regs.eflags,
+        _ => return None,
+    })
+}
+
+/// Sets a register in `regs` by name. Returns None for unknown names.
+pub fn set_register_value(regs: &mut user_regs_struct, name: &str, value: u64) -> Option<()> {
+    let register = match name {
+        "rax" => &mut regs.rax,
+        "rbx" => &mut regs.rbx,
+        "rcx" => &mut regs.rcx,
+        "rdx" => &mut regs.rdx,
+        "rsi" => &mut regs.rsi,
+        "rdi" => &mut regs.rdi,
+        "rbp" => &mut regs.rbp,
+        "rsp" => &mut regs.rsp,
+        "r8" => &mut regs.r8,
+        "r9" => &mut regs.r9,
+        "r10" => &mut regs.r10,
+        "r11" => &mut regs.r11,
+        "r12" => &mut regs.r12,
+        "r13" => &mut regs.r13,
+        "r14" => &mut regs.r14,
+        "r15" => &mut regs.r15,
+        "rip" => &mut regs.rip,
+        "eflags" => &mut regs.eflags,
+        _ => return None,
+    };
+    *register = value;
+    Some(())
 }
 
 fn align_addr_to_word(addr: usize) -> usize {
     addr & (-(size_of::<usize>() as isize) as usize)
 }
 
+/// Sends `signal` to thread `tid` of process `pid` (tgkill, which nix doesn't wrap).
+fn tgkill(pid: Pid, tid: Pid, signal: signal::Signal) -> Result<(), nix::Error> {
+    let result = unsafe {
+        libc::syscall(libc::SYS_tgkill, pid.as_raw(), tid.as_raw(), signal as libc::c_int)
+    };
+    nix::errno::Errno::result(result).map(drop)
+}
+
+/// A thread of the inferior
+struct Thread {
+    tid: Pid,
+    /// Number shown to the user
+    number: usize,
+    /// False while the thread is running
+    stopped: bool,
+    /// Signal that stopped the thread while the other threads were being stopped, delivered
+    /// when it's resumed
+    pending_signal: Option<signal::Signal>,
+    /// True if a SIGSTOP deet sent the thread hasn't arrived yet; it's swallowed when it does
+    stray_sigstop: bool,
+}
+
 pub struct Inferior {
-    child: Child,
+    pid: Pid,
+    /// True if we attached to a process that was already running, rather than starting it
+    attached: bool,
+    /// False once waitpid has reported that the process exited or was killed by a signal
+    alive: bool,
+    /// Known threads in the order they started, the main thread first
+    threads: Vec<Thread>,
+    /// Thread that stopped last, or that the user selected. Registers are read from it and
+    /// single steps are taken in it.
+    current: Pid,
+    /// New threads and processes whose first stop came before the clone or fork that created them
+    /// was reported
+    early_stops: Vec<Pid>,
+    /// Address of the breakpoint the current thread is stopped at, if any. Its original byte is
+    /// back in place so the instruction there can run, and it's armed again once it has.
+    stopped_at_bp: Option<usize>,
+    /// Set while the inferior is still running after waiting for it timed out
+    resuming: Option<Resume>,
+    /// How the inferior stopped, if it did so while running in the background and poll_running
+    /// noticed before the resume was finished
+    ready: Option<Status>,
 }
 
 impl Inferior {
-    /// Attempts to start a new inferior process. Returns Some(Inferior) if successful, or None if
-    /// an error is encountered.
-    pub fn new(target: &str, args: &Vec<String>, breakpoints: & mut HashMap<usize, Breakpoint>) -> Option<Inferior> {
-        let mut cmd = Command::new(target);
+    /// Attempts to start a new inferior process, stopped before its first instruction. Returns
+    /// Some(Inferior) if successful, or None if an error is encountered.
+    pub fn new(target: &str, args: &Vec<String>, settings: &LaunchSettings) -> Option<Inferior> {
+        let mut cmd = match &settings.cwd {
+            Some(cwd) => {
+                // A relative target path is relative to our working directory, not the inferior's
+                let target = std::fs::canonicalize(target).unwrap_or_else(|_| PathBuf::from(target));
+                let mut cmd = Command::new(target);
+                cmd.current_dir(cwd);
+                cmd
+            }
+            None => Command::new(target),
+        };
         cmd.args(args);
+        for (name, value) in &settings.env {
+            match value {
+                Some(value) => cmd.env(name, value),
+                None => cmd.env_remove(name),
+            };
+        }
         
         unsafe {
+            cmd.pre_exec(child_setpgid);
             cmd.pre_exec(child_traceme);
         }
         
         let child = cmd.spawn().ok()?;
-        let mut inferior = Inferior { child };
+        let inferior = Inferior::with_main_thread(Pid::from_raw(child.id() as i32), false);
 
-        match waitpid(nix::unistd::Pid::from_raw(inferior.child.id() as i32), None).ok()? {
+        match waitpid(inferior.pid(), None).ok()? {
             WaitStatus::Stopped(_pid, _sig) => {
-                // The target is actually loaded, add breakpoints
-                for (baddr, breakpoint) in breakpoints {
-                    match inferior.write_byte(*baddr, 0xcc) {
-                        Err(_) => {
-                            println!("Unable to set breakpoint at {}", baddr);
-                            return None;
-                        },
-                        Ok(orig_byte) => { breakpoint.orig_byte = orig_byte; }
-                    }
-                }
+                ptrace::setoptions(inferior.pid(), trace_options()).ok()?;
                 Some(inferior)
             }
             _ => {
-                return None
+                None
+            }
+        }
+    }
+
+    /// Attaches to an already running process and stops it. Returns an error if the process
+    /// doesn't exist or we aren't permitted to trace it.
+    pub fn attach(pid: Pid) -> Result<Inferior, DeetError> {
+        ptrace::attach(pid)?;
+        let mut inferior = Inferior::with_main_thread(pid, true);
+        match waitpid(pid, None)? {
+            WaitStatus::Stopped(_pid, _sig) => {}
+            _ => return Err(DeetError::Ptrace(nix::Error::Sys(nix::errno::Errno::ESRCH))),
+        }
+        ptrace::setoptions(pid, trace_options())?;
+        // Each of the other threads has to be attached to separately. One that exits before we
+        // get to it is no loss.
+        let tids: Vec<Pid> = fs::read_dir(format!("/proc/{}/task", pid))
+            .map(|tasks| {
+                tasks
+                    .filter_map(|task| task.ok()?.file_name().to_str()?.parse().ok())
+                    .map(Pid::from_raw)
+                    .filter(|&tid| tid != pid)
+                    .collect()
+            })
+            .unwrap_or_default();
+        for tid in tids {
+            let attached = ptrace::attach(tid)
+                .and_then(|_| waitpid(tid, Some(WaitPidFlag::__WALL)))
+                .and_then(|_| ptrace::setoptions(tid, trace_options()));
+            if attached.is_ok() {
+                inferior.add_thread(tid);
             }
         }
+        Ok(inferior)
+    }
+
+    /// Takes charge of a process this inferior forked, which Status::Forked reported. It counts as
+    /// attached to if this inferior was.
+    pub fn forked(&self, pid: Pid) -> Inferior {
+        Inferior::with_main_thread(pid, self.attached)
+    }
+
+    /// Returns an inferior for the stopped process `pid`, which has no other threads yet.
+    fn with_main_thread(pid: Pid, attached: bool) -> Inferior {
+        let mut inferior =
+            Inferior {
+                pid,
+                attached,
+                alive: true,
+                threads: Vec::new(),
+                current: pid,
+                early_stops: Vec::new(),
+                stopped_at_bp: None,
+                resuming: None,
+                ready: None,
+            };
+        inferior.add_thread(pid);
+        inferior
+    }
+
+    /// Starts keeping track of the stopped thread `tid`.
+    fn add_thread(&mut self, tid: Pid) {
+        let number = self.threads.last().map_or(1, |thread| thread.number + 1);
+        self.threads.push(Thread { tid, number, stopped: true, pending_signal: None, stray_sigstop: false });
+    }
+
+    fn thread_mut(&mut self, tid: Pid) -> Option<&mut Thread> {
+        self.threads.iter_mut().find(|thread| thread.tid == tid)
+    }
+
+    /// Returns the number and thread id of the current thread.
+    pub fn current_thread(&self) -> (usize, Pid) {
+        let number = self.threads.iter().find(|thread| thread.tid == self.current).map_or(0, |thread| thread.number);
+        (number, self.current)
+    }
+
+    /// Returns the number, thread id and %rip (if it can be read) of every thread.
+    pub fn threads(&self) -> Vec<(usize, Pid, Option<usize>)> {
+        self.threads
+            .iter()
+            .map(|thread| {
+                let rip = ptrace::getregs(thread.tid).ok().map(|regs| regs.rip as usize);
+                (thread.number, thread.tid, rip)
+            })
+            .collect()
+    }
+
+    /// Makes the thread with the given number the current one. Returns false if there's no such
+    /// thread. The breakpoint the previous thread was stopped at is armed again, so that thread
+    /// hits it once more when it's resumed rather than running past it.
+    pub fn select_thread(
+        &mut self,
+        number: usize,
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<bool, DeetError> {
+        let tid = match self.threads.iter().find(|thread| thread.number == number) {
+            Some(thread) => thread.tid,
+            None => return Ok(false),
+        };
+        if tid != self.current {
+            if let Some(addr) = self.stopped_at_bp.take() {
+                self.arm_breakpoint(breakpoints, addr)?;
+            }
+            self.current = tid;
+        }
+        Ok(true)
+    }
+
+    /// Returns the address the inferior's executable is mapped at (the start of the mapping of
+    /// its first page), or None if /proc doesn't say.
+    pub fn load_address(&self) -> Option<usize> {
+        let exe = fs::read_link(format!("/proc/{}/exe", self.pid())).ok()?;
+        let maps = fs::read_to_string(format!("/proc/{}/maps", self.pid())).ok()?;
+        // Each line is "start-end perms offset dev inode      path"
+        maps.lines().find_map(|line| {
+            let fields: Vec<&str> = line.splitn(6, ' ').collect();
+            match fields.as_slice() {
+                [range, _, "00000000", _, _, path] if Path::new(path.trim_start()) == exe => {
+                    usize::from_str_radix(range.split('-').next()?, 16).ok()
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns whether `addr` is in a mapping of the inferior's memory that holds code. If /proc
+    /// doesn't say, it's given the benefit of the doubt.
+    pub fn is_executable(&self, addr: usize) -> bool {
+        let maps = match fs::read_to_string(format!("/proc/{}/maps", self.pid())) {
+            Ok(maps) => maps,
+            Err(_) => return true,
+        };
+        // Each line is "start-end perms offset dev inode      path"
+        maps.lines().any(|line| {
+            let mut fields = line.split(' ');
+            let (range, perms) = match (fields.next(), fields.next()) {
+                (Some(range), Some(perms)) => (range, perms),
+                _ => return false,
+            };
+            let bounds: Vec<usize> = range.split('-').filter_map(|n| usize::from_str_radix(n, 16).ok()).collect();
+            perms.contains('x') && bounds.len() == 2 && bounds[0] <= addr && addr < bounds[1]
+        })
+    }
+
+    /// Writes 0xcc at the address of every breakpoint, saving the original bytes. Breakpoints
+    /// outside executable memory (unless set with --force) are deleted instead.
+    pub fn install_breakpoints(&mut self, breakpoints: &mut HashMap<usize, Breakpoint>) -> Result<(), DeetError> {
+        breakpoints.retain(|addr, breakpoint| {
+            let keep = breakpoint.force || self.is_executable(*addr);
+            if !keep {
+                println!(
+                    "Deleting breakpoint {}: address {:#x} is not in executable memory",
+                    breakpoint.label(),
+                    addr
+                );
+            }
+            keep
+        });
+        for (baddr, breakpoint) in breakpoints {
+            match self.write_byte(*baddr, 0xcc) {
+                Err(e) => {
+                    println!("Unable to set breakpoint at {}", baddr);
+                    return Err(e);
+                },
+                Ok(orig_byte) => { breakpoint.orig_byte = orig_byte; }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes all breakpoints from the inferior's memory and lets it continue running without
+    /// being traced.
+    pub fn detach(&mut self, breakpoints: &HashMap<usize, Breakpoint>) -> Result<(), DeetError> {
+        for breakpoint in breakpoints.values() {
+            self.write_byte(breakpoint.addr, breakpoint.orig_byte)?;
+        }
+        let mut stray_sigstop = false;
+        for thread in &self.threads {
+            if thread.tid != self.current {
+                let _ = ptrace::detach(thread.tid, thread.pending_signal);
+            }
+            stray_sigstop |= thread.stray_sigstop;
+        }
+        ptrace::detach(self.current, None)?;
+        if stray_sigstop {
+            // A SIGSTOP we sent is still on its way, and would stop the process once we're gone.
+            // Sending SIGCONT discards it.
+            signal::kill(self.pid(), signal::Signal::SIGCONT)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if we attached to this inferior rather than spawning it.
+    pub fn is_attached(&self) -> bool {
+        self.attached
     }
 
     /// Returns the pid of this inferior.
     pub fn pid(&self) -> Pid {
-        nix::unistd::Pid::from_raw(self.child.id() as i32)
+        self.pid
+    }
+
+    /// Waits until a thread of this inferior stops (just thread `tid`, if given) or the process
+    /// terminates, and returns a Status to indicate the state of the process. The thread that
+    /// stopped becomes the current one, and any other threads still running are stopped too.
+    /// Returns Status::Running if the wait timeout runs out first.
+    fn wait(&mut self, tid: Option<Pid>) -> Result<Status, DeetError> {
+        if let Some(status) = self.ready.take() {
+            return Ok(status);
+        }
+        // A program we started has the terminal while it runs, as it would without deet. We take
+        // it back before the prompt, including when the program is left running in the background.
+        let terminal_owner = if self.is_attached() { None } else { lend_terminal(self.pid) };
+        // Otherwise, ctrl+c is forwarded to the inferior for as long as we're waiting, sleeps
+        // included
+        WAITING_PID.store(self.pid().as_raw(), Ordering::SeqCst);
+        let status = self.poll_until_stopped(tid);
+        WAITING_PID.store(0, Ordering::SeqCst);
+        if let Some(owner) = terminal_owner {
+            let _ = set_foreground(owner);
+        }
+        status
     }
 
-    /// Calls waitpid on this inferior and returns a Status to indicate the state of the process
-    /// after the waitpid call.
-    pub fn wait(&self, options: Option<WaitPidFlag>) -> Result<Status, nix::Error> {
-        Ok(match waitpid(self.pid(), options)? {
-            WaitStatus::Exited(_pid, exit_code) => Status::Exited(exit_code),
-            WaitStatus::Signaled(_pid, signal, _core_dumped) => Status::Signaled(signal),
+    /// Polls waitpid for `tid` (or any thread) until wait() has something to return, sleeping
+    /// a little longer each time nothing has happened yet.
+    fn poll_until_stopped(&mut self, tid: Option<Pid>) -> Result<Status, DeetError> {
+        let started = Instant::now();
+        let timeout = wait_timeout();
+        let mut interval = Duration::from_micros(50);
+        let mut announced = false;
+        loop {
+            let flags = WaitPidFlag::__WALL | WaitPidFlag::WNOHANG;
+            match waitpid(tid.unwrap_or_else(|| Pid::from_raw(-1)), Some(flags))? {
+                WaitStatus::StillAlive => {
+                    let waited = started.elapsed();
+                    if timeout.is_some_and(|timeout| waited >= timeout) {
+                        return Ok(Status::Running);
+                    }
+                    if !announced && waited >= RUNNING_NOTICE_DELAY {
+                        eprintln!("Child is running... (Ctrl+C to interrupt)");
+                        announced = true;
+                    }
+                    thread::sleep(interval);
+                    interval = (interval * 2).min(MAX_POLL_INTERVAL);
+                }
+                status => {
+                    if let Some(status) = self.thread_status(status)? {
+                        if self.alive {
+                            self.stop_other_threads();
+                        }
+                        return Ok(status);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Interprets a wait status of one of this inferior's threads. Returns None for ones that are
+    /// dealt with here rather than reported: threads other than the main one exiting, new
+    /// threads' first stops, and SIGSTOPs deet sent itself.
+    fn thread_status(&mut self, status: WaitStatus) -> Result<Option<Status>, DeetError> {
+        let tid = match status.pid() {
+            Some(tid) => tid,
+            None => return Ok(None),
+        };
+        let known = self.threads.iter().any(|thread| thread.tid == tid);
+        if !known && tid != self.pid {
+            // A new thread or process can stop before the clone or fork that created it is
+            // reported, which is where it's dealt with; anything else that exits isn't ours
+            if let WaitStatus::Stopped(_, _) = status {
+                self.early_stops.push(tid);
+            }
+            return Ok(None);
+        }
+        match status {
+            WaitStatus::Exited(_, _) | WaitStatus::Signaled(_, _, _) if tid != self.pid => {
+                self.threads.retain(|thread| thread.tid != tid);
+                return Ok(None);
+            }
+            WaitStatus::Stopped(_, signal::Signal::SIGSTOP)
+                if self.thread_mut(tid).is_some_and(|thread| thread.stray_sigstop) =>
+            {
+                self.thread_mut(tid).unwrap().stray_sigstop = false;
+                ptrace::cont(tid, None)?;
+                return Ok(None);
+            }
+            WaitStatus::PtraceEvent(_, _, libc::PTRACE_EVENT_EXIT) if tid != self.pid => {
+                ptrace::cont(tid, None)?;
+                return Ok(None);
+            }
+            _ => {}
+        }
+        if let Some(thread) = self.thread_mut(tid) {
+            thread.stopped = true;
+        }
+        self.current = tid;
+        Ok(Some(match status {
+            WaitStatus::Exited(_pid, exit_code) => {
+                self.alive = false;
+                Status::Exited(exit_code)
+            }
+            WaitStatus::Signaled(_pid, signal, _core_dumped) => {
+                self.alive = false;
+                Status::Signaled(signal)
+            }
             WaitStatus::Stopped(_pid, signal) => {
-                let regs = ptrace::getregs(self.pid())?;
+                let regs = ptrace::getregs(tid)?;
                 Status::Stopped(signal, regs.rip as usize)
             }
-            other => panic!("waitpid returned unexpected status: {:?}", other),
-        })
+            WaitStatus::PtraceEvent(_pid, _signal, libc::PTRACE_EVENT_EXIT) => {
+                // The event message is the wait status the process will exit with
+                let exit_status = ptrace::getevent(tid)? as libc::c_int;
+                let pending = if libc::WIFSIGNALED(exit_status) {
+                    let signal = signal::Signal::try_from(libc::WTERMSIG(exit_status))?;
+                    Status::Signaled(signal)
+                } else {
+                    Status::Exited(libc::WEXITSTATUS(exit_status))
+                };
+                Status::Exiting(Box::new(pending), self.get_rip()?)
+            }
+            WaitStatus::PtraceEvent(_pid, _signal, libc::PTRACE_EVENT_EXEC) => {
+                // exec does away with every other thread, and the thread that called it takes
+                // over the main thread's id. Breakpoints went with the old program.
+                self.stopped_at_bp = None;
+                self.current = self.pid;
+                self.threads.retain(|thread| thread.number == 1);
+                Status::Exec(self.get_rip()?)
+            }
+            WaitStatus::PtraceEvent(_pid, _signal, event)
+                if event == libc::PTRACE_EVENT_FORK || event == libc::PTRACE_EVENT_VFORK =>
+            {
+                let child = Pid::from_raw(ptrace::getevent(tid)? as i32);
+                // The new process starts out stopped; collect that stop so the debugger can
+                // detach from it or take it over
+                self.collect_first_stop(child)?;
+                Status::Forked(child, event == libc::PTRACE_EVENT_VFORK, self.get_rip()?)
+            }
+            WaitStatus::PtraceEvent(_pid, _signal, libc::PTRACE_EVENT_CLONE) => {
+                self.new_thread(Pid::from_raw(ptrace::getevent(tid)? as i32))?;
+                Status::Cloned(self.get_rip()?)
+            }
+            // Anything else is a stop deet didn't ask to be told about, so it can't be reported
+            // meaningfully
+            _ => return Err(DeetError::UnexpectedStop),
+        }))
+    }
+
+    /// Starts tracking a thread whose creation was just reported.
+    fn new_thread(&mut self, tid: Pid) -> Result<(), DeetError> {
+        self.collect_first_stop(tid)?;
+        self.add_thread(tid);
+        Ok(())
+    }
+
+    /// Waits for the stop a new thread or process starts out with, unless it has already been
+    /// seen.
+    fn collect_first_stop(&mut self, pid: Pid) -> Result<(), DeetError> {
+        match self.early_stops.iter().position(|&early| early == pid) {
+            Some(index) => {
+                self.early_stops.remove(index);
+            }
+            None => {
+                waitpid(pid, Some(WaitPidFlag::__WALL))?;
+            }
+        }
+        Ok(())
     }
 
-    /// Wakes up this inferior and waits until the inferior stops or terminates.
-    pub fn cont(&self) -> Result<Status, nix::Error> {
-        ptrace::cont(self.pid(), None)?;
+    /// Stops every thread that is still running, so nothing changes while the user inspects the
+    /// inferior. A thread that stops for another reason before our SIGSTOP gets to it keeps that
+    /// stop for later: a breakpoint is backed up over so it's hit again once the thread resumes,
+    /// and a signal is delivered then.
+    fn stop_other_threads(&mut self) {
+        let running: Vec<Pid> =
+            self.threads.iter().filter(|thread| !thread.stopped).map(|thread| thread.tid).collect();
+        for tid in running {
+            // A thread still has our last SIGSTOP pending if it stopped for something else first.
+            // That one stops it; another could arrive separately and be reported.
+            let stray_pending = self.thread_mut(tid).is_some_and(|thread| thread.stray_sigstop);
+            let sent = if stray_pending { Ok(()) } else { tgkill(self.pid, tid, signal::Signal::SIGSTOP) };
+            let status = sent.and_then(|_| waitpid(tid, Some(WaitPidFlag::__WALL)));
+            let mut stray_sigstop = true;
+            let mut pending_signal = None;
+            match status {
+                Ok(WaitStatus::Stopped(_, signal::Signal::SIGSTOP)) => stray_sigstop = false,
+                Ok(WaitStatus::Stopped(_, signal::Signal::SIGTRAP)) => {
+                    if let Ok(mut regs) = ptrace::getregs(tid) {
+                        let int3 = ptrace::read(tid, (regs.rip - 1) as ptrace::AddressType)
+                            .is_ok_and(|word| word as u8 == 0xcc);
+                        if int3 {
+                            regs.rip -= 1;
+                            let _ = ptrace::setregs(tid, regs);
+                        }
+                    }
+                }
+                Ok(WaitStatus::Stopped(_, signal)) => pending_signal = Some(signal),
+                Ok(WaitStatus::PtraceEvent(_, _, libc::PTRACE_EVENT_CLONE)) => {
+                    if let Ok(new_tid) = ptrace::getevent(tid) {
+                        let _ = self.new_thread(Pid::from_raw(new_tid as i32));
+                    }
+                }
+                Ok(WaitStatus::PtraceEvent(_, _, event))
+                    if event == libc::PTRACE_EVENT_FORK || event == libc::PTRACE_EVENT_VFORK =>
+                {
+                    // Only the thread the user is debugging gets to fork a process to follow
+                    if let Ok(child) = ptrace::getevent(tid) {
+                        let child = Pid::from_raw(child as i32);
+                        let _ = self.collect_first_stop(child);
+                        let _ = ptrace::detach(child, None);
+                    }
+                }
+                // Any other event stop (like the thread exiting) holds it just as well
+                Ok(WaitStatus::PtraceEvent(_, _, _)) => {}
+                // The thread is gone
+                _ => {
+                    self.threads.retain(|thread| thread.tid != tid);
+                    continue;
+                }
+            }
+            if let Some(thread) = self.thread_mut(tid) {
+                thread.stopped = true;
+                thread.stray_sigstop = stray_sigstop;
+                thread.pending_signal = pending_signal;
+            }
+        }
+    }
+
+    /// Resumes every thread until one of them stops or the process terminates. If the current
+    /// thread is stopped at a breakpoint, it first steps past it (delivering `signal`), and the
+    /// breakpoint is armed again behind it; a stop during that step is returned as is. A thread
+    /// stopping at a breakpoint is reported as Status::Breakpoint, and will step past it the next
+    /// time it's resumed.
+    ///
+    /// If the inferior is still running from an earlier resume, this just waits for that one to
+    /// finish instead (and `signal` is dropped).
+    pub fn continue_resume(
+        &mut self,
+        breakpoints: &HashMap<usize, Breakpoint>,
+        signal: Option<signal::Signal>,
+    ) -> Result<Status, DeetError> {
+        if let Some(resume) = self.resuming.take() {
+            return self.finish_resume(breakpoints, resume);
+        }
+        let mut signal = signal;
+        self.leave_breakpoint(breakpoints)?;
+        if self.stopped_at_bp.is_some() {
+            match self.step_resume(breakpoints, signal)? {
+                Status::Stopped(signal::Signal::SIGTRAP, _) => signal = None,
+                status => return Ok(status),
+            }
+        }
+        let status = self.cont(signal)?;
+        self.after_resume(breakpoints, Resume::Continue, status)
+    }
+
+    /// Executes a single instruction in the current thread, delivering `signal` first if given.
+    /// If the thread was stopped at a breakpoint, the breakpoint is armed again behind it. Like
+    /// continue_resume, finishes an earlier resume instead if the inferior is still running.
+    pub fn step_resume(
+        &mut self,
+        breakpoints: &HashMap<usize, Breakpoint>,
+        signal: Option<signal::Signal>,
+    ) -> Result<Status, DeetError> {
+        if let Some(resume) = self.resuming.take() {
+            return self.finish_resume(breakpoints, resume);
+        }
+        self.leave_breakpoint(breakpoints)?;
+        let stopped_at_bp = self.stopped_at_bp.take();
+        let status = self.step(signal)?;
+        self.after_resume(breakpoints, Resume::Step(stopped_at_bp), status)
+    }
+
+    /// Waits for the inferior, which is still running after `resume`, and finishes that resume.
+    fn finish_resume(&mut self, breakpoints: &HashMap<usize, Breakpoint>, resume: Resume) -> Result<Status, DeetError> {
+        let tid = match resume {
+            Resume::Continue => None,
+            Resume::Step(_) => Some(self.current),
+        };
+        let status = self.wait(tid)?;
+        self.after_resume(breakpoints, resume, status)
+    }
+
+    /// Does what's left of `resume` now that the inferior has stopped with `status`, or keeps it
+    /// for later if the inferior is still running.
+    fn after_resume(
+        &mut self,
+        breakpoints: &HashMap<usize, Breakpoint>,
+        resume: Resume,
+        status: Status,
+    ) -> Result<Status, DeetError> {
+        match (resume, status) {
+            (resume, Status::Running) => {
+                self.resuming = Some(resume);
+                Ok(Status::Running)
+            }
+            // The int3 has run, so %rip is one past the breakpoint
+            (Resume::Continue, Status::Stopped(signal::Signal::SIGTRAP, rip))
+                if breakpoints.contains_key(&(rip - 1)) =>
+            {
+                self.set_rip(rip - 1)?;
+                self.stop_at_breakpoint(breakpoints, rip - 1)?;
+                Ok(Status::Breakpoint(rip - 1))
+            }
+            // After exec, the breakpoint is in a different program and stopped_at_bp was cleared
+            (Resume::Step(Some(addr)), status) if self.alive && !matches!(status, Status::Exec(_)) => {
+                self.arm_breakpoint(breakpoints, addr)?;
+                Ok(status)
+            }
+            (_, status) => Ok(status),
+        }
+    }
+
+    /// Returns whether this inferior was left running in the background because waiting for it
+    /// timed out. Until it's resumed again (which waits for it), it can't be inspected.
+    pub fn still_running(&self) -> bool {
+        self.resuming.is_some()
+    }
+
+    /// Checks, without waiting, whether this inferior has stopped while running in the
+    /// background. If it has, the next resume returns how it stopped right away.
+    pub fn poll_running(&mut self) -> Result<bool, DeetError> {
+        if self.ready.is_some() {
+            return Ok(true);
+        }
+        if self.resuming.is_none() {
+            return Ok(false);
+        }
+        loop {
+            match waitpid(Pid::from_raw(-1), Some(WaitPidFlag::WNOHANG | WaitPidFlag::__WALL))? {
+                WaitStatus::StillAlive => return Ok(false),
+                status => {
+                    if let Some(status) = self.thread_status(status)? {
+                        if self.alive {
+                            self.stop_other_threads();
+                        }
+                        self.ready = Some(status);
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Interrupts this inferior, which is running in the background, with SIGINT, as ctrl+c
+    /// would. The next resume reports the stop.
+    pub fn interrupt(&self) -> Result<(), DeetError> {
+        signal::kill(self.pid, signal::Signal::SIGINT)?;
+        Ok(())
+    }
+
+    /// Puts the original byte back at the breakpoint at `addr`, which the current thread is
+    /// stopped at, so the instruction there can run. The breakpoint is armed again once it has.
+    pub fn stop_at_breakpoint(&mut self, breakpoints: &HashMap<usize, Breakpoint>, addr: usize) -> Result<(), DeetError> {
+        if let Some(breakpoint) = breakpoints.get(&addr) {
+            self.write_byte(addr, breakpoint.orig_byte)?;
+            self.stopped_at_bp = Some(addr);
+        }
+        Ok(())
+    }
+
+    /// Makes the current thread step past a breakpoint at its %rip rather than hit it when it
+    /// resumes, however it came to stop there: not only by hitting it, but by stepping onto it,
+    /// starting out there, or being stopped there by a signal.
+    fn leave_breakpoint(&mut self, breakpoints: &HashMap<usize, Breakpoint>) -> Result<(), DeetError> {
+        if self.stopped_at_bp.is_some() {
+            return Ok(());
+        }
+        let rip = self.get_rip()?;
+        if breakpoints.contains_key(&rip) {
+            self.stop_at_breakpoint(breakpoints, rip)?;
+        }
+        Ok(())
+    }
+
+    /// Writes 0xcc at the breakpoint at `addr` again, unless it has been deleted since.
+    fn arm_breakpoint(&mut self, breakpoints: &HashMap<usize, Breakpoint>, addr: usize) -> Result<(), DeetError> {
+        if breakpoints.contains_key(&addr) {
+            self.write_byte(addr, 0xcc)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the breakpoint at `addr`, putting the original byte back unless the current thread
+    /// is stopped there and it's back already. Returns the deleted breakpoint, if there was one.
+    pub fn remove_breakpoint(
+        &mut self,
+        breakpoints: &mut HashMap<usize, Breakpoint>,
+        addr: usize,
+    ) -> Result<Option<Breakpoint>, DeetError> {
+        let breakpoint = match breakpoints.remove(&addr) {
+            Some(breakpoint) => breakpoint,
+            None => return Ok(None),
+        };
+        if self.stopped_at_bp == Some(addr) {
+            self.stopped_at_bp = None;
+        } else {
+            self.write_byte(addr, breakpoint.orig_byte)?;
+        }
+        Ok(Some(breakpoint))
+    }
+
+    /// Moves the current thread's %rip to `rip`, as if it had stopped there: a breakpoint at
+    /// `rip` is stepped past rather than hit when the thread resumes, and the one it was stopped
+    /// at (if any) is armed again.
+    pub fn jump(&mut self, breakpoints: &HashMap<usize, Breakpoint>, rip: usize) -> Result<(), DeetError> {
+        if let Some(addr) = self.stopped_at_bp.take() {
+            self.arm_breakpoint(breakpoints, addr)?;
+        }
+        self.set_rip(rip)?;
+        self.stop_at_breakpoint(breakpoints, rip)
+    }
+
+    /// Wakes up every thread of this inferior, delivering `signal` to the current one if given,
+    /// and waits until one of them stops or the process terminates.
+    fn cont(&mut self, signal: Option<signal::Signal>) -> Result<Status, DeetError> {
+        ptrace::cont(self.current, signal)?;
+        let current = self.current;
+        for thread in &mut self.threads {
+            thread.stopped = false;
+            if thread.tid != current {
+                // A thread that has exited in the meantime will be reported by waitpid
+                let _ = ptrace::cont(thread.tid, thread.pending_signal.take());
+            }
+        }
         self.wait(None)
     }
 
     /// Kills this inferior and waits it to exit.
-    pub fn kill(&mut self) -> Result<Status, nix::Error> {
-        self.child.kill().unwrap();
+    pub fn kill(&mut self) -> Result<Status, DeetError> {
         println!("Killing running inferior (pid {})", self.pid());
-        self.wait(None)
+        signal::kill(self.pid(), signal::Signal::SIGKILL)?;
+        self.reap()
     }
 
-    /// Check if this inferior is running
-    pub fn running(&mut self) -> Result<bool, nix::Error> {
-        Ok(match self.child.try_wait() {
-            Ok(Some(_)) => false,
-            Ok(None) => true,
-            Err(e) => panic!("try_wait returned unexpected err: {:?}", e)
-        })
+    /// Checks, without waiting, whether this (stopped) inferior was killed from outside deet.
+    /// Every thread is stopped between commands, so anything waitpid has to say about them means
+    /// the process is dying; in that case, waits for it to finish and returns how it terminated.
+    pub fn poll_exit(&mut self) -> Result<Option<Status>, DeetError> {
+        loop {
+            match waitpid(Pid::from_raw(-1), Some(WaitPidFlag::WNOHANG | WaitPidFlag::__WALL))? {
+                WaitStatus::StillAlive => return Ok(None),
+                WaitStatus::Exited(pid, exit_code) if pid == self.pid => {
+                    self.alive = false;
+                    return Ok(Some(Status::Exited(exit_code)));
+                }
+                WaitStatus::Signaled(pid, signal, _) if pid == self.pid => {
+                    self.alive = false;
+                    return Ok(Some(Status::Signaled(signal)));
+                }
+                // Other threads exiting, whose exits have to be collected before the main
+                // thread's is reported
+                WaitStatus::Exited(_, _) | WaitStatus::Signaled(_, _, _) => {}
+                // Stopped on the way out; make sure it finishes dying
+                status => {
+                    if let Some(tid) = status.pid() {
+                        let _ = ptrace::cont(tid, None);
+                    }
+                    let _ = signal::kill(self.pid, signal::Signal::SIGKILL);
+                    return self.reap().map(Some);
+                }
+            }
+        }
     }
 
-    /// Print this inferior's backtrace using debugging symbols
-    pub fn print_backtrace(&self, debug_data: &DwarfData) -> Result<(), nix::Error> {
-        let pid = self.pid();
-        let mut rip = ptrace::getregs(pid)?.rip as usize;
-        let mut rbp = ptrace::getregs(pid)?.rbp as usize;
+    /// Waits for this inferior, which has been killed, to exit, resuming any threads that are
+    /// still stopped so it can.
+    fn reap(&mut self) -> Result<Status, DeetError> {
         loop {
-            let func_name = debug_data.get_function_from_addr(rip).ok_or(nix::Error::Sys(nix::errno::Errno::EINVAL))?;
-            let func_line = debug_data.get_line_from_addr(rip).ok_or(nix::Error::Sys(nix::errno::Errno::EINVAL))?;
-            println!("{} ({})", func_name, func_line);
-            if func_name == "main" { break; }
-            rip = ptrace::read(pid, (rbp + 8) as ptrace::AddressType)? as usize;
-            rbp = ptrace::read(pid, rbp as ptrace::AddressType)? as usize;
+            match waitpid(Pid::from_raw(-1), Some(WaitPidFlag::__WALL))? {
+                WaitStatus::Exited(pid, exit_code) if pid == self.pid => {
+                    self.alive = false;
+                    return Ok(Status::Exited(exit_code));
+                }
+                WaitStatus::Signaled(pid, signal, _) if pid == self.pid => {
+                    self.alive = false;
+                    return Ok(Status::Signaled(signal));
+                }
+                // A stop that was already pending when we sent SIGKILL. Resume the thread so the
+                // kill takes effect, and don't leave behind a process it just forked.
+                WaitStatus::PtraceEvent(tid, _, event)
+                    if event == libc::PTRACE_EVENT_FORK || event == libc::PTRACE_EVENT_VFORK =>
+                {
+                    if let Ok(child) = ptrace::getevent(tid) {
+                        let _ = signal::kill(Pid::from_raw(child as i32), signal::Signal::SIGKILL);
+                    }
+                    let _ = ptrace::cont(tid, None);
+                }
+                WaitStatus::Stopped(tid, _) | WaitStatus::PtraceEvent(tid, _, _) => {
+                    let _ = ptrace::cont(tid, None);
+                }
+                // Other threads exiting
+                _ => {}
+            }
         }
-        Ok(())
     }
 
-    pub fn write_byte(&mut self, addr: usize, val: u8) -> Result<u8, nix::Error> {
+    /// Returns whether this inferior is still alive, as far as waitpid has told us. This doesn't
+    /// wait for the process itself, which would consume a status that wait() needs to see.
+    pub fn running(&self) -> bool {
+        self.alive
+    }
+
+    /// Walks the chain of saved %rbp values, returning the %rip and %rbp of each stack frame,
+    /// innermost first, up to and including main. A frame the debugging symbols don't cover is
+    /// still returned, so a corrupt stack or one without main in it gives a partial backtrace
+    /// rather than an error: the walk ends at a null or unreadable frame pointer, once the frame
+    /// pointer stops going up the stack (which also catches cycles), or after `limit` frames.
+    pub fn frames(&self, debug_data: &DwarfData, limit: usize) -> Result<Vec<(usize, usize)>, DeetError> {
+        let regs = self.get_regs()?;
+        let mut rip = regs.rip as usize;
+        let mut rbp = regs.rbp as usize;
+        let mut frames = Vec::new();
+        while frames.len() < limit && rip != 0 {
+            frames.push((rip, rbp));
+            if debug_data.get_function_from_addr(rip).as_deref() == Some("main") {
+                break;
+            }
+            match debug_data.get_function_containing(rip) {
+                // The innermost function's prologue hasn't run yet, so %rbp still belongs to
+                // the caller
+                Some(func) if frames.len() == 1 && (rip == func.address || rip == func.address + 1) => {
+                    match self.get_return_address(func.address) {
+                        Ok((return_addr, _)) => rip = return_addr,
+                        Err(_) => break,
+                    }
+                }
+                _ => match (self.read_word(rbp.wrapping_add(8)), self.read_word(rbp)) {
+                    (Ok(return_addr), Ok(caller_rbp)) if caller_rbp > rbp => {
+                        rip = return_addr;
+                        rbp = caller_rbp;
+                    }
+                    _ => break,
+                },
+            }
+        }
+        Ok(frames)
+    }
+
+    pub fn write_byte(&mut self, addr: usize, val: u8) -> Result<u8, DeetError> {
         let aligned_addr = align_addr_to_word(addr);
         let byte_offset = addr - aligned_addr;
-        let word = ptrace::read(self.pid(), aligned_addr as ptrace::AddressType)? as u64;
-        let orig_byte = (word >> 8 * byte_offset) & 0xff;
-        let masked_word = word & !(0xff << 8 * byte_offset);
-        let updated_word = masked_word | ((val as u64) << 8 * byte_offset);
+        let word = self.peek(aligned_addr)? as u64;
+        let orig_byte = (word >> (8 * byte_offset)) & 0xff;
+        let masked_word = word & !(0xff << (8 * byte_offset));
+        let updated_word = masked_word | ((val as u64) << (8 * byte_offset));
         ptrace::write(
-            self.pid(),
+            self.current,
             aligned_addr as ptrace::AddressType,
             updated_word as *mut std::ffi::c_void,
-        )?;
+        )
+        .map_err(|err| Inferior::memory_error(err, addr))?;
         Ok(orig_byte as u8)
     }
 
-    pub fn get_rip(&self) -> Result<usize, nix::Error> {
-        Ok(ptrace::getregs(self.pid())?.rip as usize)
+    /// Writes `bytes` to memory starting at `addr`, which needn't be word-aligned.
+    pub fn write_bytes(&mut self, addr: usize, bytes: &[u8]) -> Result<(), DeetError> {
+        for (i, byte) in bytes.iter().enumerate() {
+            self.write_byte(addr + i, *byte)?;
+        }
+        Ok(())
+    }
+
+    /// Returns all general-purpose registers of this (stopped) inferior.
+    pub fn get_regs(&self) -> Result<user_regs_struct, DeetError> {
+        Ok(ptrace::getregs(self.current)?)
     }
 
-    pub fn step_back_rip(&mut self) -> Result<(), nix::Error> {
-        let mut regs = ptrace::getregs(self.pid())?;
-        regs.rip = regs.rip - 1;
-        ptrace::setregs(self.pid(), regs)
+    pub fn set_regs(&self, regs: user_regs_struct) -> Result<(), DeetError> {
+        Ok(ptrace::setregs(self.current, regs)?)
     }
 
-    pub fn step(&self) -> Result<Status, nix::Error> {
-        ptrace::step(self.pid(), None)?;
-        self.wait(None)
+    /// Reads the word at `addr`.
+    pub fn read_word(&self, addr: usize) -> Result<usize, DeetError> {
+        self.peek(addr)
     }
-}
\ No newline at end of file
+
+    /// Reads the word at `addr` from the current thread's memory. Any failure other than the
+    /// process being gone means the address is bad.
+    fn peek(&self, addr: usize) -> Result<usize, DeetError> {
+        ptrace::read(self.current, addr as ptrace::AddressType)
+            .map(|word| word as usize)
+            .map_err(|err| Inferior::memory_error(err, addr))
+    }
+
+    /// Turns a failed ptrace memory access at `addr` into an error.
+    fn memory_error(err: nix::Error, addr: usize) -> DeetError {
+        match DeetError::from(err) {
+            err if err.process_gone() => err,
+            _ => DeetError::BadAddress(addr),
+        }
+    }
+
+    /// Reads up to `len` bytes of memory starting at `addr`, one word at a time. Stops early at
+    /// the first word that can't be read (e.g. because it's on an unmapped page), or at the end
+    /// of the address space.
+    pub fn read_bytes(&self, addr: usize, len: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut word_addr = align_addr_to_word(addr);
+        while bytes.len() < len {
+            let word = match ptrace::read(self.current, word_addr as ptrace::AddressType) {
+                Ok(word) => word as u64,
+                Err(_) => break,
+            };
+            // Only the first word can start before `addr`
+            let skip = addr.saturating_sub(word_addr);
+            let take = (size_of::<usize>() - skip).min(len - bytes.len());
+            bytes.extend_from_slice(&word.to_le_bytes()[skip..skip + take]);
+            word_addr = match word_addr.checked_add(size_of::<usize>()) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        bytes
+    }
+
+    /// Reads a NUL-terminated string at `addr`, up to `max_len` bytes. Returns the bytes before
+    /// the NUL, stopping early at unreadable memory, or None if nothing at `addr` is readable.
+    pub fn read_string(&self, addr: usize, max_len: usize) -> Option<Vec<u8>> {
+        let mut string = Vec::new();
+        while string.len() < max_len {
+            let chunk = match addr.checked_add(string.len()) {
+                Some(chunk_addr) => self.read_bytes(chunk_addr, size_of::<usize>()),
+                None => Vec::new(),
+            };
+            if chunk.is_empty() {
+                return if string.is_empty() { None } else { Some(string) };
+            }
+            for byte in chunk {
+                match byte {
+                    0 => return Some(string),
+                    byte if string.len() < max_len => string.push(byte),
+                    _ => break,
+                }
+            }
+        }
+        Some(string)
+    }
+
+    pub fn get_rip(&self) -> Result<usize, DeetError> {
+        Ok(self.get_regs()?.rip as usize)
+    }
+
+    pub fn get_rsp(&self) -> Result<usize, DeetError> {
+        Ok(self.get_regs()?.rsp as usize)
+    }
+
+    pub fn get_rax(&self) -> Result<u64, DeetError> {
+        Ok(self.get_regs()?.rax)
+    }
+
+    /// Returns the return address of the current stack frame, along with the value %rsp will
+    /// have once the frame has returned. `func_addr` is the entry address of the current
+    /// function: until its prologue (`push %rbp; mov %rsp,%rbp`) has run, %rbp still belongs to
+    /// the caller, so the return address has to be found relative to %rsp instead.
+    pub fn get_return_address(&self, func_addr: usize) -> Result<(usize, usize), DeetError> {
+        let regs = self.get_regs()?;
+        let rip = regs.rip as usize;
+        let ret_slot = if rip == func_addr {
+            regs.rsp as usize
+        } else if rip == func_addr + 1 {
+            regs.rsp as usize + 8
+        } else {
+            regs.rbp as usize + 8
+        };
+        let ret_addr = self.peek(ret_slot)?;
+        Ok((ret_addr, ret_slot + 8))
+    }
+
+    /// Moves this (stopped) inferior's instruction pointer to `rip`.
+    pub fn set_rip(&mut self, rip: usize) -> Result<(), DeetError> {
+        let mut regs = self.get_regs()?;
+        regs.rip = rip as u64;
+        self.set_regs(regs)
+    }
+
+    /// Executes a single instruction in the current thread, delivering `signal` first if given.
+    /// The other threads stay stopped.
+    fn step(&mut self, signal: Option<signal::Signal>) -> Result<Status, DeetError> {
+        let current = self.current;
+        ptrace::step(current, signal)?;
+        if let Some(thread) = self.thread_mut(current) {
+            thread.stopped = false;
+        }
+        self.wait(Some(current))
+    }
+}
+
+impl MemoryReader for Inferior {
+    fn read_memory(&self, addr: usize, len: usize) -> Option<Vec<u8>> {
+        let bytes = self.read_bytes(addr, len);
+        if bytes.len() == len {
+            Some(bytes)
+        } else {
+            None
+        }
+    }
+}
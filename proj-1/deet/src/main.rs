@@ -2,23 +2,57 @@ mod debugger;
 mod debugger_command;
 mod inferior;
 mod dwarf_data;
+mod error;
+mod expression;
 mod gimli_wrapper;
+mod output;
 
 use crate::debugger::Debugger;
-use nix::sys::signal::{signal, SigHandler, Signal};
 use std::env;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!("Usage: {} <target program>", args[0]);
-        std::process::exit(1);
-    }
-    let target = &args[1];
+    let mut args: Vec<String> = env::args().collect();
+    // -x SCRIPT can come anywhere after the program name
+    let script = match args.iter().position(|arg| arg == "-x") {
+        Some(flag) if flag + 1 < args.len() => {
+            let script = args.remove(flag + 1);
+            args.remove(flag);
+            Some(script)
+        }
+        _ => None,
+    };
+    let no_color = match args.iter().position(|arg| arg == "--no-color") {
+        Some(flag) => {
+            args.remove(flag);
+            true
+        }
+        None => false,
+    };
+    output::init(no_color);
+    let (target, pid) = match args.as_slice() {
+        [_, target] => (target.clone(), None),
+        // Without an explicit target, read debugging symbols from the process's own executable
+        [_, flag, pid] if flag == "--pid" => (format!("/proc/{}/exe", pid), Some(pid)),
+        [_, flag, pid, target] if flag == "--pid" => (target.clone(), Some(pid)),
+        _ => {
+            println!("Usage: {} <target program> [-x <script>] [--no-color]", args[0]);
+            println!("       {} --pid <pid> [target program] [-x <script>] [--no-color]", args[0]);
+            std::process::exit(1);
+        }
+    };
 
-    // Disable handling of ctrl+c in this process (so that ctrl+c only gets delivered to child
-    // processes)
-    unsafe { signal(Signal::SIGINT, SigHandler::SigIgn) }.expect("Error disabling SIGINT handling");
+    // Forward ctrl+c to the inferior while it runs, rather than letting it kill deet
+    inferior::install_sigint_handler().expect("Error installing SIGINT handler");
 
-    Debugger::new(target).run();
+    let mut debugger = Debugger::new(&target);
+    if let Some(pid) = pid {
+        debugger.attach(pid);
+    }
+    if let Some(script) = script {
+        if !debugger.run_script(&script) {
+            std::process::exit(debugger.exit_code());
+        }
+    }
+    debugger.run();
+    std::process::exit(debugger.exit_code());
 }
@@ -0,0 +1,109 @@
+//! Colors and terminal width for what deet prints. Colors are only used when stdout is a terminal
+//! and the user hasn't turned them off with --no-color or the NO_COLOR environment variable.
+
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Width assumed when stdout isn't a terminal and COLUMNS isn't set
+const DEFAULT_WIDTH: usize = 80;
+
+/// Turns colors on if stdout is a terminal, unless `no_color` is true or NO_COLOR is set.
+pub fn init(no_color: bool) {
+    let tty = unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1;
+    let no_color_env = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    COLOR.store(tty && !no_color && !no_color_env, Ordering::Relaxed);
+}
+
+/// Wraps `text` in the ANSI escape sequence for `code` if `enabled`.
+fn style(code: &str, text: &str, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn paint(code: &str, text: &str) -> String {
+    style(code, text, COLOR.load(Ordering::Relaxed))
+}
+
+pub fn function(name: &str) -> String {
+    paint("33", name)
+}
+
+/// A source location like `file.c:12`, or an address standing in for one.
+pub fn location(location: &str) -> String {
+    paint("32", location)
+}
+
+pub fn breakpoint(label: &str) -> String {
+    paint("1;34", label)
+}
+
+pub fn signal(name: &str) -> String {
+    paint("1;31", name)
+}
+
+/// Returns how many columns the terminal stdout is on has, or COLUMNS (or 80) if it isn't one.
+pub fn terminal_width() -> usize {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0 && size.ws_col > 0 {
+        return size.ws_col as usize;
+    }
+    env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()).unwrap_or(DEFAULT_WIDTH)
+}
+
+/// Shortens `path` to at most `max` characters by replacing its leading directories with "…/".
+/// The file name itself is always kept, even if it's longer than that.
+pub fn shorten_path(path: &str, max: usize) -> String {
+    if path.chars().count() <= max {
+        return path.to_string();
+    }
+    let components: Vec<&str> = path.split('/').collect();
+    for start in 1..components.len() {
+        let shortened = format!("…/{}", components[start..].join("/"));
+        if shortened.chars().count() <= max {
+            return shortened;
+        }
+    }
+    format!("…/{}", components[components.len() - 1])
+}
+
+/// Cuts `text` down to `max` characters, ending it with "…" if anything was cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_style() {
+        assert_eq!(style("33", "main", true), "\x1b[33mmain\x1b[0m");
+        assert_eq!(style("33", "main", false), "main");
+        // Colors are off until init turns them on
+        assert_eq!(function("main"), "main");
+        assert_eq!(signal("SIGSEGV"), "SIGSEGV");
+    }
+
+    #[test]
+    fn test_shorten_path() {
+        assert_eq!(shorten_path("src/main.c", 20), "src/main.c");
+        assert_eq!(shorten_path("/home/user/project/src/main.c", 16), "…/src/main.c");
+        assert_eq!(shorten_path("/home/user/project/src/main.c", 3), "…/main.c");
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("0x401136 <main+4>", 40), "0x401136 <main+4>");
+        assert_eq!(truncate("0x401136 <main+4>", 10), "0x401136 …");
+    }
+}
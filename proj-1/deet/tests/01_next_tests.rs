@@ -0,0 +1,30 @@
+mod common;
+
+use common::{build_sample, printed_lines, run_deet};
+
+/// `next` through a loop has to stop every time the loop jumps back to its condition, rather
+/// than treating the lower line number as "not past the starting line yet".
+#[test]
+fn test_next_through_loop() {
+    let target = build_sample("next_loop");
+    let mut commands = vec!["break main", "run"];
+    commands.extend(vec!["next"; 12]);
+    commands.push("quit");
+    let output = run_deet(&target, &commands);
+    assert_eq!(printed_lines(&output), vec![7, 8, 9, 10, 9, 10, 9, 10, 9, 12, 13, 14]);
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// `next` steps over calls, but still stops at a breakpoint inside the callee, and stepping off
+/// the end of the callee carries on in the caller.
+#[test]
+fn test_next_over_call() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["break 10", "run", "next", "next", "break square", "next", "next", "next", "next", "quit"],
+    );
+    // Stopped at line 10, then stepped over square() twice; the third call hits the breakpoint
+    // at square, whose lines are stepped through until it returns to main
+    assert_eq!(printed_lines(&output), vec![10, 9, 10, 3, 4, 5, 10]);
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// up/down/frame move the selection marker in backtrace, and stepping resets it to frame 0.
+#[test]
+fn test_frame_selection() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["break square", "run", "up", "bt", "up", "down", "down", "next", "bt", "quit"],
+    );
+    assert!(output.contains("#1 main ("));
+    assert!(output.contains("=> #1 main ("));
+    assert!(output.contains("No frame at level 2; the stack has 2 frames"));
+    assert!(output.contains("Bottom (innermost) frame selected; you cannot go down."));
+    // After next, the innermost frame is selected again
+    let last_backtrace = &output[output.rfind("#0").unwrap() - 3..];
+    assert!(last_backtrace.starts_with("=> #0 square ("));
+}
+
+/// Each frame in the backtrace shows the arguments its function was called with.
+#[test]
+fn test_backtrace_arguments() {
+    let target = build_sample("function_calls");
+    let output = run_deet(&target, &["break 6", "run", "backtrace", "quit"]);
+    assert!(output.contains("#0 func3 (a=100) ("));
+    assert!(output.contains("#1 func2 (a=42, b=5) ("));
+    assert!(output.contains("#2 func1 (a=42) ("));
+    assert!(output.contains("#3 main () ("));
+}
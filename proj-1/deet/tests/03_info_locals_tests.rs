@@ -0,0 +1,23 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// info locals reads the variables of the selected frame.
+#[test]
+fn test_info_locals() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["break 4", "run", "continue", "continue", "info locals", "up", "info locals", "quit"],
+    );
+    assert!(output.contains("x: int = 2"));
+    assert!(output.contains("total: int = 1"));
+    assert!(output.contains("i: int = 2"));
+}
+
+#[test]
+fn test_info_locals_without_inferior() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["info locals", "quit"]);
+    assert!(output.contains("No running subprocess"));
+}
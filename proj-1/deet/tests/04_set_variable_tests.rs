@@ -0,0 +1,27 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Assigning to a variable changes what the inferior computes.
+#[test]
+fn test_set_variable() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["break 4", "run", "set var x = 7", "up", "set var i=2", "continue", "quit"],
+    );
+    // square(0) returns 49, and the loop ends before calling square again
+    assert!(output.contains("total = 49"));
+}
+
+#[test]
+fn test_set_variable_errors() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["break 4", "run", "set var x = 99999999999", "set var y = 1", "set var $foo = 1", "quit"],
+    );
+    assert!(output.contains("99999999999 is out of range for int"));
+    assert!(output.contains("No symbol \"y\" in current context."));
+    assert!(output.contains("Invalid register `foo'"));
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// disassemble shows the original instructions at breakpoints, not the int3s deet put there,
+/// and marks where the inferior is stopped.
+#[test]
+fn test_disassemble_breakpoints() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break square", "break 4", "run", "disassemble", "quit"]);
+    let square_entry = output.lines().find(|line| line.contains("<square+0>:")).unwrap();
+    assert!(square_entry.starts_with("=> "));
+    assert!(square_entry.contains("push %rbp"));
+    assert!(!output.contains("int3"));
+    assert!(output.contains("ret"));
+}
+
+#[test]
+fn test_disassemble_function() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break square", "run", "disas main", "disas nope", "quit"]);
+    assert!(output.contains("<main+0>:"));
+    assert!(output.contains("call 0x"));
+    assert!(!output.contains("<square+"));
+    assert!(output.contains("No function or address \"nope\""));
+}
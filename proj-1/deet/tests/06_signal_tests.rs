@@ -0,0 +1,32 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// A signal that stops the inferior is delivered to it when it's continued.
+#[test]
+fn test_signal_passed_on_continue() {
+    let target = build_sample("sigusr");
+    let output = run_deet(&target, &["run", "continue", "quit"]);
+    assert!(output.contains("Child stopped (signal SIGUSR1)"));
+    assert!(output.contains("handler ran 1 times"));
+}
+
+#[test]
+fn test_handle_nopass() {
+    let target = build_sample("sigusr");
+    let output = run_deet(&target, &["handle SIGUSR1 nopass", "run", "continue", "quit"]);
+    assert!(output.contains("SIGUSR1\tNo"));
+    assert!(output.contains("handler ran 0 times"));
+}
+
+/// `signal` delivers an extra signal on the next resume.
+#[test]
+fn test_signal_command() {
+    let target = build_sample("sigusr");
+    let output = run_deet(
+        &target,
+        &["break 13", "run", "continue", "signal usr1", "continue", "continue", "quit"],
+    );
+    assert!(output.contains("Signal SIGUSR1 will be delivered on the next resume"));
+    assert!(output.contains("handler ran 2 times"));
+}
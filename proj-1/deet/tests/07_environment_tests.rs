@@ -0,0 +1,39 @@
+mod common;
+
+use common::{build_sample, run_deet};
+use std::env;
+use std::fs;
+
+/// Environment and working directory settings apply to every run until changed.
+#[test]
+fn test_env_and_cwd_persist_across_runs() {
+    let target = build_sample("env");
+    let temp_dir = fs::canonicalize(env::temp_dir()).unwrap();
+    let cd = format!("cd {}", temp_dir.display());
+    let output = run_deet(
+        &target,
+        &["set env GREETING=hello there", &cd, "run", "run", "unset env GREETING", "run", "quit"],
+    );
+    assert_eq!(output.matches("GREETING=hello there").count(), 2);
+    assert_eq!(output.matches("GREETING=(unset)").count(), 1);
+    assert_eq!(output.matches(&format!("cwd={}\n", temp_dir.display())).count(), 3);
+}
+
+#[test]
+fn test_show_env() {
+    let target = build_sample("env");
+    let output = run_deet(
+        &target,
+        &["set env GREETING hi", "show env GREETING", "unset env GREETING", "show env GREETING", "quit"],
+    );
+    assert!(output.contains("GREETING = hi"));
+    assert!(output.contains("Environment variable \"GREETING\" not defined."));
+}
+
+#[test]
+fn test_cd_errors() {
+    let target = build_sample("env");
+    let output = run_deet(&target, &["cd /nonexistent-dir", "cd /etc/passwd", "quit"]);
+    assert!(output.contains("/nonexistent-dir: No such file or directory"));
+    assert!(output.contains("/etc/passwd: Not a directory."));
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use common::{build_sample, printed_lines, run_deet};
+
+/// Bare until doesn't stop when the loop jumps back to its condition.
+#[test]
+fn test_until_leaves_loop() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["tbreak 10", "run", "until", "until", "quit"]);
+    assert_eq!(printed_lines(&output), vec![10, 12, 13]);
+}
+
+/// advance runs to a location without leaving a breakpoint behind, but still stops at user
+/// breakpoints on the way.
+#[test]
+fn test_advance() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break main", "run", "advance 12", "quit"]);
+    assert_eq!(printed_lines(&output), vec![7, 12]);
+    let output = run_deet(&target, &["break main", "run", "advance 12", "break list", "quit"]);
+    assert_eq!(output.matches("\tkeep\t").count(), 1);
+
+    let output = run_deet(&target, &["break main", "break square", "run", "advance 12", "quit"]);
+    assert_eq!(printed_lines(&output), vec![7, 3]);
+}
+
+#[test]
+fn test_advance_to_exit() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break 12", "run", "advance 4", "quit"]);
+    assert!(output.contains("Child exited (status 0)"));
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+#[test]
+fn test_info_functions() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["info functions", "quit"]);
+    assert!(output.contains("main (line 7)"));
+    assert!(output.contains("square (line 3)"));
+    assert!(!output.contains("printf"));
+
+    let output = run_deet(&target, &["info functions squ", "quit"]);
+    assert!(output.contains("square (line 3)"));
+    assert!(!output.contains("main"));
+}
+
+/// info line reports the same address break would use for a location.
+#[test]
+fn test_info_line() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["info line 10", "break 10", "quit"]);
+    let line_start = output
+        .split("starts at address ")
+        .nth(1)
+        .and_then(|rest| rest.split(' ').next())
+        .expect("info line should print a start address");
+    assert!(output.contains(&format!("Set breakpoint 0 at {}", line_start)));
+    assert!(output.contains("Line 10 of"));
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Each repetition of continue stops at the breakpoint in the loop again, so the breakpoint has
+/// to be re-armed between them.
+#[test]
+fn test_continue_count() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break 6", "run", "continue 3", "info locals", "quit"]);
+    assert_eq!(output.matches("Breakpoint 0, Stopped at").count(), 4);
+    assert!(output.contains("i: int = 3"));
+}
+
+#[test]
+fn test_continue_count_stops_at_exit() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break 6", "run", "continue 10", "quit"]);
+    assert_eq!(output.matches("Breakpoint 0, Stopped at").count(), 5);
+    assert!(output.contains("Child exited (status 0)"));
+    assert!(output.contains("continue stopped after 5 of 10 repetitions"));
+}
+
+/// next N stops early at a breakpoint it reaches.
+#[test]
+fn test_next_count() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break main", "run", "next 2", "quit"]);
+    assert!(output.contains("5\t    for"));
+    assert!(!output.contains("6\t"));
+    assert!(!output.contains("stopped after"));
+
+    let output = run_deet(&target, &["break main", "break 6", "run", "next 5", "quit"]);
+    assert!(output.contains("next stopped after 3 of 5 repetitions"));
+}
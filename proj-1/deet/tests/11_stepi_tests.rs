@@ -0,0 +1,30 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+#[test]
+fn test_nexti_steps_over_calls() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break 10", "run", "ni 3", "quit"]);
+    assert!(!output.contains("<square+"));
+    assert_eq!(output.matches("<main+").count(), 3);
+}
+
+#[test]
+fn test_stepi_enters_calls() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break 10", "run", "si 3", "quit"]);
+    assert!(output.contains("<square+0>"));
+    assert!(output.contains("3\tint square(int x) {"));
+}
+
+/// Stepping onto a breakpoint stops there as if it had been hit, and continuing from it still
+/// works.
+#[test]
+fn test_stepi_to_breakpoint() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break 10", "run", "ni 10", "continue", "info locals", "quit"]);
+    assert!(output.contains("nexti stopped after 7 of 10 repetitions"));
+    assert_eq!(output.matches("Breakpoint 0, Stopped at").count(), 3);
+    assert!(output.contains("i: int = 2"));
+}
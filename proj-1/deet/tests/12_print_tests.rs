@@ -0,0 +1,35 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+fn print_at_line_14(commands: &[&str]) -> String {
+    let target = build_sample("pointers");
+    let mut all = vec!["break 14", "run"];
+    all.extend_from_slice(commands);
+    all.push("quit");
+    run_deet(&target, &all)
+}
+
+#[test]
+fn test_print_formats() {
+    let output = print_at_line_14(&["print count", "p/x count", "p/b negative", "p/c letter"]);
+    assert!(output.contains("count = -7\n"));
+    assert!(output.contains("count = 0xfffffff9\n"));
+    assert!(output.contains("negative = 1111111111111110\n"));
+    assert!(output.contains("letter = 113 'q'\n"));
+}
+
+#[test]
+fn test_print_dereference() {
+    let output = print_at_line_14(&["print *first", "p/x *last", "print *count"]);
+    assert!(output.contains("*first = 2\n"));
+    assert!(output.contains("*last = 0xb\n"));
+    assert!(output.contains("Attempt to take contents of a non-pointer value."));
+}
+
+#[test]
+fn test_print_arrays() {
+    let output = print_at_line_14(&["print primes", "print big"]);
+    assert!(output.contains("primes = [2, 3, 5, 7, 11]\n"));
+    assert!(output.contains(&format!("big = [{}, ...]\n", vec!["0"; 16].join(", "))));
+}
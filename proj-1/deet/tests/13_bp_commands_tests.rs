@@ -0,0 +1,34 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// A command list ending in continue keeps the program going through every hit.
+#[test]
+fn test_commands_with_continue() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break 6", "commands 0 \"print i; continue\"", "run", "quit"]);
+    for i in 0..5 {
+        assert!(output.contains(&format!("i = {}\n", i)));
+    }
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Without a continue, control returns to the prompt after the commands.
+#[test]
+fn test_commands_then_prompt() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break 6", "commands 0 \"print total\"", "run", "print i", "quit"]);
+    assert!(output.contains("total = 0\n"));
+    assert!(output.contains("i = 0\n"));
+    assert!(!output.contains("Child exited"));
+
+    let output = run_deet(&target, &["break 6", "commands 0 \"print total\"", "commands 0", "run", "quit"]);
+    assert!(!output.contains("total = "));
+}
+
+#[test]
+fn test_commands_unknown_breakpoint() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["commands 3 \"bt\"", "quit"]);
+    assert!(output.contains("No breakpoint number 3."));
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::{build_sample, run_deet_with_args};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Writes a script to a temporary file named after the test and returns its path.
+fn write_script(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("deet_{}_{}.deet", name, std::process::id()));
+    fs::write(&path, contents).expect("Failed to write script");
+    path
+}
+
+#[test]
+fn test_script_runs_before_prompt() {
+    let target = build_sample("loop_bp");
+    let script = write_script("before_prompt", "# stop in the loop\n\nbreak 6\nrun\n");
+    let output = run_deet_with_args(&target, &["-x", script.to_str().unwrap()], &["print i", "quit"]);
+    fs::remove_file(&script).unwrap();
+    assert!(output.contains("(deet) break 6\n"));
+    assert!(output.contains("(deet) run\n"));
+    assert!(!output.contains("stop in the loop"));
+    assert!(output.contains("i = 0\n"));
+}
+
+/// A bad line stops the script, but the prompt still works.
+#[test]
+fn test_script_bad_line() {
+    let target = build_sample("loop_bp");
+    let script = write_script("bad_line", "break 6\nfrobnicate\nrun\n");
+    let path = script.to_str().unwrap();
+    let output = run_deet_with_args(&target, &["-x", path], &["info breakpoints", "quit"]);
+    fs::remove_file(&script).unwrap();
+    assert!(output.contains(&format!("{}:2: Undefined command: \"frobnicate\"", path)));
+    assert!(!output.contains("(deet) run"));
+    assert!(output.contains("0\tkeep"));
+}
+
+#[test]
+fn test_script_quit() {
+    let target = build_sample("loop_bp");
+    let script = write_script("quit", "quit\n");
+    let output = run_deet_with_args(&target, &["-x", script.to_str().unwrap()], &["break 6"]);
+    fs::remove_file(&script).unwrap();
+    assert!(!output.contains("Set breakpoint"));
+}
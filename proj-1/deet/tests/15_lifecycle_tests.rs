@@ -0,0 +1,23 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Running again after the inferior exits (or while it's stopped) starts a fresh one without
+/// tripping over the old one's exit status.
+#[test]
+fn test_run_after_exit() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["run", "run", "quit"]);
+    assert_eq!(output.matches("Child exited (status 0)").count(), 2);
+    assert!(!output.contains("Killing"));
+
+    let output = run_deet(&target, &["break 6", "run", "run", "quit"]);
+    assert_eq!(output.matches("Killing running inferior").count(), 2);
+}
+
+#[test]
+fn test_commands_after_exit() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["run", "continue", "kill", "next", "quit"]);
+    assert_eq!(output.matches("No running subprocess").count(), 3);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{build_sample, run_deet};
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+/// Breakpoints follow their locations into the program loaded with `file`.
+#[test]
+fn test_file_command() {
+    let loop_bp = build_sample("loop_bp");
+    let next_loop = build_sample("next_loop");
+    let file = format!("file {}", next_loop.display());
+    let output = run_deet(&loop_bp, &["break main", "break *0x400000", &file, "run", "quit"]);
+    assert!(output.contains("Reading symbols from"));
+    assert!(output.contains("breakpoint 1 is at a fixed address (*0x400000)"));
+    assert!(output.contains("Stopped at"));
+    assert!(output.contains("next_loop.c:7"));
+
+    let output = run_deet(&loop_bp, &["break main", "file /nonexistent", "run", "quit"]);
+    assert!(output.contains("Could not open file /nonexistent"));
+    assert!(output.contains("loop_bp.c:3"));
+}
+
+/// `run` notices the target was rebuilt and moves breakpoints to match.
+#[test]
+fn test_reload_on_run() {
+    let target = env::temp_dir().join(format!("deet_reload_{}", std::process::id()));
+    fs::copy(build_sample("loop_bp"), &target).unwrap();
+    let mut deet = Command::new(env!("CARGO_BIN_EXE_deet"))
+        .arg(&target)
+        .env("HOME", env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start deet");
+    let mut stdin = deet.stdin.take().unwrap();
+    let mut stdout = BufReader::new(deet.stdout.take().unwrap());
+    stdin.write_all(b"break square\nbreak main\n").unwrap();
+    // square doesn't exist yet; wait until main's breakpoint is set before swapping the binary
+    let mut output = String::new();
+    while !output.contains("Set breakpoint") {
+        assert!(stdout.read_line(&mut output).unwrap() > 0, "deet exited early: {}", output);
+    }
+
+    let rebuilt = target.with_extension("new");
+    fs::copy(build_sample("next_loop"), &rebuilt).unwrap();
+    fs::rename(&rebuilt, &target).unwrap();
+    stdin.write_all(b"run\nquit\n").unwrap();
+    drop(stdin);
+    let mut rest = String::new();
+    while stdout.read_line(&mut rest).unwrap() > 0 {}
+    deet.wait().unwrap();
+    fs::remove_file(&target).unwrap();
+
+    assert!(output.contains("No function named \"square\""));
+    assert!(rest.contains("has changed; re-reading symbols."));
+    assert!(rest.contains("next_loop.c:7"));
+}
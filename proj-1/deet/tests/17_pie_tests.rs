@@ -0,0 +1,29 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Breakpoints, stop locations and backtraces work the same whether or not the target is
+/// position-independent.
+#[test]
+fn test_breakpoints_pie_and_no_pie() {
+    for name in &["next_loop", "next_loop_pie"] {
+        let target = build_sample(name);
+        let output = run_deet(&target, &["break square", "break 12", "run", "bt", "continue 3", "print total", "quit"]);
+        assert_eq!(output.matches("Stopped at").count(), 4, "{}: {}", name, output);
+        assert!(output.contains("#0 square (x=0)"), "{}: {}", name, output);
+        assert!(output.contains("#1 main ()"), "{}: {}", name, output);
+        assert!(output.contains("next_loop.c:12"), "{}: {}", name, output);
+        assert!(output.contains("total = 5\n"), "{}: {}", name, output);
+    }
+}
+
+/// A PIE may load somewhere else on every run; breakpoints follow it.
+#[test]
+fn test_pie_rerun_and_globals() {
+    let target = build_sample("pointers_pie");
+    let output = run_deet(&target, &["break 14", "run", "print primes", "print *first", "run", "print count", "quit"]);
+    assert_eq!(output.matches("Stopped at").count(), 2, "{}", output);
+    assert!(output.contains("primes = [2, 3, 5, 7, 11]\n"));
+    assert!(output.contains("*first = 2\n"));
+    assert!(output.contains("count = -7\n"));
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{build_sample, run_deet};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Copies a sample to a temporary file and strips all symbols from it.
+fn stripped_sample(name: &str) -> PathBuf {
+    let target = env::temp_dir().join(format!("deet_{}_stripped_{}", name, std::process::id()));
+    fs::copy(build_sample(name), &target).unwrap();
+    let status = Command::new("strip")
+        .arg(&target)
+        .status()
+        .expect("Failed to run strip");
+    assert!(status.success());
+    target
+}
+
+/// Finds where `break main` puts a breakpoint in the unstripped sample.
+fn main_address(name: &str) -> String {
+    let output = run_deet(&build_sample(name), &["break main", "quit"]);
+    let line = output
+        .lines()
+        .find(|line| line.starts_with("Set breakpoint 0 at "))
+        .unwrap();
+    line.trim_start_matches("Set breakpoint 0 at ").to_string()
+}
+
+/// A stripped target can still be run and stopped at addresses.
+#[test]
+fn test_stripped_target() {
+    let addr = main_address("loop_bp");
+    let target = stripped_sample("loop_bp");
+    let bp = format!("break *{}", addr);
+    let output = run_deet(
+        &target,
+        &[
+            "break main",
+            &bp,
+            "run",
+            "bt",
+            "print total",
+            "continue",
+            "quit",
+        ],
+    );
+    fs::remove_file(&target).unwrap();
+
+    assert!(output.contains("warning: no debugging symbols found"));
+    assert!(output.contains("No symbol table is loaded."));
+    // main may turn up if the target is rebuilt with symbols
+    assert!(output.contains("Breakpoint 0 (main) pending."));
+    assert!(output.contains(&format!("Set breakpoint 1 at {}", addr)));
+    assert!(output.contains(&format!("Stopped at {}", addr)));
+    assert!(output.contains(&format!("#0 ?? () ({})", addr)));
+    assert!(output.contains("total = 10"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Targets with debugging symbols don't get the warning.
+#[test]
+fn test_no_warning_with_symbols() {
+    let output = run_deet(&build_sample("loop_bp"), &["quit"]);
+    assert!(!output.contains("no debugging symbols"));
+}
+
+/// Without a symbol for main, `start` stops at the entry point instead.
+#[test]
+fn test_start_stripped_target() {
+    let target = stripped_sample("hello");
+    let output = run_deet(&target, &["start", "continue", "quit"]);
+    fs::remove_file(&target).unwrap();
+
+    let entry = output
+        .split("stopping at the entry point ")
+        .nth(1)
+        .and_then(|rest| rest.lines().next())
+        .expect("No entry point reported");
+    assert!(output.contains(&format!("Stopped at {}\n", entry)));
+    assert!(output.contains("Hello world!"));
+    assert!(output.contains("Child exited (status 0)"));
+}
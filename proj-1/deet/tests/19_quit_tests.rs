@@ -0,0 +1,38 @@
+mod common;
+
+use common::{build_sample, run_deet, run_deet_output};
+
+/// Declining to quit leaves the inferior where it was.
+#[test]
+fn test_quit_declined() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break main", "run", "quit", "n", "continue", "quit"]);
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Confirming (or quit! skipping the question) kills the inferior without running it further.
+#[test]
+fn test_quit_confirmed() {
+    let target = build_sample("loop_bp");
+    for commands in &[
+        &["break main", "run", "quit", "y", "continue"][..],
+        &["break main", "run", "quit!", "continue"][..],
+        &["break main", "run", "quit -y", "continue"][..],
+    ] {
+        let output = run_deet(&target, commands);
+        assert!(output.contains("Stopped at"));
+        assert!(!output.contains("Child exited"), "{:?} didn't quit", commands);
+    }
+}
+
+/// deet exits with the status of the last run of the inferior.
+#[test]
+fn test_exit_status() {
+    let target = build_sample("exit_status");
+    let output = run_deet_output(&target, &[], &["run", "quit"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    // A run that didn't finish doesn't count
+    let output = run_deet_output(&target, &[], &["run", "break main", "run", "quit"]);
+    assert_eq!(output.status.code(), Some(0));
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Counts the frames a backtrace printed.
+fn frame_count(output: &str) -> usize {
+    output.lines().filter(|line| line.get(3..).is_some_and(|rest| rest.starts_with('#'))).count()
+}
+
+/// A deep stack is cut off at the requested depth (64 by default).
+#[test]
+fn test_backtrace_depth() {
+    let target = build_sample("recursion");
+    let output = run_deet(&target, &["run", "bt"]);
+    assert!(output.contains("Child stopped (signal SIGSEGV)"));
+    assert_eq!(frame_count(&output), 64);
+    assert!(output.contains("(More stack frames follow...)"));
+
+    let output = run_deet(&target, &["run", "bt 3"]);
+    assert_eq!(frame_count(&output), 3);
+
+    let output = run_deet(&target, &["run", "backtrace 5000"]);
+    assert_eq!(frame_count(&output), 1002);
+    assert!(output.contains("#1001 main"));
+    assert!(!output.contains("More stack frames"));
+}
+
+/// A clobbered frame pointer ends the backtrace early instead of failing it.
+#[test]
+fn test_backtrace_corrupt_stack() {
+    let target = build_sample("recursion");
+    let output = run_deet(&target, &["run corrupt", "bt"]);
+    assert!(output.contains("#0 descend (depth=0, corrupt=1)"));
+    assert!(!output.contains("Error"));
+    assert!(frame_count(&output) < 64);
+}
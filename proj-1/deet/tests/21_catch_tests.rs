@@ -0,0 +1,52 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// `catch exit` stops the program with its state intact, and `continue` finishes the exit.
+#[test]
+fn test_catch_exit() {
+    let target = build_sample("exit_status");
+    let output = run_deet(&target, &["catch exit", "run", "bt", "print status", "continue"]);
+    assert!(output.contains("Catchpoint 0 (exit), process about to exit with status 3"));
+    assert!(output.contains("status = 3"));
+    assert!(!output.contains("Error"));
+    let caught = output.find("Catchpoint 0 (exit),").unwrap();
+    assert!(output[caught..].contains("Child exited (status 3)"));
+}
+
+/// Without a catchpoint, or once it's deleted, the program exits without stopping.
+#[test]
+fn test_exit_not_caught() {
+    let target = build_sample("exit_status");
+    let output = run_deet(&target, &["catch exit", "delete catch 0", "catch list", "run"]);
+    assert!(output.contains("No catchpoints."));
+    assert!(!output.contains("Catchpoint 0 (exit),"));
+    assert!(output.contains("Child exited (status 3)"));
+
+    let output = run_deet(&target, &["catch exit", "catch exec", "delete catch", "catch list"]);
+    assert!(output.contains("No catchpoints."));
+    let output = run_deet(&target, &["delete catch 4"]);
+    assert!(output.contains("No catchpoint number 4."));
+}
+
+/// `catch exec` stops once the new program is loaded.
+#[test]
+fn test_catch_exec() {
+    let target = build_sample("exec_self");
+    let output = run_deet(&target, &["catch exec", "catch list", "run", "continue"]);
+    assert!(output.contains("0\texec"));
+    let exec = output.find("is executing new program").unwrap();
+    let caught = exec + output[exec..].find("Catchpoint 0 (exec)\n").unwrap();
+    assert!(output[caught..].contains("Child exited (status 0)"));
+}
+
+/// Breakpoints still work in the program after exec.
+#[test]
+fn test_breakpoints_after_exec() {
+    let target = build_sample("exec_self");
+    let output = run_deet(&target, &["break after_exec", "run", "continue"]);
+    assert!(output.contains("is executing new program"));
+    assert!(output.contains("exec_self.c:4"));
+    assert!(output.contains("Running again"));
+    assert!(output.contains("Child exited (status 0)"));
+}
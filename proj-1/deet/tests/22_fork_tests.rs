@@ -0,0 +1,47 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// By default the parent is followed, and the child runs to completion without tripping over
+/// the breakpoints it inherited.
+#[test]
+fn test_follow_parent() {
+    let target = build_sample("forker");
+    let commands =
+        ["show follow-fork-mode", "break in_child", "break in_parent", "run", "continue", "continue"];
+    let output = run_deet(&target, &commands);
+    assert!(output.contains("is \"parent\""));
+    assert!(output.contains("Detaching after fork from child process"));
+    assert!(output.contains("In the child"));
+    assert!(output.contains("forker.c:9"));
+    assert!(!output.contains("forker.c:5"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// In child mode, the child's breakpoints fire and it becomes the inferior.
+#[test]
+fn test_follow_child() {
+    let target = build_sample("forker");
+    let commands = [
+        "set follow-fork-mode child",
+        "break in_child",
+        "break in_parent",
+        "run",
+        "info inferiors",
+        "continue",
+    ];
+    let output = run_deet(&target, &commands);
+    assert!(output.contains("Attaching after process"));
+    assert!(output.contains("forker.c:5"));
+    assert!(output.contains("(detached after fork)"));
+    assert!(output.contains("In the child"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+#[test]
+fn test_info_inferiors() {
+    let target = build_sample("forker");
+    let output = run_deet(&target, &["info inferiors", "break main", "run", "info inferiors"]);
+    assert!(output.contains("No inferiors."));
+    assert!(output.contains("* process "));
+}
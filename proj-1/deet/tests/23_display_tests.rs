@@ -0,0 +1,44 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Display expressions are shown at every stop, in their format, until they're removed.
+#[test]
+fn test_display_at_each_stop() {
+    let target = build_sample("loop_bp");
+    let commands = [
+        "break 6",
+        "display i",
+        "run",
+        "display/x total",
+        "continue",
+        "continue",
+        "undisplay 0",
+        "continue",
+        "quit -y",
+    ];
+    let output = run_deet(&target, &commands);
+    assert!(output.contains("0: i = 0\n"));
+    assert!(output.contains("0: i = 1\n"));
+    assert!(output.contains("0: i = 2\n"));
+    assert!(output.contains("1: /x total = 0x1\n"));
+    assert!(!output.contains("0: i = 3\n"));
+    assert!(output.contains("1: /x total = 0x3\n"));
+}
+
+#[test]
+fn test_info_display() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["info display", "display/x total", "info display", "undisplay 4", "quit"]);
+    assert!(output.contains("There are no auto-display expressions now."));
+    assert!(output.contains("0\t/x total"));
+    assert!(output.contains("No display number 4."));
+}
+
+/// An expression that can't be evaluated where the program stopped doesn't get in the way.
+#[test]
+fn test_display_unavailable() {
+    let target = build_sample("function_calls");
+    let output = run_deet(&target, &["display nonexistent", "break main", "run", "quit -y"]);
+    assert!(output.contains("0: nonexistent = <not available in this frame>"));
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Jumping past the loop skips it entirely.
+#[test]
+fn test_jump_within_function() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break 6", "run", "jump 8", "continue", "quit"]);
+    assert!(output.contains("loop_bp.c:8"));
+    assert!(output.contains("total = 0\n"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Jumping into another function has to be confirmed.
+#[test]
+fn test_jump_elsewhere_declined() {
+    let target = build_sample("function_calls");
+    let output = run_deet(&target, &["break func2", "run", "jump func3", "n", "quit -y"]);
+    assert!(output.contains("Not confirmed."));
+}
+
+/// The innermost call returns the given value instead of dereferencing NULL.
+#[test]
+fn test_force_return() {
+    let target = build_sample("recursion");
+    let output = run_deet(&target, &["break 11", "run", "return 7", "y", "continue", "quit"]);
+    assert!(output.contains("#0 descend"));
+    assert!(output.contains("1007\n"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+#[test]
+fn test_return_from_main() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break main", "run", "return", "quit -y"]);
+    assert!(output.contains("\"return\" not meaningful in the outermost frame."));
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// A breakpoint in a thread stops the program in that thread, and every thread runs on from there.
+#[test]
+fn test_breakpoint_in_thread() {
+    let target = build_sample("threads");
+    let commands = ["break worker", "run", "info threads", "backtrace", "continue", "continue", "quit"];
+    let output = run_deet(&target, &commands);
+    assert!(output.contains("[Switching to thread"));
+    assert!(output.contains("in worker"));
+    assert!(output.contains("#0 worker"));
+    assert!(output.contains("counter = 3"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+#[test]
+fn test_select_thread() {
+    let target = build_sample("threads");
+    let output = run_deet(&target, &["info threads", "break worker", "run", "thread 1", "thread 9", "quit -y"]);
+    assert!(output.contains("No threads."));
+    assert!(output.contains("[Switching to thread 1 ("));
+    assert!(output.contains("Invalid thread ID: 9"));
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// A breakpoint inside a loop is hit on every iteration, not just the first.
+#[test]
+fn test_breakpoint_hit_repeatedly() {
+    let target = build_sample("loop_bp");
+    let mut commands = vec!["break 6", "run"];
+    commands.extend(["print i", "continue"].iter().cycle().take(10));
+    commands.push("quit");
+    let output = run_deet(&target, &commands);
+    assert_eq!(output.matches("Breakpoint 0, Stopped at").count(), 5);
+    for i in 0..5 {
+        assert!(output.contains(&format!("i = {}\n", i)));
+    }
+    assert!(output.contains("total = 10"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Stepping past one breakpoint lands straight on the next one, which is still hit.
+#[test]
+fn test_breakpoints_on_adjacent_instructions() {
+    let target = build_sample("loop_bp");
+    // Find two consecutive instructions at the start of main
+    let output = run_deet(&target, &["break main", "run", "stepi", "quit -y"]);
+    let first = output
+        .lines()
+        .find_map(|line| line.strip_prefix("Set breakpoint 0 at "))
+        .expect("No breakpoint set")
+        .to_string();
+    let second = output
+        .lines()
+        .find(|line| line.contains(" <main+"))
+        .and_then(|line| line.split(' ').next())
+        .expect("No instruction printed")
+        .to_string();
+    assert_ne!(first, second);
+
+    let break_first = format!("break *{}", first);
+    let break_second = format!("break *{}", second);
+    let commands = [break_first.as_str(), break_second.as_str(), "run", "continue", "continue", "quit"];
+    let output = run_deet(&target, &commands);
+    assert!(output.contains("Breakpoint 0, Stopped at main"));
+    assert!(output.contains("Breakpoint 1, Stopped at main"));
+    assert!(output.contains("total = 10"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// After stepping onto a breakpoint, continuing runs the instruction there instead of hitting
+/// the breakpoint again.
+#[test]
+fn test_continue_after_stepping_onto_breakpoint() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break main", "run", "stepi", "quit -y"]);
+    let second = output
+        .lines()
+        .find(|line| line.contains(" <main+"))
+        .and_then(|line| line.split(' ').next())
+        .expect("No instruction printed")
+        .to_string();
+
+    let break_second = format!("break *{}", second);
+    let output = run_deet(&target, &["break main", &break_second, "run", "stepi", "continue", "quit"]);
+    assert_eq!(output.matches("Breakpoint 1, Stopped at main").count(), 1, "{}", output);
+    assert!(output.contains("total = 10"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// A breakpoint on the first line of main is hit once, and the program carries on normally.
+#[test]
+fn test_breakpoint_on_first_line_of_main() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break 3", "run", "continue", "quit"]);
+    assert_eq!(output.matches("Breakpoint 0, Stopped at main").count(), 1, "{}", output);
+    assert!(output.contains("total = 10"));
+    assert!(output.contains("Child exited (status 0)"));
+}
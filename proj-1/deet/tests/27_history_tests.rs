@@ -0,0 +1,43 @@
+mod common;
+
+use common::{build_sample, run_deet_in_home};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Returns an empty directory to use as deet's home directory, so the history starts out empty.
+fn fresh_home(name: &str) -> PathBuf {
+    let home = env::temp_dir().join(format!("deet_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&home);
+    fs::create_dir(&home).unwrap();
+    home
+}
+
+/// History lines are numbered, can be run again with !N, and are kept for the next session.
+#[test]
+fn test_history_and_rerun() {
+    let home = fresh_home("history");
+    let target = build_sample("loop_bp");
+    let output = run_deet_in_home(&target, &home, &["break main", "info breakpoints", "history", "!2", "!9", "quit"]);
+    assert!(output.contains("    1  break main\n    2  info breakpoints\n    3  history\n"));
+    assert_eq!(output.matches("Num\tDisp").count(), 2);
+    assert!(output.contains("!9: event not found"));
+
+    let output = run_deet_in_home(&target, &home, &["history 2"]);
+    assert!(output.contains("    5  quit\n    6  history 2\n"));
+    fs::remove_dir_all(&home).unwrap();
+}
+
+/// Each program has its own history, which starts out with the one older versions of deet kept
+/// in ~/.deet_history.
+#[test]
+fn test_history_per_target() {
+    let home = fresh_home("history_per_target");
+    fs::write(home.join(".deet_history"), "break old\n").unwrap();
+    let output = run_deet_in_home(&build_sample("loop_bp"), &home, &["break main", "history"]);
+    assert!(output.contains("    1  break old\n    2  break main\n"));
+
+    let output = run_deet_in_home(&build_sample("next_loop"), &home, &["history"]);
+    assert!(output.contains("    1  break old\n    2  history\n"));
+    fs::remove_dir_all(&home).unwrap();
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::build_sample;
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// An inferior killed from outside deet between commands is reported as such, and deet carries on.
+#[test]
+fn test_inferior_killed_externally() {
+    let target = build_sample("loop_bp");
+    let mut deet = Command::new(env!("CARGO_BIN_EXE_deet"))
+        .arg(&target)
+        .env("HOME", env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start deet");
+    let mut stdin = deet.stdin.take().unwrap();
+    let mut stdout = BufReader::new(deet.stdout.take().unwrap());
+    stdin.write_all(b"break main\nrun\ninfo threads\n").unwrap();
+    let mut output = String::new();
+    while !output.contains("\tThread ") {
+        assert!(stdout.read_line(&mut output).unwrap() > 0, "deet exited early: {}", output);
+    }
+    let pid: i32 = output
+        .split("\tThread ")
+        .nth(1)
+        .and_then(|rest| rest.split('\t').next())
+        .and_then(|pid| pid.parse().ok())
+        .expect("No thread id printed");
+
+    Command::new("kill").args(["-KILL", &pid.to_string()]).status().unwrap();
+    // Give the kill time to land before deet looks again
+    thread::sleep(Duration::from_millis(200));
+    stdin.write_all(b"print total\ncontinue\nrun\nprint total\nquit -y\n").unwrap();
+    drop(stdin);
+    let mut rest = String::new();
+    while stdout.read_line(&mut rest).unwrap() > 0 {}
+    let status = deet.wait().unwrap();
+
+    assert!(rest.contains("Child signaled (signal SIGKILL)"), "{}", rest);
+    assert!(rest.contains("No running subprocess"));
+    assert!(rest.contains("Breakpoint 0, Stopped at main"));
+    assert!(status.success());
+}
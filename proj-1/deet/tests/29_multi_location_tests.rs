@@ -0,0 +1,30 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// A line in an inline function has a location wherever the function was inlined, and each one
+/// is hit in turn.
+#[test]
+fn test_break_on_inlined_line() {
+    let target = build_sample("inline");
+    let output = run_deet(&target, &["break 4", "info breakpoints", "run", "continue", "continue", "quit"]);
+    assert!(output.contains("Set breakpoint 0 with 2 locations"));
+    assert!(output.contains("0\tkeep\t<MULTIPLE>        \t2 locations"));
+    assert!(output.contains("0.1\t\t0x"));
+    assert!(output.contains("0.2\t\t0x"));
+    assert!(output.contains("Breakpoint 0.1, Stopped at"));
+    assert!(output.contains("Breakpoint 0.2, Stopped at"));
+    assert!(output.contains("6 12"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Deleting a breakpoint deletes all of its locations.
+#[test]
+fn test_delete_multi_location_breakpoint() {
+    let target = build_sample("inline");
+    let output = run_deet(&target, &["break 4", "run", "delete 0", "info breakpoints", "continue", "delete 0", "quit"]);
+    assert_eq!(output.matches("Stopped at").count(), 1);
+    assert!(output.contains("No breakpoints."));
+    assert!(output.contains("6 12"));
+    assert!(output.contains("No breakpoint number 0."));
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// `x/s` reads up to the NUL terminator, however far away it is, and escapes what it prints.
+#[test]
+fn test_examine_c_strings() {
+    let target = build_sample("strings");
+    let output = run_deet(&target, &["break show", "run", "x/s $rdi", "continue", "x/s $rdi", "continue", "quit"]);
+    assert!(output.contains(":\t\"tab\\there \\\"quoted\\\"\\n\"\n"), "{}", output);
+    assert!(output.contains(&format!(":\t\"{}\"\n", "a".repeat(999))), "{}", output);
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// `print` shows the contents and length of Rust `&str` and `String` locals.
+#[test]
+fn test_print_rust_strings() {
+    let target = build_sample("rust_strings");
+    let output = run_deet(
+        &target,
+        &["break rust_strings.rs:5", "run", "print greeting", "print owned", "print empty", "quit"],
+    );
+    assert!(output.contains("greeting = \"hello, world\" (len 12)"), "{}", output);
+    assert!(output.contains("owned = \"owned \\\"string\\\"\" (len 14)"), "{}", output);
+    assert!(output.contains("empty = \"\" (len 0)"), "{}", output);
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Each exit says how long the inferior ran for, and `info stats` counts over every run.
+#[test]
+fn test_exit_duration_and_stats() {
+    let target = build_sample("hello");
+    let output = run_deet(&target, &["break main", "run", "continue", "run", "continue", "info stats", "quit"]);
+    assert_eq!(output.matches("Child exited (status 0) after ").count(), 2, "{}", output);
+    assert!(output.contains("Runs: 2\n"));
+    assert!(output.contains("Breakpoint hits: 2\n"));
+    assert!(output.contains("Signals: 0\n"));
+    assert!(output.contains("Total runtime: "));
+}
+
+/// An inferior killed by a signal reports the signal and how long it ran for too.
+#[test]
+fn test_signaled_duration() {
+    let target = build_sample("segfault");
+    let output = run_deet(&target, &["run", "continue", "info stats", "quit"]);
+    assert!(output.contains("Child signaled (signal SIGSEGV) after "), "{}", output);
+    assert!(output.contains("Runs: 1\n"));
+    assert!(output.contains("Signals: 1\n"));
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// `start` stops at main without leaving a breakpoint behind.
+#[test]
+fn test_start_stops_at_main() {
+    let target = build_sample("hello");
+    let output = run_deet(&target, &["start", "info breakpoints", "continue", "quit"]);
+    assert!(output.contains("Stopped at main ("), "{}", output);
+    assert!(output.contains("hello.c:3)"));
+    assert!(!output.contains("Breakpoint"));
+    assert!(output.contains("No breakpoints."));
+    assert!(output.contains("Hello world!"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// A breakpoint of the user's at main survives `start`, and is reported as hit.
+#[test]
+fn test_start_keeps_user_breakpoint() {
+    let target = build_sample("hello");
+    let output = run_deet(&target, &["break main", "start", "continue", "run", "continue", "quit"]);
+    assert_eq!(output.matches("Breakpoint 0, Stopped at main").count(), 2, "{}", output);
+    assert_eq!(output.matches("Child exited (status 0)").count(), 2);
+}
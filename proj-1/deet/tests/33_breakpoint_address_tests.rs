@@ -0,0 +1,54 @@
+mod common;
+
+use common::{build_sample, run_deet};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Returns the address of a global variable in a (non-PIE) sample, as `*0x...`.
+fn data_location(target: &PathBuf, symbol: &str) -> String {
+    let output = Command::new("nm").arg(target).output().expect("Failed to run nm");
+    let symbols = String::from_utf8_lossy(&output.stdout);
+    let addr = symbols
+        .lines()
+        .find(|line| line.ends_with(&format!(" {}", symbol)))
+        .and_then(|line| line.split(' ').next())
+        .expect("Symbol not found");
+    format!("*0x{}", addr.trim_start_matches('0'))
+}
+
+/// A breakpoint in data (or at an unmapped address) is refused while the inferior is running,
+/// unless it's forced.
+#[test]
+fn test_refuse_breakpoint_outside_code() {
+    let target = build_sample("pointers");
+    let primes = data_location(&target, "primes");
+    let output = run_deet(
+        &target,
+        &[
+            "break main",
+            "run",
+            &format!("break {}", primes),
+            "break *0x10",
+            &format!("break {} --force", primes),
+            "quit",
+        ],
+    );
+    let addr = primes.trim_start_matches('*');
+    assert!(output.contains(&format!("Address {} is not in executable memory", addr)), "{}", output);
+    assert!(output.contains("Address 0x10 is not in executable memory"));
+    assert!(output.contains(&format!("Set breakpoint 1 at {}", addr)));
+}
+
+/// A breakpoint set in data before the program runs is deleted when the program starts, and
+/// the data is left alone.
+#[test]
+fn test_skip_pending_breakpoint_outside_code() {
+    let target = build_sample("pointers");
+    let primes = data_location(&target, "primes");
+    let output = run_deet(&target, &[&format!("break {}", primes), "break main", "run", "continue", "quit"]);
+    let addr = primes.trim_start_matches('*');
+    assert!(output.contains(&format!("Deleting breakpoint 0: address {} is not in executable memory", addr)));
+    assert!(output.contains("Breakpoint 1, Stopped at main"));
+    assert!(output.contains("-7 -2 q 2 11"));
+    assert!(output.contains("Child exited (status 0)"));
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// A breakpoint on a function that doesn't exist is kept as pending, and can be deleted.
+#[test]
+fn test_pending_breakpoint() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(
+        &target,
+        &["break nosuch", "info breakpoints", "run", "info breakpoints", "delete 0", "info breakpoints", "quit"],
+    );
+    assert!(output.contains("No function named \"nosuch\""));
+    assert!(output.contains("Breakpoint 0 (nosuch) pending."));
+    assert_eq!(output.matches("0\tkeep\t<PENDING>         \tnosuch").count(), 2, "{}", output);
+    assert!(output.contains("Child exited (status 0)"));
+    assert!(output.contains("No breakpoints."));
+}
+
+/// A pending breakpoint is set once its location turns up in the symbols.
+#[test]
+fn test_pending_breakpoint_resolves() {
+    let loop_bp = build_sample("loop_bp");
+    let next_loop = build_sample("next_loop");
+    let file = format!("file {}", next_loop.display());
+    let output = run_deet(&loop_bp, &["break square", &file, "run", "quit"]);
+    assert!(output.contains("Breakpoint 0 (square) pending."));
+    assert!(output.contains("Pending breakpoint 0 (square) resolved to 0x"), "{}", output);
+    assert!(output.contains("Breakpoint 0, Stopped at square"));
+}
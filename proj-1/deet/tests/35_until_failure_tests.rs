@@ -0,0 +1,34 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Runs stop at the first failure, which is run again with the same rerun argument.
+#[test]
+fn test_run_until_failure() {
+    let target = build_sample("flaky");
+    let output = run_deet(&target, &["set rerun-arg --seed={}", "run --until-failure 10", "quit"]);
+    assert_eq!(output.matches("Child exited (status 0)").count(), 2, "{}", output);
+    assert!(output.contains("Run 3 failed with status 1"));
+    assert!(output.contains("Running again with --seed=3"));
+    assert_eq!(output.matches("Failed with seed 3").count(), 2);
+    assert!(!output.contains("Run 4"));
+}
+
+/// Without a failure, the runs stop after the maximum.
+#[test]
+fn test_run_until_failure_gives_up() {
+    let target = build_sample("flaky");
+    let output = run_deet(&target, &["run --until-failure 4", "quit"]);
+    assert_eq!(output.matches("Child exited (status 0)").count(), 4, "{}", output);
+    assert!(output.contains("No failure in 4 runs"));
+}
+
+/// A breakpoint stops the runs, leaving the inferior to inspect.
+#[test]
+fn test_run_until_failure_stops_at_breakpoint() {
+    let target = build_sample("flaky");
+    let output = run_deet(&target, &["break main", "run --until-failure 4", "print seed", "quit"]);
+    assert!(output.contains("Breakpoint 0, Stopped at main"));
+    assert!(output.contains("Run 1 stopped"));
+    assert!(!output.contains("Child exited"));
+}
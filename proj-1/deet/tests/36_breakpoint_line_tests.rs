@@ -0,0 +1,52 @@
+mod common;
+
+use common::{build_sample, run_deet};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Returns the address of a function in a (non-PIE) sample.
+fn function_address(target: &PathBuf, symbol: &str) -> String {
+    let output = Command::new("nm").arg(target).output().expect("Failed to run nm");
+    let symbols = String::from_utf8_lossy(&output.stdout);
+    let addr = symbols
+        .lines()
+        .find(|line| line.ends_with(&format!(" T {}", symbol)))
+        .and_then(|line| line.split(' ').next())
+        .expect("Symbol not found");
+    format!("0x{}", addr.trim_start_matches('0'))
+}
+
+/// A breakpoint at an address that's at another line after the target changes is reported when
+/// the program is run, and listed with the line it was set at until then.
+#[test]
+fn test_address_breakpoint_moved() {
+    let loop_bp = build_sample("loop_bp");
+    let next_loop = build_sample("next_loop");
+    let addr = function_address(&loop_bp, "main");
+    let output = run_deet(
+        &loop_bp,
+        &[
+            &format!("break *{}", addr),
+            &format!("file {}", next_loop.display()),
+            "info breakpoints",
+            "run",
+            "info breakpoints",
+            "quit",
+        ],
+    );
+    assert!(output.contains(&format!("loop_bp.c:3, but {} is now", addr)), "{}", output);
+    assert_eq!(output.matches("Warning: breakpoint 0 was set at").count(), 1);
+    assert_eq!(output.matches("(set at ").count(), 1);
+    assert!(output.contains("next_loop.c:3"));
+    assert!(output.contains("Breakpoint 0, Stopped at square"));
+}
+
+/// Breakpoints that haven't moved aren't warned about.
+#[test]
+fn test_breakpoint_not_moved() {
+    let target = build_sample("loop_bp");
+    let output = run_deet(&target, &["break main", "break 6", "run", "run", "info breakpoints", "quit"]);
+    assert!(!output.contains("Warning"), "{}", output);
+    assert!(!output.contains("(set at "));
+    assert_eq!(output.matches("Breakpoint 0, Stopped at main").count(), 2);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+fn print_at_line_23(commands: &[&str]) -> String {
+    let target = build_sample("structs");
+    let mut all = vec!["break 23", "run"];
+    all.extend_from_slice(commands);
+    all.push("quit");
+    run_deet(&target, &all)
+}
+
+#[test]
+fn test_print_arithmetic() {
+    let output = print_at_line_23(&["print sum + 1", "print values[2] * 2", "p/x sum - 11", "print sum > 5 && sum < 20"]);
+    assert!(output.contains("sum + 1 = 11\n"), "{}", output);
+    assert!(output.contains("values[2] * 2 = 6\n"));
+    assert!(output.contains("sum - 11 = 0xffffffff\n"));
+    assert!(output.contains("sum > 5 && sum < 20 = 1\n"));
+}
+
+#[test]
+fn test_print_indexing_checks_bounds() {
+    let output = print_at_line_23(&["print values[3]", "print values[4]", "print values[-1]"]);
+    assert!(output.contains("values[3] = 4\n"), "{}", output);
+    assert!(output.contains("Index 4 is out of bounds for int [4] (length 4)."));
+    assert!(output.contains("Index -1 is out of bounds for int [4] (length 4)."));
+}
+
+#[test]
+fn test_print_structs() {
+    let output = print_at_line_23(&[
+        "print origin",
+        "print origin.x - origin.y",
+        "print head->next->value",
+        "print (*head).value == 10",
+        "print *head->next->next",
+        "print origin.z",
+    ]);
+    assert!(output.contains("origin = {x = 3, y = -4}\n"), "{}", output);
+    assert!(output.contains("origin.x - origin.y = 7\n"));
+    assert!(output.contains("head->next->value = 30\n"));
+    assert!(output.contains("(*head).value == 10 = 1\n"));
+    assert!(output.contains("Cannot access memory at address 0x0"));
+    assert!(output.contains("There is no member named z."));
+}
+
+/// `.len` is the length of a Rust string.
+#[test]
+fn test_print_rust_string_length() {
+    let target = build_sample("rust_strings");
+    let output = run_deet(&target, &["break rust_strings.rs:5", "run", "print owned.len", "print greeting.len + 1", "quit"]);
+    assert!(output.contains("owned.len = 14\n"), "{}", output);
+    assert!(output.contains("greeting.len + 1 = 13\n"));
+}
+
+/// A breakpoint with a condition only stops when it's true, until the condition is removed.
+#[test]
+fn test_conditional_breakpoint() {
+    let target = build_sample("structs");
+    let output = run_deet(
+        &target,
+        &["break 21", "condition 0 sum == 3", "run", "print sum", "info breakpoints", "condition 0", "continue", "print sum", "quit"],
+    );
+    assert!(output.contains("sum = 3\n"), "{}", output);
+    assert!(output.contains("\tstop only if sum == 3\n"));
+    assert!(output.contains("Breakpoint 0 now unconditional."));
+    assert!(output.contains("sum = 6\n"));
+    assert_eq!(output.matches("Breakpoint 0, Stopped at").count(), 2);
+}
+
+/// A condition with a syntax error is refused, and one that can't be evaluated stops the program.
+#[test]
+fn test_condition_errors() {
+    let target = build_sample("structs");
+    let output = run_deet(
+        &target,
+        &["break 21", "condition 0 sum ==", "condition 5 sum", "condition 0 nosuch > 1", "run", "quit"],
+    );
+    assert!(output.contains("Incomplete expression."), "{}", output);
+    assert!(output.contains("No breakpoint number 5."));
+    assert!(output.contains("Error in testing condition for breakpoint 0: No symbol \"nosuch\" in current context."));
+    assert!(output.contains("Breakpoint 0, Stopped at main"));
+}
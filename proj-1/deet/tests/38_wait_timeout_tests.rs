@@ -0,0 +1,37 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Once the wait timeout runs out, the program keeps running while deet takes commands, and
+/// `interrupt` stops it.
+#[test]
+fn test_interrupt_after_wait_timeout() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(&target, &["set wait-timeout 1", "run 30", "print i", "interrupt", "quit -y"]);
+    assert!(output.contains("Child is still running after 1.000s; use `interrupt` to stop it"));
+    assert!(output.contains("The program is running. Use `interrupt` to stop it first."));
+    assert!(output.contains("Child stopped (signal SIGINT)"));
+}
+
+/// `continue` goes back to waiting for a program left running, which reports how it ends.
+#[test]
+fn test_continue_after_wait_timeout() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(
+        &target,
+        &["set wait-timeout 1", "show wait-timeout", "run 3", "set wait-timeout 0", "continue", "quit"],
+    );
+    assert!(output.contains("Resuming the program waits up to 1.000s for it to stop."));
+    assert!(output.contains("Child is still running after 1.000s"));
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Without a timeout, deet waits for as long as the program runs.
+#[test]
+fn test_no_wait_timeout() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(&target, &["set wait-timeout 0", "show wait-timeout", "run 2", "quit"]);
+    assert!(output.contains("Resuming the program waits until it stops."));
+    assert!(!output.contains("still running"));
+    assert!(output.contains("Child exited (status 0)"));
+}
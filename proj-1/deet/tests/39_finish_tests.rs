@@ -0,0 +1,36 @@
+mod common;
+
+use common::{build_sample, printed_lines, run_deet};
+
+/// finish runs to where the current function returns to and prints the value it returned.
+#[test]
+fn test_finish() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["break square", "run", "finish", "continue", "continue", "finish", "quit"],
+    );
+    assert!(output.contains("Run till exit from square"));
+    assert!(output.contains("Returned to main ("));
+    // square(0), then square(2)
+    assert!(output.contains("Value returned: 0"));
+    assert!(output.contains("Value returned: 4"));
+    assert_eq!(printed_lines(&output), vec![3, 10, 3, 3, 10]);
+}
+
+/// The breakpoint finish sets for itself is neither listed nor left behind.
+#[test]
+fn test_finish_leaves_no_breakpoint() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break square", "run", "finish", "info breakpoints", "quit"]);
+    assert_eq!(output.matches("\tkeep\t").count(), 1);
+    let output = run_deet(&target, &["tbreak square", "run", "finish", "info breakpoints", "quit"]);
+    assert!(output.contains("No breakpoints."));
+}
+
+#[test]
+fn test_finish_in_main() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break main", "run", "finish", "quit"]);
+    assert!(output.contains("\"finish\" not meaningful in the outermost frame."));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::{build_sample, run_deet, run_deet_output};
+use std::path::Path;
+
+/// Returns the pids deet reported killing, in order.
+fn killed_pids(output: &str) -> Vec<String> {
+    output
+        .split("Killing running inferior (pid ")
+        .skip(1)
+        .filter_map(|rest| rest.split(')').next())
+        .map(|pid| pid.to_string())
+        .collect()
+}
+
+/// `kill` ends a program stopped at a breakpoint in the middle of an hour of sleeping, and a
+/// following `run` starts it over.
+#[test]
+fn test_kill_at_breakpoint() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet_output(&target, &[], &["break 13", "run 3600", "kill", "run 3600", "kill", "quit"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("Breakpoint 0, Stopped at main").count(), 2, "{}", stdout);
+    let pids = killed_pids(&stdout);
+    assert_eq!(pids.len(), 2, "{}", stdout);
+    assert_ne!(pids[0], pids[1]);
+    for pid in &pids {
+        assert!(!Path::new("/proc").join(pid).exists(), "pid {} is still around", pid);
+    }
+    assert!(output.status.success());
+}
+
+/// `kill` also ends a program left running past the wait timeout.
+#[test]
+fn test_kill_running() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(
+        &target,
+        &["set wait-timeout 1", "run 3600", "kill", "set wait-timeout 0", "run 2", "quit"],
+    );
+    assert!(output.contains("Child is still running after 1.000s"));
+    assert_eq!(killed_pids(&output).len(), 1, "{}", output);
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Without a program to kill, `kill` says so.
+#[test]
+fn test_kill_without_inferior() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(&target, &["kill", "quit"]);
+    assert!(output.contains("No running subprocess"));
+    assert!(!output.contains("Killing running inferior"));
+}
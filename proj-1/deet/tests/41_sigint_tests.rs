@@ -0,0 +1,70 @@
+mod common;
+
+use common::build_sample;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Returns true once process `pid` has a SIGINT handler installed, so SIGINT won't kill it.
+fn catches_sigint(pid: &str) -> bool {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).unwrap_or_default();
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("SigCgt:"))
+        .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+        .is_some_and(|mask| mask & (1 << (libc::SIGINT - 1)) != 0)
+}
+
+/// SIGINT sent to deet while it waits on the program (as ctrl+c would without a terminal to hand
+/// the program) is passed on to the program, which stops with it.
+#[test]
+fn test_sigint_stops_inferior() {
+    let target = build_sample("sleepy_print");
+    let mut deet = Command::new(env!("CARGO_BIN_EXE_deet"))
+        .arg(&target)
+        .env("HOME", env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start deet");
+    let mut stdin = deet.stdin.take().unwrap();
+    let mut stdout = BufReader::new(deet.stdout.take().unwrap());
+    stdin.write_all(b"run 3600\n").unwrap();
+
+    // A SIGINT that lands before deet is waiting on the program is ignored, so keep sending them
+    // until one takes. One that lands before deet installs its handler would kill it, though.
+    let stopped = Arc::new(AtomicBool::new(false));
+    let interrupter = {
+        let stopped = stopped.clone();
+        let pid = deet.id().to_string();
+        thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                if catches_sigint(&pid) {
+                    Command::new("kill").args(["-INT", &pid]).status().unwrap();
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+        })
+    };
+    let mut output = String::new();
+    while !output.contains("Child stopped (signal SIGINT)") {
+        assert!(stdout.read_line(&mut output).unwrap() > 0, "deet exited early: {}", output);
+    }
+    stopped.store(true, Ordering::SeqCst);
+    interrupter.join().unwrap();
+
+    stdin.write_all(b"kill\nquit\n").unwrap();
+    drop(stdin);
+    let mut rest = String::new();
+    while stdout.read_line(&mut rest).unwrap() > 0 {}
+    let status = deet.wait().unwrap();
+
+    assert!(rest.contains("Killing running inferior"), "{}", rest);
+    assert!(status.success());
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Returns the lines `info registers` printed for register `name`.
+fn register_lines<'a>(output: &'a str, name: &str) -> Vec<&'a str> {
+    output.lines().filter(|line| line.split_whitespace().next() == Some(name)).collect()
+}
+
+/// With no arguments, every register is listed, along with what it means where that's known.
+#[test]
+fn test_info_registers() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break square", "run", "continue", "info registers", "quit"]);
+    for name in ["rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "r8", "r15", "rip", "eflags"] {
+        assert_eq!(register_lines(&output, name).len(), 1, "{}: {}", name, output);
+    }
+    // square's argument, 1 on the second call
+    assert_eq!(register_lines(&output, "rdi"), vec!["rdi     0x0000000000000001  1"]);
+    assert!(register_lines(&output, "rip")[0].contains("<square+"));
+    assert!(register_lines(&output, "eflags")[0].ends_with(" ]"));
+}
+
+/// Named registers ($ optional) are the only ones listed, and an outer frame shows its own %rip.
+#[test]
+fn test_info_registers_by_name() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["break square", "run", "info registers $rdi rsp", "up", "info registers rip", "quit"],
+    );
+    assert_eq!(register_lines(&output, "rdi"), vec!["rdi     0x0000000000000000  0"]);
+    assert_eq!(register_lines(&output, "rsp").len(), 1);
+    assert!(register_lines(&output, "rax").is_empty());
+    assert!(register_lines(&output, "rip")[0].contains("<main+"));
+}
+
+#[test]
+fn test_info_registers_invalid() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["info registers", "break main", "run", "info registers foo", "quit"]);
+    assert!(output.contains("No running subprocess"));
+    assert!(output.contains("Invalid register `foo'"));
+}
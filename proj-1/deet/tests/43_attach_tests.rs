@@ -0,0 +1,53 @@
+mod common;
+
+use common::{build_sample, run_deet, run_deet_with_args, spawn_sleeper};
+use std::path::PathBuf;
+
+/// Breakpoints work in a process deet attached to, which is left running when deet quits.
+#[test]
+fn test_attach() {
+    let target = build_sample("sleepy_print");
+    let mut sleeper = spawn_sleeper(&target, 3600);
+    let pid = sleeper.id().to_string();
+    let output = run_deet(&target, &[&format!("attach {}", pid), "break 13", "continue", "continue", "quit"]);
+    let still_running = sleeper.try_wait().unwrap().is_none();
+    sleeper.kill().unwrap();
+    sleeper.wait().unwrap();
+
+    assert!(output.contains(&format!("Attached to process {}", pid)), "{}", output);
+    assert_eq!(output.matches("Breakpoint 0, Stopped at main").count(), 2);
+    assert!(output.contains(&format!("Detached from process {}", pid)));
+    assert!(still_running);
+}
+
+/// --pid attaches on startup, reading symbols from the process's executable if no target is given.
+#[test]
+fn test_attach_on_startup() {
+    let target = build_sample("sleepy_print");
+    let mut sleeper = spawn_sleeper(&target, 3600);
+    let pid = sleeper.id().to_string();
+    // --pid comes before the target, so it takes the target's place in run_deet's arguments
+    let flag = PathBuf::from("--pid");
+    let commands = ["break 13", "continue", "quit"];
+    let outputs = [
+        run_deet_with_args(&flag, &[&pid, target.to_str().unwrap()], &commands),
+        run_deet_with_args(&flag, &[&pid], &commands),
+    ];
+    sleeper.kill().unwrap();
+    sleeper.wait().unwrap();
+
+    for output in &outputs {
+        assert!(output.contains(&format!("Attached to process {}", pid)), "{}", output);
+        assert!(output.contains("Breakpoint 0, Stopped at main"), "{}", output);
+    }
+}
+
+#[test]
+fn test_attach_invalid() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(&target, &["attach", "attach foo", "attach 0", "quit"]);
+    assert!(output.contains("Argument required (process-id to attach)."));
+    assert!(output.contains("Invalid process id foo"));
+    assert!(output.contains("Invalid process id 0"));
+    assert!(!output.contains("Attached to process"));
+}
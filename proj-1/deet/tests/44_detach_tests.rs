@@ -0,0 +1,39 @@
+mod common;
+
+use common::{build_sample, run_deet, spawn_sleeper};
+
+/// A process detached from runs on without its breakpoints, to exit normally.
+#[test]
+fn test_detach_attached() {
+    let target = build_sample("sleepy_print");
+    let mut sleeper = spawn_sleeper(&target, 2);
+    let pid = sleeper.id().to_string();
+    let output = run_deet(&target, &[&format!("attach {}", pid), "break 13", "continue", "detach", "quit"]);
+    let status = sleeper.wait().unwrap();
+
+    assert!(output.contains("Breakpoint 0, Stopped at main"), "{}", output);
+    assert!(output.contains(&format!("Detached from process {}", pid)));
+    assert!(!output.contains("Warning"));
+    // With a breakpoint left behind, it would have died of SIGTRAP
+    assert!(status.success(), "{:?}", status);
+}
+
+/// A program deet started can be detached from too, with a warning that it will keep running.
+#[test]
+fn test_detach_spawned() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(&target, &["break 13", "run 2", "detach", "quit"]);
+    assert!(output.contains("Warning: process "));
+    assert!(output.contains("was started by deet and will keep running"));
+    assert!(output.contains("Detached from process "));
+    // The program shares deet's stdout, which it prints its last number to as it finishes
+    assert!(output.lines().any(|line| line == "1"), "{}", output);
+}
+
+#[test]
+fn test_detach_without_inferior() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(&target, &["detach", "quit"]);
+    assert!(output.contains("No running subprocess"));
+    assert!(!output.contains("Detached"));
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::{build_sample, printed_lines, run_deet};
+
+/// Locations can name the source file, by its name, a path ending in it, or its absolute path.
+#[test]
+fn test_file_line_breakpoints() {
+    let target = build_sample("next_loop");
+    let source = target.with_extension("c");
+    let output = run_deet(
+        &target,
+        &[
+            "break next_loop.c:10",
+            "break samples/next_loop.c:square",
+            &format!("break {}:12", source.display()),
+            "run",
+            "continue",
+            "delete 0",
+            "delete 1",
+            "continue",
+            "quit",
+        ],
+    );
+    assert!(output.contains("Breakpoint 0, Stopped at main"), "{}", output);
+    assert!(output.contains("Breakpoint 1, Stopped at square"));
+    assert!(output.contains("Breakpoint 2, Stopped at main"));
+    assert!(output.contains("next_loop.c:12)"));
+    assert_eq!(printed_lines(&output), vec![10, 3, 12]);
+}
+
+/// A line without code (like the blank one between square and main) breaks at the next one that
+/// has some.
+#[test]
+fn test_file_line_without_code() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break next_loop.c:6", "run", "quit"]);
+    assert!(output.contains("Breakpoint 0, Stopped at main"), "{}", output);
+    assert!(output.contains("next_loop.c:7)"));
+}
+
+#[test]
+fn test_file_line_errors() {
+    let target = build_sample("next_loop");
+    let output = run_deet(&target, &["break nosuch.c:3", "break next_loop.c:100", "quit"]);
+    assert!(output.contains("No source file matching \"nosuch.c\""));
+    assert!(output.contains("No code at or after line 100 in next_loop.c"));
+}
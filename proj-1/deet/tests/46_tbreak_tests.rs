@@ -0,0 +1,32 @@
+mod common;
+
+use common::{build_sample, printed_lines, run_deet};
+
+/// A temporary breakpoint is listed as such, and deleted the first time it's hit.
+#[test]
+fn test_tbreak() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["tbreak square", "info breakpoints", "run", "info breakpoints", "continue", "quit"],
+    );
+    assert!(output.contains("Set temporary breakpoint 0 at 0x"));
+    assert_eq!(output.matches("0\tdel\t").count(), 1, "{}", output);
+    assert_eq!(output.matches("Breakpoint 0, Stopped at square").count(), 1);
+    assert!(output.contains("Temporary breakpoint 0 hit and removed"));
+    assert!(output.contains("No breakpoints."));
+    // square is called twice more, without stopping
+    assert!(output.contains("Child exited (status 0)"));
+}
+
+/// Ordinary breakpoints alongside a temporary one are kept.
+#[test]
+fn test_tbreak_with_break() {
+    let target = build_sample("next_loop");
+    let output = run_deet(
+        &target,
+        &["tb square", "break 10", "run", "continue", "continue", "continue", "continue", "quit"],
+    );
+    assert_eq!(printed_lines(&output), vec![10, 3, 10, 10], "{}", output);
+    assert!(output.contains("Child exited (status 0)"));
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::{build_sample, run_deet};
+
+/// Arguments given with `set args` or `run` are kept for later runs, until replaced.
+#[test]
+fn test_args_remembered() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(
+        &target,
+        &["show args", "set args 1", "show args", "run", "run", "run 2", "show args", "run", "quit"],
+    );
+    assert!(output.contains("The program being debugged has no arguments."));
+    assert!(output.contains("Arguments for the program being debugged: 1\n"));
+    assert!(output.contains("Arguments for the program being debugged: 2\n"));
+    assert_eq!(output.matches("Child exited (status 0)").count(), 4, "{}", output);
+    // sleepy_print prints 0 on each run, and 1 only when told to sleep for 2 seconds
+    assert_eq!(output.lines().filter(|line| *line == "0").count(), 4);
+    assert_eq!(output.lines().filter(|line| *line == "1").count(), 2);
+}
+
+/// `set args` on its own clears them.
+#[test]
+fn test_args_cleared() {
+    let target = build_sample("sleepy_print");
+    let output = run_deet(&target, &["run 1", "set args", "show args", "run", "quit"]);
+    assert!(output.contains("Child exited (status 0)"));
+    assert!(output.contains("The program being debugged has no arguments."));
+    // Without arguments, sleepy_print prints its usage and fails
+    assert!(output.contains("Child exited (status 1)"));
+}
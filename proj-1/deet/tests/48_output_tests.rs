@@ -0,0 +1,69 @@
+mod common;
+
+use common::{build_sample, run_deet, run_deet_with_args};
+use std::env;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Like run_deet, but with COLUMNS set to `columns`, which is the width deet fits its output to
+/// when stdout isn't a terminal.
+fn run_deet_in_columns(target: &PathBuf, columns: usize, commands: &[&str]) -> String {
+    let mut deet = Command::new(env!("CARGO_BIN_EXE_deet"))
+        .arg(target)
+        .env("HOME", env::temp_dir())
+        .env("COLUMNS", columns.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start deet");
+    let mut input = commands.join("\n");
+    input.push('\n');
+    deet.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = deet.wait_with_output().expect("Failed to wait for deet");
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Returns the frames a backtrace printed, without the marker in front of each.
+fn frames(output: &str) -> Vec<&str> {
+    output.lines().filter_map(|line| line.get(3..)).filter(|rest| rest.starts_with('#')).collect()
+}
+
+/// Nothing is colored when stdout isn't a terminal, with or without --no-color.
+#[test]
+fn test_no_color_when_piped() {
+    let target = build_sample("next_loop");
+    let commands = ["break square", "run", "bt", "quit"];
+    for output in [run_deet(&target, &commands), run_deet_with_args(&target, &["--no-color"], &commands)] {
+        assert!(output.contains("Breakpoint 0, Stopped at square"), "{}", output);
+        assert!(output.contains("#0 square (x=0) ("));
+        assert!(!output.contains('\x1b'), "{:?}", output);
+    }
+}
+
+/// Frames are fit to the width by shortening their paths, keeping at least the file name.
+#[test]
+fn test_frames_fit_width() {
+    let target = build_sample("next_loop");
+    let commands = ["break square", "run", "bt", "quit"];
+    let wide = run_deet_in_columns(&target, 500, &commands);
+    assert_eq!(frames(&wide).len(), 2, "{}", wide);
+    assert!(frames(&wide).iter().all(|frame| frame.contains("/samples/next_loop.c:")));
+    assert!(!wide.contains('…'));
+
+    let narrow = run_deet_in_columns(&target, 45, &commands);
+    let narrow_frames = frames(&narrow);
+    assert_eq!(narrow_frames.len(), 2, "{}", narrow);
+    for frame in &narrow_frames {
+        assert!(frame.contains("(…/"), "{}", frame);
+        // With room for the marker in front
+        assert!(frame.chars().count() + 3 <= 45, "{}", frame);
+    }
+    assert!(narrow_frames[0].ends_with("next_loop.c:3)"));
+    assert!(narrow_frames[1].ends_with("next_loop.c:10)"));
+
+    // Too narrow for any directories, but the file name stays
+    let tiny = run_deet_in_columns(&target, 10, &commands);
+    assert!(frames(&tiny).iter().all(|frame| frame.contains("(…/next_loop.c:")), "{}", tiny);
+}
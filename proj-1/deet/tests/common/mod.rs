@@ -0,0 +1,88 @@
+use std::env;
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+
+/// Compiles samples/<name>.c (if it isn't up to date already) and returns the path to the binary.
+pub fn build_sample(name: &str) -> PathBuf {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let target = format!("samples/{}", name);
+    let status = Command::new("make")
+        .arg(&target)
+        .current_dir(&manifest_dir)
+        .stdout(Stdio::null())
+        .status()
+        .expect("Failed to run make");
+    assert!(status.success(), "Failed to build {}", target);
+    manifest_dir.join(target)
+}
+
+/// Runs deet on `target`, feeding it each of `commands` in turn, and returns everything it
+/// printed to stdout.
+#[allow(dead_code)]
+pub fn run_deet(target: &PathBuf, commands: &[&str]) -> String {
+    run_deet_with_args(target, &[], commands)
+}
+
+/// Like run_deet, but passes `args` to deet after the target.
+#[allow(dead_code)]
+pub fn run_deet_with_args(target: &PathBuf, args: &[&str], commands: &[&str]) -> String {
+    let output = run_deet_output(target, args, commands);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Like run_deet_with_args, but returns deet's exit status along with what it printed.
+#[allow(dead_code)]
+pub fn run_deet_output(target: &PathBuf, args: &[&str], commands: &[&str]) -> Output {
+    // Keep the tests from touching the real command history
+    spawn_deet(target, args, &env::temp_dir(), commands)
+}
+
+/// Like run_deet, but with `home` as the home directory, where deet keeps its command history.
+#[allow(dead_code)]
+pub fn run_deet_in_home(target: &PathBuf, home: &Path, commands: &[&str]) -> String {
+    let output = spawn_deet(target, &[], home, commands);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn spawn_deet(target: &PathBuf, args: &[&str], home: &Path, commands: &[&str]) -> Output {
+    let mut deet = Command::new(env!("CARGO_BIN_EXE_deet"))
+        .arg(target)
+        .args(args)
+        .env("HOME", home)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start deet");
+    let mut input = commands.join("\n");
+    input.push('\n');
+    // deet can quit (from a script, say) before reading them all
+    let _ = deet.stdin.take().unwrap().write_all(input.as_bytes());
+    deet.wait_with_output().expect("Failed to wait for deet")
+}
+
+/// Starts sleepy_print sleeping for `seconds`, letting any process trace it (not just its
+/// ancestors, which is all Yama allows by default).
+#[allow(dead_code)]
+pub fn spawn_sleeper(target: &Path, seconds: u32) -> Child {
+    let mut cmd = Command::new(target);
+    cmd.arg(seconds.to_string()).stdout(Stdio::null());
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::prctl(libc::PR_SET_PTRACER, libc::PR_SET_PTRACER_ANY, 0, 0, 0);
+            Ok(())
+        });
+    }
+    cmd.spawn().expect("Failed to start sleepy_print")
+}
+
+/// Returns the source line numbers deet printed (as "<line>\t<code>"), in order.
+#[allow(dead_code)]
+pub fn printed_lines(output: &str) -> Vec<usize> {
+    output
+        .lines()
+        .filter_map(|line| line.split('\t').next()?.parse().ok())
+        .collect()
+}
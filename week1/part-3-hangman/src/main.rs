@@ -27,6 +27,44 @@ fn pick_a_random_word() -> String {
     String::from(words[rand::thread_rng().gen_range(0, words.len())].trim())
 }
 
+/// What a guess comes to
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// The letter is in the word, at these positions
+    Correct(Vec<usize>),
+    /// The letter isn't in the word
+    Incorrect(char),
+    /// The letter was guessed before; this doesn't cost a guess
+    Repeated(char),
+    /// The input wasn't a single letter; this doesn't cost a guess either
+    Invalid,
+}
+
+/// Works out what `guess`, a line the user typed, comes to against the secret word. `board` is
+/// the word so far, with '-' where a letter hasn't been found yet, and `guessed` is every letter
+/// guessed already. A correct guess reveals every position the letter appears at.
+fn check_guess(chars: &[char], board: &[char], guessed: &[char], guess: &str) -> Outcome {
+    let guess_vec: Vec<char> = guess.trim().chars().collect();
+    if guess_vec.len() != 1 || !guess_vec[0].is_alphabetic() {
+        return Outcome::Invalid;
+    }
+    let letter = guess_vec[0].to_lowercase().next().unwrap();
+    if guessed.contains(&letter) || board.contains(&letter) {
+        return Outcome::Repeated(letter);
+    }
+
+    let positions: Vec<usize> = chars.iter()
+        .enumerate()
+        .filter(|&(_, c)| *c == letter)
+        .map(|(i, _)| i)
+        .collect();
+    if positions.is_empty() {
+        Outcome::Incorrect(letter)
+    } else {
+        Outcome::Correct(positions)
+    }
+}
+
 fn run(chars: &Vec<char>) {
     let mut guesses = NUM_INCORRECT_GUESSES;
     let mut guess_chars = vec!['-' ; chars.len()];
@@ -69,30 +107,24 @@ fn run(chars: &Vec<char>) {
             .read_line(&mut guess)
             .expect("Error reading line.");
 
-        let guess_vec: Vec<char> = guess.chars().collect();
-        guessed_chars.push(guess_vec[0]);
-
-        let res: Result<(), &str> = {
-            let mut found = false;
-            for (i, c) in chars.iter().enumerate() {
-                if guess_vec[0] == *c && guess_chars[i] == '-' {
-                    guess_chars[i] = *c;
-                    found = true;
-                    break;
+        match check_guess(chars, &guess_chars, &guessed_chars, &guess) {
+            Outcome::Correct(positions) => {
+                guessed_chars.push(chars[positions[0]]);
+                for &i in &positions {
+                    guess_chars[i] = chars[i];
                 }
-            }
-            match found {
-                true => Ok(()),
-                false => Err("Sorry, that letter is not in the word")
-            }
-        };
-        match res {
-            Ok(_) => {
-                correct_char_num += 1;
+                correct_char_num += positions.len();
             },
-            Err(s) => {
-                println!("{}", s);
+            Outcome::Incorrect(c) => {
+                guessed_chars.push(c);
+                println!("Sorry, that letter is not in the word");
                 guesses -= 1;
+            },
+            Outcome::Repeated(c) => {
+                println!("You already guessed '{}'; try another letter", c);
+            },
+            Outcome::Invalid => {
+                println!("Please enter a single letter");
             }
         }
         println!();
@@ -114,3 +146,49 @@ fn main() {
     run(&secret_word_chars);
 
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn chars(word: &str) -> Vec<char> {
+        word.chars().collect()
+    }
+
+    #[test]
+    fn test_reveals_every_occurrence() {
+        let secret = chars("letter");
+        let board = chars("------");
+        assert_eq!(check_guess(&secret, &board, &[], "t\n"), Outcome::Correct(vec![2, 3]));
+        assert_eq!(check_guess(&secret, &board, &[], "e\n"), Outcome::Correct(vec![1, 4]));
+        assert_eq!(check_guess(&secret, &board, &[], "l\n"), Outcome::Correct(vec![0]));
+        assert_eq!(check_guess(&secret, &board, &[], "T\n"), Outcome::Correct(vec![2, 3]));
+    }
+
+    #[test]
+    fn test_incorrect_guess() {
+        assert_eq!(check_guess(&chars("letter"), &chars("------"), &[], "z\n"), Outcome::Incorrect('z'));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let secret = chars("letter");
+        let board = chars("------");
+        assert_eq!(check_guess(&secret, &board, &[], "\n"), Outcome::Invalid);
+        assert_eq!(check_guess(&secret, &board, &[], ""), Outcome::Invalid);
+        assert_eq!(check_guess(&secret, &board, &[], "te\n"), Outcome::Invalid);
+        assert_eq!(check_guess(&secret, &board, &[], "7\n"), Outcome::Invalid);
+        assert_eq!(check_guess(&secret, &board, &[], "?\n"), Outcome::Invalid);
+    }
+
+    #[test]
+    fn test_repeated_guess() {
+        let secret = chars("letter");
+        // 't' has been revealed, and 'z' was a miss
+        let board = chars("--tt--");
+        let guessed = ['t', 'z'];
+        assert_eq!(check_guess(&secret, &board, &guessed, "t\n"), Outcome::Repeated('t'));
+        assert_eq!(check_guess(&secret, &board, &guessed, "z\n"), Outcome::Repeated('z'));
+        assert_eq!(check_guess(&secret, &board, &guessed, "e\n"), Outcome::Correct(vec![1, 4]));
+    }
+}
@@ -1,43 +1,764 @@
 use std::env;
+use std::ops::AddAssign;
 use std::process;
-use std::io::{ self, BufRead };
-use std::fs::File;
+use std::io::{ self, Read, Write };
+use std::fs::{ self, File };
+use std::path::{ Path, PathBuf };
+use std::collections::{ HashMap, HashSet };
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::sync::mpsc;
+use std::thread;
 
-fn read_file_lines(filename: &String) -> Result<Vec<String>, io::Error> {
-    
-    let file = File::open(filename)?;
-    let mut contents = Vec::new();
+const USAGE: &str = "Usage: rwc [-lwmcLr] [--follow-symlinks] [--skip-hidden] [--threads N] [--total=WHEN] FILE...\n   or: rwc --top-words N [--per-file] FILE...\n   or: rwc [-lwmcL] --files0-from=F";
 
-    for line in io::BufReader::new(file).lines() {
-        let line_str = line?;
-        contents.push(line_str);
+/// How much of a file is read at a time
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// The numbers rwc reports for a file
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Counts {
+    lines: usize,
+    words: usize,
+    chars: usize,
+    bytes: usize,
+    /// How wide the longest line is, with tabs taken to the next multiple of 8 columns, and
+    /// every other character one column wide (GNU wc makes wide characters like emoji 2)
+    max_line_length: usize,
+}
+
+impl AddAssign for Counts {
+    /// Adds up the counts, except for the longest line, where the longer of the two is kept
+    fn add_assign(&mut self, other: Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
     }
+}
 
-    Ok(contents)
+/// Which counts to print, set by the -l, -w, -m and -c flags
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Selection {
+    lines: bool,
+    words: bool,
+    chars: bool,
+    bytes: bool,
+    max_line_length: bool,
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        println!("Too few arguments.");
-        process::exit(1);
+impl Default for Selection {
+    /// Lines, words and bytes, as wc prints with no flags
+    fn default() -> Selection {
+        Selection { lines: true, words: true, chars: false, bytes: true, max_line_length: false }
+    }
+}
+
+/// What the command line asks rwc to do
+#[derive(Debug, PartialEq)]
+struct Config {
+    selection: Selection,
+    filenames: Vec<String>,
+    /// The file to read NUL-separated file names from instead, given by --files0-from, where
+    /// "-" means standard input
+    files0_from: Option<String>,
+    walk: Walk,
+    /// How many files to count at once, given by --threads; by default, one per core
+    threads: Option<usize>,
+    /// Print this many of the most frequent words instead of the counts, given by --top-words
+    top_words: Option<usize>,
+    /// Report the most frequent words of each file on its own, rather than across all of them
+    per_file: bool,
+    /// When to print the total row
+    total: Total,
+}
+
+/// How many times each word turns up, keyed by the word as normalize_word leaves it
+type Frequencies = HashMap<String, u64>;
+
+/// How directories given on the command line are handled
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Walk {
+    /// Count every regular file under a directory, given by -r or --recursive
+    recursive: bool,
+    /// Descend into symlinked directories too, which can otherwise lead round in a cycle
+    follow_symlinks: bool,
+    /// Leave out files and directories whose names start with a dot
+    skip_hidden: bool,
+}
+
+/// Splits the arguments (without the program name) into the selected counts and the file names.
+/// Flags can come in any order, before or after the file names, and can be combined, as in
+/// `-lw`. Returns an error message for a flag rwc doesn't know, or for file names given along
+/// with --files0-from.
+fn parse_args(args: &[String]) -> Result<Config, String> {
+    let mut selection = Selection { lines: false, words: false, chars: false, bytes: false, max_line_length: false };
+    let mut filenames = Vec::new();
+    let mut files0_from = None;
+    let mut walk = Walk::default();
+    let mut threads = None;
+    let mut top_words = None;
+    let mut per_file = false;
+    let mut total = Total::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(option) = arg.strip_prefix("--") {
+            match option.split_once('=') {
+                Some(("files0-from", source)) => files0_from = Some(source.to_string()),
+                None if option == "files0-from" => match args.next() {
+                    Some(source) => files0_from = Some(source.clone()),
+                    None => return Err("rwc: option '--files0-from' requires an argument".to_string()),
+                },
+                Some(("threads", number)) => threads = Some(parse_threads(number)?),
+                None if option == "threads" => match args.next() {
+                    Some(number) => threads = Some(parse_threads(number)?),
+                    None => return Err("rwc: option '--threads' requires an argument".to_string()),
+                },
+                Some(("top-words", number)) => top_words = Some(parse_top_words(number)?),
+                None if option == "top-words" => match args.next() {
+                    Some(number) => top_words = Some(parse_top_words(number)?),
+                    None => return Err("rwc: option '--top-words' requires an argument".to_string()),
+                },
+                None if option == "per-file" => per_file = true,
+                Some(("total", when)) => {
+                    total = match when {
+                        "auto" => Total::Auto,
+                        "always" => Total::Always,
+                        "never" => Total::Never,
+                        "only" => Total::Only,
+                        _ => return Err(format!("rwc: invalid argument '{}' for '--total'", when)),
+                    }
+                }
+                None if option == "recursive" => walk.recursive = true,
+                None if option == "follow-symlinks" => walk.follow_symlinks = true,
+                None if option == "skip-hidden" => walk.skip_hidden = true,
+                _ => return Err(format!("rwc: unrecognized option '{}'", arg)),
+            }
+            continue;
+        }
+        match arg.strip_prefix('-') {
+            Some(flags) if !flags.is_empty() => {
+                for flag in flags.chars() {
+                    match flag {
+                        'l' => selection.lines = true,
+                        'w' => selection.words = true,
+                        'm' => selection.chars = true,
+                        'c' => selection.bytes = true,
+                        'L' => selection.max_line_length = true,
+                        'r' => walk.recursive = true,
+                        _ => return Err(format!("rwc: invalid option -- '{}'", flag)),
+                    }
+                }
+            }
+            _ => filenames.push(arg.clone()),
+        }
+    }
+    if !(selection.lines || selection.words || selection.chars || selection.bytes || selection.max_line_length) {
+        selection = Selection::default();
+    }
+    if files0_from.is_some() && !filenames.is_empty() {
+        return Err(format!("rwc: extra operand '{}'\nfile operands cannot be combined with --files0-from", filenames[0]));
+    }
+    if per_file && top_words.is_none() {
+        return Err("rwc: --per-file only applies along with --top-words".to_string());
+    }
+    Ok(Config { selection, filenames, files0_from, walk, threads, top_words, per_file, total })
+}
+
+fn parse_top_words(number: &str) -> Result<usize, String> {
+    number.parse().map_err(|_| format!("rwc: invalid number of words: '{}'", number))
+}
+
+fn parse_threads(number: &str) -> Result<usize, String> {
+    match number.parse() {
+        Ok(threads) if threads > 0 => Ok(threads),
+        _ => Err(format!("rwc: invalid number of threads: '{}'", number)),
+    }
+}
+
+/// Lists the regular files under `root`, walking it with a stack rather than recursing, in
+/// name order so the rows come out the same every time. Each name starts with `root`, so it
+/// reads relative to what was given on the command line. A subdirectory that can't be read is
+/// reported to `err` and skipped; the second value is false if that happened.
+fn walk_dir<E: Write>(root: &str, walk: &Walk, err: &mut E) -> (Vec<String>, bool) {
+    let mut filenames = Vec::new();
+    let mut complete = true;
+    // Directories already walked, so following symlinks can't go round in a cycle
+    let mut visited = HashSet::new();
+    let mut stack = vec![PathBuf::from(root)];
+    while let Some(dir) = stack.pop() {
+        if let Ok(real) = fs::canonicalize(&dir) {
+            if !visited.insert(real) {
+                continue;
+            }
+        }
+        let mut entries = match fs::read_dir(&dir).and_then(|entries| entries.collect::<Result<Vec<_>, _>>()) {
+            Ok(entries) => entries,
+            Err(error) => {
+                let _ = writeln!(err, "rwc: {}: {}", dir.display(), describe_error(&error));
+                complete = false;
+                continue;
+            }
+        };
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut subdirs = Vec::new();
+        for entry in entries {
+            let path = entry.path();
+            if walk.skip_hidden && entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // The entry's own type doesn't follow symlinks, so look through them separately
+            let is_symlink = entry.file_type().is_ok_and(|file_type| file_type.is_symlink());
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(error) => {
+                    let _ = writeln!(err, "rwc: {}: {}", path.display(), describe_error(&error));
+                    complete = false;
+                    continue;
+                }
+            };
+            if metadata.is_dir() {
+                if !is_symlink || walk.follow_symlinks {
+                    subdirs.push(path);
+                }
+            } else if metadata.is_file() {
+                match path.to_str() {
+                    Some(name) => filenames.push(name.to_string()),
+                    None => {
+                        let _ = writeln!(err, "rwc: {}: file name isn't valid UTF-8; skipping it", path.display());
+                    }
+                }
+            }
+        }
+        // Pushed in reverse so the first subdirectory is walked next
+        stack.extend(subdirs.into_iter().rev());
+    }
+    (filenames, complete)
+}
+
+/// Replaces each directory in `filenames` with the files under it, leaving other names alone
+fn expand_dirs<E: Write>(filenames: Vec<String>, walk: &Walk, err: &mut E) -> (Vec<String>, bool) {
+    let mut expanded = Vec::new();
+    let mut complete = true;
+    for filename in filenames {
+        if filename != "-" && Path::new(&filename).is_dir() {
+            let (found, walked) = walk_dir(&filename, walk, err);
+            expanded.extend(found);
+            complete &= walked;
+        } else {
+            expanded.push(filename);
+        }
+    }
+    (expanded, complete)
+}
+
+/// Reads the file names from a list separated by NUL bytes, as `find -print0` writes, so a
+/// name can hold any other character, newlines included. Empty names are skipped with a
+/// warning to `err`, and so are names that aren't valid UTF-8, since rwc keeps file names as
+/// Strings.
+fn read_files0<R: Read, E: Write>(mut reader: R, err: &mut E) -> Result<Vec<String>, io::Error> {
+    let mut list = Vec::new();
+    reader.read_to_end(&mut list)?;
+    // A NUL after the last name doesn't start another one
+    if list.last() == Some(&0) {
+        list.pop();
+    }
+    if list.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut filenames = Vec::new();
+    for (number, name) in list.split(|&byte| byte == 0).enumerate() {
+        match String::from_utf8(name.to_vec()) {
+            Ok(name) if name.is_empty() => {
+                writeln!(err, "rwc: --files0-from: file name {} is empty; skipping it", number + 1)?;
+            }
+            Ok(name) => filenames.push(name),
+            Err(_) => writeln!(err, "rwc: --files0-from: file name {} isn't valid UTF-8; skipping it", number + 1)?,
+        }
+    }
+    Ok(filenames)
+}
+
+/// Counts the named file, or standard input for "-". Like count_reader, also returns whether
+/// it was all valid UTF-8, for the caller to warn about.
+fn count_file(filename: &String) -> Result<(Counts, bool), io::Error> {
+    match filename.as_str() {
+        "-" => count_reader(io::stdin()),
+        _ => count_reader(File::open(filename)?),
+    }
+}
+
+/// Tallies the words in the named file, or standard input for "-"
+fn tally_file(filename: &String) -> Result<Frequencies, io::Error> {
+    let counter = match filename.as_str() {
+        "-" => read_into(io::stdin(), Counter::tallying())?,
+        _ => read_into(File::open(filename)?, Counter::tallying())?,
+    };
+    Ok(counter.into_frequencies())
+}
+
+/// Runs `count` over the files on `threads` threads at once, each taking the next file nobody
+/// has started on yet. The results come back in the same order as `filenames`, whichever
+/// finishes first.
+fn count_files<T, F>(filenames: &[String], threads: usize, count: F) -> Vec<Result<T, io::Error>>
+where
+    T: Send,
+    F: Fn(&String) -> Result<T, io::Error> + Sync,
+{
+    let count = &count;
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..threads.min(filenames.len()) {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                match filenames.get(index) {
+                    Some(filename) => sender.send((index, count(filename))).expect("receiver outlives the workers"),
+                    None => break,
+                }
+            });
+        }
+    });
+    drop(sender);
+
+    let mut results: Vec<Option<Result<T, io::Error>>> = filenames.iter().map(|_| None).collect();
+    for (index, result) in receiver {
+        results[index] = Some(result);
+    }
+    results.into_iter().map(|result| result.expect("every file was counted")).collect()
+}
+
+/// Counts what `reader` reads, a buffer at a time, so only one buffer's worth is held in memory
+/// however much there is. Returns whether it was all valid UTF-8.
+fn count_reader<R: Read>(reader: R) -> Result<(Counts, bool), io::Error> {
+    Ok(read_into(reader, Counter::default())?.finish())
+}
+
+/// Feeds everything `reader` reads to `counter`, a buffer at a time.
+fn read_into<R: Read>(mut reader: R, mut counter: Counter) -> Result<Counter, io::Error> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => counter.update(&buffer[..len]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(counter)
+}
+
+/// Keeps counts across the chunks of a stream, with the same rules as count_str. A word or a
+/// UTF-8 sequence can be split between chunks, so it remembers whether the last chunk ended
+/// in a word, and holds on to the bytes of a character that isn't finished yet.
+#[derive(Default)]
+struct Counter {
+    counts: Counts,
+    in_word: bool,
+    /// How wide the line so far is
+    line_width: usize,
+    /// The start of a character the last chunk ended in the middle of
+    partial: Vec<u8>,
+    invalid_utf8: bool,
+    /// How often each word turns up, if they're being tallied
+    frequencies: Option<Frequencies>,
+    /// The word so far, when they're being tallied
+    word: String,
+}
+
+impl Counter {
+    /// A counter that also tallies how often each word turns up
+    fn tallying() -> Counter {
+        Counter { frequencies: Some(Frequencies::new()), ..Counter::default() }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        self.counts.bytes += chunk.len();
+        self.counts.lines += chunk.iter().filter(|&&byte| byte == b'\n').count();
+
+        let joined;
+        let mut rest = chunk;
+        if !self.partial.is_empty() {
+            self.partial.extend_from_slice(chunk);
+            joined = std::mem::take(&mut self.partial);
+            rest = &joined;
+        }
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    self.count_text(text);
+                    return;
+                }
+                Err(err) => {
+                    let (valid, invalid) = rest.split_at(err.valid_up_to());
+                    self.count_text(std::str::from_utf8(valid).unwrap());
+                    match err.error_len() {
+                        // The chunk ends part-way through a character
+                        None => {
+                            self.partial = invalid.to_vec();
+                            return;
+                        }
+                        Some(len) => {
+                            self.count_invalid(len);
+                            rest = &invalid[len..];
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn count_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.counts.chars += 1;
+            if c.is_whitespace() {
+                self.in_word = false;
+                self.end_word();
+            } else {
+                if !self.in_word {
+                    self.in_word = true;
+                    self.counts.words += 1;
+                }
+                if self.frequencies.is_some() {
+                    self.word.push(c);
+                }
+            }
+            match c {
+                // GNU wc starts a new line for a carriage return or form feed too
+                '\n' | '\r' | '\x0c' => self.end_line(),
+                '\t' => self.line_width = next_tab_stop(self.line_width),
+                _ => self.line_width += 1,
+            }
+        }
     }
-    let filename = &args[1];                                                                                               
-    // Your code here :)
-    let contents: Vec<String> = read_file_lines(filename).unwrap_or_else(|_| panic!("Invalid filename: {}", filename));
-    
-    let mut words: usize = 0;
-    let mut lines: usize = 0;
-    let mut characters: usize = 0;
-    
-    for line in contents.iter() {
-        characters += line.len() + 1;
-        let word_vec = line.split(" ").collect::<Vec<&str>>();
-        words += word_vec.len();
-        lines += 1;
+
+    /// Tallies the word that just ended, if words are being tallied.
+    fn end_word(&mut self) {
+        if let Some(frequencies) = &mut self.frequencies {
+            if let Some(word) = normalize_word(&self.word) {
+                *frequencies.entry(word).or_insert(0) += 1;
+            }
+            self.word.clear();
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.counts.max_line_length = self.counts.max_line_length.max(self.line_width);
+        self.line_width = 0;
+    }
+
+    /// Counts each of `len` bytes that aren't valid UTF-8 as a character, part of a word.
+    fn count_invalid(&mut self, len: usize) {
+        self.invalid_utf8 = true;
+        self.counts.chars += len;
+        self.line_width += len;
+        if !self.in_word {
+            self.in_word = true;
+            self.counts.words += 1;
+        }
+        if self.frequencies.is_some() {
+            self.word.extend(std::iter::repeat_n(char::REPLACEMENT_CHARACTER, len));
+        }
+    }
+
+    /// Returns the counts, and whether everything was valid UTF-8.
+    fn finish(mut self) -> (Counts, bool) {
+        // A character the stream ended part-way through
+        if !self.partial.is_empty() {
+            self.count_invalid(self.partial.len());
+        }
+        self.end_line();
+        (self.counts, !self.invalid_utf8)
+    }
+
+    /// Returns how often each word turned up, counting the one the stream ended in.
+    fn into_frequencies(mut self) -> Frequencies {
+        if !self.partial.is_empty() {
+            self.count_invalid(self.partial.len());
+        }
+        self.end_word();
+        self.frequencies.unwrap_or_default()
+    }
+}
+
+/// Turns a word, as split on whitespace, into the form its frequency is tallied under. Letters
+/// are lowercased, and punctuation is stripped from both ends: everything that isn't a letter
+/// or a digit, quotes and apostrophes included, so "'Tis" and "dogs'" become "tis" and "dogs".
+/// Punctuation inside a word stays, keeping "don't" and "well-known" whole. A word that was
+/// nothing but punctuation, like "--", isn't a word at all.
+fn normalize_word(word: &str) -> Option<String> {
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+    if word.is_empty() {
+        None
+    } else {
+        Some(word.to_lowercase())
     }
+}
 
-    println!("{} {} {}", words, lines, characters);
+/// The `n` most frequent words, most frequent first, with words that turn up equally often in
+/// alphabetical order.
+fn top_words(frequencies: &Frequencies, n: usize) -> Vec<(&str, u64)> {
+    let mut words: Vec<(&str, u64)> = frequencies.iter().map(|(word, &count)| (word.as_str(), count)).collect();
+    words.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    words.truncate(n);
+    words
+}
+
+/// Formats each word after its count, with the counts right-aligned.
+fn format_top_words(words: &[(&str, u64)]) -> Vec<String> {
+    let width = words.iter().map(|(_, count)| count.to_string().len()).max().unwrap_or(1);
+    words.iter().map(|(word, count)| format!("{:>width$} {}", count, word, width = width)).collect()
+}
+
+/// Counts `text` the way wc does: lines are newlines, so a last line without one isn't
+/// counted; words are runs of anything but whitespace, so spaces, tabs and blank lines
+/// between them don't matter; characters are Unicode characters. Counter streams the same
+/// rules, and the tests check it against this.
+#[cfg(test)]
+fn count_str(text: &str) -> Counts {
+    Counts {
+        lines: text.matches('\n').count(),
+        words: text.split_whitespace().count(),
+        chars: text.chars().count(),
+        bytes: text.len(),
+        max_line_length: text
+            .split(&['\n', '\r', '\x0c'][..])
+            .map(|line| line.chars().fold(0, |width, c| if c == '\t' { next_tab_stop(width) } else { width + 1 }))
+            .max()
+            .unwrap_or(0),
+    }
+}
+
+/// Returns the column a tab at `column` moves to.
+fn next_tab_stop(column: usize) -> usize {
+    (column / 8 + 1) * 8
+}
+
+/// The selected counts, in wc's order: lines, words, characters, bytes, longest line
+fn selected_counts(counts: &Counts, selection: &Selection) -> Vec<usize> {
+    let columns = [
+        (selection.lines, counts.lines),
+        (selection.words, counts.words),
+        (selection.chars, counts.chars),
+        (selection.bytes, counts.bytes),
+        (selection.max_line_length, counts.max_line_length),
+    ];
+    columns.iter().filter(|(selected, _)| *selected).map(|(_, count)| *count).collect()
+}
+
+/// Formats the selected counts, each right-aligned in a column `width` wide, then the name if
+/// there is one.
+fn format_counts(counts: &Counts, selection: &Selection, width: usize, name: Option<&str>) -> String {
+    let columns: Vec<String> = selected_counts(counts, selection)
+        .iter()
+        .map(|count| format!("{:>width$}", count, width = width))
+        .collect();
+    match name {
+        Some(name) => format!("{} {}", columns.join(" "), name),
+        None => columns.join(" "),
+    }
+}
+
+/// When to print the total row, given by --total
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Total {
+    /// Only when more than one file is named
+    #[default]
+    Auto,
+    Always,
+    Never,
+    /// Print the total alone, without the rows for each file, or even its name
+    Only,
+}
+
+/// Lays out the rows of counts for the files and their total, all in lined-up columns.
+struct Printer {
+    selection: Selection,
+    total: Total,
+    /// Whether standard input is one of the files. Like GNU wc, the columns are then at least 7
+    /// wide, since its size can't be known up front.
+    from_stdin: bool,
+}
+
+impl Printer {
+    /// Formats the rows for `files`, the counts for those of the `inputs` named files that could
+    /// be counted, with the total where `self.total` calls for it. The columns are wide enough
+    /// for the largest selected count in any row printed.
+    fn rows(&self, files: &[(&str, Counts)], inputs: usize) -> Vec<String> {
+        let mut total = Counts::default();
+        for (_, counts) in files {
+            total += *counts;
+        }
+        let mut rows: Vec<(Option<&str>, Counts)> = match self.total {
+            Total::Only => Vec::new(),
+            _ => files.iter().map(|(name, counts)| (Some(*name), *counts)).collect(),
+        };
+        match self.total {
+            Total::Auto if inputs > 1 => rows.push((Some("total"), total)),
+            Total::Always => rows.push((Some("total"), total)),
+            Total::Only => rows.push((None, total)),
+            _ => {}
+        }
+
+        let largest = rows
+            .iter()
+            .flat_map(|(_, counts)| selected_counts(counts, &self.selection))
+            .max()
+            .unwrap_or(0);
+        let mut width = largest.to_string().len();
+        if self.from_stdin {
+            width = width.max(7);
+        }
+        rows.iter().map(|(name, counts)| format_counts(counts, &self.selection, width, *name)).collect()
+    }
+}
+
+/// Describes an error opening or reading a file the way other command-line tools do, without
+/// the "(os error 2)" Rust adds.
+fn describe_error(err: &io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::NotFound => "No such file or directory".to_string(),
+        io::ErrorKind::PermissionDenied => "Permission denied".to_string(),
+        io::ErrorKind::IsADirectory => "Is a directory".to_string(),
+        _ => err.to_string(),
+    }
+}
+
+/// Runs rwc on `args` (without the program name), writing the report to `out` and errors to
+/// `err`, and returns the exit status: 0 if everything was counted, 1 if any file couldn't be,
+/// and 2 for a command line rwc doesn't understand.
+fn run<O: Write, E: Write>(args: &[String], out: &mut O, err: &mut E) -> i32 {
+    let config = match parse_args(args) {
+        Ok(config) if config.filenames.is_empty() && config.files0_from.is_none() => {
+            Err("rwc: no files given".to_string())
+        }
+        parsed => parsed,
+    };
+    let Config { selection, filenames, files0_from, walk, threads, top_words: top, per_file, total } = match config {
+        Ok(config) => config,
+        Err(message) => {
+            let _ = writeln!(err, "{}\n{}", message, USAGE);
+            return 2;
+        }
+    };
+    let filenames = match files0_from {
+        None => filenames,
+        Some(source) => {
+            let list = match source.as_str() {
+                "-" => read_files0(io::stdin(), err),
+                _ => File::open(&source).and_then(|file| read_files0(file, err)),
+            };
+            match list {
+                Ok(list) => list,
+                Err(error) => {
+                    let _ = writeln!(err, "rwc: cannot read file names from {}: {}", source, describe_error(&error));
+                    return 1;
+                }
+            }
+        }
+    };
+
+    let (filenames, complete) = if walk.recursive { expand_dirs(filenames, &walk, err) } else { (filenames, true) };
+    let threads = threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()));
+    let printed = match top {
+        Some(n) => print_top_words(&filenames, threads, n, per_file, out, err),
+        None => print_counts(&filenames, threads, &selection, total, out, err),
+    };
+    match printed {
+        Ok(true) if complete => 0,
+        Ok(_) => 1,
+        Err(error) => {
+            let _ = writeln!(err, "rwc: write error: {}", describe_error(&error));
+            1
+        }
+    }
+}
+
+/// Prints the counts for each file, and their total if there's more than one. A file that can't
+/// be counted is reported to `err` and the rest are still counted; returns whether they all were.
+fn print_counts<O: Write, E: Write>(
+    filenames: &[String],
+    threads: usize,
+    selection: &Selection,
+    total: Total,
+    out: &mut O,
+    err: &mut E,
+) -> Result<bool, io::Error> {
+    // The column width depends on every count, so nothing is printed until they're all in
+    let mut rows = Vec::new();
+    let mut all_counted = true;
+    for (filename, result) in filenames.iter().zip(count_files(filenames, threads, count_file)) {
+        match result {
+            Ok((counts, valid_utf8)) => {
+                if !valid_utf8 {
+                    writeln!(err, "rwc: {}: not valid UTF-8; counting each bad byte as a character", filename)?;
+                }
+                rows.push((filename.as_str(), counts));
+            }
+            Err(error) => {
+                writeln!(err, "rwc: {}: {}", filename, describe_error(&error))?;
+                all_counted = false;
+            }
+        }
+    }
+    let printer = Printer { selection: *selection, total, from_stdin: filenames.iter().any(|filename| filename == "-") };
+    for row in printer.rows(&rows, filenames.len()) {
+        writeln!(out, "{}", row)?;
+    }
+    Ok(all_counted)
+}
+
+/// Prints the `n` most frequent words across the files, or in each file with `per_file`. Like
+/// print_counts, returns whether every file could be read.
+fn print_top_words<O: Write, E: Write>(
+    filenames: &[String],
+    threads: usize,
+    n: usize,
+    per_file: bool,
+    out: &mut O,
+    err: &mut E,
+) -> Result<bool, io::Error> {
+    let mut all = Frequencies::new();
+    let mut all_counted = true;
+    for (index, (filename, result)) in filenames.iter().zip(count_files(filenames, threads, tally_file)).enumerate() {
+        match result {
+            Ok(frequencies) if per_file => {
+                if index > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "==> {} <==", filename)?;
+                for row in format_top_words(&top_words(&frequencies, n)) {
+                    writeln!(out, "{}", row)?;
+                }
+            }
+            Ok(frequencies) => {
+                for (word, count) in frequencies {
+                    *all.entry(word).or_insert(0) += count;
+                }
+            }
+            Err(error) => {
+                writeln!(err, "rwc: {}: {}", filename, describe_error(&error))?;
+                all_counted = false;
+            }
+        }
+    }
+    if !per_file {
+        for row in format_top_words(&top_words(&all, n)) {
+            writeln!(out, "{}", row)?;
+        }
+    }
+    Ok(all_counted)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    process::exit(run(&args[1..], &mut io::stdout().lock(), &mut io::stderr()));
 }
 
 
@@ -45,15 +766,582 @@ fn main() {
 mod test {
     use super::*;
 
+    /// Counts `contents` as if it were a file.
+    fn count_bytes(contents: &[u8]) -> (Counts, bool) {
+        count_reader(contents).unwrap()
+    }
+
+    /// Reads from `contents` at most `chunk_size` bytes at a time.
+    struct Chunked<'a> {
+        contents: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let len = self.chunk_size.min(buffer.len()).min(self.contents.len());
+            buffer[..len].copy_from_slice(&self.contents[..len]);
+            self.contents = &self.contents[len..];
+            Ok(len)
+        }
+    }
+
     #[test]
-    fn test_read_file_lines() {
-        let lines_result = read_file_lines(&String::from("handout-a.txt"));
-        assert!(lines_result.is_ok());
-        let lines = lines_result.unwrap();
-        assert_eq!(lines.len(), 8);
+    fn test_chunk_boundaries() {
+        // Every chunk size splits words, and most split the multi-byte characters too
+        let text = "héllo  wörld\t👋🎉 span\nning\r\n words\n\nend";
+        for chunk_size in 1..=text.len() {
+            let chunked = Chunked { contents: text.as_bytes(), chunk_size };
+            assert_eq!(count_reader(chunked).unwrap(), (count_str(text), true), "chunks of {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_truncated_character() {
+        // The last character is cut off after its first two bytes
+        let (counts, valid_utf8) = count_bytes(&"ok 👋".as_bytes()[..5]);
+        assert_eq!(counts, Counts { lines: 0, words: 2, chars: 5, bytes: 5, max_line_length: 5 });
+        assert!(!valid_utf8);
+    }
+
+    /// Reads `pattern` over and over, up to `len` bytes, without holding more than one copy.
+    struct Repeated {
+        pattern: &'static [u8],
+        position: usize,
+        len: usize,
+    }
+
+    impl Read for Repeated {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let mut written = 0;
+            while written < buffer.len() && self.position < self.len {
+                let offset = self.position % self.pattern.len();
+                let len = (self.pattern.len() - offset).min(buffer.len() - written).min(self.len - self.position);
+                buffer[written..written + len].copy_from_slice(&self.pattern[offset..offset + len]);
+                written += len;
+                self.position += len;
+            }
+            Ok(written)
+        }
+    }
+
+    #[test]
+    #[ignore] // Slow in a debug build; run with `cargo test -- --ignored`
+    fn test_large_input() {
+        // About 100 MB, made up as it's read, of a line whose length doesn't divide the buffer
+        // size, so characters and words get split between buffers
+        let pattern = "héllo wörld 👋 again\n";
+        let repeats = 100_000_000 / pattern.len();
+        let reader = Repeated { pattern: pattern.as_bytes(), position: 0, len: repeats * pattern.len() };
+        let one = count_str(pattern);
+        let expected = Counts {
+            lines: one.lines * repeats,
+            words: one.words * repeats,
+            chars: one.chars * repeats,
+            bytes: one.bytes * repeats,
+            max_line_length: one.max_line_length,
+        };
+        assert_eq!(count_reader(reader).unwrap(), (expected, true));
+    }
+
+    #[test]
+    fn test_count_against_wc() {
+        // The expected numbers are what `wc -lwmc` prints in a UTF-8 locale
+        let emoji = "héllo 👋 wörld\nbye 🎉\n".as_bytes();
+        assert_eq!(count_bytes(emoji), (Counts { lines: 2, words: 5, chars: 20, bytes: 28, max_line_length: 13 }, true));
+        let crlf = b"one two\r\nthree\r\n";
+        assert_eq!(count_bytes(crlf), (Counts { lines: 2, words: 3, chars: 16, bytes: 16, max_line_length: 7 }, true));
+        let no_final_newline = b"no final\nnewline here";
+        assert_eq!(count_bytes(no_final_newline), (Counts { lines: 1, words: 4, chars: 21, bytes: 21, max_line_length: 12 }, true));
+        assert_eq!(count_bytes(b""), (Counts::default(), true));
+    }
+
+    #[test]
+    fn test_count_str() {
+        // (text, lines, words)
+        let cases = [
+            ("", 0, 0),
+            ("one", 0, 1),
+            ("a  b", 0, 2),
+            ("  leading and trailing  \n", 1, 3),
+            ("tab\tseparated\tfields\n", 1, 3),
+            ("mixed \t runs\t \tof space\n", 1, 4),
+            ("\n\n\n", 3, 0),
+            ("   \n\t\n", 2, 0),
+            ("first\n\nthird\n", 3, 2),
+            ("crlf\r\nline\r\n", 2, 2),
+        ];
+        for &(text, lines, words) in &cases {
+            let counts = count_str(text);
+            assert_eq!((counts.lines, counts.words), (lines, words), "counting {:?}", text);
+            assert_eq!(counts.bytes, text.len());
+            assert_eq!(counts.chars, text.chars().count());
+        }
+    }
+
+    #[test]
+    fn test_handout_against_system_wc() {
+        // Skipped where there's no wc to compare with
+        let output = match process::Command::new("wc").arg("handout-a.txt").output() {
+            Ok(output) if output.status.success() => output,
+            _ => return,
+        };
+        let expected: Vec<usize> = String::from_utf8(output.stdout)
+            .unwrap()
+            .split_whitespace()
+            .take(3)
+            .map(|count| count.parse().unwrap())
+            .collect();
+        let counts = count_file(&String::from("handout-a.txt")).unwrap().0;
+        assert_eq!(vec![counts.lines, counts.words, counts.bytes], expected);
+    }
+
+    #[test]
+    fn test_count_invalid_utf8() {
+        // wc skips the bad byte and counts 8 characters; rwc counts it as a character
+        let invalid = b"ab\xffcd\nok\n";
+        assert_eq!(count_bytes(invalid), (Counts { lines: 2, words: 2, chars: 9, bytes: 9, max_line_length: 5 }, false));
+    }
+
+    #[test]
+    fn test_count_file() {
+        let counts = count_file(&String::from("simple-a.txt")).unwrap().0;
+        assert_eq!(counts, Counts { lines: 5, words: 5, chars: 10, bytes: 10, max_line_length: 1 });
+        let counts = count_file(&String::from("handout-a.txt")).unwrap().0;
+        assert_eq!(counts, Counts { lines: 8, words: 96, chars: 573, bytes: 573, max_line_length: 80 });
+        assert!(count_file(&String::from("no-such-file.txt")).is_err());
+    }
+
+    #[test]
+    fn test_add_counts() {
+        let mut total = Counts::default();
+        total += Counts { lines: 5, words: 5, chars: 10, bytes: 10, max_line_length: 1 };
+        total += Counts { lines: 8, words: 96, chars: 573, bytes: 573, max_line_length: 80 };
+        total += Counts::default();
+        // The total's longest line is the longest of any file's, like GNU wc's
+        assert_eq!(total, Counts { lines: 13, words: 101, chars: 583, bytes: 583, max_line_length: 80 });
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let config = parse_args(&args(&["a.txt", "b.txt"])).unwrap();
+        assert_eq!(config.selection, Selection::default());
+        assert_eq!(config.filenames, args(&["a.txt", "b.txt"]));
+
+        let config = parse_args(&args(&["-w", "a.txt", "-l"])).unwrap();
+        assert_eq!(config.selection, Selection { lines: true, words: true, chars: false, bytes: false, max_line_length: false });
+        assert_eq!(config.filenames, args(&["a.txt"]));
+
+        let config = parse_args(&args(&["-cm", "a.txt"])).unwrap();
+        assert_eq!(config.selection, Selection { lines: false, words: false, chars: true, bytes: true, max_line_length: false });
+
+        assert_eq!(parse_args(&args(&["-lx", "a.txt"])), Err("rwc: invalid option -- 'x'".to_string()));
+    }
+
+    #[test]
+    fn test_format_counts() {
+        // "héllo wörld\nbye\n" has 2 lines, 3 words, 16 characters and 18 bytes
+        let (counts, _) = count_bytes("héllo wörld\nbye\n".as_bytes());
+        let format = |flags: &[&str]| {
+            let config = parse_args(&args(flags)).unwrap();
+            format_counts(&counts, &config.selection, 1, Some("fixture"))
+        };
+        assert_eq!(format(&[]), "2 3 18 fixture");
+        assert_eq!(format(&["-l"]), "2 fixture");
+        assert_eq!(format(&["-w"]), "3 fixture");
+        assert_eq!(format(&["-m"]), "16 fixture");
+        assert_eq!(format(&["-c"]), "18 fixture");
+        // The order of the flags doesn't change the order of the columns
+        assert_eq!(format(&["-c", "-l"]), "2 18 fixture");
+        assert_eq!(format(&["-cmwl"]), "2 3 16 18 fixture");
+        assert_eq!(format(&["-m", "-w"]), "3 16 fixture");
+        assert_eq!(format(&["-L"]), "11 fixture");
+        assert_eq!(format(&["-Lc"]), "18 11 fixture");
+        assert_eq!(format_counts(&counts, &Selection::default(), 4, Some("fixture")), "   2    3   18 fixture");
+        assert_eq!(format_counts(&counts, &Selection::default(), 4, None), "   2    3   18");
+    }
+
+    #[test]
+    fn test_printer() {
+        // The expected rows are what GNU wc prints for these files
+        let handout = count_file(&"handout-a.txt".to_string()).unwrap().0;
+        let simple = count_file(&"simple-a.txt".to_string()).unwrap().0;
+        let both = [("handout-a.txt", handout), ("simple-a.txt", simple)];
+        let printer = |flags: &[&str], from_stdin| {
+            let config = parse_args(&args(flags)).unwrap();
+            Printer { selection: config.selection, total: config.total, from_stdin }
+        };
+
+        let auto = printer(&[], false);
+        assert_eq!(auto.rows(&both[..1], 1), vec!["  8  96 573 handout-a.txt"]);
+        assert_eq!(
+            auto.rows(&both, 2).join("\n"),
+            "  8  96 573 handout-a.txt\n  5   5  10 simple-a.txt\n 13 101 583 total"
+        );
+        // A file that couldn't be counted still makes for more than one input
+        assert_eq!(auto.rows(&both[1..], 2), vec![" 5  5 10 simple-a.txt", " 5  5 10 total"]);
+
+        let always = printer(&["--total=always"], false);
+        assert_eq!(always.rows(&both[1..], 1), vec![" 5  5 10 simple-a.txt", " 5  5 10 total"]);
+        assert_eq!(always.rows(&both, 2), auto.rows(&both, 2));
+
+        let never = printer(&["--total=never"], false);
+        assert_eq!(never.rows(&both, 2), vec!["  8  96 573 handout-a.txt", "  5   5  10 simple-a.txt"]);
+        assert_eq!(never.rows(&both[1..], 1), vec![" 5  5 10 simple-a.txt"]);
+
+        let only = printer(&["--total=only"], false);
+        assert_eq!(only.rows(&both, 2), vec![" 13 101 583"]);
+        assert_eq!(only.rows(&both[1..], 1), vec![" 5  5 10"]);
+        assert_eq!(only.rows(&[], 0), vec!["0 0 0"]);
+
+        // Only the selected counts decide the width, whichever rows are printed
+        assert_eq!(printer(&["-l"], false).rows(&both[1..], 1), vec!["5 simple-a.txt"]);
+        assert_eq!(printer(&["-l", "--total=only"], false).rows(&both, 2), vec!["13"]);
+        assert_eq!(printer(&["-cw", "--total=never"], false).rows(&both, 2), vec![" 96 573 handout-a.txt", "  5  10 simple-a.txt"]);
+        assert_eq!(printer(&["-w", "--total=always"], false).rows(&both, 2), vec![" 96 handout-a.txt", "  5 simple-a.txt", "101 total"]);
+
+        assert_eq!(printer(&[], true).rows(&[("-", simple)], 1), vec!["      5       5      10 -"]);
+        assert_eq!(printer(&["--total=only"], true).rows(&[("-", simple)], 1), vec!["      5       5      10"]);
+    }
+
+    #[test]
+    fn test_parse_total() {
+        assert_eq!(parse_args(&args(&["a.txt"])).unwrap().total, Total::Auto);
+        assert_eq!(parse_args(&args(&["--total=never", "a.txt"])).unwrap().total, Total::Never);
+        assert_eq!(parse_args(&args(&["--total=only", "-l"])).unwrap().total, Total::Only);
+        assert_eq!(parse_args(&args(&["--total=sometimes"])), Err("rwc: invalid argument 'sometimes' for '--total'".to_string()));
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        // The expected widths are what GNU `wc -L` prints
+        let max_line_length = |text: &str| count_bytes(text.as_bytes()).0.max_line_length;
+        assert_eq!(max_line_length(""), 0);
+        assert_eq!(max_line_length("a\tb\n\tx\nabcdefgh\ty\n"), 17);
+        assert_eq!(max_line_length("short\nlonger line\n"), 11);
+        assert_eq!(max_line_length("no newline at the end"), 21);
+        assert_eq!(max_line_length("ab\rc\n"), 2);
+        assert_eq!(max_line_length("x\x0cyy\n"), 2);
+        assert_eq!(max_line_length("1234567\t\t.\n"), 17);
+    }
+
+    #[test]
+    fn test_parse_files0_from() {
+        let config = parse_args(&args(&["-l", "--files0-from=list"])).unwrap();
+        assert_eq!(config.files0_from, Some("list".to_string()));
+        assert!(config.filenames.is_empty());
+        let config = parse_args(&args(&["--files0-from", "-"])).unwrap();
+        assert_eq!(config.files0_from, Some("-".to_string()));
+        assert!(parse_args(&args(&["--files0-from"])).is_err());
         assert_eq!(
-            lines[0],
-            "This week's exercises will continue easing you into Rust and will feature some"
+            parse_args(&args(&["--files0-from=list", "a.txt"])),
+            Err("rwc: extra operand 'a.txt'\nfile operands cannot be combined with --files0-from".to_string())
         );
+        assert_eq!(parse_args(&args(&["--nope"])), Err("rwc: unrecognized option '--nope'".to_string()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_read_files0() {
+        let mut warnings = Vec::new();
+        let list = read_files0(&b"with space\0new\nline\0\0last\0"[..], &mut warnings).unwrap();
+        assert_eq!(list, args(&["with space", "new\nline", "last"]));
+        assert_eq!(String::from_utf8(warnings).unwrap(), "rwc: --files0-from: file name 3 is empty; skipping it\n");
+        assert_eq!(read_files0(&b"no trailing nul"[..], &mut io::sink()).unwrap(), args(&["no trailing nul"]));
+        assert!(read_files0(&b""[..], &mut io::sink()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_count_files0_names() {
+        // Names that would be split up if the list were separated by whitespace or newlines
+        let dir = env::temp_dir().join(format!("rwc-files0-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spaced = dir.join("with space.txt");
+        let newlined = dir.join("new\nline.txt");
+        std::fs::write(&spaced, "one two\n").unwrap();
+        std::fs::write(&newlined, "three\n").unwrap();
+        let mut list = Vec::new();
+        for path in &[&spaced, &newlined] {
+            list.extend_from_slice(path.to_str().unwrap().as_bytes());
+            list.push(0);
+        }
+
+        let filenames = read_files0(&list[..], &mut io::sink()).unwrap();
+        let counts: Vec<Counts> = filenames.iter().map(|name| count_file(name).unwrap().0).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(counts.iter().map(|counts| counts.words).collect::<Vec<usize>>(), vec![2, 1]);
+    }
+
+    #[test]
+    fn test_parse_walk() {
+        let config = parse_args(&args(&["-lr", "dir"])).unwrap();
+        assert_eq!(config.walk, Walk { recursive: true, follow_symlinks: false, skip_hidden: false });
+        let config = parse_args(&args(&["--recursive", "--follow-symlinks", "--skip-hidden", "dir"])).unwrap();
+        assert_eq!(config.walk, Walk { recursive: true, follow_symlinks: true, skip_hidden: true });
+        assert_eq!(parse_args(&args(&["dir"])).unwrap().walk, Walk::default());
+    }
+
+    #[test]
+    fn test_walk_dir() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = env::temp_dir().join(format!("rwc-walk-{}", process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        fs::create_dir_all(root.join(".hidden-dir")).unwrap();
+        fs::write(root.join("top.txt"), "one two\n").unwrap();
+        fs::write(root.join("sub/middle.txt"), "three\nfour\n").unwrap();
+        fs::write(root.join("sub/deeper/bottom.txt"), "five six seven\n").unwrap();
+        fs::write(root.join(".hidden.txt"), "eight\n").unwrap();
+        fs::write(root.join(".hidden-dir/inside.txt"), "nine\n").unwrap();
+        // Pointing back up at the root would make a cycle if it were followed
+        std::os::unix::fs::symlink(&root, root.join("sub/loop")).unwrap();
+
+        let root_name = root.to_str().unwrap();
+        let relative = |filenames: &[String]| -> Vec<String> {
+            filenames.iter().map(|name| name[root_name.len() + 1..].to_string()).collect()
+        };
+        let total = |filenames: &[String]| {
+            let mut total = Counts::default();
+            for filename in filenames {
+                total += count_file(filename).unwrap().0;
+            }
+            total
+        };
+
+        let (filenames, complete) = walk_dir(root_name, &Walk { recursive: true, ..Walk::default() }, &mut io::sink());
+        assert!(complete);
+        assert_eq!(
+            relative(&filenames),
+            args(&[".hidden.txt", "top.txt", ".hidden-dir/inside.txt", "sub/middle.txt", "sub/deeper/bottom.txt"])
+        );
+        let counts = total(&filenames);
+        assert_eq!((counts.lines, counts.words), (6, 9));
+
+        let (filenames, _) = walk_dir(root_name, &Walk { recursive: true, skip_hidden: true, ..Walk::default() }, &mut io::sink());
+        assert_eq!(relative(&filenames), args(&["top.txt", "sub/middle.txt", "sub/deeper/bottom.txt"]));
+        let counts = total(&filenames);
+        assert_eq!((counts.lines, counts.words), (4, 7));
+
+        // Following the loop back to the root doesn't count anything twice
+        let (filenames, _) = walk_dir(root_name, &Walk { recursive: true, follow_symlinks: true, skip_hidden: true }, &mut io::sink());
+        assert_eq!(relative(&filenames), args(&["top.txt", "sub/middle.txt", "sub/deeper/bottom.txt"]));
+
+        // An unreadable subdirectory is skipped and the rest is still walked, though root can
+        // read it anyway, so then there's nothing to check
+        let locked = root.join("sub/deeper");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read_dir(&locked).is_err() {
+            let mut warnings = Vec::new();
+            let walk = Walk { recursive: true, skip_hidden: true, ..Walk::default() };
+            let (filenames, complete) = walk_dir(root_name, &walk, &mut warnings);
+            assert!(!complete);
+            assert_eq!(relative(&filenames), args(&["top.txt", "sub/middle.txt"]));
+            assert_eq!(String::from_utf8(warnings).unwrap(), format!("rwc: {}: Permission denied\n", locked.display()));
+        }
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_threads() {
+        assert_eq!(parse_args(&args(&["a.txt"])).unwrap().threads, None);
+        assert_eq!(parse_args(&args(&["--threads", "4", "a.txt"])).unwrap().threads, Some(4));
+        assert_eq!(parse_args(&args(&["--threads=2"])).unwrap().threads, Some(2));
+        assert_eq!(parse_args(&args(&["--threads=0"])), Err("rwc: invalid number of threads: '0'".to_string()));
+        assert!(parse_args(&args(&["--threads", "many"])).is_err());
+        assert!(parse_args(&args(&["--threads"])).is_err());
+    }
+
+    /// Writes `count` files into a fresh directory, the nth holding n+1 lines of n+1 words each.
+    fn generated_files(label: &str, count: usize, repeat: usize) -> (PathBuf, Vec<String>) {
+        let dir = env::temp_dir().join(format!("rwc-{}-{}", label, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let filenames = (0..count)
+            .map(|n| {
+                let path = dir.join(format!("{:03}.txt", n));
+                let line = "word ".repeat(n + 1) + "\n";
+                fs::write(&path, line.repeat((n + 1) * repeat)).unwrap();
+                path.to_str().unwrap().to_string()
+            })
+            .collect();
+        (dir, filenames)
+    }
+
+    #[test]
+    fn test_count_files_in_order() {
+        let (dir, mut filenames) = generated_files("order", 40, 1);
+        // A missing file's error comes back in its place too
+        filenames.insert(17, dir.join("missing.txt").to_str().unwrap().to_string());
+        for &threads in &[1, 2, 4, 16, 64] {
+            let results = count_files(&filenames, threads, count_file);
+            assert_eq!(results.len(), filenames.len());
+            for (index, result) in results.iter().enumerate() {
+                match index {
+                    17 => assert!(result.is_err()),
+                    _ => {
+                        let n = if index < 17 { index + 1 } else { index };
+                        assert_eq!(result.as_ref().unwrap().0.words, n * n, "file {} on {} threads", index, threads);
+                    }
+                }
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[ignore] // Slow; run with --release -- --ignored
+    fn test_count_files_faster_on_threads() {
+        use std::time::Instant;
+        // Without the cores to spread over, more threads only add overhead
+        if thread::available_parallelism().map_or(1, |threads| threads.get()) < 4 {
+            return;
+        }
+        let (dir, filenames) = generated_files("speed", 8, 20000);
+        let time = |threads| {
+            let start = Instant::now();
+            let results = count_files(&filenames, threads, count_file);
+            assert!(results.iter().all(|result| result.is_ok()));
+            start.elapsed()
+        };
+        let serial = time(1);
+        let parallel = time(4);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(parallel < serial, "4 threads took {:?}, 1 thread took {:?}", parallel, serial);
+    }
+
+    #[test]
+    fn test_normalize_word() {
+        assert_eq!(normalize_word("Word"), Some("word".to_string()));
+        assert_eq!(normalize_word("end."), Some("end".to_string()));
+        assert_eq!(normalize_word("\"Don't,\""), Some("don't".to_string()));
+        assert_eq!(normalize_word("'Tis"), Some("tis".to_string()));
+        assert_eq!(normalize_word("dogs'"), Some("dogs".to_string()));
+        assert_eq!(normalize_word("(well-known)"), Some("well-known".to_string()));
+        assert_eq!(normalize_word("ÉTÉ!"), Some("été".to_string()));
+        assert_eq!(normalize_word("42%"), Some("42".to_string()));
+        assert_eq!(normalize_word("--"), None);
+        assert_eq!(normalize_word(""), None);
+    }
+
+    #[test]
+    fn test_top_words() {
+        // words-a.txt is made to have these frequencies, with ties among the 2s and the 1s
+        let frequencies = tally_file(&"words-a.txt".to_string()).unwrap();
+        assert_eq!(
+            top_words(&frequencies, 100),
+            vec![
+                ("the", 5),
+                ("end", 3),
+                ("and", 2),
+                ("cat", 2),
+                ("don't", 2),
+                ("dog", 1),
+                ("dog's", 1),
+                ("matter", 1),
+                ("quotes", 1),
+                ("said", 1),
+                ("well-known", 1),
+            ]
+        );
+        assert_eq!(format_top_words(&top_words(&frequencies, 4)), vec!["5 the", "3 end", "2 and", "2 cat"]);
+        assert!(top_words(&frequencies, 0).is_empty());
+        assert_eq!(format_top_words(&[("many", 120), ("few", 7)]), vec!["120 many", "  7 few"]);
+    }
+
+    #[test]
+    fn test_tally_across_chunks() {
+        // A word split between reads is still tallied once, whole
+        let text = "Alpha beta, ALPHA gamma alpha! beta";
+        for chunk_size in 1..8 {
+            let reader = Chunked { contents: text.as_bytes(), chunk_size };
+            let frequencies = read_into(reader, Counter::tallying()).unwrap().into_frequencies();
+            assert_eq!(top_words(&frequencies, 3), vec![("alpha", 3), ("beta", 2), ("gamma", 1)]);
+        }
+    }
+
+    #[test]
+    fn test_parse_top_words() {
+        let config = parse_args(&args(&["--top-words", "10", "a.txt"])).unwrap();
+        assert_eq!((config.top_words, config.per_file), (Some(10), false));
+        let config = parse_args(&args(&["--top-words=3", "--per-file", "a.txt"])).unwrap();
+        assert_eq!((config.top_words, config.per_file), (Some(3), true));
+        assert!(parse_args(&args(&["--top-words", "lots"])).is_err());
+        assert!(parse_args(&args(&["--per-file", "a.txt"])).is_err());
+    }
+
+    /// Runs rwc as the command line would, returning the exit status, stdout and stderr.
+    fn run_rwc(command_line: &[&str]) -> (i32, String, String) {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let status = run(&args(command_line), &mut out, &mut err);
+        (status, String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+    }
+
+    #[test]
+    fn test_run_exit_status() {
+        let (status, out, err) = run_rwc(&["handout-a.txt", "simple-a.txt"]);
+        assert_eq!((status, err.as_str()), (0, ""));
+        assert_eq!(out, "  8  96 573 handout-a.txt\n  5   5  10 simple-a.txt\n 13 101 583 total\n");
+
+        // The other files are still counted after one that's missing
+        let (status, out, err) = run_rwc(&["-l", "no-such-file.txt", "simple-a.txt"]);
+        assert_eq!(status, 1);
+        assert_eq!(err, "rwc: no-such-file.txt: No such file or directory\n");
+        assert_eq!(out, "5 simple-a.txt\n5 total\n");
+
+        let (status, out, err) = run_rwc(&["-x", "simple-a.txt"]);
+        assert_eq!((status, out.as_str()), (2, ""));
+        assert_eq!(err, format!("rwc: invalid option -- 'x'\n{}\n", USAGE));
+        let (status, _, err) = run_rwc(&[]);
+        assert_eq!(status, 2);
+        assert!(err.starts_with("rwc: no files given\n"));
+    }
+
+    #[test]
+    fn test_run_file_errors() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = env::temp_dir().join(format!("rwc-errors-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let dir_name = dir.to_str().unwrap();
+
+        let (status, _, err) = run_rwc(&[dir_name]);
+        assert_eq!(status, 1);
+        assert_eq!(err, format!("rwc: {}: Is a directory\n", dir_name));
+
+        let dangling = dir.join("dangling");
+        std::os::unix::fs::symlink(dir.join("gone"), &dangling).unwrap();
+        let (status, _, err) = run_rwc(&[dangling.to_str().unwrap()]);
+        assert_eq!(status, 1);
+        assert_eq!(err, format!("rwc: {}: No such file or directory\n", dangling.display()));
+
+        // Root can read the file anyway, so then there's nothing to check
+        let locked = dir.join("locked.txt");
+        fs::write(&locked, "secret\n").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if File::open(&locked).is_err() {
+            let (status, _, err) = run_rwc(&[locked.to_str().unwrap()]);
+            assert_eq!(status, 1);
+            assert_eq!(err, format!("rwc: {}: Permission denied\n", locked.display()));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_invalid_utf8_warning() {
+        let path = env::temp_dir().join(format!("rwc-latin1-{}.txt", process::id()));
+        // "café" in Latin-1
+        fs::write(&path, b"caf\xe9\n").unwrap();
+        let name = path.to_str().unwrap();
+        let (status, out, err) = run_rwc(&["-m", name]);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(status, 0);
+        assert_eq!(out, format!("5 {}\n", name));
+        assert_eq!(err, format!("rwc: {}: not valid UTF-8; counting each bad byte as a character\n", name));
+    }
+}
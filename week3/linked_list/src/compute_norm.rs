@@ -0,0 +1,67 @@
+use crate::linked_list::LinkedList;
+
+/// Types with a length, in the sense of a vector's Euclidean norm.
+pub trait ComputeNorm {
+    fn norm(&self) -> f64;
+}
+
+/// The Euclidean norm of the values, taken as a vector.
+impl ComputeNorm for LinkedList<f64> {
+    fn norm(&self) -> f64 {
+        self.into_iter().map(|value| value * value).sum::<f64>().sqrt()
+    }
+}
+
+/// The Euclidean norm of the inner lists' norms, which is the norm of all the values together.
+impl ComputeNorm for LinkedList<LinkedList<f64>> {
+    fn norm(&self) -> f64 {
+        self.into_iter().map(|list| list.norm().powi(2)).sum::<f64>().sqrt()
+    }
+}
+
+/// Adds up the norms of `items`.
+pub fn total_norm<T: ComputeNorm>(items: &[T]) -> f64 {
+    items.iter().map(|item| item.norm()).sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn list_of(values: &[f64]) -> LinkedList<f64> {
+        values.iter().cloned().collect()
+    }
+
+    /// Asserts that `actual` is `expected` give or take rounding, which Miri varies on purpose.
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} is not {}", actual, expected);
+    }
+
+    #[test]
+    fn test_norm() {
+        assert_close(list_of(&[3.0, 4.0]).norm(), 5.0);
+        assert_close(list_of(&[-3.0, 4.0]).norm(), 5.0);
+        assert_close(list_of(&[2.0]).norm(), 2.0);
+        assert_close(list_of(&[]).norm(), 0.0);
+        // Taking the norm leaves the list as it was
+        let list = list_of(&[3.0, 4.0]);
+        list.norm();
+        assert_eq!(list.get_size(), 2);
+    }
+
+    #[test]
+    fn test_norm_of_norms() {
+        let lists: LinkedList<LinkedList<f64>> =
+            vec![list_of(&[3.0, 4.0]), list_of(&[]), list_of(&[12.0])].into_iter().collect();
+        assert_close(lists.norm(), 13.0);
+        assert_close(LinkedList::<LinkedList<f64>>::new().norm(), 0.0);
+    }
+
+    #[test]
+    fn test_total_norm() {
+        assert_close(total_norm(&[list_of(&[3.0, 4.0]), list_of(&[6.0, 8.0]), list_of(&[])]), 15.0);
+        assert_close(total_norm::<LinkedList<f64>>(&[]), 0.0);
+        let nested: LinkedList<LinkedList<f64>> = vec![list_of(&[5.0, 12.0])].into_iter().collect();
+        assert_close(total_norm(&[nested]), 13.0);
+    }
+}
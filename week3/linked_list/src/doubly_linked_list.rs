@@ -0,0 +1,337 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// A list linked both ways, so it can be pushed to, popped from and iterated from either end in
+/// O(1). The nodes are heap allocations the list owns through raw pointers, since each one is
+/// pointed to from both sides.
+pub struct DoublyLinkedList<T> {
+    head: Option<NonNull<Node<T>>>,
+    tail: Option<NonNull<Node<T>>>,
+    size: usize,
+    /// The list owns its nodes, as far as drop checking is concerned
+    marker: PhantomData<Box<Node<T>>>,
+}
+
+pub struct Iter<'a, T> {
+    front: Option<NonNull<Node<T>>>,
+    back: Option<NonNull<Node<T>>>,
+    /// Values not yet yielded from either end, which keeps the ends from passing each other
+    remaining: usize,
+    marker: PhantomData<&'a Node<T>>,
+}
+
+struct Node<T> {
+    value: T,
+    prev: Option<NonNull<Node<T>>>,
+    next: Option<NonNull<Node<T>>>,
+}
+
+// The list owns its values like a Box would, so it can be sent and shared when they can
+unsafe impl<T: Send> Send for DoublyLinkedList<T> {}
+unsafe impl<T: Sync> Sync for DoublyLinkedList<T> {}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // SAFETY: the node belongs to the list this iterator borrows, which can't change
+        // while it does
+        let node: &'a Node<T> = unsafe { &*self.front?.as_ptr() };
+        self.front = node.next;
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // SAFETY: as in next
+        let node: &'a Node<T> = unsafe { &*self.back?.as_ptr() };
+        self.back = node.prev;
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+}
+
+impl<T> DoublyLinkedList<T> {
+    pub fn new() -> DoublyLinkedList<T> {
+        DoublyLinkedList { head: None, tail: None, size: 0, marker: PhantomData }
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Same as get_size, named like the std collections' method.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Moves `value` into a new node of its own, which the list takes over.
+    fn new_node(value: T, prev: Option<NonNull<Node<T>>>, next: Option<NonNull<Node<T>>>) -> NonNull<Node<T>> {
+        NonNull::from(Box::leak(Box::new(Node { value, prev, next })))
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        let node = DoublyLinkedList::new_node(value, None, self.head);
+        match self.head {
+            // SAFETY: the list owns the head node, and nothing else refers to it right now
+            Some(head) => unsafe { (*head.as_ptr()).prev = Some(node) },
+            None => self.tail = Some(node),
+        }
+        self.head = Some(node);
+        self.size += 1;
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        let node = DoublyLinkedList::new_node(value, self.tail, None);
+        match self.tail {
+            // SAFETY: as in push_front
+            Some(tail) => unsafe { (*tail.as_ptr()).next = Some(node) },
+            None => self.head = Some(node),
+        }
+        self.tail = Some(node);
+        self.size += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head?;
+        // SAFETY: the node came from a Box in new_node, and once it's unlinked here, nothing
+        // points to it anymore, so the Box can take it back
+        let node: Box<Node<T>> = unsafe { Box::from_raw(head.as_ptr()) };
+        self.head = node.next;
+        match self.head {
+            // SAFETY: as in push_front
+            Some(next) => unsafe { (*next.as_ptr()).prev = None },
+            None => self.tail = None,
+        }
+        self.size -= 1;
+        Some(node.value)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        // SAFETY: as in pop_front
+        let node: Box<Node<T>> = unsafe { Box::from_raw(tail.as_ptr()) };
+        self.tail = node.prev;
+        match self.tail {
+            // SAFETY: as in push_front
+            Some(prev) => unsafe { (*prev.as_ptr()).next = None },
+            None => self.head = None,
+        }
+        self.size -= 1;
+        Some(node.value)
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        // SAFETY: the node lives as long as the list does, and can't change while it's borrowed
+        self.head.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        // SAFETY: as in front
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    /// Returns an iterator over the values, from front to back, which can also be run from the
+    /// back with `rev` or `next_back`.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { front: self.head, back: self.tail, remaining: self.size, marker: PhantomData }
+    }
+
+    /// Removes every value, one node at a time.
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        DoublyLinkedList::new()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DoublyLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for DoublyLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list: DoublyLinkedList<T> = DoublyLinkedList::new();
+        for value in iter {
+            list.push_back(value);
+        }
+        list
+    }
+}
+
+impl<T: Clone> Clone for DoublyLinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for DoublyLinkedList<T> {
+    fn eq(&self, rhs: &DoublyLinkedList<T>) -> bool {
+        self.size == rhs.size && self.iter().eq(rhs)
+    }
+}
+
+/// Shows the values separated by spaces, like "1 2 3", as LinkedList does.
+impl<T: fmt::Display> fmt::Display for DoublyLinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, value) in self.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DoublyLinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::scrambled;
+    use std::collections::VecDeque;
+
+    /// Checks that the size, both ends and the values (in both directions) agree with `expected`.
+    fn check<T: PartialEq + fmt::Debug>(list: &DoublyLinkedList<T>, expected: &[T]) {
+        assert_eq!(list.get_size(), expected.len());
+        assert_eq!(list.is_empty(), expected.is_empty());
+        assert_eq!(list.front(), expected.first());
+        assert_eq!(list.back(), expected.last());
+        assert!(list.iter().eq(expected.iter()));
+        assert!(list.iter().rev().eq(expected.iter().rev()));
+    }
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut list: DoublyLinkedList<i32> = DoublyLinkedList::new();
+        check(&list, &[]);
+        list.push_back(2);
+        check(&list, &[2]);
+        list.push_front(1);
+        list.push_back(3);
+        check(&list, &[1, 2, 3]);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        check(&list, &[2]);
+        // The last node goes the same way from either end
+        assert_eq!(list.pop_back(), Some(2));
+        check(&list, &[]);
+        assert_eq!(list.pop_back(), None);
+        assert_eq!(list.pop_front(), None);
+        list.push_front(4);
+        check(&list, &[4]);
+        assert_eq!(list.pop_front(), Some(4));
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_iter_from_both_ends() {
+        let list: DoublyLinkedList<i32> = (1..=4).collect();
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next_back(), Some(&3));
+        // The ends have met
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(DoublyLinkedList::<i32>::new().iter().next_back(), None);
+    }
+
+    #[test]
+    fn test_clone_and_eq() {
+        let list: DoublyLinkedList<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let mut clone = list.clone();
+        assert!(list == clone);
+        clone.push_back("c".to_string());
+        assert!(list != clone);
+        clone.pop_back();
+        clone.pop_front();
+        clone.push_front("x".to_string());
+        assert!(list != clone);
+        check(&list, &["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_display_and_debug() {
+        let list: DoublyLinkedList<i32> = (1..=3).collect();
+        assert_eq!(list.to_string(), "1 2 3");
+        assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        assert_eq!(DoublyLinkedList::<i32>::new().to_string(), "");
+    }
+
+    #[test]
+    // A million nodes take Miri far too long
+    #[cfg_attr(miri, ignore)]
+    fn test_long_list() {
+        // Long enough to overflow the stack if dropping or cloning recursed per node
+        let list: DoublyLinkedList<u32> = (0..1_000_000).collect();
+        let clone = list.clone();
+        assert!(list == clone);
+        assert_eq!(clone.back(), Some(&999_999));
+    }
+
+    #[test]
+    fn test_matches_vec_deque() {
+        let mut list: DoublyLinkedList<u64> = DoublyLinkedList::new();
+        let mut deque: VecDeque<u64> = VecDeque::new();
+        // Fewer steps under Miri, which is much slower
+        let steps = if cfg!(miri) { 500 } else { 10_000 };
+        for (step, number) in (0..steps).zip(scrambled(42)) {
+            match number % 5 {
+                0 => {
+                    list.push_front(step);
+                    deque.push_front(step);
+                }
+                1 => {
+                    list.push_back(step);
+                    deque.push_back(step);
+                }
+                2 => assert_eq!(list.pop_front(), deque.pop_front()),
+                3 => assert_eq!(list.pop_back(), deque.pop_back()),
+                _ => {
+                    list.clear();
+                    deque.clear();
+                }
+            }
+            assert_eq!(list.len(), deque.len());
+            assert_eq!(list.front(), deque.front());
+            assert_eq!(list.back(), deque.back());
+        }
+        assert!(list.iter().eq(deque.iter()));
+        assert!(list.iter().rev().eq(deque.iter().rev()));
+    }
+}
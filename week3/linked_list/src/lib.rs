@@ -0,0 +1,13 @@
+//! Linked lists, with the operations and traits std's collections have: a singly linked one,
+//! and a doubly linked one that is O(1) at both ends. Also the week 3 `ComputeNorm` trait
+//! exercise over the singly linked list.
+
+pub mod compute_norm;
+pub mod doubly_linked_list;
+pub mod linked_list;
+#[cfg(test)]
+mod test_util;
+
+pub use crate::compute_norm::{total_norm, ComputeNorm};
+pub use crate::doubly_linked_list::DoublyLinkedList;
+pub use crate::linked_list::LinkedList;
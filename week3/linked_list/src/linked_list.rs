@@ -1,4 +1,7 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::{FromIterator, FusedIterator};
 use std::option::Option;
 
 pub struct LinkedList<T> {
@@ -6,8 +9,47 @@ pub struct LinkedList<T> {
     size: usize,
 }
 
-pub struct LinkedListIter<'a, T> {
-    current: &'a Option<Box<Node<T>>>
+pub struct Iter<'a, T> {
+    current: &'a Option<Box<Node<T>>>,
+    /// Values not yet yielded, for ExactSizeIterator
+    remaining: usize,
+}
+
+pub struct IterMut<'a, T> {
+    current: Option<&'a mut Node<T>>
+}
+
+/// Iterator that takes ownership of a list and yields its values
+pub struct IntoIter<T> {
+    list: LinkedList<T>
+}
+
+/// Iterator that removes each value from a list as it yields it. Whatever it doesn't get to is
+/// removed when it's dropped, so the list ends up empty either way.
+pub struct Drain<'a, T> {
+    list: &'a mut LinkedList<T>
+}
+
+/// A position in a list, which can be moved towards the back and edited at in O(1). It's either
+/// at a node (the current one) or at the end of the list, past the last node.
+pub struct CursorMut<'a, T> {
+    /// The link to the current node, or the empty link at the tail at the end. Only None for a
+    /// moment while the cursor moves.
+    link: Option<&'a mut Option<Box<Node<T>>>>,
+    size: &'a mut usize,
+}
+
+/// Error for an index past the end of the list
+#[derive(Debug, PartialEq)]
+pub struct IndexOutOfBounds {
+    pub index: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for IndexOutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "index {} is out of bounds for a list of length {}", self.index, self.len)
+    }
 }
 
 struct Node<T> {
@@ -15,22 +57,56 @@ struct Node<T> {
     next: Option<Box<Node<T>>>,
 }
 
-impl<'a, T> Iterator for LinkedListIter<'a, T> {
+impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
     fn next(&mut self) -> Option<&'a T> {
         match self.current {
             Some(node) => {
                 self.current = &node.next;
+                self.remaining -= 1;
                 Some(&node.value)
             },
             None => None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
-impl<T: Clone> Clone for Node<T> {
-    fn clone(&self) -> Self {
-        Node { value: self.value.clone(), next: self.next.clone() }
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+// Once the end of the list is reached, the iterator stays there
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        // Take the node out so its value and next link can be borrowed for 'a separately
+        let node: &'a mut Node<T> = self.current.take()?;
+        self.current = node.next.as_deref_mut();
+        Some(&mut node.value)
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+}
+
+impl<'a, T> Iterator for Drain<'a, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.list.pop_front()
+    }
+}
+
+impl<'a, T> Drop for Drain<'a, T> {
+    fn drop(&mut self) {
+        while self.list.pop_front().is_some() {}
     }
 }
 
@@ -42,13 +118,68 @@ impl<T: PartialEq> PartialEq for Node<T> {
 
 impl<T> Node<T> {
     pub fn new(value: T, next: Option<Box<Node<T>>>) -> Node<T> {
-        Node {value: value, next: next}
+        Node {value, next}
+    }
+}
+
+/// Sorts the `len` nodes starting at `head` by relinking them, and returns the new head. The
+/// first half is cut off where the count says rather than found with a fast/slow walk, since the
+/// length is known; either way, the recursion is only O(log n) deep.
+fn merge_sort<T, F: FnMut(&T, &T) -> Ordering>(
+    mut head: Option<Box<Node<T>>>,
+    len: usize,
+    compare: &mut F,
+) -> Option<Box<Node<T>>> {
+    if len < 2 {
+        return head;
     }
+    let mut link: &mut Option<Box<Node<T>>> = &mut head;
+    for _ in 0..len / 2 {
+        link = &mut link.as_mut().unwrap().next;
+    }
+    let back = link.take();
+    let front = merge_sort(head, len / 2, compare);
+    let back = merge_sort(back, len - len / 2, compare);
+    merge(front, back, compare)
 }
 
+/// Merges two sorted chains of nodes into one. On ties, `front`'s node goes first, which keeps
+/// the sort stable.
+fn merge<T, F: FnMut(&T, &T) -> Ordering>(
+    mut front: Option<Box<Node<T>>>,
+    mut back: Option<Box<Node<T>>>,
+    compare: &mut F,
+) -> Option<Box<Node<T>>> {
+    let mut merged: Option<Box<Node<T>>> = None;
+    let mut tail: &mut Option<Box<Node<T>>> = &mut merged;
+    while let (Some(f), Some(b)) = (&front, &back) {
+        let from = if compare(&b.value, &f.value) == Ordering::Less { &mut back } else { &mut front };
+        let mut node: Box<Node<T>> = from.take().unwrap();
+        *from = node.next.take();
+        tail = &mut tail.insert(node).next;
+    }
+    *tail = if front.is_some() { front } else { back };
+    merged
+}
+
+/// Builds the list in the iterator's order, appending through a tail cursor.
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list: LinkedList<T> = LinkedList::new();
+        let mut tail: &mut Option<Box<Node<T>>> = &mut list.head;
+        for value in iter {
+            tail = &mut tail.insert(Box::new(Node::new(value, None))).next;
+            list.size += 1;
+        }
+        list
+    }
+}
+
+/// Copies the list front to back with FromIterator. Cloning the head node and letting each node
+/// clone the next would recurse once per node, overflowing the stack on long lists.
 impl<T: Clone> Clone for LinkedList<T> {
     fn clone(&self) -> Self {
-        LinkedList { head: self.head.clone(), size: self.size}
+        self.into_iter().cloned().collect()
     }
 }
 
@@ -75,22 +206,106 @@ impl<T: PartialEq> PartialEq for LinkedList<T> {
     }
 }
 
+impl<'a, T> CursorMut<'a, T> {
+    /// Moves to the next node. At the end of the list, the cursor stays where it is.
+    pub fn move_next(&mut self) {
+        let link = self.link.take().unwrap();
+        self.link = if link.is_some() { Some(&mut link.as_mut().unwrap().next) } else { Some(link) };
+    }
+
+    /// Returns the value of the current node, or None at the end of the list.
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.link.as_mut().unwrap().as_mut().map(|node| &mut node.value)
+    }
+
+    /// Inserts `value` before the current node, or appends it at the end of the list. The cursor
+    /// stays on the same node (or at the end).
+    pub fn insert_before(&mut self, value: T) {
+        let link = self.link.take().unwrap();
+        let next = link.take();
+        let node: &'a mut Box<Node<T>> = link.insert(Box::new(Node::new(value, next)));
+        self.link = Some(&mut node.next);
+        *self.size += 1;
+    }
+
+    /// Inserts `value` after the current node, leaving the cursor on it. At the end of the list,
+    /// where there's no current node, this appends like insert_before.
+    pub fn insert_after(&mut self, value: T) {
+        match self.link.as_mut().unwrap().as_mut() {
+            Some(node) => {
+                let next = node.next.take();
+                node.next = Some(Box::new(Node::new(value, next)));
+                *self.size += 1;
+            }
+            None => self.insert_before(value),
+        }
+    }
+
+    /// Removes the current node and returns its value, moving the cursor to the node after it.
+    /// Returns None at the end of the list.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let link = self.link.as_mut().unwrap();
+        let node: Box<Node<T>> = link.take()?;
+        **link = node.next;
+        *self.size -= 1;
+        Some(node.value)
+    }
+}
+
+impl<T: Eq> Eq for LinkedList<T> {}
+
+/// Compares lexicographically, like the std collections: a list that is a prefix of another
+/// comes first.
+impl<T: PartialOrd> PartialOrd for LinkedList<T> {
+    fn partial_cmp(&self, other: &LinkedList<T>) -> Option<Ordering> {
+        self.into_iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for LinkedList<T> {
+    fn cmp(&self, other: &LinkedList<T>) -> Ordering {
+        self.into_iter().cmp(other)
+    }
+}
+
+/// Hashes the length and then each value, so equal lists hash equally (and lists of lists don't
+/// run together).
+impl<T: Hash> Hash for LinkedList<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.size);
+        for value in self {
+            value.hash(state);
+        }
+    }
+}
+
 impl<'a, T> IntoIterator for &'a LinkedList<T> {
 
     type Item = &'a T;
-    type IntoIter = LinkedListIter<'a, T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
 
-    fn into_iter(self) -> LinkedListIter<'a, T> {
-        LinkedListIter { current: &self.head }
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
     }
 }
 
-impl<T: Clone> Iterator for LinkedList<T> {
+impl<T> IntoIterator for LinkedList<T> {
 
     type Item = T;
+    type IntoIter = IntoIter<T>;
 
-    fn next(&mut self) -> Option<T> {
-        self.pop_front()
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { list: self }
     }
 }
 
@@ -102,6 +317,11 @@ impl<T> LinkedList<T> {
     pub fn get_size(&self) -> usize {
         self.size
     }
+
+    /// Same as get_size, named like the std collections' method.
+    pub fn len(&self) -> usize {
+        self.size
+    }
     
     pub fn is_empty(&self) -> bool {
         self.get_size() == 0
@@ -119,34 +339,931 @@ impl<T> LinkedList<T> {
         self.size -= 1;
         Some(node.value)
     }
-}
 
+    /// Adds `value` at the tail. The list only links forward, so this walks to the tail: O(n).
+    pub fn push_back(&mut self, value: T) {
+        let mut current: &mut Option<Box<Node<T>>> = &mut self.head;
+        while let Some(node) = current {
+            current = &mut node.next;
+        }
+        *current = Some(Box::new(Node::new(value, None)));
+        self.size += 1;
+    }
+
+    /// Removes the tail and returns its value. Like push_back, this is O(n).
+    pub fn pop_back(&mut self) -> Option<T> {
+        let mut current: &mut Option<Box<Node<T>>> = &mut self.head;
+        // Stop at the link that points to the last node (the head itself for a single node)
+        while current.as_ref()?.next.is_some() {
+            current = &mut current.as_mut().unwrap().next;
+        }
+        let node: Box<Node<T>> = current.take()?;
+        self.size -= 1;
+        Some(node.value)
+    }
 
-impl<T: fmt::Display > fmt::Display for LinkedList<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    /// Returns the value at `index` (0 is the front), walking the list to get there: O(index).
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.nth(index)
+    }
+
+    /// Same as get, named like the Iterator method, but without consuming anything.
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        let mut node: &Node<T> = self.head.as_deref()?;
+        for _ in 0..n {
+            node = node.next.as_deref()?;
+        }
+        Some(&node.value)
+    }
+
+    /// Returns the index of the first value for which `f` returns true.
+    pub fn position<F: FnMut(&T) -> bool>(&self, mut f: F) -> Option<usize> {
         let mut current: &Option<Box<Node<T>>> = &self.head;
-        let mut result = String::new();
-        loop {
-            match current {
-                Some(node) => {
-                    result = format!("{} {}", result, node.value);
-                    current = &node.next;
-                },
-                None => break,
+        let mut index = 0;
+        while let Some(node) = current {
+            if f(&node.value) {
+                return Some(index);
             }
+            current = &node.next;
+            index += 1;
         }
-        write!(f, "{}", result)
+        None
     }
-}
 
-impl<T> Drop for LinkedList<T> {
-    fn drop(&mut self) {
+    /// Returns an iterator over the values, from front to back.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { current: &self.head, remaining: self.size }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.iter_mut().nth(index)
+    }
+
+    /// Inserts `value` so that it ends up at `index`, moving the values from there on back by
+    /// one. `index` may be the length of the list, which appends. O(index).
+    pub fn insert_at(&mut self, index: usize, value: T) -> Result<(), IndexOutOfBounds> {
+        let len = self.size;
+        let link = self.link_mut(index).ok_or(IndexOutOfBounds { index, len })?;
+        let next = link.take();
+        *link = Some(Box::new(Node::new(value, next)));
+        self.size += 1;
+        Ok(())
+    }
+
+    /// Removes the value at `index` and returns it, or None if there's none there. O(index).
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        let link = self.link_mut(index)?;
+        let node: Box<Node<T>> = link.take()?;
+        *link = node.next;
+        self.size -= 1;
+        Some(node.value)
+    }
+
+    /// Moves all of `other`'s nodes to the back of this list, leaving `other` empty. The nodes
+    /// are spliced in rather than copied, but finding the tail is O(n).
+    pub fn append(&mut self, other: &mut LinkedList<T>) {
+        let len = self.size;
+        let tail = self.link_mut(len).unwrap();
+        *tail = other.head.take();
+        self.size += other.size;
+        other.size = 0;
+    }
+
+    /// Detaches the values from index `at` onward into a new list, which is returned. O(at).
+    ///
+    /// Panics if `at` is greater than the length of the list.
+    pub fn split_off(&mut self, at: usize) -> LinkedList<T> {
+        let len = self.size;
+        let link = match self.link_mut(at) {
+            Some(link) => link,
+            None => panic!("`at` split index (is {}) should be <= len (is {})", at, len),
+        };
+        let head = link.take();
+        self.size = at;
+        LinkedList { head, size: len - at }
+    }
+
+    /// Removes every value for which `f` returns false, keeping the rest in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut link: &mut Option<Box<Node<T>>> = &mut self.head;
+        while link.is_some() {
+            if f(&link.as_ref().unwrap().value) {
+                link = &mut link.as_mut().unwrap().next;
+            } else {
+                // Unlink the node; the link now points to the one after it, which is checked next
+                let node: Box<Node<T>> = link.take().unwrap();
+                *link = node.next;
+                self.size -= 1;
+            }
+        }
+    }
+
+    /// Sorts the list with `compare`, keeping equal values in the order they were in. This is a
+    /// merge sort that relinks the nodes, so it takes O(n log n) time and moves no values.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        self.head = merge_sort(self.head.take(), self.size, &mut compare);
+    }
+
+    /// Returns a cursor at the front of the list (which is also the end if the list is empty).
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { link: Some(&mut self.head), size: &mut self.size }
+    }
+
+    /// Returns the link that points to the node at `index`: the head for 0, and the empty link
+    /// at the tail for the length of the list. None if `index` is past that.
+    fn link_mut(&mut self, index: usize) -> Option<&mut Option<Box<Node<T>>>> {
+        if index > self.size {
+            return None;
+        }
+        let mut link: &mut Option<Box<Node<T>>> = &mut self.head;
+        for _ in 0..index {
+            link = &mut link.as_mut()?.next;
+        }
+        Some(link)
+    }
+
+    /// Returns a new list of `f` applied to each value, in the same order.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> LinkedList<U> {
+        self.into_iter().map(f).collect()
+    }
+
+    /// Folds every value into an accumulator, starting with `init`, from front to back.
+    pub fn fold<B, F: FnMut(B, &T) -> B>(&self, init: B, f: F) -> B {
+        self.into_iter().fold(init, f)
+    }
+
+    /// Returns an iterator over mutable references to the values, from front to back.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { current: self.head.as_deref_mut() }
+    }
+
+    /// Returns an iterator that removes the values from the front as it yields them, leaving the
+    /// list empty (but still usable) afterwards.
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        Drain { list: self }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.value)
+    }
+
+    /// Same as front.
+    pub fn peek_front(&self) -> Option<&T> {
+        self.front()
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.value)
+    }
+
+    /// Removes every value. The nodes are unlinked one at a time, since dropping the head and
+    /// letting each node drop the next would recurse once per node.
+    pub fn clear(&mut self) {
         let mut current = self.head.take();
         while let Some(mut node) = current {
             current = node.next.take();
         }
+        self.size = 0;
+    }
+
+    /// Returns the value at the tail, walking the list to get there: O(n).
+    pub fn back(&self) -> Option<&T> {
+        let mut node: &Node<T> = self.head.as_ref()?;
+        while let Some(next) = &node.next {
+            node = next;
+        }
+        Some(&node.value)
+    }
+
+    /// Same as back.
+    pub fn last(&self) -> Option<&T> {
+        self.back()
+    }
+}
+
+impl<T: Clone> LinkedList<T> {
+    /// Returns a new list of copies of the values for which `f` returns true, in the same order.
+    pub fn filter<F: FnMut(&T) -> bool>(&self, mut f: F) -> LinkedList<T> {
+        self.into_iter().filter(|value| f(value)).cloned().collect()
+    }
+}
+
+impl<T: Ord> LinkedList<T> {
+    /// Sorts the list in ascending order, like sort_by with `Ord::cmp`.
+    pub fn sort(&mut self) {
+        self.sort_by(|a, b| a.cmp(b));
+    }
+}
+
+impl<T: PartialEq> LinkedList<T> {
+    pub fn contains(&self, value: &T) -> bool {
+        self.into_iter().any(|other| other == value)
+    }
+
+    /// Removes the first value equal to `value`. Returns whether there was one.
+    pub fn remove_first(&mut self, value: &T) -> bool {
+        let mut link: &mut Option<Box<Node<T>>> = &mut self.head;
+        while link.is_some() {
+            if link.as_ref().unwrap().value == *value {
+                let node: Box<Node<T>> = link.take().unwrap();
+                *link = node.next;
+                self.size -= 1;
+                return true;
+            }
+            link = &mut link.as_mut().unwrap().next;
+        }
+        false
+    }
+
+    /// Removes every value equal to `value`, and returns how many there were.
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        let size = self.size;
+        self.retain(|other| other != value);
+        size - self.size
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        LinkedList::new()
+    }
+}
+
+
+impl<T: fmt::Display> LinkedList<T> {
+    /// Returns the values separated by `sep`, like "1, 2, 3" for ", ".
+    pub fn join(&self, sep: &str) -> String {
+        let mut joined = String::new();
+        // Writing to a String can't fail
+        self.write_joined(&mut joined, sep).unwrap();
+        joined
+    }
+
+    fn write_joined<W: fmt::Write>(&self, out: &mut W, sep: &str) -> fmt::Result {
+        for (index, value) in self.iter().enumerate() {
+            if index > 0 {
+                out.write_str(sep)?;
+            }
+            write!(out, "{}", value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows the values separated by spaces, like "1 2 3".
+impl<T: fmt::Display> fmt::Display for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_joined(f, " ")
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
     }
 }
 
+impl<T> Drop for LinkedList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::scrambled;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+
+    /// Checks that the size and emptiness agree with `expected`, and so do both ends.
+    fn check<T: PartialEq + fmt::Debug>(list: &LinkedList<T>, expected: &[T]) {
+        assert_eq!(list.get_size(), expected.len());
+        assert_eq!(list.is_empty(), expected.is_empty());
+        assert_eq!(list.front(), expected.first());
+        assert_eq!(list.back(), expected.last());
+        assert!(list.into_iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_push_and_pop_back() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.push_back(1);
+        check(&list, &[1]);
+        list.push_back(2);
+        check(&list, &[1, 2]);
+        assert_eq!(list.pop_back(), Some(2));
+        check(&list, &[1]);
+        assert_eq!(list.pop_back(), Some(1));
+        check(&list, &[]);
+        assert_eq!(list.pop_back(), None);
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_front_and_back_interleaved() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.push_front(2);
+        check(&list, &[2]);
+        list.push_back(3);
+        check(&list, &[2, 3]);
+        list.push_front(1);
+        check(&list, &[1, 2, 3]);
+        assert_eq!(list.pop_back(), Some(3));
+        check(&list, &[1, 2]);
+        assert_eq!(list.pop_front(), Some(1));
+        check(&list, &[2]);
+        // The last node goes the same way from either end
+        assert_eq!(list.pop_back(), Some(2));
+        check(&list, &[]);
+        list.push_back(4);
+        check(&list, &[4]);
+        assert_eq!(list.pop_front(), Some(4));
+        check(&list, &[]);
+        assert_eq!(list.pop_front(), None);
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        for value in 1..=3 {
+            list.push_back(value);
+        }
+        for value in list.iter_mut() {
+            *value *= 10;
+        }
+        check(&list, &[10, 20, 30]);
+        for value in &mut list {
+            *value += 1;
+        }
+        check(&list, &[11, 21, 31]);
+        // Every reference can be held at once
+        let values: Vec<&mut i32> = list.iter_mut().collect();
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn test_iter_mut_empty() {
+        let mut list: LinkedList<String> = LinkedList::new();
+        assert!(list.iter_mut().next().is_none());
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        // No Clone needed to take the values out
+        struct NotClone(i32);
+        let mut list: LinkedList<NotClone> = LinkedList::new();
+        for value in 1..=3 {
+            list.push_back(NotClone(value));
+        }
+        let mut total = 0;
+        for value in &list {
+            total += value.0;
+        }
+        assert_eq!(total, 6);
+        // Iterating by reference leaves the list as it was
+        assert_eq!(list.get_size(), 3);
+        // Iterating by value moves the list, so it can't be used afterwards
+        let values: Vec<i32> = list.into_iter().map(|value| value.0).collect();
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        for value in 1..=3 {
+            list.push_back(value);
+        }
+        let values: Vec<i32> = list.drain().collect();
+        assert_eq!(values, vec![1, 2, 3]);
+        check(&list, &[]);
+        list.push_back(4);
+        list.push_back(5);
+        // Stopping early still empties the list
+        assert_eq!(list.drain().next(), Some(4));
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_get() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert_eq!(list.get(0), None);
+        for value in 1..=3 {
+            list.push_back(value);
+        }
+        assert_eq!(list.get(0), Some(&1));
+        assert_eq!(list.get(2), Some(&3));
+        assert_eq!(list.get(3), None);
+        *list.get_mut(1).unwrap() = 20;
+        assert_eq!(list.get_mut(3), None);
+        check(&list, &[1, 20, 3]);
+    }
+
+    #[test]
+    fn test_insert_at() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert_eq!(list.insert_at(1, 0), Err(IndexOutOfBounds { index: 1, len: 0 }));
+        check(&list, &[]);
+        assert_eq!(list.insert_at(0, 2), Ok(()));
+        check(&list, &[2]);
+        // At the length, it appends
+        assert_eq!(list.insert_at(1, 4), Ok(()));
+        check(&list, &[2, 4]);
+        assert_eq!(list.insert_at(1, 3), Ok(()));
+        check(&list, &[2, 3, 4]);
+        assert_eq!(list.insert_at(0, 1), Ok(()));
+        check(&list, &[1, 2, 3, 4]);
+        assert_eq!(list.insert_at(5, 6), Err(IndexOutOfBounds { index: 5, len: 4 }));
+        check(&list, &[1, 2, 3, 4]);
+        assert_eq!(list.pop_front(), Some(1));
+        list.push_front(0);
+        check(&list, &[0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_remove_at() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert_eq!(list.remove_at(0), None);
+        for value in 1..=4 {
+            list.push_back(value);
+        }
+        assert_eq!(list.remove_at(4), None);
+        check(&list, &[1, 2, 3, 4]);
+        assert_eq!(list.remove_at(1), Some(2));
+        check(&list, &[1, 3, 4]);
+        assert_eq!(list.remove_at(2), Some(4));
+        check(&list, &[1, 3]);
+        assert_eq!(list.remove_at(0), Some(1));
+        check(&list, &[3]);
+        // Removing the last value leaves the list empty for push_front
+        assert_eq!(list.remove_at(0), Some(3));
+        check(&list, &[]);
+        list.push_front(5);
+        check(&list, &[5]);
+        assert_eq!(list.pop_front(), Some(5));
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        let mut other: LinkedList<i32> = LinkedList::new();
+        list.append(&mut other);
+        check(&list, &[]);
+        check(&other, &[]);
+        other.push_back(1);
+        other.push_back(2);
+        list.append(&mut other);
+        check(&list, &[1, 2]);
+        check(&other, &[]);
+        other.push_back(3);
+        list.append(&mut other);
+        check(&list, &[1, 2, 3]);
+        check(&other, &[]);
+        // Appending an empty list changes nothing
+        list.append(&mut other);
+        check(&list, &[1, 2, 3]);
+        // Both lists keep working afterwards
+        other.push_front(4);
+        list.push_back(5);
+        check(&list, &[1, 2, 3, 5]);
+        check(&other, &[4]);
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        for value in 1..=4 {
+            list.push_back(value);
+        }
+        let back = list.split_off(2);
+        check(&list, &[1, 2]);
+        check(&back, &[3, 4]);
+        // Splitting at the length gives an empty list
+        let empty = list.split_off(2);
+        check(&list, &[1, 2]);
+        check(&empty, &[]);
+        // Splitting at 0 takes everything
+        let all = list.split_off(0);
+        check(&list, &[]);
+        check(&all, &[1, 2]);
+        assert_eq!(all.get_size() + back.get_size() + empty.get_size() + list.get_size(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "`at` split index (is 3) should be <= len (is 2)")]
+    fn test_split_off_out_of_range() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.split_off(3);
+    }
+    fn list_of(values: &[i32]) -> LinkedList<i32> {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        for value in values {
+            list.push_back(*value);
+        }
+        list
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut list = list_of(&[1, 2, 3, 4, 5, 6]);
+        list.retain(|value| value % 2 == 0);
+        check(&list, &[2, 4, 6]);
+        // Matching nodes at the head and in a row
+        let mut list = list_of(&[1, 1, 2, 3, 3, 3, 4]);
+        list.retain(|value| *value != 1 && *value != 3);
+        check(&list, &[2, 4]);
+        list.retain(|_| false);
+        check(&list, &[]);
+        list.push_back(7);
+        check(&list, &[7]);
+    }
+
+    #[test]
+    fn test_retain_everything() {
+        let mut list = list_of(&[1, 2, 3]);
+        list.retain(|_| true);
+        check(&list, &[1, 2, 3]);
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        empty.retain(|_| false);
+        check(&empty, &[]);
+    }
+
+    #[test]
+    fn test_remove_first() {
+        let mut list = list_of(&[1, 2, 1, 3]);
+        assert!(list.remove_first(&1));
+        check(&list, &[2, 1, 3]);
+        assert!(list.remove_first(&3));
+        check(&list, &[2, 1]);
+        assert!(!list.remove_first(&4));
+        check(&list, &[2, 1]);
+        assert!(list.remove_first(&2));
+        assert!(list.remove_first(&1));
+        check(&list, &[]);
+        assert!(!list.remove_first(&1));
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let mut list = list_of(&[5, 5, 1, 5, 5, 2, 5]);
+        assert_eq!(list.remove_all(&5), 5);
+        check(&list, &[1, 2]);
+        assert_eq!(list.remove_all(&5), 0);
+        check(&list, &[1, 2]);
+        let mut list = list_of(&[3, 3, 3]);
+        assert_eq!(list.remove_all(&3), 3);
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut list = list_of(&[3, 1, 4, 1, 5, 9, 2, 6]);
+        list.sort();
+        check(&list, &[1, 1, 2, 3, 4, 5, 6, 9]);
+        // Already sorted
+        list.sort();
+        check(&list, &[1, 1, 2, 3, 4, 5, 6, 9]);
+        let mut list = list_of(&[5, 4, 3, 2, 1]);
+        list.sort();
+        check(&list, &[1, 2, 3, 4, 5]);
+        let mut list = list_of(&[7]);
+        list.sort();
+        check(&list, &[7]);
+        let mut list = list_of(&[]);
+        list.sort();
+        check(&list, &[]);
+        // The sorted list links up properly at both ends
+        let mut list = list_of(&[2, 1]);
+        list.sort();
+        list.push_back(3);
+        list.push_front(0);
+        check(&list, &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_sort_by_is_stable() {
+        // Sorted by key only; the tag records the original order
+        let mut list: LinkedList<(i32, usize)> = LinkedList::new();
+        for (tag, key) in [2, 1, 2, 0, 1, 2, 0].iter().enumerate() {
+            list.push_back((*key, tag));
+        }
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+        check(&list, &[(0, 3), (0, 6), (1, 1), (1, 4), (2, 0), (2, 2), (2, 5)]);
+        list.sort_by(|a, b| b.0.cmp(&a.0));
+        check(&list, &[(2, 0), (2, 2), (2, 5), (1, 1), (1, 4), (0, 3), (0, 6)]);
+    }
+
+    #[test]
+    fn test_sort_matches_vec() {
+        let mut numbers = scrambled(12345);
+        for len in 0..100 {
+            let mut values: Vec<i32> = numbers.by_ref().take(len).map(|number| number as i32 % 50).collect();
+            let mut list = list_of(&values);
+            list.sort();
+            values.sort();
+            check(&list, &values);
+        }
+    }
+
+    #[test]
+    fn test_debug() {
+        assert_eq!(format!("{:?}", list_of(&[1, 2, 3])), "[1, 2, 3]");
+        assert_eq!(format!("{:?}", list_of(&[])), "[]");
+        let mut list: LinkedList<&str> = LinkedList::new();
+        list.push_back("a");
+        assert_eq!(format!("{:?}", list), "[\"a\"]");
+    }
+
+    #[test]
+    fn test_default() {
+        let list: LinkedList<i32> = LinkedList::default();
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_ord() {
+        assert!(list_of(&[1, 2]) < list_of(&[1, 3]));
+        assert!(list_of(&[2]) > list_of(&[1, 3]));
+        // A prefix comes first
+        assert!(list_of(&[1, 2]) < list_of(&[1, 2, 0]));
+        assert!(list_of(&[]) < list_of(&[0]));
+        assert_eq!(list_of(&[1, 2]).cmp(&list_of(&[1, 2])), Ordering::Equal);
+        assert_eq!(list_of(&[]).cmp(&list_of(&[])), Ordering::Equal);
+        let mut lists = vec![list_of(&[2]), list_of(&[1, 2]), list_of(&[]), list_of(&[1])];
+        lists.sort();
+        assert_eq!(lists, vec![list_of(&[]), list_of(&[1]), list_of(&[1, 2]), list_of(&[2])]);
+    }
+
+    #[test]
+    fn test_partial_ord() {
+        let mut list: LinkedList<f64> = LinkedList::new();
+        list.push_back(1.0);
+        list.push_back(f64::NAN);
+        let mut other = list.clone();
+        assert_eq!(list.partial_cmp(&other), None);
+        other.push_front(0.0);
+        assert_eq!(list.partial_cmp(&other), Some(Ordering::Greater));
+    }
+
+    fn hash_of(list: &LinkedList<i32>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        list.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_hash() {
+        // Built differently, but equal
+        let list = list_of(&[1, 2, 3]);
+        let mut other: LinkedList<i32> = LinkedList::new();
+        other.push_front(3);
+        other.push_front(2);
+        other.push_front(1);
+        assert_eq!(hash_of(&list), hash_of(&other));
+        assert_ne!(hash_of(&list), hash_of(&list_of(&[1, 2])));
+        let mut set = HashSet::new();
+        set.insert(list);
+        set.insert(other);
+        assert_eq!(set.len(), 1);
+        set.insert(list_of(&[3, 2, 1]));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_clone() {
+        let list = list_of(&[1, 2, 3]);
+        let mut clone = list.clone();
+        check(&clone, &[1, 2, 3]);
+        assert!(list == clone);
+        // The clone is separate from the original
+        clone.push_back(4);
+        *clone.get_mut(0).unwrap() = 0;
+        check(&list, &[1, 2, 3]);
+        check(&clone, &[0, 2, 3, 4]);
+        check(&list_of(&[]).clone(), &[]);
+    }
 
+    #[test]
+    // A million nodes take Miri far too long
+    #[cfg_attr(miri, ignore)]
+    fn test_clone_long_list() {
+        // Long enough to overflow the stack if cloning, comparing or dropping recursed per node
+        let mut list: LinkedList<u32> = LinkedList::new();
+        for value in 0..1_000_000 {
+            list.push_front(value);
+        }
+        let clone = list.clone();
+        assert_eq!(clone.get_size(), 1_000_000);
+        assert_eq!(clone.front(), Some(&999_999));
+        assert!(list == clone);
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut list = list_of(&[1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.insert_before(0);
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.move_next();
+        *cursor.current().unwrap() = 20;
+        cursor.insert_after(25);
+        assert_eq!(cursor.current(), Some(&mut 20));
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(25));
+        assert_eq!(cursor.current(), Some(&mut 3));
+        check(&list, &[0, 1, 20, 3]);
+    }
+
+    #[test]
+    fn test_cursor_at_end() {
+        let mut list = list_of(&[1]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        // Moving past the end stays there
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.remove_current(), None);
+        cursor.insert_before(2);
+        cursor.insert_after(3);
+        assert_eq!(cursor.current(), None);
+        check(&list, &[1, 2, 3]);
+        // Removing every node from the front
+        let mut cursor = list.cursor_front_mut();
+        while cursor.remove_current().is_some() {}
+        check(&list, &[]);
+        list.cursor_front_mut().insert_after(4);
+        check(&list, &[4]);
+    }
+
+    #[test]
+    fn test_cursor_insertion_sort() {
+        let mut values = vec![5, 3, 8, 1, 9, 2, 7, 3, 0, 6];
+        let mut list: LinkedList<i32> = LinkedList::new();
+        for value in &values {
+            let mut cursor = list.cursor_front_mut();
+            while cursor.current().is_some_and(|current| *current <= *value) {
+                cursor.move_next();
+            }
+            cursor.insert_before(*value);
+        }
+        values.sort();
+        check(&list, &values);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let list: LinkedList<i32> = (1..=3).collect();
+        check(&list, &[1, 2, 3]);
+        let empty: LinkedList<i32> = Vec::new().into_iter().collect();
+        check(&empty, &[]);
+    }
+
+    #[test]
+    fn test_map_and_filter() {
+        let list = list_of(&[1, 2, 3, 4, 5]);
+        let strings: LinkedList<String> = list.map(|value| value.to_string());
+        check(&strings, &["1", "2", "3", "4", "5"].map(String::from));
+        let odd_squares = list.map(|value| value * value).filter(|value| value % 2 == 1);
+        check(&odd_squares, &[1, 9, 25]);
+        // The original is untouched
+        check(&list, &[1, 2, 3, 4, 5]);
+        check(&list.filter(|_| false), &[]);
+        check(&list_of(&[]).map(|value| value + 1), &[]);
+    }
+
+    #[test]
+    fn test_fold() {
+        let values = vec![3, 1, 4, 1, 5];
+        let list = list_of(&values);
+        assert_eq!(list.fold(0, |sum, value| sum + value), values.iter().sum::<i32>());
+        // Front to back
+        assert_eq!(list.fold(String::new(), |text, value| text + &value.to_string()), "31415");
+        assert_eq!(list_of(&[]).fold(7, |sum, value| sum + value), 7);
+    }
+
+    #[test]
+    fn test_push_and_pop_front() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        check(&list, &[]);
+        list.push_front(2);
+        list.push_front(1);
+        check(&list, &[1, 2]);
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_front(), None);
+        check(&list, &[]);
+    }
 
+    #[test]
+    fn test_len() {
+        let mut list = list_of(&[1, 2, 3]);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.len(), list.get_size());
+        list.pop_back();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list_of(&[]).len(), 0);
+    }
+
+    #[test]
+    fn test_peek_front() {
+        let mut list: LinkedList<i32> = LinkedList::new();
+        assert_eq!(list.peek_front(), None);
+        assert_eq!(list.peek_front_mut(), None);
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.peek_front(), Some(&1));
+        *list.peek_front_mut().unwrap() = 10;
+        check(&list, &[10, 2]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let list = list_of(&[1, 2, 3]);
+        assert!(list.contains(&1));
+        assert!(list.contains(&3));
+        assert!(!list.contains(&4));
+        assert!(!list_of(&[]).contains(&1));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut list = list_of(&[1, 2, 3]);
+        list.clear();
+        check(&list, &[]);
+        list.clear();
+        check(&list, &[]);
+        // Still usable afterwards
+        list.push_back(4);
+        check(&list, &[4]);
+        // Long enough to overflow the stack if nodes were dropped recursively (but shorter under
+        // Miri, which is much slower)
+        let len = if cfg!(miri) { 1_000 } else { 1_000_000 };
+        let mut list: LinkedList<u32> = (0..len).collect();
+        list.clear();
+        check(&list, &[]);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(list_of(&[1, 2, 3]).to_string(), "1 2 3");
+        assert_eq!(list_of(&[1]).to_string(), "1");
+        assert_eq!(list_of(&[]).to_string(), "");
+        assert_eq!(format!("[{}]", list_of(&[1, 2])), "[1 2]");
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(list_of(&[1, 2, 3]).join(", "), "1, 2, 3");
+        assert_eq!(list_of(&[1, 2, 3]).join(""), "123");
+        assert_eq!(list_of(&[1]).join(", "), "1");
+        assert_eq!(list_of(&[]).join(", "), "");
+    }
+
+    #[test]
+    fn test_nth_and_last() {
+        let list = list_of(&[1, 2, 3]);
+        assert_eq!(list.nth(0), Some(&1));
+        assert_eq!(list.nth(2), Some(&3));
+        assert_eq!(list.nth(3), None);
+        assert_eq!(list.last(), Some(&3));
+        assert_eq!(list_of(&[]).nth(0), None);
+        assert_eq!(list_of(&[]).last(), None);
+        // Nothing was taken out
+        check(&list, &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_position() {
+        let list = list_of(&[4, 5, 6, 5]);
+        assert_eq!(list.position(|value| *value == 4), Some(0));
+        assert_eq!(list.position(|value| *value == 5), Some(1));
+        assert_eq!(list.position(|value| *value > 5), Some(2));
+        assert_eq!(list.position(|value| *value == 7), None);
+        assert_eq!(list_of(&[]).position(|_| true), None);
+        check(&list, &[4, 5, 6, 5]);
+    }
+
+    #[test]
+    fn test_iter_len() {
+        let list = list_of(&[1, 2, 3]);
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 3);
+        iter.next();
+        assert_eq!(iter.len(), 2);
+        iter.next();
+        iter.next();
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        // Fused: the end stays the end
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(list_of(&[]).iter().len(), 0);
+    }
+}
@@ -1,5 +1,4 @@
 use linked_list::LinkedList;
-pub mod linked_list;
 
 fn main() {
     let mut list: LinkedList<String> = LinkedList::new();
@@ -13,7 +12,8 @@ fn main() {
     println!("top element: {}", list.pop_front().unwrap());
     println!("{}", list);
     println!("size: {}", list.get_size());
-    println!("{}", list.to_string()); // ToString impl for anything impl Display
+    let list_string = list.to_string(); // ToString impl for anything impl Display
+    println!("{}", list_string);
 
     let mut clone_list = list.clone();
     println!("{}", list == clone_list);
@@ -26,12 +26,16 @@ fn main() {
     for i in &list {
         print!("{} ", i);
     }
-    println!("");
+    println!();
 
     println!("{}", list);
 
-    // If you implement iterator trait:
-    //for val in &list {
-    //    println!("{}", val);
-    //}
+    // drain takes the values out but leaves the list around to be reused
+    let drained: Vec<String> = clone_list.drain().collect();
+    println!("drained {} values, {} left", drained.len(), clone_list.get_size());
+
+    // Iterating by value moves the list
+    for val in list {
+        println!("{}", val);
+    }
 }
@@ -0,0 +1,11 @@
+//! Helpers shared by the lists' tests.
+
+/// An endless run of numbers below 2^31 that look random, but are the same for the same seed
+/// every run, from a linear congruential generator.
+pub fn scrambled(seed: u64) -> impl Iterator<Item = u64> {
+    let mut state = seed;
+    std::iter::repeat_with(move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        state >> 33
+    })
+}
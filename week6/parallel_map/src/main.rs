@@ -1,4 +1,16 @@
-use crossbeam_channel;
+use crossbeam_channel::{Receiver, Sender};
+use crossbeam_deque::{Steal, Stealer, Worker};
+use std::any::Any;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Range;
+use std::convert::Infallible;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 use std::{thread, time};
 
 struct Data<T: Send> {
@@ -6,55 +18,1029 @@ struct Data<T: Send> {
     index: usize
 }
 
-fn parallel_map<T, U, F>(mut input_vec: Vec<T>, num_threads: usize, f: F) -> Vec<U>
+/// The first error `f` returned in parallel_try_map, along with the index of the input it was
+/// returned for
+#[derive(Debug, PartialEq)]
+struct MapError<E> {
+    index: usize,
+    error: E,
+}
+
+fn parallel_map<T, U, F>(input_vec: Vec<T>, num_threads: usize, f: F) -> Vec<U>
 where
-    F: FnOnce(T) -> U + Send + Copy + 'static,
+    F: Fn(T) -> U + Send + Sync + 'static,
     T: Send + 'static,
-    U: Send + 'static + Default,
+    U: Send + 'static,
 {
-    let mut output_vec: Vec<U> = Vec::with_capacity(input_vec.len());
-    // TODO: implement parallel map!
-    let (input_tx, input_rx) = crossbeam_channel::unbounded::<Data<T>>();
-    let (output_tx, output_rx) = crossbeam_channel::unbounded::<Data<U>>();
+    match parallel_try_map(input_vec, num_threads, move |data| Ok::<U, Infallible>(f(data))) {
+        Ok(output_vec) => output_vec,
+        Err(e) => match e.error {},
+    }
+}
+
+/// Like parallel_map, for an `f` that can fail. Once an error comes back, the inputs that no
+/// worker has started on yet are dropped, and the error is returned when the workers are done.
+fn parallel_try_map<T, U, E, F>(input_vec: Vec<T>, num_threads: usize, f: F) -> Result<Vec<U>, MapError<E>>
+where
+    F: Fn(T) -> Result<U, E> + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+    E: Send + 'static,
+{
+    parallel_try_map_with(input_vec, num_threads, MapOptions::default(), f)
+}
+
+/// Settings for parallel_map_with and parallel_try_map_with
+#[derive(Clone, Copy, Debug, Default)]
+struct MapOptions {
+    /// How many inputs, and separately how many results, can wait in a channel at once. With
+    /// None, the whole input is queued up front; with a limit, inputs are taken from the
+    /// iterator only as the workers catch up, so at most about `num_threads` + 2 * capacity
+    /// items are in flight at a time.
+    capacity: Option<usize>,
+    /// How the inputs are handed out to the workers
+    schedule: Schedule,
+    /// Whether to time the workers, for parallel_map_with_stats
+    instrumentation: Instrumentation,
+    /// For debugging a closure whose bugs depend on the order inputs are mapped in: with a
+    /// seed, the inputs are mapped one at a time, on one worker, in an order shuffled by the
+    /// seed, so a failing order can be replayed by running with the same seed again. The
+    /// whole input is read in before any of it is mapped.
+    deterministic: Option<u64>,
+}
+
+/// Whether a map times its workers. When it's off, the workers don't read the clock at all.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Instrumentation {
+    #[default]
+    Off,
+    On,
+}
+
+/// How long a map took, and how its workers spent that time
+#[derive(Debug)]
+struct MapStats {
+    wall_time: time::Duration,
+    /// One per worker, in the order they were started
+    workers: Vec<WorkerStats>,
+}
+
+#[derive(Debug, Default)]
+struct WorkerStats {
+    /// Which worker this is, counting from 0 in the order they were started
+    worker: usize,
+    items: usize,
+    /// Time spent in `f`
+    busy: time::Duration,
+    /// Time spent waiting for an input
+    idle: time::Duration,
+}
+
+/// Shows a row per worker, then the wall time, like:
+///
+/// ```text
+/// worker  items      busy      idle
+///      0     12    24.3ms     1.2ms
+/// wall time: 26.0ms
+/// ```
+impl fmt::Display for MapStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>6} {:>6} {:>9} {:>9}", "worker", "items", "busy", "idle")?;
+        for stats in &self.workers {
+            let busy = format!("{:.1?}", stats.busy);
+            let idle = format!("{:.1?}", stats.idle);
+            writeln!(f, "{:>6} {:>6} {:>9} {:>9}", stats.worker, stats.items, busy, idle)?;
+        }
+        write!(f, "wall time: {:.1?}", self.wall_time)
+    }
+}
+
+/// Keeps a worker's stats, if instrumentation is on; if it's off, this just runs what it's
+/// given without reading the clock.
+struct WorkerTimer(Option<WorkerStats>);
+
+impl WorkerTimer {
+    fn new(worker: usize, stats_tx: &Option<Sender<WorkerStats>>) -> WorkerTimer {
+        WorkerTimer(stats_tx.as_ref().map(|_| WorkerStats { worker, ..WorkerStats::default() }))
+    }
+
+    /// Runs `wait`, which waits for an input, counting the time as idle.
+    fn idle<R>(&mut self, wait: impl FnOnce() -> R) -> R {
+        match &mut self.0 {
+            None => wait(),
+            Some(stats) => {
+                let started = time::Instant::now();
+                let waited = wait();
+                stats.idle += started.elapsed();
+                waited
+            }
+        }
+    }
+
+    /// Runs `work`, which maps an input, counting the time as busy.
+    fn busy<R>(&mut self, work: impl FnOnce() -> R) -> R {
+        match &mut self.0 {
+            None => work(),
+            Some(stats) => {
+                let started = time::Instant::now();
+                let result = work();
+                stats.busy += started.elapsed();
+                stats.items += 1;
+                result
+            }
+        }
+    }
+
+    /// Sends the stats kept, if any, once the worker's done.
+    fn finish(self, stats_tx: Option<Sender<WorkerStats>>) {
+        if let (Some(stats), Some(stats_tx)) = (self.0, stats_tx) {
+            stats_tx.send(stats).unwrap();
+        }
+    }
+}
+
+/// How a map hands out its inputs to the workers
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Schedule {
+    /// The workers all take inputs, one at a time, from one channel the feeder fills
+    #[default]
+    Shared,
+    /// The inputs are dealt out up front, in chunks, to a queue per worker. A worker takes
+    /// inputs from the front of its own queue, and once that's empty, from the front of the
+    /// others', so a chunk that's slow to get through doesn't hold up the rest of its queue.
+    /// The whole input is queued at once, so `capacity` only limits the results.
+    WorkStealing,
+}
+
+/// Like parallel_map, but takes any iterator of inputs and the given options.
+fn parallel_map_with<I, T, U, F>(inputs: I, num_threads: usize, options: MapOptions, f: F) -> Vec<U>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    match parallel_try_map_with(inputs, num_threads, options, move |data| Ok::<U, Infallible>(f(data))) {
+        Ok(output_vec) => output_vec,
+        Err(e) => match e.error {},
+    }
+}
+
+/// Like parallel_map_with, but times the workers, whatever `options.instrumentation` says, and
+/// returns how they spent their time along with the results.
+fn parallel_map_with_stats<I, T, U, F>(inputs: I, num_threads: usize, options: MapOptions, f: F) -> (Vec<U>, MapStats)
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    let options = MapOptions { instrumentation: Instrumentation::On, ..options };
+    let map = move |data| Ok::<U, Infallible>(f(data));
+    let (output_vec, failed, stats) = try_map_until_cancelled(inputs, num_threads, options, &CancellationToken::new(), map);
+    if let Some(e) = failed {
+        match e.error {}
+    }
+    let output_vec = output_vec
+        .into_iter()
+        .enumerate()
+        .map(|(index, output)| output.unwrap_or_else(|| panic!("No result for input {}", index)))
+        .collect();
+    // Empty if there were no inputs, since no workers were started
+    let stats = stats.unwrap_or(MapStats { wall_time: time::Duration::ZERO, workers: Vec::new() });
+    (output_vec, stats)
+}
+
+/// Like parallel_try_map, but takes any iterator of inputs and the given options.
+fn parallel_try_map_with<I, T, U, E, F>(
+    inputs: I,
+    num_threads: usize,
+    options: MapOptions,
+    f: F,
+) -> Result<Vec<U>, MapError<E>>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(T) -> Result<U, E> + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+    E: Send + 'static,
+{
+    let (output_vec, failed, _) = try_map_until_cancelled(inputs, num_threads, options, &CancellationToken::new(), f);
+    if let Some(failed) = failed {
+        return Err(failed);
+    }
+    Ok(output_vec
+        .into_iter()
+        .enumerate()
+        .map(|(index, output)| output.unwrap_or_else(|| panic!("No result for input {}", index)))
+        .collect())
+}
+
+/// A flag shared between threads that tells a map to stop. Clones share the flag, so one can
+/// be handed to a map while another is kept to cancel it with.
+#[derive(Clone, Debug, Default)]
+struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Tells every map using this token to stop taking new inputs.
+    fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// What parallel_map_cancellable returns when it was cancelled before every input was mapped
+#[derive(Debug, PartialEq)]
+struct Cancelled;
+
+/// Like parallel_map, but stops once `token` is cancelled: no more inputs are started, and once
+/// the workers finish the ones they're on, this returns Cancelled. If every input was already
+/// mapped by then, the results are returned as usual.
+fn parallel_map_cancellable<T, U, F>(
+    input_vec: Vec<T>,
+    num_threads: usize,
+    token: &CancellationToken,
+    f: F,
+) -> Result<Vec<U>, Cancelled>
+where
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    let output_vec = parallel_map_cancellable_partial(input_vec, num_threads, token, f);
+    let complete: Option<Vec<U>> = output_vec.into_iter().collect();
+    match complete {
+        Some(output_vec) => Ok(output_vec),
+        None if token.is_cancelled() => Err(Cancelled),
+        None => panic!("No result for an input that wasn't cancelled"),
+    }
+}
+
+/// Like parallel_map_cancellable, but returns whatever got mapped before the cancellation, with
+/// None for each input that didn't.
+fn parallel_map_cancellable_partial<T, U, F>(
+    input_vec: Vec<T>,
+    num_threads: usize,
+    token: &CancellationToken,
+    f: F,
+) -> Vec<Option<U>>
+where
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    let len = input_vec.len();
+    let map = move |data| Ok::<U, Infallible>(f(data));
+    let (mut output_vec, failed, _) = try_map_until_cancelled(input_vec, num_threads, MapOptions::default(), token, map);
+    if let Some(e) = failed {
+        match e.error {}
+    }
+    output_vec.resize_with(len, || None);
+    output_vec
+}
+
+/// Maps the inputs until they run out, `f` returns an error, or `token` is cancelled, and
+/// returns a slot per input fed to the workers, holding its result if it was mapped, along
+/// with the first error, and the stats if `options.instrumentation` is on. Panics, once the
+/// workers are done, if `f` panicked.
+fn try_map_until_cancelled<I, T, U, E, F>(
+    inputs: I,
+    num_threads: usize,
+    options: MapOptions,
+    token: &CancellationToken,
+    f: F,
+) -> (Vec<Option<U>>, Option<MapError<E>>, Option<MapStats>)
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(T) -> Result<U, E> + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+    E: Send + 'static,
+{
+    let inputs = inputs.into_iter();
+    let num_threads = match options.deterministic {
+        Some(_) => worker_count(num_threads, inputs.size_hint().1).min(1),
+        None => worker_count(num_threads, inputs.size_hint().1),
+    };
+    if num_threads == 0 {
+        return (Vec::new(), None, None);
+    }
+    let started = match options.instrumentation {
+        Instrumentation::Off => None,
+        Instrumentation::On => Some(time::Instant::now()),
+    };
+    // Each worker sends its stats here when it's done, if instrumentation is on
+    let (stats_tx, stats_rx) = crossbeam_channel::unbounded::<WorkerStats>();
+    let stats_tx = started.map(|_| stats_tx);
+    let (input_tx, input_rx) = channel::<Data<T>>(options.capacity);
+    // A worker sends back the panic instead of a result if `f` panics
+    let (output_tx, output_rx) = channel::<Data<thread::Result<Result<U, E>>>>(options.capacity);
+    // Cancelled by the collector on the first error, so this thread stops feeding
+    let failing = CancellationToken::new();
+    let mut inputs = inputs.enumerate();
+    // With a seed, the inputs are all shuffled up front, then fed before the (by then empty)
+    // rest of the iterator
+    let shuffled = match options.deterministic {
+        Some(seed) => shuffle(inputs.by_ref().collect(), seed),
+        None => Vec::new(),
+    };
+    let mut inputs = shuffled.into_iter().chain(inputs);
+    let mut len = 0;
+    let threads = match options.schedule {
+        Schedule::Shared => spawn_workers(num_threads, &input_rx, &output_tx, token, stats_tx, f),
+        Schedule::WorkStealing => {
+            let (queues, dealt) = deal_out(&mut inputs, num_threads);
+            len = dealt;
+            spawn_stealing_workers(queues, &output_tx, [token.clone(), failing.clone()], stats_tx, f)
+        }
+    };
+    drop(output_tx);
+
+    // Results are collected on their own thread while this one feeds the workers; with bounded
+    // channels, feeding and collecting on one thread would deadlock once both channels filled
+    let collector = {
+        let failing = failing.clone();
+        thread::spawn(move || {
+            // Each result goes in the slot for its input's index; a slot still empty at the end
+            // means a result got lost
+            let mut output_vec: Vec<Option<U>> = Vec::new();
+            // The panic for the lowest input index, if any
+            let mut panicked: Option<(usize, Box<dyn Any + Send>)> = None;
+            let mut failed: Option<MapError<E>> = None;
+            while let Ok(Data { data, index }) = output_rx.recv() {
+                match data {
+                    Ok(Ok(output)) => {
+                        if output_vec.len() <= index {
+                            output_vec.resize_with(index + 1, || None);
+                        }
+                        output_vec[index] = Some(output);
+                    }
+                    Ok(Err(error)) => {
+                        if failed.is_none() {
+                            failed = Some(MapError { index, error });
+                            failing.cancel();
+                        }
+                    }
+                    Err(payload) => {
+                        if panicked.as_ref().is_none_or(|(first, _)| index < *first) {
+                            panicked = Some((index, payload));
+                        }
+                    }
+                }
+                if failed.is_some() {
+                    // The workers stop once there's nothing left to take. This runs after each
+                    // result, since the feeder may have sent one more input before it noticed.
+                    while input_rx.try_recv().is_ok() {}
+                }
+            }
+            (output_vec, panicked, failed)
+        })
+    };
+
+    // Nothing's left to feed if the inputs were all dealt out already
+    for (index, data) in inputs {
+        if failing.is_cancelled() || token.is_cancelled() {
+            break;
+        }
+        input_tx.send(Data { data, index }).unwrap();
+        // The indices can come in any order if they were shuffled
+        len = len.max(index + 1);
+    }
+
+    drop(input_tx);
+
+    for handle in threads {
+        handle.join().expect("Panic occurs in a thread!");
+    }
+    let (mut output_vec, panicked, failed) = collector.join().expect("Panic occurs in a thread!");
+
+    if let Some((index, payload)) = panicked {
+        panic!("parallel_map: input {} panicked: {}", index, panic_message(&*payload));
+    }
+
+    let stats = started.map(|started| {
+        let mut workers: Vec<WorkerStats> = stats_rx.try_iter().collect();
+        workers.sort_by_key(|stats| stats.worker);
+        MapStats { wall_time: started.elapsed(), workers }
+    });
+
+    output_vec.resize_with(len, || None);
+    (output_vec, failed, stats)
+}
+
+/// Puts the inputs, along with their indices, in an order that depends only on `seed`. Each
+/// index gets a sort key made from it and the seed, so any two inputs come in the same order
+/// relative to each other however long the input is; smallest_failing_prefix relies on that.
+fn shuffle<T>(mut inputs: Vec<(usize, T)>, seed: u64) -> Vec<(usize, T)> {
+    inputs.sort_by_cached_key(|&(index, _)| {
+        // splitmix64's mixing function, so neighbouring indices get unrelated keys
+        let mut key = seed ^ (index as u64).wrapping_mul(0x9E3779B97F4A7C15);
+        key = (key ^ (key >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        key = (key ^ (key >> 27)).wrapping_mul(0x94D049BB133111EB);
+        key ^ (key >> 31)
+    });
+    inputs
+}
+
+/// Looks for the shortest prefix of `input` that `fails` for any of `seeds`, and returns that
+/// seed and the prefix's length, or None if the whole input doesn't fail for any of them.
+/// `fails` is given a prefix and a seed, and should run the map being debugged on the prefix
+/// with `deterministic` set to the seed. The length is found by bisecting, which assumes that
+/// if a prefix fails for a seed, any longer prefix does too; that holds for bugs that need
+/// certain inputs to be mapped in a certain order, since shuffle keeps the relative order.
+fn smallest_failing_prefix<T, F>(input: &[T], seeds: Range<u64>, mut fails: F) -> Option<(u64, usize)>
+where
+    T: Clone,
+    F: FnMut(Vec<T>, u64) -> bool,
+{
+    let mut smallest: Option<(u64, usize)> = None;
+    for seed in seeds {
+        // Only a prefix shorter than the one already found is worth looking for
+        let mut high = smallest.map_or(input.len(), |(_, len)| len - 1);
+        if high == 0 || !fails(input[..high].to_vec(), seed) {
+            continue;
+        }
+        // The prefix of length `high` fails, and the one of length `low` - 1 doesn't
+        let mut low = 1;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if fails(input[..middle].to_vec(), seed) {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        smallest = Some((seed, high));
+    }
+    smallest
+}
+
+/// Returns a thread count to pass to the parallel_map functions: one per CPU the program can
+/// use, or 1 if that can't be found out.
+fn auto_num_threads() -> usize {
+    thread::available_parallelism().map_or(1, |count| count.get())
+}
+
+/// Returns how many workers to start for at most `max_inputs` inputs (None for no known limit):
+/// `num_threads`, or fewer if some of them would never get an input. Panics if `num_threads`
+/// is 0, since no workers would ever get through the inputs.
+fn worker_count(num_threads: usize, max_inputs: Option<usize>) -> usize {
+    assert!(num_threads > 0, "parallel_map: num_threads must be at least 1 (auto_num_threads() picks one)");
+    max_inputs.map_or(num_threads, |max_inputs| num_threads.min(max_inputs))
+}
+
+/// Starts `num_threads` workers that call `f` on each input they take from `input_rx`, and send
+/// back what it returned, or the panic if it panicked, until the input channel closes. The
+/// workers share `f`, so it can own whatever it captures, like a lookup table it reads from.
+/// Once `token` is cancelled, the workers throw the inputs they take away unmapped; they keep
+/// taking them so that a feeder blocked on a full channel can see the cancellation. Given a
+/// `stats_tx`, each worker times itself and sends its stats there when it's done.
+fn spawn_workers<T, U, F>(
+    num_threads: usize,
+    input_rx: &Receiver<Data<T>>,
+    output_tx: &Sender<Data<thread::Result<U>>>,
+    token: &CancellationToken,
+    stats_tx: Option<Sender<WorkerStats>>,
+    f: F,
+) -> Vec<JoinHandle<()>>
+where
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    let f = Arc::new(f);
     let mut threads = Vec::new();
 
-    for _ in 0..num_threads {
+    for worker in 0..num_threads {
         let input_rx = input_rx.clone();
         let output_tx = output_tx.clone();
+        let f = Arc::clone(&f);
+        let token = token.clone();
+        let stats_tx = stats_tx.clone();
         threads.push(
             thread::spawn(move || {
-                while let Ok(received) = input_rx.recv() {
-                    let output: Data<U> = Data { data: f(received.data), index: received.index };
+                let mut timer = WorkerTimer::new(worker, &stats_tx);
+                while let Ok(received) = timer.idle(|| input_rx.recv()) {
+                    if token.is_cancelled() {
+                        continue;
+                    }
+                    let data = received.data;
+                    let result = timer.busy(|| panic::catch_unwind(AssertUnwindSafe(|| f(data))));
+                    let output = Data { data: result, index: received.index };
                     output_tx.send(output).unwrap();
                 }
                 drop(output_tx);
+                timer.finish(stats_tx);
+            })
+        );
+    }
+
+    threads
+}
+
+/// Deals the inputs out to `num_queues` queues, a chunk of neighbouring inputs to each in turn,
+/// with several chunks per queue so there's something left to steal from a slow one. Also
+/// returns how many inputs were dealt.
+fn deal_out<T: Send>(inputs: impl Iterator<Item = (usize, T)>, num_queues: usize) -> (Vec<Worker<Data<T>>>, usize) {
+    let inputs: Vec<Data<T>> = inputs.map(|(index, data)| Data { data, index }).collect();
+    let len = inputs.len();
+    let chunk_size = (len / (num_queues * 8)).max(1);
+    let queues: Vec<Worker<Data<T>>> = (0..num_queues).map(|_| Worker::new_fifo()).collect();
+    for (position, input) in inputs.into_iter().enumerate() {
+        queues[position / chunk_size % num_queues].push(input);
+    }
+    (queues, len)
+}
+
+/// Like spawn_workers, but each worker takes its inputs from its own queue, or steals them
+/// from the others' once its own is empty, and stops when they're all empty. Once either of
+/// `tokens` is cancelled, the workers stop, and the inputs left in the queues are dropped.
+fn spawn_stealing_workers<T, U, F>(
+    queues: Vec<Worker<Data<T>>>,
+    output_tx: &Sender<Data<thread::Result<U>>>,
+    tokens: [CancellationToken; 2],
+    stats_tx: Option<Sender<WorkerStats>>,
+    f: F,
+) -> Vec<JoinHandle<()>>
+where
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    let f = Arc::new(f);
+    let stealers: Arc<Vec<Stealer<Data<T>>>> = Arc::new(queues.iter().map(Worker::stealer).collect());
+    let mut threads = Vec::new();
+
+    for (own, queue) in queues.into_iter().enumerate() {
+        let output_tx = output_tx.clone();
+        let f = Arc::clone(&f);
+        let stealers = Arc::clone(&stealers);
+        let tokens = tokens.clone();
+        let stats_tx = stats_tx.clone();
+        threads.push(
+            thread::spawn(move || {
+                let mut timer = WorkerTimer::new(own, &stats_tx);
+                while let Some(Data { data, index }) = timer.idle(|| queue.pop().or_else(|| steal(&stealers, own))) {
+                    if tokens.iter().any(CancellationToken::is_cancelled) {
+                        break;
+                    }
+                    let result = timer.busy(|| panic::catch_unwind(AssertUnwindSafe(|| f(data))));
+                    output_tx.send(Data { data: result, index }).unwrap();
+                }
+                timer.finish(stats_tx);
             })
         );
     }
 
+    threads
+}
+
+/// Takes an input from the first queue other than `own` that has one, or returns None once
+/// they're all empty.
+fn steal<T>(stealers: &[Stealer<T>], own: usize) -> Option<T> {
+    loop {
+        let mut retry = false;
+        for (index, stealer) in stealers.iter().enumerate() {
+            if index == own {
+                continue;
+            }
+            match stealer.steal() {
+                Steal::Success(input) => return Some(input),
+                Steal::Empty => {}
+                Steal::Retry => retry = true,
+            }
+        }
+        if !retry {
+            return None;
+        }
+    }
+}
+
+/// Maps each input to a key and value, and collects the pairs into a HashMap as they come back.
+/// If two inputs map to the same key, the pair that came back last wins; the workers finish in
+/// no particular order, so that's not necessarily the one for the later input.
+fn parallel_map_to_hashmap<T, K, V, F>(input_vec: Vec<T>, num_threads: usize, f: F) -> HashMap<K, V>
+where
+    F: Fn(T) -> (K, V) + Send + Sync + 'static,
+    T: Send + 'static,
+    K: Eq + Hash + Send + 'static,
+    V: Send + 'static,
+{
+    match map_pairs(input_vec, num_threads, false, f) {
+        Ok(map) => map,
+        Err(_) => unreachable!("duplicate keys are allowed"),
+    }
+}
+
+/// A key more than one input mapped to, in parallel_try_map_to_hashmap
+#[derive(Debug, PartialEq)]
+struct DuplicateKey<K> {
+    key: K,
+}
+
+/// Like parallel_map_to_hashmap, but returns the first key that comes back twice, instead of
+/// keeping either value. Once it does, the inputs that no worker has started on are dropped.
+fn parallel_try_map_to_hashmap<T, K, V, F>(
+    input_vec: Vec<T>,
+    num_threads: usize,
+    f: F,
+) -> Result<HashMap<K, V>, DuplicateKey<K>>
+where
+    F: Fn(T) -> (K, V) + Send + Sync + 'static,
+    T: Send + 'static,
+    K: Eq + Hash + Send + 'static,
+    V: Send + 'static,
+{
+    map_pairs(input_vec, num_threads, true, f)
+}
+
+/// Does the work for parallel_map_to_hashmap and parallel_try_map_to_hashmap. The pairs go into
+/// the map in the order they arrive, so unlike the other maps, inputs aren't sent with their
+/// index.
+fn map_pairs<T, K, V, F>(
+    input_vec: Vec<T>,
+    num_threads: usize,
+    reject_duplicates: bool,
+    f: F,
+) -> Result<HashMap<K, V>, DuplicateKey<K>>
+where
+    F: Fn(T) -> (K, V) + Send + Sync + 'static,
+    T: Send + 'static,
+    K: Eq + Hash + Send + 'static,
+    V: Send + 'static,
+{
+    let num_threads = worker_count(num_threads, Some(input_vec.len()));
+    let mut map = HashMap::with_capacity(input_vec.len());
+    if num_threads == 0 {
+        return Ok(map);
+    }
+    let (input_tx, input_rx) = crossbeam_channel::unbounded::<T>();
+    let (output_tx, output_rx) = crossbeam_channel::unbounded::<thread::Result<(K, V)>>();
+    let f = Arc::new(f);
+    let mut threads = Vec::new();
+
+    for _ in 0..num_threads {
+        let input_rx = input_rx.clone();
+        let output_tx = output_tx.clone();
+        let f = Arc::clone(&f);
+        threads.push(thread::spawn(move || {
+            while let Ok(data) = input_rx.recv() {
+                output_tx.send(panic::catch_unwind(AssertUnwindSafe(|| f(data)))).unwrap();
+            }
+        }));
+    }
+
     drop(output_tx);
 
-    for (index, data) in input_vec.into_iter().enumerate() {
-        input_tx.send(Data { data, index }).unwrap();
+    for data in input_vec {
+        input_tx.send(data).unwrap();
     }
 
     drop(input_tx);
-    
-    while let Ok(received) = output_rx.recv() {
-        if output_vec.len() <= received.index {
-            let len = output_vec.len();
-            for _ in 0..(received.index - len + 1) {
-                output_vec.push(U::default());
+
+    // The first panic to come back, if any
+    let mut panicked: Option<Box<dyn Any + Send>> = None;
+    let mut duplicate: Option<K> = None;
+    while let Ok(result) = output_rx.recv() {
+        match result {
+            Ok((key, value)) => {
+                if !reject_duplicates {
+                    map.insert(key, value);
+                } else if duplicate.is_none() {
+                    match map.entry(key) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(value);
+                        }
+                        Entry::Occupied(entry) => {
+                            duplicate = Some(entry.remove_entry().0);
+                            // As in parallel_try_map, the workers stop once there's nothing left
+                            while input_rx.try_recv().is_ok() {}
+                        }
+                    }
+                }
+            }
+            Err(payload) => {
+                panicked.get_or_insert(payload);
             }
         }
-        output_vec[received.index] = received.data;
     }
 
     for handle in threads {
         handle.join().expect("Panic occurs in a thread!");
     }
 
+    if let Some(payload) = panicked {
+        panic!("parallel_map: an input panicked: {}", panic_message(&*payload));
+    }
+    match duplicate {
+        Some(key) => Err(DuplicateKey { key }),
+        None => Ok(map),
+    }
+}
+
+/// Like parallel_map, but for inputs and an `f` that borrow from the caller. The workers are
+/// scoped threads, which are all joined before this returns, so nothing has to be 'static.
+fn parallel_map_scoped<T, U, F>(input_vec: Vec<T>, num_threads: usize, f: F) -> Vec<U>
+where
+    F: Fn(T) -> U + Sync,
+    T: Send,
+    U: Send,
+{
+    let num_threads = worker_count(num_threads, Some(input_vec.len()));
+    if num_threads == 0 {
+        return Vec::new();
+    }
+    let mut output_vec: Vec<Option<U>> = (0..input_vec.len()).map(|_| None).collect();
+    let (input_tx, input_rx) = crossbeam_channel::unbounded::<Data<T>>();
+    let (output_tx, output_rx) = crossbeam_channel::unbounded::<Data<thread::Result<U>>>();
+    // The panic for the lowest input index, if any
+    let mut panicked: Option<(usize, Box<dyn Any + Send>)> = None;
+
+    thread::scope(|scope| {
+        let f = &f;
+        for _ in 0..num_threads {
+            let input_rx = input_rx.clone();
+            let output_tx = output_tx.clone();
+            scope.spawn(move || {
+                while let Ok(Data { data, index }) = input_rx.recv() {
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| f(data)));
+                    output_tx.send(Data { data: result, index }).unwrap();
+                }
+            });
+        }
+        drop(output_tx);
+
+        for (index, data) in input_vec.into_iter().enumerate() {
+            input_tx.send(Data { data, index }).unwrap();
+        }
+        drop(input_tx);
+
+        while let Ok(Data { data, index }) = output_rx.recv() {
+            match data {
+                Ok(output) => output_vec[index] = Some(output),
+                Err(payload) => {
+                    if panicked.as_ref().is_none_or(|(first, _)| index < *first) {
+                        panicked = Some((index, payload));
+                    }
+                }
+            }
+        }
+    });
+
+    if let Some((index, payload)) = panicked {
+        panic!("parallel_map: input {} panicked: {}", index, panic_message(&*payload));
+    }
+
     output_vec
+        .into_iter()
+        .enumerate()
+        .map(|(index, output)| output.unwrap_or_else(|| panic!("No result for input {}", index)))
+        .collect()
+}
+
+/// Runs `fa` and `fb` at the same time and returns both results: `fb` on a scoped thread of
+/// its own, and `fa` on the calling thread, which then waits for `fb`. There's no fixed pool of
+/// workers to run out of, since every map starts its own threads, so this can be called from
+/// inside a parallel_map closure, or from `fa` or `fb`, without waiting on a thread that's busy
+/// waiting itself. If either closure panics, this panics once both are done, with the first
+/// closure's panic winning if both do.
+fn parallel_join<A, B>(fa: impl FnOnce() -> A + Send, fb: impl FnOnce() -> B + Send) -> (A, B)
+where
+    A: Send,
+    B: Send,
+{
+    let (a, b) = thread::scope(|scope| {
+        let handle = scope.spawn(fb);
+        let a = panic::catch_unwind(AssertUnwindSafe(fa));
+        (a, handle.join())
+    });
+    match (a, b) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(payload), _) => panic!("parallel_join: the first closure panicked: {}", panic_message(&*payload)),
+        (_, Err(payload)) => panic!("parallel_join: the second closure panicked: {}", panic_message(&*payload)),
+    }
+}
+
+/// Calls `f` on each item of `items` to change it in place, with the items split into a chunk
+/// per thread. Nothing goes through a channel, and no second Vec is needed for the results.
+/// If `f` panics, the chunk it panicked in is left part-way done, and once every thread is
+/// done, the panic for the lowest index is raised again here.
+fn parallel_map_in_place<T, F>(items: &mut [T], num_threads: usize, f: F)
+where
+    F: Fn(&mut T) + Sync,
+    T: Send,
+{
+    let num_threads = worker_count(num_threads, Some(items.len()));
+    if num_threads == 0 {
+        return;
+    }
+    let chunk_size = items.len().div_ceil(num_threads);
+    let f = &f;
+    let panicked = thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks_mut(chunk_size)
+            .enumerate()
+            .map(|(chunk_index, chunk)| {
+                scope.spawn(move || {
+                    for (offset, item) in chunk.iter_mut().enumerate() {
+                        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| f(item))) {
+                            return Some((chunk_index * chunk_size + offset, payload));
+                        }
+                    }
+                    None
+                })
+            })
+            .collect();
+        // The chunks are in index order, so the first panic is the one for the lowest index
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().expect("Panic occurs in a thread!"))
+            .next()
+    });
+
+    if let Some((index, payload)) = panicked {
+        panic!("parallel_map: input {} panicked: {}", index, panic_message(&*payload));
+    }
+}
+
+/// Like parallel_map, but takes the inputs from an iterator only as they're needed and returns
+/// the results as an iterator, in input order. A few inputs per thread are mapped ahead of the
+/// one the caller is waiting on; results that come back early wait in a buffer until their
+/// turn. Dropping the iterator stops the workers once they finish the inputs they're on.
+fn parallel_map_iter<I, T, U, F>(input: I, num_threads: usize, f: F) -> impl Iterator<Item = U>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    map_iter(input, num_threads, f)
+}
+
+/// Like parallel_map, but hands each result to `consumer`, along with its input's index, in
+/// input order, as soon as the results before it have been handed over. Inputs are taken as in
+/// parallel_map_iter, at most a few per thread ahead of the consumer, so a slow consumer or a
+/// slow first input holds up feeding rather than letting results pile up. The consumer runs on
+/// the calling thread, which also collects the results. Returns the most results that were
+/// ever waiting in the reorder buffer for an earlier one.
+fn parallel_map_streamed<I, T, U, F, C>(input: I, num_threads: usize, f: F, mut consumer: C) -> usize
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+    C: FnMut(usize, U),
+{
+    let mut outputs = map_iter(input, num_threads, f);
+    for (index, output) in (&mut outputs).enumerate() {
+        consumer(index, output);
+    }
+    outputs.peak_early
+}
+
+/// Starts the workers for parallel_map_iter and parallel_map_streamed.
+fn map_iter<I, T, U, F>(input: I, num_threads: usize, f: F) -> ParallelMapIter<I::IntoIter, T, U>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(T) -> U + Send + Sync + 'static,
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    let input = input.into_iter();
+    // No workers for an empty input; the iterator ends right away
+    let num_threads = worker_count(num_threads, input.size_hint().1);
+    let (input_tx, input_rx) = crossbeam_channel::unbounded::<Data<T>>();
+    let (output_tx, output_rx) = crossbeam_channel::unbounded::<Data<thread::Result<U>>>();
+    let threads = spawn_workers(num_threads, &input_rx, &output_tx, &CancellationToken::new(), None, f);
+    ParallelMapIter {
+        input,
+        input_tx: Some(input_tx),
+        input_rx,
+        output_rx,
+        threads,
+        window: 2 * num_threads,
+        fed: 0,
+        yielded: 0,
+        early: BTreeMap::new(),
+        peak_early: 0,
+    }
+}
+
+/// The iterator parallel_map_iter returns. Since no more than `window` inputs are fed in ahead of
+/// the next result to yield, at most `window` - 1 results wait in `early`.
+struct ParallelMapIter<I, T: Send, U: Send> {
+    input: I,
+    /// None once the input has run out
+    input_tx: Option<Sender<Data<T>>>,
+    /// Kept to throw away the inputs not yet started when the iterator is dropped
+    input_rx: Receiver<Data<T>>,
+    output_rx: Receiver<Data<thread::Result<U>>>,
+    threads: Vec<JoinHandle<()>>,
+    /// How many inputs can be fed in ahead of the next result to yield
+    window: usize,
+    fed: usize,
+    yielded: usize,
+    /// Results that came back before the ones for earlier inputs, by input index
+    early: BTreeMap<usize, thread::Result<U>>,
+    /// The most results there have been in `early` at once
+    peak_early: usize,
+}
+
+impl<I, T, U> ParallelMapIter<I, T, U>
+where
+    I: Iterator<Item = T>,
+    T: Send,
+    U: Send,
+{
+    /// Feeds inputs to the workers until the window is full or the input runs out.
+    fn feed(&mut self) {
+        while self.fed - self.yielded < self.window {
+            let input_tx = match &self.input_tx {
+                Some(input_tx) => input_tx,
+                None => return,
+            };
+            match self.input.next() {
+                Some(data) => {
+                    input_tx.send(Data { data, index: self.fed }).unwrap();
+                    self.fed += 1;
+                }
+                None => self.input_tx = None,
+            }
+        }
+    }
+}
+
+impl<I, T, U> Iterator for ParallelMapIter<I, T, U>
+where
+    I: Iterator<Item = T>,
+    T: Send,
+    U: Send,
+{
+    type Item = U;
+
+    fn next(&mut self) -> Option<U> {
+        self.feed();
+        if self.yielded == self.fed {
+            return None;
+        }
+        let result = match self.early.remove(&self.yielded) {
+            Some(result) => result,
+            None => loop {
+                let Data { data, index } = self.output_rx.recv().expect("Panic occurs in a thread!");
+                if index == self.yielded {
+                    break data;
+                }
+                self.early.insert(index, data);
+                self.peak_early = self.peak_early.max(self.early.len());
+            },
+        };
+        let index = self.yielded;
+        self.yielded += 1;
+        match result {
+            Ok(output) => Some(output),
+            Err(payload) => panic!("parallel_map: input {} panicked: {}", index, panic_message(&*payload)),
+        }
+    }
+}
+
+impl<I, T: Send, U: Send> Drop for ParallelMapIter<I, T, U> {
+    fn drop(&mut self) {
+        // The workers finish what they've started, then find the input channel closed and empty
+        self.input_tx = None;
+        while self.input_rx.try_recv().is_ok() {}
+        for handle in self.threads.drain(..) {
+            // Don't panic again if the drop comes from a panic in next
+            if handle.join().is_err() && !thread::panicking() {
+                panic!("Panic occurs in a thread!");
+            }
+        }
+    }
+}
+
+/// Makes a channel that holds at most `capacity` messages, or any number with None.
+fn channel<T>(capacity: Option<usize>) -> (Sender<T>, Receiver<T>) {
+    match capacity {
+        Some(capacity) => crossbeam_channel::bounded(capacity),
+        None => crossbeam_channel::unbounded(),
+    }
+}
+
+/// Returns the message a panic was started with, if it was given one.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
 }
 
 fn main() {
@@ -65,4 +1051,751 @@ fn main() {
         num * num
     });
     println!("squares: {:?}", squares);
+
+    let parsed = parallel_try_map(vec!["1", "2", "three", "4"], auto_num_threads(), |text| text.parse::<i32>());
+    match parsed {
+        Ok(numbers) => println!("parsed: {:?}", numbers),
+        Err(e) => println!("input {} failed to parse: {}", e.index, e.error),
+    }
+
+    // Only a few inputs are taken from the range ahead of the workers
+    let options = MapOptions { capacity: Some(4), ..MapOptions::default() };
+    let cubes = parallel_map_with(1..=10, 3, options, |num: u64| num * num * num);
+    println!("cubes: {:?}", cubes);
+
+    // Prints the first few results as they come in, without collecting them first
+    let peak = parallel_map_streamed(0..6, 3, |num: u64| num.pow(4), |index, output| {
+        println!("input {} to the fourth is {}", index, output);
+    });
+    println!("at most {} results had to wait for an earlier one", peak);
+
+    // Shows how busy the workers were; one of the four gets a third input
+    let (_, stats) = parallel_map_with_stats(0..9, 4, MapOptions::default(), |num: u64| {
+        thread::sleep(time::Duration::from_millis(50));
+        num
+    });
+    println!("{}", stats);
+
+    // The first input is slow, so the other workers steal the inputs queued up behind it
+    let options = MapOptions { schedule: Schedule::WorkStealing, ..MapOptions::default() };
+    let doubled = parallel_map_with(0..12, 3, options, |num: u64| {
+        if num == 0 {
+            thread::sleep(time::Duration::from_millis(500));
+        }
+        num * 2
+    });
+    println!("doubled: {:?}", doubled);
+
+    // Gives up on the slow inputs that haven't started after a second
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    thread::spawn(move || {
+        thread::sleep(time::Duration::from_secs(1));
+        canceller.cancel();
+    });
+    let slow = parallel_map_cancellable_partial((1..=20).collect(), 4, &token, |num: u64| {
+        thread::sleep(time::Duration::from_millis(300));
+        num
+    });
+    let finished: Vec<u64> = slow.into_iter().flatten().collect();
+    println!("finished before the cancel: {:?}", finished);
+    // The token stays cancelled, so a map using it again doesn't start anything
+    println!("with the same token: {:?}", parallel_map_cancellable(vec![1, 2, 3], 2, &token, |num: i32| num));
+
+    // Counts how many words have each length; only the last word of each length is kept
+    let words = vec!["map", "the", "inputs", "in", "parallel"];
+    let by_length = parallel_map_to_hashmap(words.clone(), 2, |word: &str| (word.len(), word));
+    println!("a word of each length: {:?}", by_length);
+    match parallel_try_map_to_hashmap(words, 2, |word: &str| (word.len(), word)) {
+        Ok(by_length) => println!("one word per length: {:?}", by_length),
+        Err(e) => println!("more than one word has length {}", e.key),
+    }
+
+    // Maps in the same shuffled order each time it's run with the same seed. Say mapping 5
+    // before 1 is a bug: this finds the seed that needs the fewest inputs to show it.
+    let map_in_order = |inputs: Vec<u64>, seed: u64| {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&order);
+        let options = MapOptions { deterministic: Some(seed), ..MapOptions::default() };
+        parallel_map_with(inputs, 4, options, move |num: u64| seen.lock().unwrap().push(num));
+        let order = order.lock().unwrap().clone();
+        order
+    };
+    println!("seed 2020 maps 0 to 7 in the order {:?}", map_in_order((0..8).collect(), 2020));
+    let five_before_one = |inputs: Vec<u64>, seed: u64| {
+        let order = map_in_order(inputs, seed);
+        let position = |wanted| order.iter().position(|&num| num == wanted);
+        matches!((position(5), position(1)), (Some(five), Some(one)) if five < one)
+    };
+    if let Some((seed, len)) = smallest_failing_prefix(&(0..8).collect::<Vec<u64>>(), 0..10, five_before_one) {
+        println!("seed {} maps 5 before 1 with just the first {} inputs", seed, len);
+    }
+
+    // Sums each half of the range at the same time
+    let (low, high) = parallel_join(|| (1..=50u64).sum::<u64>(), || (51..=100u64).sum::<u64>());
+    println!("1 to 100 sums to {} + {} = {}", low, high, low + high);
+
+    // Updates the numbers where they are, without a second Vec
+    let mut numbers: Vec<u64> = (1..=10).collect();
+    parallel_map_in_place(&mut numbers, 4, |num| *num *= 100);
+    println!("in place: {:?}", numbers);
+
+    // The words are borrowed from `sentence` rather than copied for the workers
+    let sentence = String::from("the quick brown fox");
+    let lengths = parallel_map_scoped(sentence.split(' ').collect(), 2, |word: &str| word.len());
+    println!("word lengths: {:?}", lengths);
+
+    // Stops taking inputs from the endless range once the first five results are in
+    for (num, halved) in parallel_map_iter(1.., 4, |num: u64| num as f64 / 2.0).take(5).enumerate() {
+        println!("{} halved is {}", num + 1, halved);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::sync::atomic::AtomicUsize;
+
+    thread_local! {
+        /// Whether this thread's allocations are counted in TRACKED_ALLOCATED
+        static TRACKED: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// Bytes allocated by tracked threads, for tests about memory use. Only the threads a test
+    /// marks are counted, so other tests allocating at the same time don't throw it off.
+    static TRACKED_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Marks the current thread's allocations as counted, or not.
+    fn track_allocations(tracked: bool) {
+        TRACKED.with(|flag| flag.set(tracked));
+    }
+
+    /// The system allocator, plus a count of the bytes tracked threads allocate
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            // try_with, since a thread can still allocate while its thread-locals are torn down
+            if !ptr.is_null() && TRACKED.try_with(Cell::get).unwrap_or(false) {
+                TRACKED_ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn test_non_default_output() {
+        // No Default for this one
+        #[derive(Debug, PartialEq)]
+        struct Doubled(i32);
+        let doubled = parallel_map(vec![1, 2, 3], 2, |num| Doubled(num * 2));
+        assert_eq!(doubled, vec![Doubled(2), Doubled(4), Doubled(6)]);
+    }
+
+    #[test]
+    fn test_order_preserved() {
+        let input: Vec<u64> = (0..50).collect();
+        let output = parallel_map(input, 8, |num| {
+            // A delay that varies from item to item without any pattern, so items finish
+            // out of order
+            let delay = num.wrapping_mul(2654435761) % 20;
+            thread::sleep(time::Duration::from_millis(delay));
+            num * 10
+        });
+        assert_eq!(output, (0..50).map(|num| num * 10).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_empty_input() {
+        let output: Vec<i32> = parallel_map(Vec::new(), 4, |num: i32| num);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "parallel_map: input 7 panicked: no sevens")]
+    fn test_one_panic() {
+        parallel_map((0..20).collect(), 4, |num: i32| {
+            if num == 7 {
+                panic!("no sevens");
+            }
+            num
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "parallel_map: input 0 panicked: nothing works")]
+    fn test_every_input_panics() {
+        // Every worker panics over and over; the first input's panic is the one reported
+        parallel_map((0..20).collect(), 4, |num: i32| -> i32 {
+            thread::sleep(time::Duration::from_millis((20 - num) as u64));
+            panic!("nothing works");
+        });
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let payload = panic::catch_unwind(|| panic!("formatted {}", 1)).unwrap_err();
+        assert_eq!(panic_message(&*payload), "formatted 1");
+        let payload = panic::catch_unwind(|| panic::panic_any(3)).unwrap_err();
+        assert_eq!(panic_message(&*payload), "Box<dyn Any>");
+    }
+
+    #[test]
+    fn test_try_map() {
+        let parsed = parallel_try_map(vec!["3", "1", "2"], 2, |text| text.parse::<i32>());
+        assert_eq!(parsed, Ok(vec![3, 1, 2]));
+        let parsed = parallel_try_map(vec!["3", "x", "2"], 2, |text| text.parse::<i32>());
+        let e = parsed.unwrap_err();
+        assert_eq!(e.index, 1);
+        assert_eq!(e.error.to_string(), "invalid digit found in string");
+    }
+
+    #[test]
+    fn test_try_map_stops_early() {
+        static MAPPED: AtomicUsize = AtomicUsize::new(0);
+        let result = parallel_try_map((0..10_000).collect(), 4, |num: usize| {
+            if num == 3 {
+                return Err("three");
+            }
+            MAPPED.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(time::Duration::from_millis(1));
+            Ok(num)
+        });
+        assert_eq!(result, Err(MapError { index: 3, error: "three" }));
+        // Mapping everything would take 10,000 / 4 ms at least
+        assert!(MAPPED.load(Ordering::SeqCst) < 1000);
+    }
+
+    #[test]
+    fn test_bounded_capacity() {
+        // Counts the items alive at once, to check that a bounded map doesn't pull the whole
+        // input in before the workers get to it
+        static LIVE: AtomicUsize = AtomicUsize::new(0);
+        static MAX_LIVE: AtomicUsize = AtomicUsize::new(0);
+        struct Tracked(Vec<u8>);
+        impl Tracked {
+            fn new(len: usize) -> Tracked {
+                let live = LIVE.fetch_add(1, Ordering::SeqCst) + 1;
+                MAX_LIVE.fetch_max(live, Ordering::SeqCst);
+                Tracked(vec![0; len])
+            }
+        }
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                LIVE.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        // 4 GiB of input in all, 1 MiB at a time
+        let num_items = 4096;
+        let num_threads = 4;
+        let capacity = 2;
+        let inputs = (0..num_items).map(|_| Tracked::new(1 << 20));
+        let options = MapOptions { capacity: Some(capacity), ..MapOptions::default() };
+        let lens = parallel_map_with(inputs, num_threads, options, |item: Tracked| item.0.len());
+        assert_eq!(lens, vec![1 << 20; num_items]);
+        assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+        // One per worker, the ones queued, and the one being fed in
+        assert!(MAX_LIVE.load(Ordering::SeqCst) <= num_threads + capacity + 1);
+    }
+
+    #[test]
+    fn test_bounded_try_map() {
+        let options = MapOptions { capacity: Some(1), ..MapOptions::default() };
+        let result = parallel_try_map_with(0..10_000, 3, options, |num: usize| {
+            if num == 500 {
+                return Err(num);
+            }
+            Ok(num)
+        });
+        assert_eq!(result, Err(MapError { index: 500, error: 500 }));
+        // A zero capacity hands each item straight from one thread to another
+        let options = MapOptions { capacity: Some(0), ..MapOptions::default() };
+        assert_eq!(parallel_map_with(0..100, 3, options, |num: i32| num + 1), (1..101).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn test_map_iter() {
+        let output: Vec<u64> = parallel_map_iter(0..1000u64, 8, |num| {
+            thread::sleep(time::Duration::from_micros(num.wrapping_mul(2654435761) % 500));
+            num * num
+        })
+        .collect();
+        assert_eq!(output, (0..1000).map(|num| num * num).collect::<Vec<u64>>());
+        assert_eq!(parallel_map_iter(Vec::<i32>::new(), 2, |num| num).next(), None);
+    }
+
+    #[test]
+    fn test_map_iter_is_lazy() {
+        static MAPPED: AtomicUsize = AtomicUsize::new(0);
+        let num_threads = 4;
+        let first: Vec<usize> = parallel_map_iter(0..10_000, num_threads, |num: usize| {
+            MAPPED.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(time::Duration::from_millis(1));
+            num
+        })
+        .take(10)
+        .collect();
+        assert_eq!(first, (0..10).collect::<Vec<usize>>());
+        // The iterator's been dropped, so its workers are done; no more get mapped after this
+        let mapped = MAPPED.load(Ordering::SeqCst);
+        assert!(mapped <= 10 + 2 * num_threads);
+        thread::sleep(time::Duration::from_millis(20));
+        assert_eq!(MAPPED.load(Ordering::SeqCst), mapped);
+    }
+
+    #[test]
+    #[should_panic(expected = "parallel_map: input 3 panicked: no threes")]
+    fn test_map_iter_panic() {
+        for _ in parallel_map_iter(0..10, 2, |num: i32| {
+            if num == 3 {
+                panic!("no threes");
+            }
+        }) {}
+    }
+
+    #[test]
+    fn test_closure_owns_captures() {
+        // Neither the String nor the HashMap is Copy, so a closure owning them isn't either
+        let suffix = String::from("!");
+        let shouted = parallel_map(vec!["a", "b"], 2, move |text: &str| text.to_uppercase() + &suffix);
+        assert_eq!(shouted, vec!["A!", "B!"]);
+
+        let names: HashMap<u32, &str> = vec![(1, "one"), (2, "two"), (3, "three")].into_iter().collect();
+        let input: Vec<u32> = (0..100).map(|num| num % 3 + 1).collect();
+        let expected: Vec<&str> = input.iter().map(|num| names[num]).collect();
+        let output = parallel_map(input, 4, move |num| names[&num]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_scoped_borrows_inputs() {
+        let owned: Vec<String> = (0..10).map(|num| num.to_string()).collect();
+        let words: Vec<&str> = owned.iter().map(String::as_str).collect();
+        let joined = parallel_map_scoped(words.chunks(3).collect(), 3, |chunk: &[&str]| chunk.join("+"));
+        assert_eq!(joined, vec!["0+1+2", "3+4+5", "6+7+8", "9"]);
+    }
+
+    #[test]
+    fn test_scoped_borrows_captures() {
+        let counter = Mutex::new(0);
+        let output = parallel_map_scoped((1..=100).collect(), 4, |num: i32| {
+            *counter.lock().unwrap() += num;
+            -num
+        });
+        assert_eq!(output, (1..=100).map(|num| -num).collect::<Vec<i32>>());
+        assert_eq!(counter.into_inner().unwrap(), 5050);
+    }
+
+    #[test]
+    #[should_panic(expected = "parallel_map: input 2 panicked: no twos")]
+    fn test_scoped_panic() {
+        let limit = 2;
+        parallel_map_scoped((0..10).collect(), 3, |num: i32| {
+            if num == limit {
+                panic!("no twos");
+            }
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "parallel_map: num_threads must be at least 1")]
+    fn test_zero_threads() {
+        parallel_map(vec![1, 2, 3], 0, |num: i32| num);
+    }
+
+    #[test]
+    fn test_worker_count() {
+        assert_eq!(worker_count(64, Some(3)), 3);
+        assert_eq!(worker_count(4, Some(100)), 4);
+        assert_eq!(worker_count(4, None), 4);
+        assert_eq!(worker_count(4, Some(0)), 0);
+        assert!(auto_num_threads() >= 1);
+    }
+
+    #[test]
+    fn test_more_threads_than_inputs() {
+        // Only three workers get started here, one per input
+        assert_eq!(parallel_map(vec![1, 2, 3], 64, |num: i32| -num), vec![-1, -2, -3]);
+        let scoped = parallel_map_scoped(vec![1, 2, 3], 64, |num: i32| num * 2);
+        assert_eq!(scoped, vec![2, 4, 6]);
+        assert_eq!(parallel_map_iter(vec![1, 2, 3], 64, |num: i32| num + 1).collect::<Vec<i32>>(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_empty_input_everywhere() {
+        assert_eq!(parallel_map_scoped(Vec::new(), 4, |num: i32| num), Vec::<i32>::new());
+        assert_eq!(parallel_try_map(Vec::new(), 4, |num: i32| Ok::<i32, ()>(num)), Ok(Vec::new()));
+        assert_eq!(parallel_map_iter(Vec::<i32>::new(), 4, |num| num).count(), 0);
+    }
+
+    #[test]
+    fn test_cancel_from_another_thread() {
+        static MAPPED: AtomicUsize = AtomicUsize::new(0);
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        // Cancels once all four workers are busy
+        thread::spawn(move || {
+            while MAPPED.load(Ordering::SeqCst) < 4 {
+                thread::sleep(time::Duration::from_millis(1));
+            }
+            canceller.cancel();
+        });
+        // Each input holds its worker until the cancel, so nothing can start after it
+        let waiting = token.clone();
+        let result = parallel_map_cancellable((0..200).collect(), 4, &token, move |num: i32| {
+            MAPPED.fetch_add(1, Ordering::SeqCst);
+            while !waiting.is_cancelled() {
+                thread::sleep(time::Duration::from_millis(1));
+            }
+            num
+        });
+        assert_eq!(result, Err(Cancelled));
+        assert_eq!(MAPPED.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_cancel_partial() {
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        let partial = parallel_map_cancellable_partial((0..100).collect(), 2, &token, move |num: usize| {
+            if num == 10 {
+                canceller.cancel();
+            }
+            thread::sleep(time::Duration::from_millis(1));
+            num * 2
+        });
+        assert_eq!(partial.len(), 100);
+        // The input that cancelled still has its result, and what did get mapped is in place,
+        // but the workers skipped whatever they took after the other worker's current input
+        assert_eq!(partial[10], Some(20));
+        assert!(partial.iter().enumerate().all(|(num, output)| output.is_none() || *output == Some(num * 2)));
+        assert!(partial[20..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_cancel_after_finishing() {
+        let token = CancellationToken::new();
+        assert_eq!(parallel_map_cancellable(vec![1, 2], 2, &token, |num: i32| num), Ok(vec![1, 2]));
+        token.cancel();
+        assert!(token.is_cancelled());
+        // Cancelled before it started, so nothing gets mapped
+        assert_eq!(parallel_map_cancellable(vec![1, 2], 2, &token, |num: i32| num), Err(Cancelled));
+    }
+
+    #[test]
+    fn test_work_stealing_skewed() {
+        static MAPPED: AtomicUsize = AtomicUsize::new(0);
+        static STOLEN_FROM_SLOW: AtomicBool = AtomicBool::new(false);
+        let options = MapOptions { schedule: Schedule::WorkStealing, ..MapOptions::default() };
+        // Input 0 holds up its worker until every other input is mapped, including the ones
+        // queued behind it, which only happens if the other workers steal them. The deadline
+        // only keeps a broken scheduler from hanging the test.
+        let output = parallel_map_with(0..1000, 4, options, |num: u64| {
+            if num == 0 {
+                let deadline = time::Instant::now() + time::Duration::from_secs(10);
+                while MAPPED.load(Ordering::SeqCst) < 999 && time::Instant::now() < deadline {
+                    thread::sleep(time::Duration::from_millis(1));
+                }
+                STOLEN_FROM_SLOW.store(MAPPED.load(Ordering::SeqCst) == 999, Ordering::SeqCst);
+            } else {
+                MAPPED.fetch_add(1, Ordering::SeqCst);
+            }
+            num * 3
+        });
+        assert_eq!(output, (0..1000).map(|num| num * 3).collect::<Vec<u64>>());
+        assert!(STOLEN_FROM_SLOW.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_work_stealing_stops() {
+        let options = MapOptions { schedule: Schedule::WorkStealing, capacity: Some(2), ..MapOptions::default() };
+        let result = parallel_try_map_with(0..10_000, 4, options, |num: usize| {
+            thread::sleep(time::Duration::from_micros(100));
+            if num == 10 {
+                return Err("ten");
+            }
+            Ok(num)
+        });
+        assert_eq!(result, Err(MapError { index: 10, error: "ten" }));
+        let output = parallel_map_with(vec!["a"; 3], 8, options, |text: &str| text.len());
+        assert_eq!(output, vec![1, 1, 1]);
+    }
+
+    /// `count` numbers that look random, but are the same for the same seed every run
+    fn scrambled(count: usize, mut seed: u64) -> Vec<u64> {
+        (0..count)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                seed >> 33
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hashmap_matches_sequential() {
+        let input = scrambled(5000, 7);
+        // Lots of inputs share a key, but the value only depends on the key, so whichever
+        // pair wins doesn't change the map
+        let pair = |num: u64| (num % 700, (num % 700).to_string());
+        let expected: HashMap<u64, String> = input.iter().map(|&num| pair(num)).collect();
+        assert_eq!(parallel_map_to_hashmap(input.clone(), 4, pair), expected);
+
+        let mut unique = input;
+        unique.sort_unstable();
+        unique.dedup();
+        let expected: HashMap<u64, u64> = unique.iter().map(|&num| (num, num / 3)).collect();
+        let pairs = parallel_try_map_to_hashmap(unique, 4, |num| (num, num / 3));
+        assert_eq!(pairs, Ok(expected));
+    }
+
+    #[test]
+    fn test_hashmap_duplicates() {
+        // One of the two values for "b" is kept
+        let letters = parallel_map_to_hashmap(vec!["a1", "b2", "b3"], 3, |text: &str| {
+            (text[..1].to_string(), text[1..].parse::<i32>().unwrap())
+        });
+        assert_eq!(letters.len(), 2);
+        assert_eq!(letters["a"], 1);
+        assert!(letters["b"] == 2 || letters["b"] == 3);
+
+        let letters = parallel_try_map_to_hashmap(vec!["a1", "b2", "c3", "b4"], 2, |text: &str| {
+            (text[..1].to_string(), text[1..].to_string())
+        });
+        assert_eq!(letters, Err(DuplicateKey { key: "b".to_string() }));
+        assert_eq!(parallel_map_to_hashmap(Vec::new(), 2, |num: i32| (num, num)), HashMap::new());
+    }
+
+    #[test]
+    fn test_in_place() {
+        let mut numbers: Vec<u64> = (0..10_000_000).collect();
+        let mut expected = numbers.clone();
+        expected.iter_mut().for_each(|num| *num = num.wrapping_mul(2654435761) % 1000);
+        // Everything this thread and the workers allocate is counted, which comes nowhere near
+        // the 80 MB a second Vec of 10M u64s would take. The workers mark themselves on their
+        // first input, before they could allocate anything for the results.
+        track_allocations(true);
+        parallel_map_in_place(&mut numbers, 4, |num| {
+            track_allocations(true);
+            *num = num.wrapping_mul(2654435761) % 1000;
+        });
+        track_allocations(false);
+        assert!(TRACKED_ALLOCATED.load(Ordering::SeqCst) < 40_000_000);
+        assert_eq!(numbers, expected);
+
+        let mut empty: Vec<u64> = Vec::new();
+        parallel_map_in_place(&mut empty, 4, |num| *num += 1);
+        let mut few = vec![1, 2, 3];
+        parallel_map_in_place(&mut few, 64, |num| *num += 1);
+        assert_eq!(few, vec![2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "parallel_map: input 60 panicked: no sixties")]
+    fn test_in_place_panic() {
+        let mut numbers: Vec<i32> = (0..100).collect();
+        parallel_map_in_place(&mut numbers, 4, |num| {
+            if *num / 10 == 6 {
+                panic!("no sixties");
+            }
+        });
+    }
+
+    #[test]
+    fn test_stats() {
+        for &schedule in &[Schedule::Shared, Schedule::WorkStealing] {
+            let options = MapOptions { schedule, ..MapOptions::default() };
+            let (output, stats) = parallel_map_with_stats(0..40, 4, options, |num: u32| {
+                thread::sleep(time::Duration::from_millis(2));
+                num + 1
+            });
+            assert_eq!(output, (1..41).collect::<Vec<u32>>());
+            assert_eq!(stats.workers.len(), 4);
+            assert_eq!(stats.workers.iter().map(|worker| worker.items).sum::<usize>(), 40);
+            for (index, worker) in stats.workers.iter().enumerate() {
+                assert_eq!(worker.worker, index);
+                assert!(worker.items == 0 || worker.busy >= time::Duration::from_millis(2 * worker.items as u64));
+            }
+            assert!(stats.wall_time >= time::Duration::from_millis(20));
+
+            let table = stats.to_string();
+            assert!(table.starts_with("worker  items      busy      idle\n"));
+            assert_eq!(table.lines().count(), 6);
+            assert!(table.lines().last().unwrap().starts_with("wall time: "));
+        }
+    }
+
+    #[test]
+    fn test_no_stats_when_off() {
+        let token = CancellationToken::new();
+        let (output, failed, stats) =
+            try_map_until_cancelled(0..10, 2, MapOptions::default(), &token, |num: i32| Ok::<i32, ()>(num));
+        assert_eq!(output.len(), 10);
+        assert!(failed.is_none());
+        assert!(stats.is_none());
+    }
+
+    #[test]
+    fn test_streamed_in_order() {
+        let mut seen = Vec::new();
+        let peak = parallel_map_streamed(0..10_000u64, 8, |num| {
+            // A delay that varies from item to item without any pattern
+            thread::sleep(time::Duration::from_micros(num.wrapping_mul(2654435761) % 200));
+            num * 2
+        }, |index, output| {
+            assert_eq!(output, index as u64 * 2);
+            seen.push(index);
+        });
+        assert_eq!(seen, (0..10_000).collect::<Vec<usize>>());
+        assert!(peak < 16);
+    }
+
+    #[test]
+    fn test_streamed_slow_first_input() {
+        // Everything after the first input finishes long before it does, but only the window's
+        // worth of inputs gets fed in meanwhile
+        let num_threads = 4;
+        let mut seen = 0;
+        let peak = parallel_map_streamed(0..1000, num_threads, |num: usize| {
+            if num == 0 {
+                thread::sleep(time::Duration::from_millis(100));
+            }
+            num
+        }, |index, output| {
+            assert_eq!((index, output), (seen, seen));
+            seen += 1;
+        });
+        assert_eq!(seen, 1000);
+        assert!(peak < 2 * num_threads);
+    }
+
+    #[test]
+    fn test_streamed_slow_consumer() {
+        static MAPPED: AtomicUsize = AtomicUsize::new(0);
+        let num_threads = 4;
+        parallel_map_streamed(0..200, num_threads, |num: usize| {
+            MAPPED.fetch_add(1, Ordering::SeqCst);
+            num
+        }, |index, _| {
+            thread::sleep(time::Duration::from_micros(200));
+            // The workers can't get more than the window ahead of the consumer
+            assert!(MAPPED.load(Ordering::SeqCst) <= index + 1 + 2 * num_threads);
+        });
+        assert_eq!(MAPPED.load(Ordering::SeqCst), 200);
+    }
+
+    #[test]
+    fn test_join() {
+        let text = String::from("borrowed");
+        let (len, upper) = parallel_join(|| text.len(), || text.to_uppercase());
+        assert_eq!((len, upper.as_str()), (8, "BORROWED"));
+        // Each closure hears from the other before it returns, which can only happen if they
+        // run at the same time. The timeout only keeps a serial join from hanging the test.
+        let (a_tx, a_rx) = crossbeam_channel::bounded(1);
+        let (b_tx, b_rx) = crossbeam_channel::bounded(1);
+        let timeout = time::Duration::from_secs(10);
+        let handshake = parallel_join(
+            || {
+                a_tx.send(()).unwrap();
+                b_rx.recv_timeout(timeout).is_ok()
+            },
+            || {
+                b_tx.send(()).unwrap();
+                a_rx.recv_timeout(timeout).is_ok()
+            },
+        );
+        assert_eq!(handshake, (true, true));
+    }
+
+    #[test]
+    fn test_join_nested_in_map() {
+        // More inputs than workers, and each of them joins two more closures, one of which
+        // joins again
+        let output = parallel_map((0..8).collect(), 4, |num: u64| {
+            let (doubled, (squared, cubed)) = parallel_join(
+                || num * 2,
+                || parallel_join(|| num * num, || num * num * num),
+            );
+            doubled + squared + cubed
+        });
+        assert_eq!(output, (0..8).map(|num| num * 2 + num * num + num * num * num).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "parallel_join: the second closure panicked: right side")]
+    fn test_join_panic() {
+        parallel_join(|| 1, || -> i32 { panic!("right side") });
+    }
+
+    #[test]
+    #[should_panic(expected = "parallel_map: input 1 panicked: parallel_join: the first closure panicked: odd")]
+    fn test_join_panic_in_map() {
+        parallel_map(vec![0, 1, 2], 2, |num: i32| {
+            parallel_join(
+                || {
+                    if num % 2 == 1 {
+                        panic!("odd");
+                    }
+                },
+                || num,
+            )
+        });
+    }
+
+    /// Maps 0..`len` with `deterministic` set to `seed`, and returns the order the inputs were
+    /// mapped in.
+    fn mapping_order(len: u64, seed: u64) -> Vec<u64> {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&order);
+        let options = MapOptions { deterministic: Some(seed), ..MapOptions::default() };
+        let output = parallel_map_with(0..len, 4, options, move |num: u64| {
+            seen.lock().unwrap().push(num);
+            num + 1
+        });
+        assert_eq!(output, (1..=len).collect::<Vec<u64>>());
+        let order = order.lock().unwrap().clone();
+        order
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+        let order = mapping_order(100, 7);
+        assert_eq!(mapping_order(100, 7), order);
+        assert_ne!(order, (0..100).collect::<Vec<u64>>());
+        assert_ne!(mapping_order(100, 8), order);
+        // Inputs keep their order relative to each other in a longer input
+        let longer: Vec<u64> = mapping_order(150, 7).into_iter().filter(|&num| num < 100).collect();
+        assert_eq!(longer, order);
+        // The error still comes with the right index when inputs are mapped out of order
+        let options = MapOptions { deterministic: Some(7), ..MapOptions::default() };
+        let result = parallel_try_map_with(0..100, 4, options, |num: i32| if num == 42 { Err(num) } else { Ok(num) });
+        assert_eq!(result, Err(MapError { index: 42, error: 42 }));
+    }
+
+    #[test]
+    fn test_smallest_failing_prefix() {
+        // The bug: mapping 7 before 2 goes wrong
+        let fails = |prefix: Vec<u64>, seed: u64| {
+            let order = mapping_order(prefix.len() as u64, seed);
+            let position = |num| order.iter().position(|&mapped| mapped == num);
+            matches!((position(7), position(2)), (Some(seven), Some(two)) if seven < two)
+        };
+        let input: Vec<u64> = (0..20).collect();
+        let (seed, len) = smallest_failing_prefix(&input, 0..20, fails).unwrap();
+        assert_eq!(len, 8);
+        assert!(fails(input[..8].to_vec(), seed));
+        assert!(!fails(input[..7].to_vec(), seed));
+        // Nothing fails if 7 isn't in the input
+        assert_eq!(smallest_failing_prefix(&input[..7], 0..20, fails), None);
+    }
 }